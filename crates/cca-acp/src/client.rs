@@ -27,6 +27,37 @@ pub enum ConnectionState {
     Reconnecting,
 }
 
+/// Callback invoked with each new connection state
+pub type StateChangeCallback = Box<dyn Fn(ConnectionState) + Send + Sync>;
+
+/// State changes buffered for listeners before new ones are dropped
+const STATE_CHANGE_CAPACITY: usize = 64;
+
+/// Connection state shared with the connection task, reporting each change
+#[derive(Clone)]
+struct StateHandle {
+    current: Arc<RwLock<ConnectionState>>,
+    changes: mpsc::Sender<ConnectionState>,
+}
+
+impl StateHandle {
+    async fn get(&self) -> ConnectionState {
+        *self.current.read().await
+    }
+
+    async fn set(&self, state: ConnectionState) {
+        let mut current = self.current.write().await;
+        if *current == state {
+            return;
+        }
+        *current = state;
+        // Sent under the lock so listeners see changes in order; never wait on them
+        if self.changes.try_send(state).is_err() {
+            warn!("Connection state listeners are falling behind, dropped change to {:?}", state);
+        }
+    }
+}
+
 /// Pending request awaiting response
 struct PendingRequest {
     sender: oneshot::Sender<AcpMessage>,
@@ -64,7 +95,10 @@ pub struct AcpClient {
     agent_id: AgentId,
     config: AcpClientConfig,
     sender: Arc<RwLock<Option<mpsc::Sender<String>>>>,
-    state: Arc<RwLock<ConnectionState>>,
+    state: StateHandle,
+    state_listeners: Arc<std::sync::RwLock<Vec<StateChangeCallback>>>,
+    /// Taken by the listener task when the client first connects
+    state_changes: std::sync::Mutex<Option<mpsc::Receiver<ConnectionState>>>,
     pending_requests: Arc<RwLock<HashMap<String, PendingRequest>>>,
     message_tx: mpsc::Sender<AcpMessage>,
     shutdown: tokio::sync::broadcast::Sender<()>,
@@ -86,12 +120,18 @@ impl AcpClient {
     pub fn with_config(agent_id: AgentId, config: AcpClientConfig) -> Self {
         let (message_tx, _) = mpsc::channel(100);
         let (shutdown_tx, _) = tokio::sync::broadcast::channel(1);
+        let (changes_tx, changes_rx) = mpsc::channel(STATE_CHANGE_CAPACITY);
 
         Self {
             agent_id,
             config,
             sender: Arc::new(RwLock::new(None)),
-            state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
+            state: StateHandle {
+                current: Arc::new(RwLock::new(ConnectionState::Disconnected)),
+                changes: changes_tx,
+            },
+            state_listeners: Arc::new(std::sync::RwLock::new(Vec::new())),
+            state_changes: std::sync::Mutex::new(Some(changes_rx)),
             pending_requests: Arc::new(RwLock::new(HashMap::new())),
             message_tx,
             shutdown: shutdown_tx,
@@ -100,12 +140,22 @@ impl AcpClient {
 
     /// Get current connection state
     pub async fn state(&self) -> ConnectionState {
-        *self.state.read().await
+        self.state.get().await
     }
 
     /// Check if connected
     pub async fn is_connected(&self) -> bool {
-        *self.state.read().await == ConnectionState::Connected
+        self.state.get().await == ConnectionState::Connected
+    }
+
+    /// Register a callback for connection state changes
+    ///
+    /// Fires on every transition, including `Reconnecting` while backing off
+    /// between attempts. Callbacks run on a separate task once the client
+    /// connects, so a slow callback delays other callbacks but never the
+    /// connection itself.
+    pub fn on_state_change(&self, callback: StateChangeCallback) {
+        self.state_listeners.write().unwrap().push(callback);
     }
 
     /// Connect to the ACP server with automatic reconnection
@@ -121,6 +171,17 @@ impl AcpClient {
         let pending = self.pending_requests.clone();
         let mut shutdown_rx = self.shutdown.subscribe();
 
+        if let Some(mut changes) = self.state_changes.lock().unwrap().take() {
+            let listeners = self.state_listeners.clone();
+            tokio::spawn(async move {
+                while let Some(new_state) = changes.recv().await {
+                    for listener in listeners.read().unwrap().iter() {
+                        listener(new_state);
+                    }
+                }
+            });
+        }
+
         // Spawn connection manager task
         tokio::spawn(async move {
             let mut reconnect_attempts = 0u32;
@@ -133,14 +194,13 @@ impl AcpClient {
                 }
 
                 // Update state
-                {
-                    let mut s = state.write().await;
-                    *s = if reconnect_attempts > 0 {
+                state
+                    .set(if reconnect_attempts > 0 {
                         ConnectionState::Reconnecting
                     } else {
                         ConnectionState::Connecting
-                    };
-                }
+                    })
+                    .await;
 
                 let url = format!("{}/ws/{}", config.server_url, agent_id);
                 info!("Connecting to ACP server: {} (attempt {})", url, reconnect_attempts + 1);
@@ -151,10 +211,7 @@ impl AcpClient {
                         reconnect_attempts = 0;
                         reconnect_delay = config.reconnect_interval;
 
-                        {
-                            let mut s = state.write().await;
-                            *s = ConnectionState::Connected;
-                        }
+                        state.set(ConnectionState::Connected).await;
 
                         let (mut write, mut read) = ws_stream.split();
 
@@ -215,15 +272,10 @@ impl AcpClient {
                                                 Ok(acp_msg) => {
                                                     debug!("Received: {:?}", acp_msg.method);
 
-                                                    // Check if this is a response to a pending request
-                                                    if acp_msg.id.is_some() && acp_msg.method.is_none() {
-                                                        let id = acp_msg.id.as_ref().unwrap();
-                                                        let mut pending = pending_clone.write().await;
-                                                        if let Some(req) = pending.remove(id) {
-                                                            let _ = req.sender.send(acp_msg.clone());
-                                                            continue;
-                                                        }
-                                                    }
+                                                    // Responses go to their pending request
+                                                    let Some(acp_msg) = route_response(&pending_clone, acp_msg).await else {
+                                                        continue;
+                                                    };
 
                                                     // Forward to message channel
                                                     if message_tx_clone.send(acp_msg).await.is_err() {
@@ -280,10 +332,7 @@ impl AcpClient {
                 }
 
                 // Update state to disconnected
-                {
-                    let mut s = state.write().await;
-                    *s = ConnectionState::Disconnected;
-                }
+                state.set(ConnectionState::Disconnected).await;
 
                 // Check max reconnect attempts
                 reconnect_attempts += 1;
@@ -319,8 +368,7 @@ impl AcpClient {
         let _ = self.shutdown.send(());
         let mut sender = self.sender.write().await;
         *sender = None;
-        let mut state = self.state.write().await;
-        *state = ConnectionState::Disconnected;
+        self.state.set(ConnectionState::Disconnected).await;
     }
 
     /// Send a message to the server
@@ -336,12 +384,12 @@ impl AcpClient {
         Ok(())
     }
 
-    /// Send a request and wait for response
+    /// Send a request; await the handle's `response()` or cancel it
     pub async fn request(
         &self,
         method: impl Into<String>,
         params: serde_json::Value,
-    ) -> Result<AcpMessage> {
+    ) -> Result<RequestHandle> {
         self.request_with_timeout(method, params, self.config.request_timeout)
             .await
     }
@@ -352,7 +400,7 @@ impl AcpClient {
         method: impl Into<String>,
         params: serde_json::Value,
         timeout: Duration,
-    ) -> Result<AcpMessage> {
+    ) -> Result<RequestHandle> {
         let id = uuid::Uuid::new_v4().to_string();
         let message = AcpMessage::request(&id, method, params);
 
@@ -364,19 +412,25 @@ impl AcpClient {
         }
 
         // Send the request
-        self.send(message).await?;
-
-        // Wait for response with timeout
-        match tokio::time::timeout(timeout, rx).await {
-            Ok(Ok(response)) => Ok(response),
-            Ok(Err(_)) => Err(anyhow!("Request channel closed")),
-            Err(_) => {
-                // Remove pending request on timeout
-                let mut pending = self.pending_requests.write().await;
-                pending.remove(&id);
-                Err(anyhow!("Request timeout"))
-            }
+        if let Err(e) = self.send(message).await {
+            self.pending_requests.write().await.remove(&id);
+            return Err(e);
         }
+
+        Ok(RequestHandle {
+            id,
+            response: rx,
+            timeout,
+            pending: self.pending_requests.clone(),
+        })
+    }
+
+    /// Cancel an in-flight request by id
+    ///
+    /// Whoever awaits its response gets an error, and a response that arrives
+    /// later is dropped. Returns false if the request already completed.
+    pub async fn cancel_request(&self, id: &str) -> bool {
+        self.pending_requests.write().await.remove(id).is_some()
     }
 
     /// Send a notification (no response expected)
@@ -389,6 +443,8 @@ impl AcpClient {
     pub async fn get_status(&self) -> Result<crate::message::StatusResponse> {
         let response = self
             .request(methods::GET_STATUS, serde_json::json!({}))
+            .await?
+            .response()
             .await?;
 
         if let Some(result) = response.result {
@@ -408,6 +464,8 @@ impl AcpClient {
 
         let response = self
             .request(methods::HEARTBEAT, serde_json::to_value(params)?)
+            .await?
+            .response()
             .await?;
 
         if let Some(result) = response.result {
@@ -420,6 +478,60 @@ impl AcpClient {
     }
 }
 
+/// An in-flight request
+pub struct RequestHandle {
+    id: String,
+    response: oneshot::Receiver<AcpMessage>,
+    timeout: Duration,
+    pending: Arc<RwLock<HashMap<String, PendingRequest>>>,
+}
+
+impl RequestHandle {
+    /// JSON-RPC id of the request, for [`AcpClient::cancel_request`]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Wait for the response, up to the request timeout
+    pub async fn response(self) -> Result<AcpMessage> {
+        match tokio::time::timeout(self.timeout, self.response).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(anyhow!("Request cancelled")),
+            Err(_) => {
+                // Remove pending request on timeout
+                self.pending.write().await.remove(&self.id);
+                Err(anyhow!("Request timeout"))
+            }
+        }
+    }
+
+    /// Stop waiting for the response and forget the request
+    pub async fn cancel(self) {
+        self.pending.write().await.remove(&self.id);
+    }
+}
+
+/// Hand a response to its pending request
+///
+/// Returns the message if it should be forwarded to the message channel.
+/// Responses nobody waits for any more (cancelled or timed out) are dropped.
+async fn route_response(
+    pending: &RwLock<HashMap<String, PendingRequest>>,
+    message: AcpMessage,
+) -> Option<AcpMessage> {
+    let (Some(id), None) = (message.id.as_ref(), message.method.as_ref()) else {
+        return Some(message);
+    };
+
+    match pending.write().await.remove(id) {
+        Some(req) => {
+            let _ = req.sender.send(message);
+        }
+        None => debug!("Dropping response to abandoned request {}", id),
+    }
+    None
+}
+
 /// Generate random jitter for reconnection backoff (0-500ms)
 fn rand_jitter() -> u64 {
     use std::time::SystemTime;
@@ -454,4 +566,76 @@ mod tests {
         assert_eq!(client.state().await, ConnectionState::Disconnected);
         assert!(!client.is_connected().await);
     }
+
+    #[tokio::test]
+    async fn test_cancelled_request_is_forgotten() {
+        let client = AcpClient::new(AgentId::new(), "ws://localhost:8581");
+        let (tx, mut outbound) = mpsc::channel(10);
+        *client.sender.write().await = Some(tx);
+
+        let handle = client.request(methods::GET_STATUS, serde_json::json!({})).await.unwrap();
+        let id = handle.id().to_string();
+        assert!(outbound.recv().await.is_some());
+        assert!(client.pending_requests.read().await.contains_key(&id));
+
+        handle.cancel().await;
+        assert!(client.pending_requests.read().await.is_empty());
+
+        // The late response neither reaches a waiter nor the message channel
+        let late = AcpMessage::response(id, serde_json::json!({}));
+        assert!(route_response(&client.pending_requests, late).await.is_none());
+
+        // Cancelling by id wakes up whoever is waiting
+        let handle = client.request(methods::GET_STATUS, serde_json::json!({})).await.unwrap();
+        assert!(client.cancel_request(handle.id()).await);
+        assert!(handle.response().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_state_change_callbacks_follow_reconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // Drop the first connection to force a reconnect, then hold the second
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            ws.close(None).await.unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let mut client = AcpClient::with_config(
+            AgentId::new(),
+            AcpClientConfig {
+                server_url: format!("ws://{addr}"),
+                reconnect_interval: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        client.on_state_change(Box::new(move |state| recorder.lock().unwrap().push(state)));
+        let _messages = client.connect().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while seen.lock().unwrap().len() < 5 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("state changes were not reported");
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ConnectionState::Connecting,
+                ConnectionState::Connected,
+                ConnectionState::Disconnected,
+                ConnectionState::Reconnecting,
+                ConnectionState::Connected,
+            ]
+        );
+        client.disconnect().await;
+    }
 }
@@ -31,12 +31,14 @@ pub mod client;
 pub mod message;
 pub mod server;
 
-pub use client::{AcpClient, AcpClientConfig, ConnectionState};
+pub use client::{
+    AcpClient, AcpClientConfig, ConnectionState, RequestHandle, StateChangeCallback,
+};
 pub use message::*;
 pub use server::{
     AcpAuthConfig, AcpServer, AgentConnection, ApiKeyMetadata, BackpressureConfig,
     BackpressureMetrics, BroadcastResult, ConnectionBackpressureInfo, DefaultHandler,
-    MessageHandler, SendResult, TaskResponse,
+    MessageHandler, SendResult, TaskResponse, DEFAULT_MAX_MESSAGE_SIZE, scope_grants,
 };
 
 // Re-export core ACP types
@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use cca_core::communication::AcpMessage;
 use cca_core::{AgentId, TaskId};
 
 /// ACP method names
@@ -13,7 +14,8 @@ pub mod methods {
     pub const HEARTBEAT: &str = "heartbeat";
     pub const TASK_ASSIGN: &str = "taskAssign";
     pub const TASK_RESULT: &str = "taskResult";
-    pub const TASK_PROGRESS: &str = "taskProgress";
+    /// Worker → daemon notification reporting progress on a `task.execute` request
+    pub const TASK_PROGRESS: &str = "task.progress";
     pub const BROADCAST: &str = "broadcast";
     pub const QUERY_AGENT: &str = "queryAgent";
     pub const REGISTER_AGENT: &str = "registerAgent";
@@ -22,6 +24,9 @@ pub mod methods {
 /// Parameters for sendMessage method
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendMessageParams {
+    /// Sending agent, set when the daemon relays a message between agents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
     pub to: String,
     pub content: String,
     #[serde(default)]
@@ -94,16 +99,31 @@ pub struct TaskResultParams {
     pub metadata: serde_json::Value,
 }
 
-/// Parameters for task progress updates
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Parameters for `task.progress` notifications
+///
+/// `task_id` is the API task id the daemon sent with `task.execute`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskProgressParams {
-    pub task_id: TaskId,
-    pub progress_pct: u8,
-    pub message: String,
-    #[serde(default)]
-    pub subtasks_completed: u32,
+    pub task_id: String,
+    /// Completion estimate, 0-100
+    pub percent: u8,
     #[serde(default)]
-    pub subtasks_total: u32,
+    pub message: String,
+}
+
+impl TaskProgressParams {
+    /// Extract progress from a `task.progress` notification
+    ///
+    /// Returns `None` for other methods or malformed params; `percent` is
+    /// capped at 100.
+    pub fn from_notification(message: &AcpMessage) -> Option<Self> {
+        if message.method.as_deref() != Some(methods::TASK_PROGRESS) {
+            return None;
+        }
+        let mut params: Self = serde_json::from_value(message.params.clone()?).ok()?;
+        params.percent = params.percent.min(100);
+        Some(params)
+    }
 }
 
 /// Parameters for broadcast messages
@@ -134,12 +154,29 @@ pub enum BroadcastType {
 pub struct RegisterAgentParams {
     pub agent_id: AgentId,
     pub role: String,
+    /// Stable ID of the logical worker, the same across its reconnects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<String>,
     #[serde(default)]
     pub capabilities: Vec<String>,
     #[serde(default)]
     pub metadata: serde_json::Value,
 }
 
+/// Capabilities a worker advertises in `agent.register`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentCapabilities {
+    /// Model the worker runs tasks with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Largest context the worker accepts, in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context: Option<u64>,
+    /// Tools the worker is allowed to use
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_tools: Vec<String>,
+}
+
 /// Response to agent registration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterAgentResponse {
@@ -170,3 +207,33 @@ pub enum AgentQueryType {
     /// Get agent workload
     Workload,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_progress_notification_parses() {
+        let message = AcpMessage::notification(
+            methods::TASK_PROGRESS,
+            serde_json::json!({ "task_id": "task-1", "percent": 140, "message": "Running tests" }),
+        );
+
+        let progress = TaskProgressParams::from_notification(&message).unwrap();
+        assert_eq!(progress.task_id, "task-1");
+        assert_eq!(progress.percent, 100);
+        assert_eq!(progress.message, "Running tests");
+    }
+
+    #[test]
+    fn test_task_progress_ignores_other_and_malformed_messages() {
+        let heartbeat = AcpMessage::notification(methods::HEARTBEAT, serde_json::json!({ "timestamp": 0 }));
+        assert!(TaskProgressParams::from_notification(&heartbeat).is_none());
+
+        let malformed = AcpMessage::notification(
+            methods::TASK_PROGRESS,
+            serde_json::json!({ "task_id": "task-1", "percent": "half" }),
+        );
+        assert!(TaskProgressParams::from_notification(&malformed).is_none());
+    }
+}
@@ -19,22 +19,24 @@ use anyhow::Result;
 use cca_core::util::constant_time_eq;
 use serde::{Deserialize, Serialize};
 use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 use tokio::time::interval;
 use tokio_tungstenite::{
-    accept_hdr_async,
+    accept_hdr_async_with_config,
     tungstenite::{
         handshake::server::{Request, Response},
-        Message,
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+        Error as WsError, Message,
     },
 };
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 use cca_core::communication::{AcpError, AcpMessage};
 use cca_core::AgentId;
 
-use crate::message::{methods, HeartbeatParams, HeartbeatResponse};
+use crate::message::{methods, AgentCapabilities, CancelTaskParams, HeartbeatParams, HeartbeatResponse};
 
 /// Metadata for an API key including permissions
 #[derive(Debug, Clone, Default)]
@@ -45,6 +47,47 @@ pub struct ApiKeyMetadata {
     pub allowed_roles: Vec<String>,
     /// Optional identifier for this key (for logging)
     pub key_id: Option<String>,
+    /// HTTP API scopes such as `tasks:write` (empty = all scopes for backwards compat)
+    pub allowed_scopes: Vec<String>,
+}
+
+impl ApiKeyMetadata {
+    /// Whether this key may call endpoints requiring `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.allowed_scopes.is_empty() || self.allowed_scopes.iter().any(|s| scope_grants(s, scope))
+    }
+}
+
+/// Whether a granted scope satisfies a required one
+///
+/// Scopes are `resource:level` with levels ordered `read` < `write` < `admin`,
+/// so `tasks:write` also grants `tasks:read`. `*` grants everything and
+/// `resource:*` grants every level of that resource.
+pub fn scope_grants(granted: &str, required: &str) -> bool {
+    fn rank(level: &str) -> Option<u8> {
+        match level {
+            "read" => Some(0),
+            "write" => Some(1),
+            "admin" | "*" => Some(2),
+            _ => None,
+        }
+    }
+
+    if granted == "*" || granted == required {
+        return true;
+    }
+
+    let (Some((granted_resource, granted_level)), Some((required_resource, required_level))) =
+        (granted.split_once(':'), required.split_once(':'))
+    else {
+        return false;
+    };
+
+    granted_resource == required_resource
+        && matches!(
+            (rank(granted_level), rank(required_level)),
+            (Some(g), Some(r)) if g >= r
+        )
 }
 
 /// Authentication configuration for the ACP server
@@ -92,6 +135,9 @@ impl AcpAuthConfig {
     }
 }
 
+/// Default maximum size of an incoming WebSocket message or frame (16 MiB)
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 << 20;
+
 /// Configuration for backpressure handling
 #[derive(Debug, Clone)]
 pub struct BackpressureConfig {
@@ -229,6 +275,13 @@ pub struct AgentConnection {
     pub authenticated_key: Option<String>,
     /// Backpressure metrics for this connection
     pub backpressure: BackpressureMetrics,
+    /// Capabilities advertised at registration, if any
+    pub capabilities: Option<AgentCapabilities>,
+    /// Stable worker ID supplied at registration, if any
+    ///
+    /// `agent_id` is new for every connection; this stays the same when the
+    /// same logical worker reconnects.
+    pub worker_id: Option<String>,
 }
 
 impl AgentConnection {
@@ -244,6 +297,8 @@ impl AgentConnection {
             authenticated: false, // Must authenticate if auth is required
             authenticated_key: None,
             backpressure: BackpressureMetrics::default(),
+            capabilities: None,
+            worker_id: None,
         }
     }
 
@@ -314,6 +369,8 @@ impl AgentConnection {
 struct PendingRequest {
     sender: oneshot::Sender<AcpMessage>,
     created_at: std::time::Instant,
+    /// `acp_request` span of the caller, so the response is logged alongside it
+    span: tracing::Span,
 }
 
 /// ACP WebSocket server
@@ -328,6 +385,8 @@ pub struct AcpServer {
     auth_config: AcpAuthConfig,
     /// Backpressure configuration
     backpressure_config: BackpressureConfig,
+    /// Largest incoming message or frame accepted, in bytes
+    max_message_size: usize,
 }
 
 /// Handler for incoming ACP messages
@@ -357,7 +416,48 @@ impl DefaultHandler {
     async fn handle_register(&self, from: AgentId, params: Option<&serde_json::Value>) -> Option<serde_json::Value> {
         if let Some(params) = params {
             if let Some(role) = params.get("role").and_then(|r| r.as_str()) {
+                let capabilities = match parse_capabilities(params.get("capabilities")) {
+                    Ok(capabilities) => capabilities,
+                    Err(e) => {
+                        return Some(serde_json::json!({
+                            "success": false,
+                            "error": format!("Invalid capabilities: {e}")
+                        }));
+                    }
+                };
+                let worker_id = match parse_worker_id(params.get("worker_id")) {
+                    Ok(worker_id) => worker_id,
+                    Err(e) => {
+                        return Some(serde_json::json!({
+                            "success": false,
+                            "error": format!("Invalid worker_id: {e}")
+                        }));
+                    }
+                };
+
                 let mut conns = self.connections.write().await;
+
+                // A worker that reconnects before its old connection is dropped
+                // replaces it, so each worker ID has one live connection. Only
+                // the same credentials may take over a worker ID.
+                let mut stale = Vec::new();
+                if let Some(worker_id) = &worker_id {
+                    let key = conns.get(&from).and_then(|c| c.authenticated_key.as_ref());
+                    for (id, conn) in conns.iter() {
+                        if *id == from || conn.worker_id.as_ref() != Some(worker_id) {
+                            continue;
+                        }
+                        if conn.authenticated_key.as_ref() != key {
+                            warn!("Agent {} tried to take over worker ID {} held by {}", from, worker_id, id);
+                            return Some(serde_json::json!({
+                                "success": false,
+                                "error": "Invalid worker_id: already in use"
+                            }));
+                        }
+                        stale.push(*id);
+                    }
+                }
+
                 if let Some(conn) = conns.get_mut(&from) {
                     // SECURITY: Check role authorization if authentication is required
                     if self.auth_config.require_auth {
@@ -398,13 +498,29 @@ impl DefaultHandler {
                     }
 
                     conn.role = Some(role.to_string());
-                    info!("Agent {} registered with role: {}", from, role);
+                    conn.capabilities = capabilities;
+                    conn.worker_id = worker_id.clone();
+                    match &worker_id {
+                        Some(worker_id) => info!(
+                            "Agent {} registered with role: {} (worker: {})",
+                            from, role, worker_id
+                        ),
+                        None => info!("Agent {} registered with role: {}", from, role),
+                    }
+                    for id in stale {
+                        conns.remove(&id);
+                        info!("Dropped stale connection {} of reconnected worker", id);
+                    }
                 }
-                return Some(serde_json::json!({
+                let mut response = serde_json::json!({
                     "success": true,
                     "agent_id": from.to_string(),
                     "role": role
-                }));
+                });
+                if let Some(worker_id) = worker_id {
+                    response["worker_id"] = worker_id.into();
+                }
+                return Some(response);
             }
         }
         Some(serde_json::json!({
@@ -414,6 +530,42 @@ impl DefaultHandler {
     }
 }
 
+/// Parse the `capabilities` registration param
+///
+/// Older workers send a plain list of protocol features there; that form
+/// carries no capability details and is treated as absent.
+fn parse_capabilities(value: Option<&serde_json::Value>) -> serde_json::Result<Option<AgentCapabilities>> {
+    match value {
+        Some(value @ serde_json::Value::Object(_)) => serde_json::from_value(value.clone()).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Longest accepted `worker_id`
+const MAX_WORKER_ID_LEN: usize = 128;
+
+/// Parse the optional `worker_id` registration param
+///
+/// Worker IDs show up in logs and metrics labels, so they're limited to
+/// ASCII letters, digits, `-`, `_`, `.` and `:`.
+fn parse_worker_id(value: Option<&serde_json::Value>) -> Result<Option<String>, String> {
+    let worker_id = match value {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(serde_json::Value::String(worker_id)) => worker_id,
+        Some(_) => return Err("must be a string".to_string()),
+    };
+    if worker_id.is_empty() || worker_id.len() > MAX_WORKER_ID_LEN {
+        return Err(format!("must be 1 to {MAX_WORKER_ID_LEN} characters"));
+    }
+    if !worker_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+    {
+        return Err("may only contain letters, digits, '-', '_', '.' and ':'".to_string());
+    }
+    Ok(Some(worker_id.clone()))
+}
+
 #[async_trait::async_trait]
 impl MessageHandler for DefaultHandler {
     async fn handle(&self, from: AgentId, message: AcpMessage) -> Option<AcpMessage> {
@@ -530,9 +682,19 @@ impl AcpServer {
             shutdown: shutdown_tx,
             auth_config,
             backpressure_config,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
         }
     }
 
+    /// Set the largest incoming message accepted, in bytes
+    ///
+    /// Connections that send anything larger are closed with code 1009
+    /// (message too big).
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
     /// Get the backpressure configuration
     pub fn backpressure_config(&self) -> &BackpressureConfig {
         &self.backpressure_config
@@ -590,6 +752,7 @@ impl AcpServer {
                             let broadcast_tx = self.broadcast_tx.clone();
                             let auth_config = self.auth_config.clone();
                             let backpressure_config = self.backpressure_config.clone();
+                            let max_message_size = self.max_message_size;
 
                             tokio::spawn(async move {
                                 if let Err(e) = handle_connection(
@@ -601,6 +764,7 @@ impl AcpServer {
                                     broadcast_tx,
                                     auth_config,
                                     backpressure_config,
+                                    max_message_size,
                                 ).await {
                                     error!("Connection error from {}: {}", addr, e);
                                 }
@@ -687,6 +851,9 @@ impl AcpServer {
     }
 
     /// Send a request to an agent and wait for response
+    ///
+    /// Runs in an `acp_request` span carrying the request id and method; the
+    /// worker sees the same id as the JSON-RPC `id`.
     pub async fn request(
         &self,
         agent_id: AgentId,
@@ -695,35 +862,44 @@ impl AcpServer {
         timeout: Duration,
     ) -> Result<AcpMessage> {
         let id = uuid::Uuid::new_v4().to_string();
+        let method = method.into();
+        let span = tracing::info_span!("acp_request", request_id = %id, method = %method, agent_id = %agent_id);
         let message = AcpMessage::request(&id, method, params);
 
-        // Create pending request
-        let (tx, rx) = oneshot::channel();
-        {
-            let mut pending = self.pending_requests.write().await;
-            pending.insert(
-                id.clone(),
-                PendingRequest {
-                    sender: tx,
-                    created_at: std::time::Instant::now(),
-                },
-            );
-        }
-
-        // Send the request
-        self.send_to(agent_id, message).await?;
-
-        // Wait for response with timeout
-        match tokio::time::timeout(timeout, rx).await {
-            Ok(Ok(response)) => Ok(response),
-            Ok(Err(_)) => Err(anyhow::anyhow!("Request channel closed")),
-            Err(_) => {
-                // Remove pending request on timeout
+        async {
+            // Create pending request
+            let (tx, rx) = oneshot::channel();
+            {
                 let mut pending = self.pending_requests.write().await;
-                pending.remove(&id);
-                Err(anyhow::anyhow!("Request timeout"))
+                pending.insert(
+                    id.clone(),
+                    PendingRequest {
+                        sender: tx,
+                        created_at: std::time::Instant::now(),
+                        span: tracing::Span::current(),
+                    },
+                );
+            }
+
+            // Send the request
+            debug!("Sending request");
+            self.send_to(agent_id, message).await?;
+
+            // Wait for response with timeout
+            match tokio::time::timeout(timeout, rx).await {
+                Ok(Ok(response)) => Ok(response),
+                Ok(Err(_)) => Err(anyhow::anyhow!("Request channel closed")),
+                Err(_) => {
+                    // Remove pending request on timeout
+                    let mut pending = self.pending_requests.write().await;
+                    pending.remove(&id);
+                    warn!("Request timed out after {:?}", timeout);
+                    Err(anyhow::anyhow!("Request timeout"))
+                }
             }
         }
+        .instrument(span)
+        .await
     }
 
     /// Broadcast a message to all connected agents with backpressure handling.
@@ -829,6 +1005,24 @@ impl AcpServer {
             .collect()
     }
 
+    /// Get all agents with their roles and advertised capabilities
+    pub async fn agents_with_capabilities(&self) -> Vec<(AgentId, Option<String>, Option<AgentCapabilities>)> {
+        let connections = self.connections.read().await;
+        connections
+            .values()
+            .map(|conn| (conn.agent_id, conn.role.clone(), conn.capabilities.clone()))
+            .collect()
+    }
+
+    /// Stable worker IDs of connections that registered one
+    pub async fn worker_ids(&self) -> HashMap<AgentId, String> {
+        let connections = self.connections.read().await;
+        connections
+            .values()
+            .filter_map(|conn| Some((conn.agent_id, conn.worker_id.clone()?)))
+            .collect()
+    }
+
     /// Register an agent with a role (called when agent sends register message)
     pub async fn register_agent_role(&self, agent_id: AgentId, role: &str) {
         let mut connections = self.connections.write().await;
@@ -851,16 +1045,24 @@ impl AcpServer {
 
     /// Send a task to an agent and wait for response
     /// Returns TaskResponse with output, tokens_used, and success status
+    ///
+    /// `request_id` is the originating API request's id, forwarded so the
+    /// worker's logs can be correlated with the daemon's. `task_id` is the
+    /// API task the work belongs to; workers quote it in `task.progress`.
     pub async fn send_task(
         &self,
         agent_id: AgentId,
+        task_id: Option<&str>,
         task: &str,
         context: Option<&str>,
+        request_id: Option<&str>,
         timeout: Duration,
     ) -> Result<TaskResponse> {
         let params = serde_json::json!({
+            "task_id": task_id,
             "task": task,
-            "context": context
+            "context": context,
+            "request_id": request_id
         });
 
         let response = self
@@ -900,6 +1102,20 @@ impl AcpServer {
 
         Err(anyhow::anyhow!("Invalid response from agent"))
     }
+
+    /// Tell an agent to stop working on a task (`task.cancel` notification)
+    ///
+    /// Best effort: returns false if the agent is gone or its queue is full.
+    pub async fn cancel_task(&self, agent_id: AgentId, task_id: &str) -> bool {
+        let params = CancelTaskParams {
+            task_id: task_id.to_string(),
+        };
+        let Ok(params) = serde_json::to_value(params) else {
+            return false;
+        };
+        self.send_to_best_effort(agent_id, AcpMessage::notification("task.cancel", params))
+            .await
+    }
 }
 
 /// Handle the agent.authenticate message
@@ -1065,6 +1281,29 @@ struct HandshakeAuthResult {
     api_key: Option<String>,
 }
 
+/// Hand a response to the request waiting for it, logging in the request's span
+async fn complete_pending_request(
+    pending_requests: &RwLock<HashMap<String, PendingRequest>>,
+    agent_id: AgentId,
+    message: &AcpMessage,
+) {
+    let (Some(id), None) = (message.id.as_ref(), message.method.as_ref()) else {
+        return;
+    };
+    let Some(req) = pending_requests.write().await.remove(id) else {
+        return;
+    };
+
+    let _entered = req.span.enter();
+    debug!(
+        "Response from {} after {:?} ({})",
+        agent_id,
+        req.created_at.elapsed(),
+        if message.error.is_some() { "error" } else { "ok" }
+    );
+    let _ = req.sender.send(message.clone());
+}
+
 async fn handle_connection(
     stream: TcpStream,
     addr: SocketAddr,
@@ -1074,6 +1313,7 @@ async fn handle_connection(
     broadcast_tx: broadcast::Sender<AcpMessage>,
     auth_config: AcpAuthConfig,
     backpressure_config: BackpressureConfig,
+    max_message_size: usize,
 ) -> Result<()> {
     // Track authentication state from handshake using Arc<Mutex>
     let auth_result = Arc::new(std::sync::Mutex::new(HandshakeAuthResult {
@@ -1085,7 +1325,15 @@ async fn handle_connection(
 
     // Use accept_hdr_async to access HTTP request headers during WebSocket handshake
     // SEC: Validate API key during handshake to prevent unauthenticated connections
-    let ws_stream = accept_hdr_async(stream, move |request: &Request, response: Response| {
+    // Oversized messages fail the read with a capacity error instead of being buffered
+    let ws_config = WebSocketConfig {
+        max_message_size: Some(max_message_size),
+        max_frame_size: Some(max_message_size),
+        ..Default::default()
+    };
+
+    #[allow(clippy::result_large_err)] // Error type is dictated by tungstenite's Callback trait
+    let ws_stream = accept_hdr_async_with_config(stream, move |request: &Request, response: Response| {
         debug!("WebSocket handshake from {}: {:?}", addr, request.uri());
 
         // If auth is not required, allow all connections
@@ -1113,7 +1361,7 @@ async fn handle_connection(
         // Allow connection but mark as unauthenticated
         // Worker must authenticate via agent.authenticate message
         Ok(response)
-    }).await?;
+    }, Some(ws_config)).await?;
 
     // Extract auth result after handshake
     let handshake_result = {
@@ -1159,14 +1407,30 @@ async fn handle_connection(
     // Notify handler of connection
     handler.on_connect(agent_id).await;
 
-    // Spawn write task
-    let write_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if write.send(Message::Text(msg)).await.is_err() {
-                break;
+    // Spawn write task; a frame on `close_tx` ends the connection with that
+    // close code and hands the sink back for the lingering close below
+    let (close_tx, mut close_rx) = oneshot::channel::<CloseFrame<'static>>();
+    let mut write_task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                // Cleanup drops the connection's sender right after requesting a
+                // close, so the close must win over the closed channel
+                biased;
+                frame = &mut close_rx => {
+                    let Ok(frame) = frame else { break None };
+                    let _ = write.send(Message::Close(Some(frame))).await;
+                    break Some(write);
+                }
+                msg = rx.recv() => {
+                    let Some(msg) = msg else { break None };
+                    if write.send(Message::Text(msg)).await.is_err() {
+                        break None;
+                    }
+                }
             }
         }
     });
+    let mut closing = false;
 
     // Handle incoming messages
     while let Some(msg) = read.next().await {
@@ -1176,6 +1440,32 @@ async fn handle_connection(
                     Ok(acp_msg) => {
                         debug!("Received from {}: {:?}", agent_id, acp_msg.method);
 
+                        // Reject malformed messages before they reach any handler
+                        if let Err(error) = acp_msg.validate() {
+                            warn!("Invalid message from {}: {}", agent_id, error.message);
+                            let error_response = AcpMessage {
+                                id: acp_msg.id.clone(),
+                                ..AcpMessage::error_response(String::new(), error)
+                            };
+                            let should_disconnect = {
+                                let mut conns = connections.write().await;
+                                if let Some(conn) = conns.get_mut(&agent_id) {
+                                    let json = serde_json::to_string(&error_response)?;
+                                    matches!(
+                                        conn.try_send_with_backpressure(json, backpressure_config.max_consecutive_drops),
+                                        SendResult::DisconnectSlowConsumer
+                                    )
+                                } else {
+                                    false
+                                }
+                            };
+                            if should_disconnect {
+                                warn!("Disconnecting slow consumer {} during error response", agent_id);
+                                break;
+                            }
+                            continue;
+                        }
+
                         // Handle authentication message
                         if acp_msg.method.as_deref() == Some("agent.authenticate") {
                             let response = handle_authenticate(
@@ -1239,14 +1529,7 @@ async fn handle_connection(
                         }
 
                         // Check if this is a response to a pending request
-                        if acp_msg.id.is_some() && acp_msg.method.is_none() {
-                            // This is a response
-                            let id = acp_msg.id.as_ref().unwrap();
-                            let mut pending = pending_requests.write().await;
-                            if let Some(req) = pending.remove(id) {
-                                let _ = req.sender.send(acp_msg.clone());
-                            }
-                        }
+                        complete_pending_request(&pending_requests, agent_id, &acp_msg).await;
 
                         // Broadcast to subscribers
                         let _ = broadcast_tx.send(acp_msg.clone());
@@ -1299,6 +1582,18 @@ async fn handle_connection(
                 info!("Agent {} disconnected (close frame)", agent_id);
                 break;
             }
+            Err(WsError::Capacity(e)) => {
+                warn!(
+                    "Closing connection from agent {}: {} (limit: {} bytes)",
+                    agent_id, e, max_message_size
+                );
+                let _ = close_tx.send(CloseFrame {
+                    code: CloseCode::Size,
+                    reason: "Message too big".into(),
+                });
+                closing = true;
+                break;
+            }
             Err(e) => {
                 error!("WebSocket error from {}: {}", agent_id, e);
                 break;
@@ -1314,6 +1609,21 @@ async fn handle_connection(
         conns.remove(&agent_id);
     }
 
+    // Dropping a socket with unread input resets it, and the peer may then lose
+    // the close frame. Finish our side and drain whatever it still sends first.
+    if closing {
+        if let Ok(Ok(Some(write))) = tokio::time::timeout(Duration::from_secs(1), &mut write_task).await {
+            if let Ok(mut ws_stream) = read.reunite(write) {
+                let socket = ws_stream.get_mut();
+                let _ = tokio::time::timeout(Duration::from_secs(1), async {
+                    let _ = socket.shutdown().await;
+                    let mut buf = [0u8; 4096];
+                    while matches!(socket.read(&mut buf).await, Ok(n) if n > 0) {}
+                })
+                .await;
+            }
+        }
+    }
     write_task.abort();
 
     Ok(())
@@ -1335,6 +1645,171 @@ mod tests {
         assert!(conn.authenticated_key.is_none());
     }
 
+    fn handler_with_connection() -> (DefaultHandler, AgentId) {
+        let (tx, _rx) = mpsc::channel(10);
+        let agent_id = AgentId::new();
+        let connections = Arc::new(RwLock::new(HashMap::from([(
+            agent_id,
+            AgentConnection::new(agent_id, tx),
+        )])));
+        (DefaultHandler::new(connections, AcpAuthConfig::default()), agent_id)
+    }
+
+    #[tokio::test]
+    async fn test_register_with_capabilities() {
+        let (handler, agent_id) = handler_with_connection();
+        let params = serde_json::json!({
+            "role": "backend",
+            "capabilities": {
+                "model": "claude-sonnet-4",
+                "max_context": 200_000,
+                "supported_tools": ["Read", "Grep"]
+            }
+        });
+
+        let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+        assert_eq!(result["success"], true);
+
+        let conns = handler.connections.read().await;
+        let conn = &conns[&agent_id];
+        assert_eq!(conn.role.as_deref(), Some("backend"));
+        assert_eq!(
+            conn.capabilities,
+            Some(AgentCapabilities {
+                model: Some("claude-sonnet-4".to_string()),
+                max_context: Some(200_000),
+                supported_tools: vec!["Read".to_string(), "Grep".to_string()],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_without_capabilities() {
+        let (handler, agent_id) = handler_with_connection();
+
+        // Omitted, and the legacy list form, both leave capabilities unset
+        for params in [
+            serde_json::json!({ "role": "qa" }),
+            serde_json::json!({ "role": "qa", "capabilities": ["execute_task"] }),
+        ] {
+            let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+            assert_eq!(result["success"], true);
+            let conns = handler.connections.read().await;
+            assert_eq!(conns[&agent_id].role.as_deref(), Some("qa"));
+            assert!(conns[&agent_id].capabilities.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_malformed_capabilities() {
+        let (handler, agent_id) = handler_with_connection();
+        let params = serde_json::json!({
+            "role": "qa",
+            "capabilities": { "max_context": "lots" }
+        });
+
+        let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+        assert_eq!(result["success"], false);
+        assert!(handler.connections.read().await[&agent_id].role.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_with_worker_id() {
+        let (handler, agent_id) = handler_with_connection();
+        let params = serde_json::json!({ "role": "backend", "worker_id": "host-1:backend" });
+
+        let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+        assert_eq!(result["success"], true);
+        assert_eq!(result["worker_id"], "host-1:backend");
+        assert_eq!(
+            handler.connections.read().await[&agent_id].worker_id.as_deref(),
+            Some("host-1:backend")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_without_worker_id() {
+        let (handler, agent_id) = handler_with_connection();
+        let params = serde_json::json!({ "role": "backend" });
+
+        let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+        assert_eq!(result["success"], true);
+        assert!(result.get("worker_id").is_none());
+        assert!(handler.connections.read().await[&agent_id].worker_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_worker_id() {
+        let (handler, agent_id) = handler_with_connection();
+        let too_long = "w".repeat(MAX_WORKER_ID_LEN + 1);
+
+        for worker_id in [
+            serde_json::json!(""),
+            serde_json::json!(42),
+            serde_json::json!("has space"),
+            serde_json::json!(too_long),
+        ] {
+            let params = serde_json::json!({ "role": "qa", "worker_id": worker_id });
+            let result = handler.handle_register(agent_id, Some(&params)).await.unwrap();
+            assert_eq!(result["success"], false);
+        }
+        assert!(handler.connections.read().await[&agent_id].role.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reconnected_worker_keeps_worker_id() {
+        let server = AcpServer::new("127.0.0.1:0".parse().unwrap());
+        let handler = DefaultHandler::new(server.connections.clone(), AcpAuthConfig::default());
+        let params = serde_json::json!({ "role": "backend", "worker_id": "worker-7" });
+
+        // Each connection gets a fresh agent ID; the worker ID ties them
+        // together and the newer connection replaces the older one
+        let mut agent_ids = Vec::new();
+        for _ in 0..2 {
+            let (tx, _rx) = mpsc::channel(10);
+            let agent_id = AgentId::new();
+            server
+                .connections
+                .write()
+                .await
+                .insert(agent_id, AgentConnection::new(agent_id, tx));
+            handler.handle_register(agent_id, Some(&params)).await.unwrap();
+            agent_ids.push(agent_id);
+        }
+        assert_ne!(agent_ids[0], agent_ids[1]);
+
+        let worker_ids = server.worker_ids().await;
+        assert_eq!(worker_ids.len(), 1);
+        assert_eq!(worker_ids[&agent_ids[1]], "worker-7");
+        assert!(!server.connections.read().await.contains_key(&agent_ids[0]));
+    }
+
+    #[tokio::test]
+    async fn test_worker_id_held_by_other_credentials_is_rejected() {
+        let server = AcpServer::new("127.0.0.1:0".parse().unwrap());
+        let handler = DefaultHandler::new(server.connections.clone(), AcpAuthConfig::default());
+        let params = serde_json::json!({ "role": "backend", "worker_id": "worker-7" });
+
+        let mut agent_ids = Vec::new();
+        for key in ["key-a", "key-b"] {
+            let (tx, _rx) = mpsc::channel(10);
+            let agent_id = AgentId::new();
+            let mut conn = AgentConnection::new(agent_id, tx);
+            conn.set_authenticated(Some(key.to_string()));
+            server.connections.write().await.insert(agent_id, conn);
+            agent_ids.push(agent_id);
+        }
+
+        let first = handler.handle_register(agent_ids[0], Some(&params)).await.unwrap();
+        assert_eq!(first["success"], true);
+        let second = handler.handle_register(agent_ids[1], Some(&params)).await.unwrap();
+        assert_eq!(second["success"], false);
+
+        let conns = server.connections.read().await;
+        assert_eq!(conns[&agent_ids[0]].worker_id.as_deref(), Some("worker-7"));
+        assert!(conns[&agent_ids[1]].role.is_none());
+    }
+
     #[test]
     fn test_agent_connection_authentication() {
         let (tx, _rx) = mpsc::channel(10);
@@ -1378,11 +1853,13 @@ mod tests {
                     key: "backend-key".to_string(),
                     allowed_roles: vec!["backend".to_string(), "worker".to_string()],
                     key_id: Some("backend-agent".to_string()),
+                    allowed_scopes: vec![],
                 },
                 ApiKeyMetadata {
                     key: "admin-key".to_string(),
                     allowed_roles: vec![], // Empty = all roles allowed
                     key_id: Some("admin-agent".to_string()),
+                    allowed_scopes: vec![],
                 },
             ],
             require_auth: true,
@@ -1403,6 +1880,40 @@ mod tests {
         assert!(!config.is_role_authorized("unknown-key", "backend"));
     }
 
+    #[test]
+    fn test_scope_grants() {
+        assert!(scope_grants("tasks:read", "tasks:read"));
+        assert!(scope_grants("tasks:write", "tasks:read"));
+        assert!(scope_grants("rl:admin", "rl:write"));
+        assert!(scope_grants("rl:*", "rl:admin"));
+        assert!(scope_grants("*", "config:admin"));
+
+        assert!(!scope_grants("tasks:read", "tasks:write"));
+        assert!(!scope_grants("rl:write", "rl:admin"));
+        assert!(!scope_grants("memory:admin", "tasks:read"));
+        assert!(!scope_grants("tasks", "tasks:read"));
+        assert!(!scope_grants("tasks:bogus", "tasks:read"));
+    }
+
+    #[test]
+    fn test_api_key_has_scope() {
+        let read_only = ApiKeyMetadata {
+            key: "reader".to_string(),
+            allowed_scopes: vec!["tasks:read".to_string(), "memory:read".to_string()],
+            ..Default::default()
+        };
+        assert!(read_only.has_scope("tasks:read"));
+        assert!(!read_only.has_scope("tasks:write"));
+        assert!(!read_only.has_scope("rl:admin"));
+
+        // No scopes configured = unrestricted (backwards compat)
+        let unrestricted = ApiKeyMetadata {
+            key: "legacy".to_string(),
+            ..Default::default()
+        };
+        assert!(unrestricted.has_scope("rl:admin"));
+    }
+
     #[test]
     fn test_get_key_id() {
         let config = AcpAuthConfig {
@@ -1411,6 +1922,7 @@ mod tests {
                 key: "tracked-key".to_string(),
                 allowed_roles: vec![],
                 key_id: Some("my-agent-id".to_string()),
+                allowed_scopes: vec![],
             }],
             require_auth: true,
         };
@@ -1471,6 +1983,7 @@ mod tests {
                 key: "metadata-key-456".to_string(),
                 allowed_roles: vec!["worker".to_string()],
                 key_id: Some("worker-1".to_string()),
+                allowed_scopes: vec![],
             }],
             require_auth: true,
         };
@@ -1520,6 +2033,146 @@ mod tests {
         assert!((config.warning_threshold - 0.8).abs() < f32::EPSILON);
     }
 
+    /// Log output captured by a test subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_span_carries_request_id() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let server = AcpServer::new("127.0.0.1:0".parse().unwrap());
+        let agent_id = AgentId::new();
+        let (tx, mut outbound) = mpsc::channel(10);
+        server
+            .connections
+            .write()
+            .await
+            .insert(agent_id, AgentConnection::new(agent_id, tx));
+
+        let worker = async {
+            let request: AcpMessage = serde_json::from_str(&outbound.recv().await.unwrap()).unwrap();
+            let id = request.id.unwrap();
+            let response = AcpMessage::response(&id, serde_json::json!({ "success": true }));
+            complete_pending_request(&server.pending_requests, agent_id, &response).await;
+            id
+        };
+        let (response, id) = tokio::join!(
+            server.request(agent_id, "task.execute", serde_json::json!({}), Duration::from_secs(5)),
+            worker
+        );
+        assert!(response.is_ok());
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let response_line = logs
+            .lines()
+            .find(|line| line.contains("Response from"))
+            .expect("response was not logged");
+        assert!(response_line.contains(&format!("request_id={id}")));
+        assert!(response_line.contains("method=task.execute"));
+    }
+
+    /// Serve a single connection through `handle_connection` on a random port
+    async fn serve_one_connection(
+        max_message_size: usize,
+    ) -> (
+        SocketAddr,
+        Arc<RwLock<HashMap<AgentId, AgentConnection>>>,
+        tokio::task::JoinHandle<Result<()>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(RwLock::new(HashMap::new()));
+        let server = {
+            let connections = connections.clone();
+            tokio::spawn(async move {
+                let (stream, peer) = listener.accept().await.unwrap();
+                let (broadcast_tx, _) = broadcast::channel(16);
+                let handler = Arc::new(DefaultHandler::new(connections.clone(), AcpAuthConfig::default()));
+                handle_connection(
+                    stream,
+                    peer,
+                    connections,
+                    Arc::new(RwLock::new(HashMap::new())),
+                    handler,
+                    broadcast_tx,
+                    AcpAuthConfig::default(),
+                    BackpressureConfig::default(),
+                    max_message_size,
+                )
+                .await
+            })
+        };
+        (addr, connections, server)
+    }
+
+    #[tokio::test]
+    async fn test_invalid_message_gets_invalid_request_error() {
+        let (addr, _connections, _server) = serve_one_connection(DEFAULT_MAX_MESSAGE_SIZE).await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{addr}"))
+            .await
+            .unwrap();
+
+        let malformed = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "req-1",
+            "result": {},
+            "error": {"code": 1, "message": "both"}
+        });
+        ws.send(Message::Text(malformed.to_string())).await.unwrap();
+
+        let reply = loop {
+            match ws.next().await {
+                Some(Ok(Message::Text(text))) => break serde_json::from_str::<AcpMessage>(&text).unwrap(),
+                Some(Ok(_)) => continue,
+                other => panic!("expected an error response, got {other:?}"),
+            }
+        };
+        assert_eq!(reply.id.as_deref(), Some("req-1"));
+        assert_eq!(reply.error.unwrap().code, -32600);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_closes_connection() {
+        let (addr, connections, server) = serve_one_connection(1024).await;
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{addr}"))
+            .await
+            .unwrap();
+        ws.send(Message::Text("x".repeat(2048))).await.unwrap();
+
+        let frame = loop {
+            match ws.next().await {
+                Some(Ok(Message::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("expected a close frame, got {other:?}"),
+            }
+        };
+        assert_eq!(frame.unwrap().code, CloseCode::Size);
+        drop(ws);
+
+        assert!(server.await.unwrap().is_ok());
+        assert!(connections.read().await.is_empty());
+    }
+
     #[test]
     fn test_backpressure_metrics_default() {
         let metrics = BackpressureMetrics::default();
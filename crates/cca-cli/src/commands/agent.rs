@@ -1,14 +1,18 @@
 //! Agent management commands
 
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use cca_core::util::safe_truncate;
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use tokio::io::AsyncBufReadExt;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use uuid::Uuid;
 
 use super::http;
+use super::output::{print_json, response_json, OutputFormat};
 
 /// Get the daemon URL from environment or use default
 fn daemon_url() -> String {
@@ -37,12 +41,37 @@ pub enum AgentCommands {
         /// Task message to send
         message: String,
     },
+    /// Show an agent's recent log entries
+    Logs {
+        /// Agent ID (short or full) or role name
+        id: String,
+
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value = "50")]
+        lines: usize,
+
+        /// Minimum level to show (TRACE, DEBUG, INFO, WARN, ERROR)
+        #[arg(long)]
+        level: Option<String>,
+
+        /// Keep printing new entries as they are written (Ctrl-C to stop)
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Seconds between checks for new entries when following
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
     /// Run system diagnostics
     Diag,
     /// Run as an agent worker (connects via WebSocket)
     Worker {
         /// Agent role (coordinator, frontend, backend, dba, devops, security, qa)
         role: String,
+
+        /// Stable ID for this worker, kept across reconnects and restarts
+        #[arg(long, env = "CCA_WORKER_ID")]
+        worker_id: Option<String>,
     },
 }
 
@@ -57,13 +86,18 @@ async fn check_daemon() -> Result<()> {
     Ok(())
 }
 
-pub async fn run(cmd: AgentCommands) -> Result<()> {
+pub async fn run(cmd: AgentCommands, output: OutputFormat) -> Result<()> {
     match cmd {
-        AgentCommands::List => list().await,
-        AgentCommands::Stop { id } => stop(&id).await,
-        AgentCommands::Send { id, message } => send(&id, &message).await,
+        AgentCommands::List => list(output).await,
+        AgentCommands::Stop { id } => stop(&id, output).await,
+        AgentCommands::Send { id, message } => send(&id, &message, output).await,
+        AgentCommands::Logs { id, lines, level, follow, interval } => {
+            let interval = Duration::from_secs(interval.max(1));
+            logs(&id, lines, level.as_deref(), follow, interval, output).await
+        }
+        AgentCommands::Diag if output.is_json() => diag_json().await,
         AgentCommands::Diag => diag().await,
-        AgentCommands::Worker { role } => worker(&role).await,
+        AgentCommands::Worker { role, worker_id } => worker(&role, worker_id.as_deref()).await,
     }
 }
 
@@ -103,11 +137,14 @@ async fn resolve_worker_id(id_or_role: &str) -> Result<String> {
 }
 
 /// List connected agent workers
-async fn list() -> Result<()> {
+async fn list(output: OutputFormat) -> Result<()> {
     check_daemon().await?;
 
     let resp = http::get(&format!("{}/api/v1/acp/status", daemon_url())).await;
 
+    if output.is_json() {
+        return print_json(&response_json(resp.context("Failed to fetch workers")?).await);
+    }
     match resp {
         Ok(r) if r.status().is_success() => {
             let data: serde_json::Value = r.json().await?;
@@ -155,13 +192,15 @@ async fn list() -> Result<()> {
 }
 
 /// Stop/disconnect a worker
-async fn stop(id: &str) -> Result<()> {
+async fn stop(id: &str, output: OutputFormat) -> Result<()> {
     check_daemon().await?;
 
     let agent_id = resolve_worker_id(id).await?;
     let short_id = safe_truncate(&agent_id, 8);
 
-    println!("Disconnecting worker {short_id}...");
+    if !output.is_json() {
+        println!("Disconnecting worker {short_id}...");
+    }
 
     // Send disconnect request to daemon
     let resp = http::post_json(
@@ -171,6 +210,9 @@ async fn stop(id: &str) -> Result<()> {
     .await
     .context("Failed to send disconnect request")?;
 
+    if output.is_json() {
+        return print_json(&response_json(resp).await);
+    }
     if resp.status().is_success() {
         println!("Worker {short_id} disconnected successfully");
     } else {
@@ -183,13 +225,15 @@ async fn stop(id: &str) -> Result<()> {
 }
 
 /// Send a task to a specific worker
-async fn send(id: &str, message: &str) -> Result<()> {
+async fn send(id: &str, message: &str, output: OutputFormat) -> Result<()> {
     check_daemon().await?;
 
     let agent_id = resolve_worker_id(id).await?;
     let short_id = safe_truncate(&agent_id, 8);
 
-    println!("Sending task to worker {short_id}...");
+    if !output.is_json() {
+        println!("Sending task to worker {short_id}...");
+    }
 
     // Send task via daemon API
     let resp = http::post_json(
@@ -202,6 +246,9 @@ async fn send(id: &str, message: &str) -> Result<()> {
     .await
     .context("Failed to send task")?;
 
+    if output.is_json() {
+        return print_json(&response_json(resp).await);
+    }
     if resp.status().is_success() {
         let data: serde_json::Value = resp.json().await?;
         println!("\nResponse from worker {short_id}:");
@@ -221,6 +268,148 @@ async fn send(id: &str, message: &str) -> Result<()> {
     Ok(())
 }
 
+/// Resolve agent identifier (short ID, full ID, or role name) to a managed agent ID
+async fn resolve_agent_id(id_or_role: &str) -> Result<String> {
+    let resp = http::get(&format!("{}/api/v1/agents", daemon_url()))
+        .await
+        .context("Failed to fetch agents")?;
+
+    let data: serde_json::Value = resp.json().await?;
+
+    if let Some(agents) = data["agents"].as_array() {
+        for agent in agents {
+            let agent_id = agent["agent_id"].as_str().unwrap_or("");
+            let role = agent["role"].as_str().unwrap_or("");
+
+            if role.eq_ignore_ascii_case(id_or_role) || agent_id.starts_with(id_or_role) {
+                return Ok(agent_id.to_string());
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!("Agent '{id_or_role}' not found"))
+}
+
+/// A log entry as returned by `GET /api/v1/agents/:id/logs`
+#[derive(Debug, Deserialize, Serialize)]
+struct LogLine {
+    seq: u64,
+    timestamp: String,
+    level: String,
+    message: String,
+}
+
+/// One response from the logs endpoint
+#[derive(Debug, Deserialize)]
+struct LogPage {
+    #[serde(default)]
+    logs: Vec<LogLine>,
+    #[serde(default)]
+    cursor: u64,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Tracks which log entries have already been printed while following
+#[derive(Debug, Default)]
+struct LogCursor {
+    seq: u64,
+}
+
+impl LogCursor {
+    /// Keep only entries newer than anything seen so far, then move past the page
+    fn advance(&mut self, page: LogPage) -> Vec<LogLine> {
+        let new: Vec<LogLine> = page.logs.into_iter().filter(|line| line.seq > self.seq).collect();
+        let newest = new.last().map_or(0, |line| line.seq);
+        self.seq = self.seq.max(page.cursor).max(newest);
+        new
+    }
+}
+
+async fn fetch_logs(agent_id: &str, query: &str) -> Result<LogPage> {
+    let url = format!("{}/api/v1/agents/{agent_id}/logs?{query}", daemon_url());
+    let resp = http::get(&url).await.context("Failed to fetch logs")?;
+    if !resp.status().is_success() {
+        anyhow::bail!("Failed to fetch logs: HTTP {}", resp.status());
+    }
+    let page: LogPage = resp.json().await.context("Invalid logs response")?;
+    if let Some(error) = page.error {
+        anyhow::bail!(error);
+    }
+    Ok(page)
+}
+
+/// Print log entries, one JSON object per line in JSON mode
+fn print_log_lines(lines: &[LogLine], output: OutputFormat) -> Result<()> {
+    for line in lines {
+        if output.is_json() {
+            println!("{}", serde_json::to_string(line)?);
+        } else {
+            println!("{} [{:<5}] {}", line.timestamp, line.level, line.message);
+        }
+    }
+    Ok(())
+}
+
+/// Show an agent's logs, optionally following new entries until Ctrl-C
+async fn logs(
+    id: &str,
+    lines: usize,
+    level: Option<&str>,
+    follow: bool,
+    interval: Duration,
+    output: OutputFormat,
+) -> Result<()> {
+    check_daemon().await?;
+
+    let agent_id = resolve_agent_id(id).await?;
+    let level_query = level.map(|l| format!("&level={l}")).unwrap_or_default();
+
+    let mut cursor = LogCursor::default();
+    let page = fetch_logs(&agent_id, &format!("lines={lines}{level_query}")).await?;
+    print_log_lines(&cursor.advance(page), output)?;
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        let query = format!("since={}&lines={lines}{level_query}", cursor.seq);
+        let poll = async {
+            tokio::time::sleep(interval).await;
+            fetch_logs(&agent_id, &query).await
+        };
+        let page = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            page = poll => page?,
+        };
+        print_log_lines(&cursor.advance(page), output)?;
+    }
+}
+
+/// Run system diagnostics, printing each endpoint's raw response as one JSON object
+async fn diag_json() -> Result<()> {
+    let checks = [
+        ("health", "/api/v1/health"),
+        ("acp", "/api/v1/acp/status"),
+        ("redis", "/api/v1/redis/status"),
+        ("postgres", "/api/v1/postgres/status"),
+        ("rl", "/api/v1/rl/stats"),
+        ("tasks", "/api/v1/tasks?limit=5"),
+        ("workloads", "/api/v1/workloads"),
+    ];
+
+    let mut report = serde_json::Map::new();
+    for (name, path) in checks {
+        let result = match http::get(&format!("{}{path}", daemon_url())).await {
+            Ok(resp) => response_json(resp).await,
+            Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+        };
+        report.insert(name.to_string(), result);
+    }
+    print_json(&report)
+}
+
 /// Run system diagnostics
 async fn diag() -> Result<()> {
     println!("CCA System Diagnostics");
@@ -271,6 +460,9 @@ async fn diag() -> Result<()> {
                 println!("[WARN] not connected");
             }
         }
+        Ok(r) if r.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE => {
+            println!("[WARN] not connected");
+        }
         Ok(r) => println!("[SKIP] HTTP {}", r.status()),
         Err(e) => println!("[FAIL] {e}"),
     }
@@ -289,6 +481,9 @@ async fn diag() -> Result<()> {
                 println!("[WARN] not connected");
             }
         }
+        Ok(r) if r.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE => {
+            println!("[WARN] not connected");
+        }
         Ok(r) => println!("[SKIP] HTTP {}", r.status()),
         Err(e) => println!("[FAIL] {e}"),
     }
@@ -467,7 +662,7 @@ fn format_tool_action(tool_name: &str, input: Option<&serde_json::Value>) -> Str
 }
 
 /// Run as a persistent agent worker connected via WebSocket
-async fn worker(role: &str) -> Result<()> {
+async fn worker(role: &str, worker_id: Option<&str>) -> Result<()> {
     let agent_id = Uuid::new_v4();
     let ws_url = acp_url();
 
@@ -519,6 +714,7 @@ async fn worker(role: &str) -> Result<()> {
         "params": {
             "agent_id": agent_id.to_string(),
             "role": role,
+            "worker_id": worker_id,
             "capabilities": ["execute_task"]
         },
         "id": Uuid::new_v4().to_string()
@@ -563,9 +759,13 @@ async fn worker(role: &str) -> Result<()> {
                         let params = json.get("params").cloned().unwrap_or_default();
                         let task = params.get("task").and_then(|t| t.as_str()).unwrap_or("");
                         let context = params.get("context").and_then(|c| c.as_str());
+                        let trace_id = params.get("request_id").and_then(|r| r.as_str());
 
                         println!("\n{}", "=".repeat(60));
                         println!("[TASK] Request ID: {request_id}");
+                        if let Some(trace_id) = trace_id {
+                            println!("[TASK] X-Request-Id: {trace_id}");
+                        }
                         println!("[TASK] Task ({} chars):", task.len());
                         println!("{task}");
                         if let Some(ctx) = context {
@@ -675,7 +875,7 @@ Task: "#;
                         {
                             Ok(c) => c,
                             Err(e) => {
-                                println!("[FAIL] Failed to spawn claude: {e}");
+                                println!("[FAIL] Request {request_id}: failed to spawn claude: {e}");
                                 let response = serde_json::json!({
                                     "jsonrpc": "2.0",
                                     "error": {
@@ -715,10 +915,8 @@ Task: "#;
                                                 let elapsed = start_time.elapsed().as_secs();
 
                                                 match event.get("type").and_then(|t| t.as_str()) {
-                                                    Some("system") => {
-                                                        if event.get("subtype").and_then(|s| s.as_str()) == Some("init") {
-                                                            println!("  [{elapsed:>3}s] 🚀 Session initialized");
-                                                        }
+                                                    Some("system") if event.get("subtype").and_then(|s| s.as_str()) == Some("init") => {
+                                                        println!("  [{elapsed:>3}s] 🚀 Session initialized");
                                                     }
                                                     Some("user") => {
                                                         println!("  [{elapsed:>3}s] 📝 Processing task...");
@@ -852,7 +1050,7 @@ Task: "#;
                                 })
                             }
                             Ok(s) => {
-                                println!("[FAIL] Task failed (exit code: {:?})", s.code());
+                                println!("[FAIL] Request {request_id}: task failed (exit code: {:?})", s.code());
                                 println!("[STDERR] {stderr_output}");
                                 if !output.is_empty() {
                                     println!("[STDOUT] {output}");
@@ -868,7 +1066,7 @@ Task: "#;
                                 })
                             }
                             Err(e) => {
-                                println!("[FAIL] Failed to wait for claude: {e}");
+                                println!("[FAIL] Request {request_id}: failed to wait for claude: {e}");
                                 serde_json::json!({
                                     "jsonrpc": "2.0",
                                     "error": {
@@ -881,12 +1079,12 @@ Task: "#;
                             }
                         };
 
-                        println!("[SEND] Sending response via WebSocket...");
+                        println!("[SEND] Sending response for request {request_id} via WebSocket...");
                         if let Err(e) = write.send(Message::Text(response.to_string())).await
                         {
-                            eprintln!("[ERROR] Failed to send response: {e}");
+                            eprintln!("[ERROR] Failed to send response for request {request_id}: {e}");
                         } else {
-                            println!("[SEND] Response sent successfully");
+                            println!("[SEND] Response for request {request_id} sent");
                         }
                         println!("{}", "=".repeat(60));
                     } else if json.get("method").and_then(|m| m.as_str()) == Some("heartbeat") {
@@ -922,3 +1120,59 @@ Task: "#;
     println!("Worker stopped.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(seqs: &[u64], cursor: u64) -> LogPage {
+        LogPage {
+            logs: seqs
+                .iter()
+                .map(|&seq| LogLine {
+                    seq,
+                    timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+                    level: "INFO".to_string(),
+                    message: format!("line {seq}"),
+                })
+                .collect(),
+            cursor,
+            error: None,
+        }
+    }
+
+    fn seqs(lines: &[LogLine]) -> Vec<u64> {
+        lines.iter().map(|line| line.seq).collect()
+    }
+
+    #[test]
+    fn test_log_cursor_does_not_repeat_lines() {
+        let mut cursor = LogCursor::default();
+        assert_eq!(seqs(&cursor.advance(page(&[1, 2, 3], 3))), vec![1, 2, 3]);
+
+        // A poll that overlaps what was already printed only yields the new lines
+        assert_eq!(seqs(&cursor.advance(page(&[3, 4, 5], 5))), vec![4, 5]);
+
+        // Nothing new
+        assert!(cursor.advance(page(&[], 5)).is_empty());
+        assert_eq!(cursor.seq, 5);
+
+        // Entries hidden by a level filter still move the cursor forward
+        assert_eq!(seqs(&cursor.advance(page(&[8], 9))), vec![8]);
+        assert!(cursor.advance(page(&[8, 9], 9)).is_empty());
+    }
+
+    #[test]
+    fn test_log_page_parses_daemon_response() {
+        let page: LogPage = serde_json::from_value(serde_json::json!({
+            "agent_id": "a",
+            "logs": [{"seq": 7, "timestamp": "t", "level": "WARN", "message": "careful"}],
+            "cursor": 7
+        }))
+        .unwrap();
+        assert_eq!((page.logs[0].seq, page.cursor), (7, 7));
+
+        let page: LogPage = serde_json::from_value(serde_json::json!({"error": "Invalid agent ID: x"})).unwrap();
+        assert_eq!(page.error.as_deref(), Some("Invalid agent ID: x"));
+    }
+}
@@ -39,6 +39,8 @@ pub enum DaemonCommands {
         #[arg(short, long)]
         follow: bool,
     },
+    /// Check the daemon and the services it depends on
+    Doctor,
 }
 
 fn get_pid_file() -> PathBuf {
@@ -226,6 +228,7 @@ pub async fn run(cmd: DaemonCommands) -> Result<()> {
         DaemonCommands::Stop => stop().await,
         DaemonCommands::Status => status().await,
         DaemonCommands::Logs { lines, follow } => logs(lines, follow).await,
+        DaemonCommands::Doctor => doctor().await,
     }
 }
 
@@ -473,3 +476,221 @@ async fn logs(lines: usize, follow: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Outcome of a single `doctor` check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    /// Optional service missing; the daemon still works without it
+    Warn,
+    /// Critical service missing
+    Fail,
+}
+
+#[derive(Debug)]
+struct DoctorCheck {
+    name: &'static str,
+    status: CheckStatus,
+    detail: String,
+    /// What to do about a warning or failure
+    hint: Option<&'static str>,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint: (status != CheckStatus::Pass).then_some(hint),
+        }
+    }
+}
+
+/// Check the daemon and the services it depends on, exiting non-zero if a critical one is down
+async fn doctor() -> Result<()> {
+    let base_url = daemon_url();
+    println!("CCA Doctor ({base_url})");
+    println!("==========\n");
+
+    let checks = run_doctor_checks(&base_url).await;
+    for check in &checks {
+        let mark = match check.status {
+            CheckStatus::Pass => "[PASS]",
+            CheckStatus::Warn => "[WARN]",
+            CheckStatus::Fail => "[FAIL]",
+        };
+        println!("{mark} {:<12} {}", check.name, check.detail);
+        if let Some(hint) = check.hint {
+            println!("       {hint}");
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.status == CheckStatus::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} critical check(s) failed");
+    }
+    println!("\nAll critical services are up.");
+    Ok(())
+}
+
+async fn run_doctor_checks(base_url: &str) -> Vec<DoctorCheck> {
+    let health: serde_json::Value = match http::get(&format!("{base_url}/health")).await {
+        Ok(resp) if resp.status().is_success() => resp.json().await.unwrap_or_default(),
+        Ok(resp) => {
+            return vec![DoctorCheck::new(
+                "Daemon",
+                CheckStatus::Fail,
+                format!("health check returned HTTP {}", resp.status()),
+                "Check the daemon log with `cca daemon logs`",
+            )];
+        }
+        Err(_) => {
+            return vec![DoctorCheck::new(
+                "Daemon",
+                CheckStatus::Fail,
+                "not reachable",
+                "Daemon down: start it with `cca daemon start` or set CCA_DAEMON_URL",
+            )];
+        }
+    };
+
+    let mut checks = vec![DoctorCheck::new(
+        "Daemon",
+        CheckStatus::Pass,
+        format!(
+            "{} (version {})",
+            health["status"].as_str().unwrap_or("unknown"),
+            health["version"].as_str().unwrap_or("unknown")
+        ),
+        "",
+    )];
+
+    checks.push(
+        service_check(
+            base_url,
+            "Redis",
+            "/api/v1/redis/status",
+            "Redis down: start it or set CCA__REDIS__URL",
+            |data| format!("{} agents tracked", data["agents_tracked"].as_u64().unwrap_or(0)),
+        )
+        .await,
+    );
+    checks.push(
+        service_check(
+            base_url,
+            "PostgreSQL",
+            "/api/v1/postgres/status",
+            "Postgres down: start it or set CCA__POSTGRES__URL",
+            |data| format!("{} patterns stored", data["patterns_count"].as_i64().unwrap_or(0)),
+        )
+        .await,
+    );
+
+    // Embeddings are optional; memory search falls back to text matching without them
+    checks.push(if health["services"]["embeddings"].as_bool().unwrap_or(false) {
+        DoctorCheck::new("Embeddings", CheckStatus::Pass, "Ollama reachable", "")
+    } else {
+        DoctorCheck::new(
+            "Embeddings",
+            CheckStatus::Warn,
+            "unavailable, memory search uses text matching",
+            "Start Ollama or set CCA__EMBEDDINGS__ENABLED=true and CCA__EMBEDDINGS__OLLAMA_URL",
+        )
+    });
+
+    checks
+}
+
+/// Check a `{"connected": bool, ...}` status endpoint
+async fn service_check(
+    base_url: &str,
+    name: &'static str,
+    path: &str,
+    hint: &'static str,
+    detail: impl FnOnce(&serde_json::Value) -> String,
+) -> DoctorCheck {
+    // Unavailable services answer 503 with a JSON body, so read it regardless of status
+    let data: serde_json::Value = match http::get(&format!("{base_url}{path}")).await {
+        Ok(resp) => resp.json().await.unwrap_or_default(),
+        Err(e) => return DoctorCheck::new(name, CheckStatus::Fail, format!("status request failed: {e}"), hint),
+    };
+
+    if data["connected"].as_bool().unwrap_or(false) {
+        DoctorCheck::new(name, CheckStatus::Pass, detail(&data), hint)
+    } else {
+        let error = data["error"].as_str().unwrap_or("not connected");
+        DoctorCheck::new(name, CheckStatus::Fail, error, hint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{http::StatusCode, routing::get, Json, Router};
+
+    /// A daemon whose Redis and embeddings are down
+    async fn degraded_daemon() -> String {
+        let app = Router::new()
+            .route(
+                "/health",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "status": "degraded",
+                        "version": "0.3.0",
+                        "services": { "redis": false, "postgres": true, "acp": true, "embeddings": false }
+                    }))
+                }),
+            )
+            .route(
+                "/api/v1/redis/status",
+                get(|| async {
+                    (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        Json(serde_json::json!({ "connected": false, "error": "Redis not available" })),
+                    )
+                }),
+            )
+            .route(
+                "/api/v1/postgres/status",
+                get(|| async { Json(serde_json::json!({ "connected": true, "patterns_count": 12 })) }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_doctor_reports_degraded_services() {
+        let base_url = degraded_daemon().await;
+        let checks = run_doctor_checks(&base_url).await;
+
+        let summary: Vec<(&str, CheckStatus)> = checks.iter().map(|c| (c.name, c.status)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Daemon", CheckStatus::Pass),
+                ("Redis", CheckStatus::Fail),
+                ("PostgreSQL", CheckStatus::Pass),
+                ("Embeddings", CheckStatus::Warn),
+            ]
+        );
+        assert_eq!(checks[0].detail, "degraded (version 0.3.0)");
+        assert_eq!(checks[1].detail, "Redis not available");
+        assert!(checks[1].hint.unwrap().contains("CCA__REDIS__URL"));
+        assert_eq!(checks[2].detail, "12 patterns stored");
+        assert!(checks[2].hint.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_doctor_fails_when_daemon_unreachable() {
+        // Nothing listens on port 1
+        let checks = run_doctor_checks("http://127.0.0.1:1").await;
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert!(checks[0].hint.unwrap().contains("cca daemon start"));
+    }
+}
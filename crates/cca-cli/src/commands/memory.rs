@@ -7,6 +7,7 @@ use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 
 use super::http;
+use super::output::{json_unsupported, print_json, response_json, OutputFormat};
 
 fn daemon_url() -> String {
     std::env::var("CCA_DAEMON_URL").unwrap_or_else(|_| "http://127.0.0.1:8580".to_string())
@@ -31,6 +32,14 @@ pub enum MemoryCommands {
         /// Maximum results
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Minimum similarity score (0-1) for semantic matches
+        #[arg(short = 's', long, value_parser = parse_similarity)]
+        min_similarity: Option<f64>,
+
+        /// Only show patterns of this type (solution, error_recovery, refactoring, ...)
+        #[arg(short = 't', long = "type", value_name = "PATTERN_TYPE")]
+        pattern_type: Option<String>,
     },
     /// Show memory statistics
     Stats,
@@ -85,13 +94,30 @@ pub enum MemoryCommands {
     },
 }
 
-pub async fn run(cmd: MemoryCommands) -> Result<()> {
+pub async fn run(cmd: MemoryCommands, output: OutputFormat) -> Result<()> {
     match cmd {
+        MemoryCommands::Store { .. } if output.is_json() => json_unsupported("cca memory store"),
+        MemoryCommands::Stats if output.is_json() => json_unsupported("cca memory stats"),
+        MemoryCommands::Export { .. } if output.is_json() => json_unsupported("cca memory export"),
+        MemoryCommands::Import { .. } if output.is_json() => json_unsupported("cca memory import"),
         MemoryCommands::Store {
             pattern,
             pattern_type,
         } => store(&pattern, &pattern_type).await,
-        MemoryCommands::Search { query, limit } => search(&query, limit).await,
+        MemoryCommands::Search {
+            query,
+            limit,
+            min_similarity,
+            pattern_type,
+        } => {
+            let request = PatternSearchRequest {
+                query,
+                limit: limit as i32,
+                min_similarity,
+                pattern_type,
+            };
+            search(&request, output).await
+        }
         MemoryCommands::Stats => stats().await,
         MemoryCommands::Export { output } => export(&output).await,
         MemoryCommands::Import { input } => import(&input).await,
@@ -101,13 +127,13 @@ pub async fn run(cmd: MemoryCommands) -> Result<()> {
             exclude,
             batch_size,
             follow,
-        } => index(&path, &extensions, &exclude, batch_size, follow).await,
-        MemoryCommands::IndexStatus { job_id } => index_status(job_id.as_deref()).await,
+        } => index(&path, &extensions, &exclude, batch_size, follow, output).await,
+        MemoryCommands::IndexStatus { job_id } => index_status(job_id.as_deref(), output).await,
         MemoryCommands::CodeSearch {
             query,
             limit,
             language,
-        } => code_search(&query, limit, language.as_deref()).await,
+        } => code_search(&query, limit, language.as_deref(), output).await,
     }
 }
 
@@ -120,17 +146,40 @@ async fn store(pattern: &str, pattern_type: &str) -> Result<()> {
     Ok(())
 }
 
-async fn search(query: &str, limit: usize) -> Result<()> {
-    println!("Searching patterns: \"{query}\" (limit: {limit})\n");
+/// Parse `--min-similarity`, which must be between 0 and 1
+fn parse_similarity(value: &str) -> Result<f64, String> {
+    let similarity: f64 = value.parse().map_err(|_| format!("'{value}' is not a number"))?;
+    if (0.0..=1.0).contains(&similarity) {
+        Ok(similarity)
+    } else {
+        Err(format!("must be between 0 and 1, got {similarity}"))
+    }
+}
+
+/// Body of `POST /api/v1/memory/search`
+#[derive(Debug, Serialize)]
+struct PatternSearchRequest {
+    query: String,
+    limit: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_similarity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern_type: Option<String>,
+}
+
+async fn search(request: &PatternSearchRequest, output: OutputFormat) -> Result<()> {
+    if !output.is_json() {
+        println!("Searching patterns: \"{}\" (limit: {})\n", request.query, request.limit);
+    }
 
     let url = format!("{}/api/v1/memory/search", daemon_url());
-    let resp = http::post_json(&url, &serde_json::json!({
-        "query": query,
-        "limit": limit as i32
-    }))
-    .await
-    .context("Failed to search patterns")?;
+    let resp = http::post_json(&url, request)
+        .await
+        .context("Failed to search patterns")?;
 
+    if output.is_json() {
+        return print_json(&response_json(resp).await);
+    }
     if !resp.status().is_success() {
         println!("Error: HTTP {}", resp.status());
         return Ok(());
@@ -161,14 +210,18 @@ async fn search(query: &str, limit: usize) -> Result<()> {
             for p in patterns {
                 let id = p.get("id").and_then(|v| v.as_str()).unwrap_or("-");
                 let ptype = p.get("pattern_type").and_then(|v| v.as_str()).unwrap_or("-");
-                let score = p.get("similarity").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
+                // Text search results have no similarity score
+                let score = p
+                    .get("similarity")
+                    .and_then(serde_json::Value::as_f64)
+                    .map_or_else(|| "-".to_string(), |s| format!("{s:.2}"));
                 let content = p.get("content").and_then(|v| v.as_str()).unwrap_or("-");
                 let content_short = if content.len() > 27 {
                     format!("{}...", &content[..27])
                 } else {
                     content.to_string()
                 };
-                println!("{id:<36} {ptype:<12} {score:<10.2} {content_short:<30}");
+                println!("{id:<36} {ptype:<12} {score:<10} {content_short:<30}");
             }
         }
     }
@@ -262,13 +315,16 @@ async fn index(
     exclude: &str,
     batch_size: usize,
     follow: bool,
+    output: OutputFormat,
 ) -> Result<()> {
     // Canonicalize path
     let abs_path = std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
 
-    println!("Indexing codebase: {}", abs_path.display());
-    println!("Extensions: {extensions}");
-    println!("Excluding: {exclude}");
+    if !output.is_json() {
+        println!("Indexing codebase: {}", abs_path.display());
+        println!("Extensions: {extensions}");
+        println!("Excluding: {exclude}");
+    }
 
     let ext_list: Vec<String> = extensions
         .split(',')
@@ -291,6 +347,13 @@ async fn index(
         .await
         .context("Failed to start indexing")?;
 
+    if output.is_json() {
+        let data = response_json(resp).await;
+        return match data["job_id"].as_str() {
+            Some(job_id) if follow => print_json(&wait_for_index_job(job_id).await?),
+            _ => print_json(&data),
+        };
+    }
     if !resp.status().is_success() {
         println!("Error: HTTP {}", resp.status());
         return Ok(());
@@ -356,12 +419,36 @@ async fn index(
     Ok(())
 }
 
-async fn index_status(job_id: Option<&str>) -> Result<()> {
+/// Poll an indexing job until it finishes, returning its final status response
+async fn wait_for_index_job(job_id: &str) -> Result<serde_json::Value> {
+    let status_url = format!("{}/api/v1/memory/index/{}", daemon_url(), job_id);
+    loop {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let resp = http::get(&status_url)
+            .await
+            .context("Failed to get job status")?;
+        if !resp.status().is_success() {
+            return Ok(response_json(resp).await);
+        }
+
+        let data: serde_json::Value = resp.json().await?;
+        let status = data["job"]["status"].as_str().unwrap_or_default();
+        if matches!(status, "completed" | "failed" | "cancelled") || data["job"].is_null() {
+            return Ok(data);
+        }
+    }
+}
+
+async fn index_status(job_id: Option<&str>, output: OutputFormat) -> Result<()> {
     if let Some(id) = job_id {
         // Get specific job status
         let url = format!("{}/api/v1/memory/index/{}", daemon_url(), id);
         let resp = http::get(&url).await.context("Failed to get job status")?;
 
+        if output.is_json() {
+            return print_json(&response_json(resp).await);
+        }
         if !resp.status().is_success() {
             println!("Error: HTTP {}", resp.status());
             return Ok(());
@@ -400,6 +487,9 @@ async fn index_status(job_id: Option<&str>) -> Result<()> {
         let url = format!("{}/api/v1/memory/index/jobs", daemon_url());
         let resp = http::get(&url).await.context("Failed to list jobs")?;
 
+        if output.is_json() {
+            return print_json(&response_json(resp).await);
+        }
         if !resp.status().is_success() {
             println!("Error: HTTP {}", resp.status());
             return Ok(());
@@ -448,8 +538,10 @@ struct CodeSearchRequest {
     language: Option<String>,
 }
 
-async fn code_search(query: &str, limit: usize, language: Option<&str>) -> Result<()> {
-    println!("Searching code: \"{query}\"\n");
+async fn code_search(query: &str, limit: usize, language: Option<&str>, output: OutputFormat) -> Result<()> {
+    if !output.is_json() {
+        println!("Searching code: \"{query}\"\n");
+    }
 
     let request = CodeSearchRequest {
         query: query.to_string(),
@@ -462,6 +554,9 @@ async fn code_search(query: &str, limit: usize, language: Option<&str>) -> Resul
         .await
         .context("Failed to search code")?;
 
+    if output.is_json() {
+        return print_json(&response_json(resp).await);
+    }
     if !resp.status().is_success() {
         println!("Error: HTTP {}", resp.status());
         return Ok(());
@@ -516,3 +611,65 @@ async fn code_search(query: &str, limit: usize, language: Option<&str>) -> Resul
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(subcommand)]
+        command: MemoryCommands,
+    }
+
+    fn parse(args: &[&str]) -> Result<MemoryCommands, clap::Error> {
+        TestCli::try_parse_from(["cca"].iter().chain(args)).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_search_parses_filters() {
+        let MemoryCommands::Search { query, limit, min_similarity, pattern_type } = parse(&[
+            "search", "retry with backoff", "--limit", "5", "--min-similarity", "0.75", "--type", "error_recovery",
+        ])
+        .unwrap() else {
+            panic!("expected search command");
+        };
+        assert_eq!(query, "retry with backoff");
+        assert_eq!(limit, 5);
+        assert_eq!(min_similarity, Some(0.75));
+        assert_eq!(pattern_type.as_deref(), Some("error_recovery"));
+
+        let MemoryCommands::Search { limit, min_similarity, pattern_type, .. } = parse(&["search", "auth"]).unwrap() else {
+            panic!("expected search command");
+        };
+        assert_eq!((limit, min_similarity, pattern_type), (10, None, None));
+    }
+
+    #[test]
+    fn test_search_rejects_out_of_range_similarity() {
+        for value in ["1.5", "-0.1", "high"] {
+            assert!(parse(&["search", "auth", "--min-similarity", value]).is_err(), "{value}");
+        }
+        assert!(parse(&["search", "auth", "--min-similarity", "0"]).is_ok());
+        assert!(parse(&["search", "auth", "--min-similarity", "1"]).is_ok());
+    }
+
+    #[test]
+    fn test_search_request_body() {
+        let request = PatternSearchRequest {
+            query: "auth".to_string(),
+            limit: 5,
+            min_similarity: Some(0.5),
+            pattern_type: Some("solution".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({ "query": "auth", "limit": 5, "min_similarity": 0.5, "pattern_type": "solution" })
+        );
+
+        // Unset filters are left out so the daemon applies its defaults
+        let request = PatternSearchRequest { min_similarity: None, pattern_type: None, ..request };
+        assert_eq!(serde_json::to_value(&request).unwrap(), serde_json::json!({ "query": "auth", "limit": 5 }));
+    }
+}
@@ -5,4 +5,6 @@ pub mod config;
 pub mod daemon;
 pub mod http;
 pub mod memory;
+pub mod output;
+pub mod status;
 pub mod task;
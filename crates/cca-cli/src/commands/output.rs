@@ -0,0 +1,46 @@
+//! Output format selection for command results
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How commands print their results (`--output`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// The daemon's JSON response, for scripts
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == Self::Json
+    }
+}
+
+/// Print a value as pretty JSON on stdout
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Read a daemon response body as JSON
+///
+/// Bodies that aren't JSON are reported as `{"success": false, "error": "HTTP <status>"}`
+/// so JSON output stays parseable when the daemon fails.
+pub async fn response_json(resp: reqwest::Response) -> serde_json::Value {
+    let status = resp.status();
+    resp.json().await.unwrap_or_else(|_| {
+        serde_json::json!({
+            "success": false,
+            "error": format!("HTTP {status}")
+        })
+    })
+}
+
+/// Fail for commands that have no JSON form yet
+pub fn json_unsupported(command: &str) -> Result<()> {
+    anyhow::bail!("`{command}` does not support --output json yet")
+}
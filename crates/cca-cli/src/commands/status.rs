@@ -0,0 +1,84 @@
+//! System status command
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::http;
+use super::output::{print_json, OutputFormat};
+
+fn daemon_url() -> String {
+    std::env::var("CCA_DAEMON_URL").unwrap_or_else(|_| "http://127.0.0.1:8580".to_string())
+}
+
+/// What `cca status` reports
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    daemon_url: String,
+    daemon_running: bool,
+    /// Body of `GET /api/v1/health` when the daemon answered
+    health: Option<serde_json::Value>,
+}
+
+pub async fn run(output: OutputFormat) -> Result<()> {
+    let report = status_report(&daemon_url()).await;
+    if output.is_json() {
+        return print_json(&report);
+    }
+
+    println!("CCA Status");
+    println!("==========");
+    println!("Daemon: {}", if report.daemon_running { "running" } else { "not running" });
+    Ok(())
+}
+
+async fn status_report(base_url: &str) -> StatusReport {
+    let health = match http::get(&format!("{base_url}/api/v1/health")).await {
+        Ok(resp) if resp.status().is_success() => Some(resp.json().await.unwrap_or_default()),
+        _ => None,
+    };
+
+    StatusReport {
+        daemon_url: base_url.to_string(),
+        daemon_running: health.is_some(),
+        health,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{routing::get, Json, Router};
+
+    async fn mock_daemon() -> String {
+        let app = Router::new().route(
+            "/api/v1/health",
+            get(|| async { Json(serde_json::json!({ "status": "healthy", "version": "0.3.0" })) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_status_json_is_parseable() {
+        let base_url = mock_daemon().await;
+        let json = serde_json::to_string_pretty(&status_report(&base_url).await).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["daemon_running"], true);
+        assert_eq!(parsed["daemon_url"], base_url);
+        assert_eq!(parsed["health"]["status"], "healthy");
+    }
+
+    #[tokio::test]
+    async fn test_status_json_when_daemon_is_down() {
+        // Nothing listens on port 1
+        let json = serde_json::to_string(&status_report("http://127.0.0.1:1").await).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["daemon_running"], false);
+        assert!(parsed["health"].is_null());
+    }
+}
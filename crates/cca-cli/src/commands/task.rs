@@ -1,7 +1,17 @@
 //! Task management commands
 
-use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
 use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+
+use super::http;
+use super::output::{json_unsupported, print_json, response_json, OutputFormat};
+
+fn daemon_url() -> String {
+    std::env::var("CCA_DAEMON_URL").unwrap_or_else(|_| "http://127.0.0.1:8580".to_string())
+}
 
 #[derive(Subcommand)]
 pub enum TaskCommands {
@@ -30,14 +40,62 @@ pub enum TaskCommands {
         /// Task ID
         id: String,
     },
+    /// Wait for a task to finish, exiting non-zero unless it completes
+    Wait {
+        /// Task ID
+        id: String,
+
+        /// Give up after this many seconds
+        #[arg(long, default_value = "600")]
+        timeout: u64,
+
+        /// Seconds between status checks
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+}
+
+/// Statuses after which a task won't change again
+const TERMINAL_STATUSES: &[&str] = &["completed", "failed", "partial", "cancelled"];
+
+/// The fields of `GET /api/v1/tasks/:id` that `wait` reports
+#[derive(Debug, Deserialize, Serialize)]
+struct TaskSnapshot {
+    status: String,
+    #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Which polls `poll_until_finished` prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Progress {
+    /// Nothing (JSON output)
+    Quiet,
+    /// Only status changes
+    Changes,
+    /// Every poll (`--verbose`)
+    Every,
 }
 
-pub async fn run(cmd: TaskCommands) -> Result<()> {
+pub async fn run(cmd: TaskCommands, verbose: bool, output: OutputFormat) -> Result<()> {
     match cmd {
+        TaskCommands::Create { .. } if output.is_json() => json_unsupported("cca task create"),
+        TaskCommands::Status { .. } if output.is_json() => json_unsupported("cca task status"),
+        TaskCommands::List { .. } if output.is_json() => json_unsupported("cca task list"),
         TaskCommands::Create { description, agent } => create(&description, agent).await,
         TaskCommands::Status { id } => status(&id).await,
         TaskCommands::List { limit } => list(limit).await,
-        TaskCommands::Cancel { id } => cancel(&id).await,
+        TaskCommands::Cancel { id } => cancel(&id, output).await,
+        TaskCommands::Wait { id, timeout, interval } => {
+            let progress = match (output, verbose) {
+                (OutputFormat::Json, _) => Progress::Quiet,
+                (OutputFormat::Text, true) => Progress::Every,
+                (OutputFormat::Text, false) => Progress::Changes,
+            };
+            wait(&id, Duration::from_secs(timeout), Duration::from_secs(interval.max(1)), progress).await
+        }
     }
 }
 
@@ -69,9 +127,166 @@ async fn list(limit: usize) -> Result<()> {
     Ok(())
 }
 
-async fn cancel(id: &str) -> Result<()> {
-    println!("Cancelling task {id}...");
-    // TODO: Call daemon API
-    println!("Task cancelled");
+async fn cancel(id: &str, output: OutputFormat) -> Result<()> {
+    if !output.is_json() {
+        println!("Cancelling task {id}...");
+    }
+    let url = format!("{}/api/v1/tasks/{id}/cancel", daemon_url());
+    let resp = http::auth_post(&url)
+        .send()
+        .await
+        .context("Failed to cancel task")?;
+
+    if output.is_json() {
+        return print_json(&response_json(resp).await);
+    }
+    match resp.status() {
+        s if s.is_success() => println!("Task cancelled"),
+        reqwest::StatusCode::NOT_FOUND => println!("Error: task {id} not found"),
+        reqwest::StatusCode::CONFLICT => println!("Task {id} already finished"),
+        s => println!("Error: HTTP {s}"),
+    }
     Ok(())
 }
+
+async fn wait(id: &str, timeout: Duration, interval: Duration, progress: Progress) -> Result<()> {
+    let json = progress == Progress::Quiet;
+    if !json {
+        println!("Waiting for task {id}...");
+    }
+    let task = poll_until_finished(&daemon_url(), id, timeout, interval, progress).await?;
+
+    if json {
+        print_json(&task)?;
+    } else if let Some(output) = &task.output {
+        println!("\n{output}");
+    }
+    match task.status.as_str() {
+        "completed" => {
+            if !json {
+                println!("Task {id} completed");
+            }
+            Ok(())
+        }
+        status => {
+            let error = task.error.as_deref().unwrap_or("no error reported");
+            anyhow::bail!("Task {id} {status}: {error}")
+        }
+    }
+}
+
+/// Poll a task until it reaches a terminal status or `timeout` elapses
+///
+/// `progress` controls whether status changes or every poll are printed.
+async fn poll_until_finished(
+    base_url: &str,
+    id: &str,
+    timeout: Duration,
+    interval: Duration,
+    progress: Progress,
+) -> Result<TaskSnapshot> {
+    let url = format!("{base_url}/api/v1/tasks/{id}");
+    let start = Instant::now();
+    let mut last_status = String::new();
+
+    loop {
+        let resp = http::get(&url).await.context("Failed to connect to daemon")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Task {id} not found");
+        }
+        if !resp.status().is_success() {
+            anyhow::bail!("Failed to get task {id}: HTTP {}", resp.status());
+        }
+        let task: TaskSnapshot = resp.json().await.context("Invalid task response")?;
+
+        let elapsed = start.elapsed().as_secs();
+        match progress {
+            Progress::Every => println!("[{elapsed:>4}s] {id}: {}", task.status),
+            Progress::Changes if task.status != last_status => println!("Status: {}", task.status),
+            _ => {}
+        }
+        last_status.clone_from(&task.status);
+
+        if TERMINAL_STATUSES.contains(&task.status.as_str()) {
+            return Ok(task);
+        }
+        if start.elapsed() + interval > timeout {
+            anyhow::bail!(
+                "Timed out after {}s waiting for task {id} (last status: {last_status})",
+                timeout.as_secs()
+            );
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use axum::{extract::State, routing::get, Json, Router};
+
+    /// Serve a task that is pending, then running, then finishes with `final_status`
+    async fn mock_daemon(final_status: &'static str) -> String {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/api/v1/tasks/:id",
+                get(move |State(polls): State<Arc<AtomicUsize>>| async move {
+                    let status = match polls.fetch_add(1, Ordering::SeqCst) {
+                        0 => "pending",
+                        1 => "running",
+                        _ => final_status,
+                    };
+                    Json(serde_json::json!({
+                        "task_id": "task-1",
+                        "status": status,
+                        "output": (status == "completed").then_some("All done"),
+                        "error": null,
+                        "assigned_agent": null
+                    }))
+                }),
+            )
+            .with_state(polls);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_wait_polls_until_completed() {
+        let base_url = mock_daemon("completed").await;
+
+        let task = poll_until_finished(
+            &base_url,
+            "task-1",
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+            Progress::Every,
+        )
+        .await
+        .unwrap();
+        assert_eq!(task.status, "completed");
+        assert_eq!(task.output.as_deref(), Some("All done"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out_and_reports_missing_tasks() {
+        let base_url = mock_daemon("running").await;
+        let timeout = Duration::from_millis(50);
+        let interval = Duration::from_millis(20);
+        let err = poll_until_finished(&base_url, "task-1", timeout, interval, Progress::Changes)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Timed out"), "{err}");
+
+        let err = poll_until_finished(&base_url, "missing/extra", timeout, interval, Progress::Quiet)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+    }
+}
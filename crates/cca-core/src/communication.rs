@@ -110,6 +110,8 @@ pub mod channels {
 /// ACP (Agent Client Protocol) message following JSON-RPC 2.0
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AcpMessage {
+    /// Empty when the peer left it out, which [`AcpMessage::validate`] rejects
+    #[serde(default)]
     pub jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -124,14 +126,18 @@ pub struct AcpMessage {
 }
 
 impl AcpMessage {
+    /// Build a request. The id must not be empty, since responses are matched
+    /// to requests by id (checked in debug builds)
     pub fn request(
         id: impl Into<String>,
         method: impl Into<String>,
         params: serde_json::Value,
     ) -> Self {
+        let id = id.into();
+        debug_assert!(!id.is_empty(), "ACP request id must not be empty");
         Self {
             jsonrpc: "2.0".to_string(),
-            id: Some(id.into()),
+            id: Some(id),
             method: Some(method.into()),
             params: Some(params),
             result: None,
@@ -171,6 +177,50 @@ impl AcpMessage {
             error: Some(error),
         }
     }
+
+    /// A call that expects a response: has `method` and `id`
+    pub fn is_request(&self) -> bool {
+        self.method.is_some() && self.id.is_some()
+    }
+
+    /// A one-way call: has `method` but no `id`
+    pub fn is_notification(&self) -> bool {
+        self.method.is_some() && self.id.is_none()
+    }
+
+    /// A reply to a request: no `method`, and a `result` or `error`
+    pub fn is_response(&self) -> bool {
+        self.method.is_none() && (self.result.is_some() || self.error.is_some())
+    }
+
+    /// Check the JSON-RPC 2.0 shape of a received message
+    ///
+    /// A message is a request (`method` and `id`), a notification (`method`
+    /// only) or a response (`id` with exactly one of `result` and `error`;
+    /// error responses may omit the `id`).
+    pub fn validate(&self) -> Result<(), AcpError> {
+        let invalid = |reason: &str| Err(AcpError::custom(-32600, format!("Invalid Request: {reason}")));
+
+        if self.jsonrpc != "2.0" {
+            return invalid("jsonrpc must be \"2.0\"");
+        }
+        if self.result.is_some() && self.error.is_some() {
+            return invalid("result and error are mutually exclusive");
+        }
+
+        match &self.method {
+            Some(method) if method.is_empty() => invalid("method must not be empty"),
+            Some(_) if self.result.is_some() || self.error.is_some() => {
+                invalid("requests must not carry result or error")
+            }
+            Some(_) => Ok(()),
+            None if self.result.is_none() && self.error.is_none() => {
+                invalid("message has neither method nor result/error")
+            }
+            None if self.id.is_none() && self.result.is_some() => invalid("response is missing id"),
+            None => Ok(()),
+        }
+    }
 }
 
 /// ACP error structure
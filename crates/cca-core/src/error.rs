@@ -45,5 +45,61 @@ pub enum CCAError {
     Internal(String),
 }
 
+impl CCAError {
+    /// Stable machine-readable code for this kind of error
+    ///
+    /// Clients branch on this instead of the message text, so existing codes
+    /// must never change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Agent(_) => "agent_error",
+            Self::Task(_) => "task_error",
+            Self::Communication(_) => "communication_error",
+            Self::Memory(_) => "memory_error",
+            Self::Config(_) => "config_error",
+            Self::Pty(_) => "pty_error",
+            Self::Redis(_) => "redis_error",
+            Self::Database(_) => "database_error",
+            Self::Serialization(_) => "serialization_error",
+            Self::Io(_) => "io_error",
+            Self::NotFound(_) => "not_found",
+            Self::Timeout(_) => "timeout",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+}
+
 /// Result type alias for CCA operations
 pub type Result<T> = std::result::Result<T, CCAError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_error_codes_are_stable_and_unique() {
+        let serialization = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let cases = [
+            (CCAError::Agent(String::new()), "agent_error"),
+            (CCAError::Task(String::new()), "task_error"),
+            (CCAError::Communication(String::new()), "communication_error"),
+            (CCAError::Memory(String::new()), "memory_error"),
+            (CCAError::Config(String::new()), "config_error"),
+            (CCAError::Pty(String::new()), "pty_error"),
+            (CCAError::Redis(String::new()), "redis_error"),
+            (CCAError::Database(String::new()), "database_error"),
+            (CCAError::Serialization(serialization), "serialization_error"),
+            (CCAError::Io(std::io::Error::other("disk")), "io_error"),
+            (CCAError::NotFound(String::new()), "not_found"),
+            (CCAError::Timeout(String::new()), "timeout"),
+            (CCAError::Internal(String::new()), "internal_error"),
+        ];
+
+        let mut seen = HashSet::new();
+        for (error, code) in &cases {
+            assert_eq!(error.code(), *code);
+            assert!(seen.insert(error.code()), "duplicate error code {code}");
+        }
+    }
+}
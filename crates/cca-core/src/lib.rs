@@ -20,5 +20,5 @@ pub mod util;
 
 pub use agent::{Agent, AgentId, AgentRole, AgentState};
 pub use error::{CCAError, Result};
-pub use task::{Task, TaskId, TaskResult, TaskStatus};
+pub use task::{Task, TaskId, TaskResult, TaskRunStatus, TaskStatus};
 pub use types::*;
@@ -1,10 +1,15 @@
 //! Memory types for ReasoningBank and context management
 
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::agent::AgentId;
+use crate::error::{CCAError, Result};
 use crate::types::PatternId;
 
 /// Pattern stored in ReasoningBank
@@ -77,6 +82,183 @@ pub enum PatternType {
     Custom(String),
 }
 
+impl PatternType {
+    /// Name used when the type is stored or filtered on as text
+    pub fn as_str(&self) -> &str {
+        match self {
+            PatternType::Code => "code",
+            PatternType::Routing => "routing",
+            PatternType::ErrorHandling => "error_handling",
+            PatternType::Communication => "communication",
+            PatternType::Optimization => "optimization",
+            PatternType::Custom(name) => name,
+        }
+    }
+}
+
+impl From<&str> for PatternType {
+    /// Unknown names become `Custom`, so any stored type round-trips
+    fn from(name: &str) -> Self {
+        match name {
+            "code" => PatternType::Code,
+            "routing" => PatternType::Routing,
+            "error_handling" => PatternType::ErrorHandling,
+            "communication" => PatternType::Communication,
+            "optimization" => PatternType::Optimization,
+            other => PatternType::Custom(other.to_string()),
+        }
+    }
+}
+
+/// Parameters for `PatternStore::search`
+#[derive(Debug, Clone)]
+pub struct PatternQuery {
+    /// Text matched against pattern content when no embedding is given
+    pub text: String,
+    /// Query embedding; when set, patterns are ranked by cosine similarity
+    pub embedding: Option<Vec<f32>>,
+    /// Minimum similarity for embedding matches
+    pub min_similarity: f64,
+    pub pattern_type: Option<PatternType>,
+    pub limit: usize,
+}
+
+impl PatternQuery {
+    pub fn text(text: impl Into<String>, limit: usize) -> Self {
+        Self {
+            text: text.into(),
+            embedding: None,
+            min_similarity: 0.0,
+            pattern_type: None,
+            limit,
+        }
+    }
+
+    pub fn with_embedding(mut self, embedding: Vec<f32>, min_similarity: f64) -> Self {
+        self.embedding = Some(embedding);
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    pub fn with_pattern_type(mut self, pattern_type: PatternType) -> Self {
+        self.pattern_type = Some(pattern_type);
+        self
+    }
+}
+
+/// Storage backend for ReasoningBank patterns
+///
+/// Embedding queries score matches by cosine similarity; text queries match
+/// content case-insensitively and score by success rate.
+#[async_trait]
+pub trait PatternStore: Send + Sync {
+    /// Store a pattern under its own id
+    async fn create(&self, pattern: Pattern) -> Result<PatternId>;
+
+    /// Best matches for the query, highest score first
+    async fn search(&self, query: &PatternQuery) -> Result<Vec<SearchMatch>>;
+
+    async fn get(&self, id: PatternId) -> Result<Option<Pattern>>;
+
+    /// Most similar stored pattern at or above `threshold`, if any
+    async fn find_duplicate(&self, embedding: &[f32], threshold: f64) -> Result<Option<PatternId>> {
+        let query = PatternQuery::text("", 1).with_embedding(embedding.to_vec(), threshold);
+        Ok(self.search(&query).await?.first().map(|m| m.pattern.id))
+    }
+
+    /// Count another successful use of a stored pattern
+    async fn record_success(&self, id: PatternId) -> Result<()>;
+}
+
+/// Pattern store kept in process memory, for tests and single-node setups
+#[derive(Default)]
+pub struct InMemoryPatternStore {
+    patterns: RwLock<HashMap<PatternId, Pattern>>,
+}
+
+impl InMemoryPatternStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PatternStore for InMemoryPatternStore {
+    async fn create(&self, pattern: Pattern) -> Result<PatternId> {
+        let id = pattern.id;
+        self.patterns.write().unwrap().insert(id, pattern);
+        Ok(id)
+    }
+
+    async fn search(&self, query: &PatternQuery) -> Result<Vec<SearchMatch>> {
+        let patterns = self.patterns.read().unwrap();
+        let candidates = patterns.values().filter(|p| {
+            query.pattern_type.is_none() || query.pattern_type.as_ref() == Some(&p.pattern_type)
+        });
+
+        let mut matches: Vec<SearchMatch> = if let Some(ref embedding) = query.embedding {
+            candidates
+                .filter_map(|p| {
+                    let score = cosine_similarity(embedding, p.embedding.as_deref()?);
+                    (score >= query.min_similarity).then(|| SearchMatch {
+                        pattern: p.clone(),
+                        score,
+                    })
+                })
+                .collect()
+        } else {
+            let needle = query.text.to_lowercase();
+            candidates
+                .filter(|p| p.content.to_lowercase().contains(&needle))
+                .map(|p| SearchMatch {
+                    pattern: p.clone(),
+                    score: p.success_rate(),
+                })
+                .collect()
+        };
+
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.pattern.created_at.cmp(&a.pattern.created_at))
+        });
+        matches.truncate(query.limit);
+        Ok(matches)
+    }
+
+    async fn get(&self, id: PatternId) -> Result<Option<Pattern>> {
+        Ok(self.patterns.read().unwrap().get(&id).cloned())
+    }
+
+    async fn record_success(&self, id: PatternId) -> Result<()> {
+        match self.patterns.write().unwrap().get_mut(&id) {
+            Some(pattern) => {
+                pattern.record_success();
+                Ok(())
+            }
+            None => Err(CCAError::NotFound(format!("Pattern {}", id.0))),
+        }
+    }
+}
+
+/// Cosine similarity of two vectors (0 when the lengths differ or either is zero)
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
 /// Context snapshot for recovery
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContextSnapshot {
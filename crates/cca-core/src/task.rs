@@ -44,6 +44,74 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+/// Status of a task submitted through the daemon's task API
+///
+/// Serialized in lowercase (`"pending"`, `"running"`, ...), the strings the
+/// API has always returned. Separate from [`TaskStatus`], which tracks the
+/// orchestrator's own task queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskRunStatus {
+    /// Waiting for dependencies or a coordinator
+    Pending,
+    /// Sent to the coordinator or its delegates
+    Running,
+    /// Every delegation succeeded
+    Completed,
+    /// Some delegations failed
+    Partial,
+    /// The task could not be run, or every delegation failed
+    Failed,
+    /// Cancelled through the API
+    Cancelled,
+    /// The request was rejected before a task was created; never stored
+    Error,
+}
+
+impl TaskRunStatus {
+    /// Whether the task is finished and its status won't change again
+    pub fn is_terminal(self) -> bool {
+        !matches!(self, Self::Pending | Self::Running)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Partial => "partial",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::fmt::Display for TaskRunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TaskRunStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            Self::Pending,
+            Self::Running,
+            Self::Completed,
+            Self::Partial,
+            Self::Failed,
+            Self::Cancelled,
+            Self::Error,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == s)
+        .ok_or_else(|| format!("unknown task status '{s}'"))
+    }
+}
+
 /// A task to be executed by an agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -177,6 +245,43 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_task_run_status_serializes_as_api_strings() {
+        for status in [
+            TaskRunStatus::Pending,
+            TaskRunStatus::Running,
+            TaskRunStatus::Completed,
+            TaskRunStatus::Partial,
+            TaskRunStatus::Failed,
+            TaskRunStatus::Cancelled,
+            TaskRunStatus::Error,
+        ] {
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{status}\""));
+            assert_eq!(serde_json::from_str::<TaskRunStatus>(&json).unwrap(), status);
+        }
+        assert!(serde_json::from_str::<TaskRunStatus>("\"in_progress\"").is_err());
+    }
+
+    #[test]
+    fn test_task_run_status_from_str() {
+        assert_eq!("partial".parse::<TaskRunStatus>(), Ok(TaskRunStatus::Partial));
+        assert_eq!("cancelled".parse::<TaskRunStatus>(), Ok(TaskRunStatus::Cancelled));
+        assert!("Completed".parse::<TaskRunStatus>().is_err());
+        assert!("in_progress".parse::<TaskRunStatus>().is_err());
+    }
+
+    #[test]
+    fn test_task_run_status_is_terminal() {
+        assert!(!TaskRunStatus::Pending.is_terminal());
+        assert!(!TaskRunStatus::Running.is_terminal());
+        assert!(TaskRunStatus::Completed.is_terminal());
+        assert!(TaskRunStatus::Partial.is_terminal());
+        assert!(TaskRunStatus::Failed.is_terminal());
+        assert!(TaskRunStatus::Cancelled.is_terminal());
+        assert!(TaskRunStatus::Error.is_terminal());
+    }
+
     #[test]
     fn test_task_creation() {
         let task = Task::new("Test task description");
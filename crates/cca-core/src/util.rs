@@ -69,9 +69,13 @@ pub fn safe_truncate_with_ellipsis(s: &str, max_chars: usize) -> String {
 /// `true` if the strings are equal, `false` otherwise
 ///
 /// # Security
-/// This function takes constant time regardless of where strings differ,
-/// preventing attackers from learning partial information about secrets
-/// through timing analysis.
+/// Running time depends only on the length of the longer input. It does not
+/// depend on where the strings differ or on whether their lengths match: there
+/// is no early return on a length mismatch. Both inputs are walked to the
+/// longer length (the shorter one padded with zero bytes) and the length
+/// comparison is folded into the result. When the caller's input is at least
+/// as long as the secret, the timing reveals nothing about the secret's
+/// contents or length.
 ///
 /// # Example
 /// ```
@@ -82,12 +86,14 @@ pub fn safe_truncate_with_ellipsis(s: &str, max_chars: usize) -> String {
 /// assert!(!constant_time_eq("secret", "SECRET"));
 /// ```
 pub fn constant_time_eq(a: &str, b: &str) -> bool {
-    // First check lengths to avoid leaking timing through length comparison
-    // Both strings must be same length for constant-time comparison
-    if a.len() != b.len() {
-        return false;
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut equal = (a.len() as u64).ct_eq(&(b.len() as u64));
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        equal &= x.ct_eq(&y);
     }
-    a.as_bytes().ct_eq(b.as_bytes()).into()
+    equal.into()
 }
 
 /// Load environment variables from CCA env file if not already set.
@@ -120,27 +126,141 @@ pub fn load_env_file() {
 }
 
 /// Parse env file contents and set environment variables (only if not already set).
-/// Supports formats:
-/// - `KEY=value`
-/// - `export KEY=value`
-/// - `KEY="quoted value"`
-/// - `KEY='single quoted'`
-/// - Comments starting with #
+/// See [`parse_env`] for the supported syntax.
 pub fn parse_env_file(contents: &str) {
-    for line in contents.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
+    for (key, value) in parse_env(contents) {
+        if std::env::var(&key).is_err() {
+            std::env::set_var(key, value);
         }
-        let line = line.strip_prefix("export ").unwrap_or(line);
-        if let Some((key, value)) = line.split_once('=') {
-            let key = key.trim();
-            let value = value.trim().trim_matches('"').trim_matches('\'');
-            if std::env::var(key).is_err() {
-                std::env::set_var(key, value);
+    }
+}
+
+/// Parse env file contents into `(key, value)` pairs, in file order.
+///
+/// Each entry is parsed with [`parse_env_line`]. A quoted value may also span
+/// several lines; the line breaks are kept in the value. Lines that are not
+/// valid entries are skipped.
+///
+/// # Example
+/// ```
+/// use cca_core::util::parse_env;
+///
+/// let vars = parse_env("# comment\nA=1\nB=\"x\ny\"\n");
+/// assert_eq!(vars, vec![("A".into(), "1".into()), ("B".into(), "x\ny".into())]);
+/// ```
+pub fn parse_env(contents: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let mut entry = line.to_string();
+        loop {
+            match parse_entry(&entry) {
+                Entry::Var(key, value) => {
+                    vars.push((key, value));
+                    break;
+                }
+                Entry::Skip => break,
+                Entry::Unterminated => match lines.next() {
+                    Some(next) => {
+                        entry.push('\n');
+                        entry.push_str(next);
+                    }
+                    None => break,
+                },
             }
         }
     }
+    vars
+}
+
+/// Parse a single env file line into a `(key, value)` pair.
+///
+/// Supports formats:
+/// - `KEY=value`, with an optional trailing ` # comment`
+/// - `export KEY=value`
+/// - `KEY="double quoted"`, where `\"`, `\\`, `\n` and `\t` are unescaped
+/// - `KEY='single quoted'`, taken literally
+///
+/// Only the first unquoted `=` separates key and value, so `KEY="a=b"` and
+/// `KEY=a=b` both give `a=b`. Returns `None` for blank lines, comments, lines
+/// without a key, and quoted values that are not closed on the line.
+///
+/// # Example
+/// ```
+/// use cca_core::util::parse_env_line;
+///
+/// assert_eq!(
+///     parse_env_line(r#"export URL="postgres://u:p@h/db?sslmode=require""#),
+///     Some(("URL".into(), "postgres://u:p@h/db?sslmode=require".into()))
+/// );
+/// assert_eq!(parse_env_line("# KEY=value"), None);
+/// ```
+pub fn parse_env_line(line: &str) -> Option<(String, String)> {
+    match parse_entry(line) {
+        Entry::Var(key, value) => Some((key, value)),
+        Entry::Skip | Entry::Unterminated => None,
+    }
+}
+
+enum Entry {
+    Var(String, String),
+    Skip,
+    /// A quoted value that continues on the next line
+    Unterminated,
+}
+
+fn parse_entry(line: &str) -> Entry {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with('#') {
+        return Entry::Skip;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let Some((key, raw)) = line.split_once('=') else {
+        return Entry::Skip;
+    };
+    let key = key.trim();
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Entry::Skip;
+    }
+
+    let raw = raw.trim_start();
+    let value = match raw.chars().next() {
+        Some(quote @ ('"' | '\'')) => match parse_quoted(&raw[1..], quote) {
+            Some(value) => value,
+            None => return Entry::Unterminated,
+        },
+        _ => strip_inline_comment(raw).trim_end().to_string(),
+    };
+    Entry::Var(key.to_string(), value)
+}
+
+/// Read a quoted value up to its closing `quote`, or `None` if it isn't closed
+fn parse_quoted(rest: &str, quote: char) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => return Some(value),
+            '\\' if quote == '"' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                c @ ('"' | '\\') => value.push(c),
+                c => {
+                    value.push('\\');
+                    value.push(c);
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Drop a ` # comment` from an unquoted value; a `#` inside a word is kept
+fn strip_inline_comment(raw: &str) -> &str {
+    raw.char_indices()
+        .find(|&(i, c)| c == '#' && raw[..i].ends_with(char::is_whitespace))
+        .map_or(raw, |(i, _)| &raw[..i])
 }
 
 #[cfg(test)]
@@ -180,18 +300,116 @@ mod tests {
         assert!(constant_time_eq("", ""));
     }
 
+    #[test]
+    fn test_constant_time_eq_equal_inputs() {
+        assert!(constant_time_eq("a", "a"));
+        assert!(constant_time_eq("cca_key_0123456789", "cca_key_0123456789"));
+        assert!(constant_time_eq("日本語", "日本語"));
+        let long = "k".repeat(4096);
+        assert!(constant_time_eq(&long, &long.clone()));
+    }
+
+    #[test]
+    fn test_constant_time_eq_unequal_same_length() {
+        // A difference at the first, a middle and the last byte
+        assert!(!constant_time_eq("xecret", "secret"));
+        assert!(!constant_time_eq("secXet", "secret"));
+        assert!(!constant_time_eq("secreX", "secret"));
+        assert!(!constant_time_eq("日本語", "日本人"));
+    }
+
+    #[test]
+    fn test_constant_time_eq_different_lengths() {
+        // Prefixes and extensions in both argument orders
+        assert!(!constant_time_eq("secret", "secrets"));
+        assert!(!constant_time_eq("secrets", "secret"));
+        assert!(!constant_time_eq("", "secret"));
+        assert!(!constant_time_eq("secret", ""));
+        // The shorter input is padded with zero bytes, so trailing NULs must
+        // still fail on the length check
+        assert!(!constant_time_eq("secret", "secret\0"));
+        assert!(!constant_time_eq("secret\0\0", "secret"));
+        assert!(!constant_time_eq("\0", ""));
+    }
+
+    fn env_line(line: &str) -> Option<(String, String)> {
+        parse_env_line(line)
+    }
+
+    fn var(key: &str, value: &str) -> Option<(String, String)> {
+        Some((key.to_string(), value.to_string()))
+    }
+
+    #[test]
+    fn test_parse_env_line_keeps_equals_in_values() {
+        assert_eq!(env_line(r#"KEY="a=b""#), var("KEY", "a=b"));
+        assert_eq!(env_line("KEY='a=b=c'"), var("KEY", "a=b=c"));
+        assert_eq!(env_line("KEY=a=b"), var("KEY", "a=b"));
+        assert_eq!(
+            env_line(r#"export CCA__POSTGRES__URL="postgres://u:p@localhost/cca?sslmode=disable""#),
+            var("CCA__POSTGRES__URL", "postgres://u:p@localhost/cca?sslmode=disable")
+        );
+    }
+
+    #[test]
+    fn test_parse_env_line_quotes() {
+        // Double quotes unescape, single quotes are literal
+        assert_eq!(env_line(r#"KEY="say \"hi\"""#), var("KEY", r#"say "hi""#));
+        assert_eq!(env_line(r#"KEY="a\\b""#), var("KEY", r"a\b"));
+        assert_eq!(env_line(r#"KEY="line1\nline2""#), var("KEY", "line1\nline2"));
+        assert_eq!(env_line(r"KEY='say \'hi'"), var("KEY", r"say \"));
+        assert_eq!(env_line(r"KEY='a\nb'"), var("KEY", r"a\nb"));
+        assert_eq!(env_line(r#"KEY='it "works"'"#), var("KEY", r#"it "works""#));
+        assert_eq!(env_line(r#"KEY="it's""#), var("KEY", "it's"));
+        // Surrounding whitespace is kept inside quotes only
+        assert_eq!(env_line(r#"KEY = "  padded  "  "#), var("KEY", "  padded  "));
+        assert_eq!(env_line("KEY=  bare  "), var("KEY", "bare"));
+        assert_eq!(env_line(r#"KEY="""#), var("KEY", ""));
+        assert_eq!(env_line("KEY="), var("KEY", ""));
+        // Unclosed quotes are not a complete entry
+        assert_eq!(env_line(r#"KEY="open"#), None);
+    }
+
+    #[test]
+    fn test_parse_env_line_comments() {
+        assert_eq!(env_line("# KEY=value"), None);
+        assert_eq!(env_line("   # indented comment"), None);
+        assert_eq!(env_line(""), None);
+        assert_eq!(env_line("not an entry"), None);
+        assert_eq!(env_line("=value"), None);
+        assert_eq!(env_line("KEY=value # trailing"), var("KEY", "value"));
+        assert_eq!(env_line(r#"KEY="value # kept" # trailing"#), var("KEY", "value # kept"));
+        assert_eq!(env_line("KEY=a#b"), var("KEY", "a#b"));
+    }
+
+    #[test]
+    fn test_parse_env_multiline_values() {
+        let contents = "A=1\nB=\"first\nsecond\"\n# comment\nC='x\ny'\nD=\"never closed\nE=2";
+        let vars = parse_env(contents);
+        assert_eq!(
+            vars,
+            vec![
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "first\nsecond".to_string()),
+                ("C".to_string(), "x\ny".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_env_file() {
         // Clear any existing test vars
         std::env::remove_var("TEST_CCA_VAR1");
         std::env::remove_var("TEST_CCA_VAR2");
         std::env::remove_var("TEST_CCA_VAR3");
+        std::env::remove_var("TEST_CCA_VAR4");
 
         let contents = r#"
             # This is a comment
             TEST_CCA_VAR1=value1
             export TEST_CCA_VAR2="quoted value"
             TEST_CCA_VAR3='single quoted'
+            TEST_CCA_VAR4="a=b"
         "#;
 
         parse_env_file(contents);
@@ -199,5 +417,6 @@ mod tests {
         assert_eq!(std::env::var("TEST_CCA_VAR1").unwrap(), "value1");
         assert_eq!(std::env::var("TEST_CCA_VAR2").unwrap(), "quoted value");
         assert_eq!(std::env::var("TEST_CCA_VAR3").unwrap(), "single quoted");
+        assert_eq!(std::env::var("TEST_CCA_VAR4").unwrap(), "a=b");
     }
 }
@@ -51,6 +51,106 @@ fn test_acp_message_error_response() {
     assert!(msg.result.is_none());
 }
 
+#[test]
+fn test_acp_message_classification() {
+    let request = AcpMessage::request("1", "task.execute", json!({}));
+    assert!(request.is_request());
+    assert!(!request.is_notification());
+    assert!(!request.is_response());
+
+    let notification = AcpMessage::notification("task.progress", json!({}));
+    assert!(notification.is_notification());
+    assert!(!notification.is_request());
+    assert!(!notification.is_response());
+
+    let response = AcpMessage::response("1", json!({"ok": true}));
+    assert!(response.is_response());
+    assert!(!response.is_request());
+    assert!(!response.is_notification());
+
+    let error = AcpMessage::error_response("1", AcpError::method_not_found());
+    assert!(error.is_response());
+    assert!(!error.is_request());
+    assert!(!error.is_notification());
+}
+
+#[test]
+fn test_acp_message_classification_of_received_messages() {
+    let notification: AcpMessage =
+        serde_json::from_value(json!({"jsonrpc": "2.0", "method": "heartbeat"})).unwrap();
+    assert!(notification.is_notification());
+
+    // Neither a call nor a reply
+    let shapeless: AcpMessage = serde_json::from_value(json!({"jsonrpc": "2.0", "id": "1"})).unwrap();
+    assert!(!shapeless.is_request());
+    assert!(!shapeless.is_notification());
+    assert!(!shapeless.is_response());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "ACP request id must not be empty")]
+fn test_acp_message_request_requires_id() {
+    let _ = AcpMessage::request("", "task.execute", json!({}));
+}
+
+#[test]
+fn test_acp_message_validate_accepts_well_formed_messages() {
+    assert!(AcpMessage::request("1", "task.execute", json!({})).validate().is_ok());
+    assert!(AcpMessage::notification("task.progress", json!({})).validate().is_ok());
+    assert!(AcpMessage::response("1", json!({"ok": true})).validate().is_ok());
+    assert!(AcpMessage::error_response("1", AcpError::method_not_found()).validate().is_ok());
+
+    // Error responses to unparseable requests have no id
+    let mut anonymous_error = AcpMessage::error_response("1", AcpError::parse_error());
+    anonymous_error.id = None;
+    assert!(anonymous_error.validate().is_ok());
+}
+
+#[test]
+fn test_acp_message_validate_rejects_missing_version() {
+    let msg: AcpMessage = serde_json::from_value(json!({"id": "1", "method": "status.get"})).unwrap();
+    assert_eq!(msg.validate().unwrap_err().code, -32600);
+
+    let mut msg = AcpMessage::request("1", "status.get", json!({}));
+    msg.jsonrpc = "1.0".to_string();
+    assert!(msg.validate().is_err());
+}
+
+#[test]
+fn test_acp_message_validate_rejects_result_and_error() {
+    let mut msg = AcpMessage::response("1", json!({}));
+    msg.error = Some(AcpError::internal_error("boom"));
+    let err = msg.validate().unwrap_err();
+    assert_eq!(err.code, -32600);
+    assert!(err.message.contains("mutually exclusive"));
+}
+
+#[test]
+fn test_acp_message_validate_rejects_request_with_result() {
+    let mut msg = AcpMessage::request("1", "status.get", json!({}));
+    msg.result = Some(json!({}));
+    assert!(msg.validate().is_err());
+}
+
+#[test]
+fn test_acp_message_validate_rejects_empty_method() {
+    assert!(AcpMessage::request("1", "", json!({})).validate().is_err());
+}
+
+#[test]
+fn test_acp_message_validate_rejects_shapeless_message() {
+    let msg: AcpMessage = serde_json::from_value(json!({"jsonrpc": "2.0", "id": "1"})).unwrap();
+    assert!(msg.validate().is_err());
+}
+
+#[test]
+fn test_acp_message_validate_rejects_result_without_id() {
+    let mut msg = AcpMessage::response("1", json!({}));
+    msg.id = None;
+    assert!(msg.validate().is_err());
+}
+
 #[test]
 fn test_acp_error_codes() {
     assert_eq!(AcpError::parse_error().code, -32700);
@@ -1,9 +1,10 @@
 //! Integration tests for Memory types
 //! Tests Pattern, ContextSnapshot, and related types
 
-use cca_core::AgentId;
+use cca_core::{AgentId, PatternId};
 use cca_core::memory::{
     Pattern, PatternType, ContextSnapshot, SearchMatch, AgentContext, ContextMessage, MessageRole,
+    InMemoryPatternStore, PatternQuery, PatternStore,
 };
 use chrono::Utc;
 use uuid::Uuid;
@@ -251,3 +252,77 @@ fn test_context_snapshot_with_large_data() {
 
     assert_eq!(snapshot.compressed_context.len(), 1000000);
 }
+
+#[test]
+fn test_pattern_type_string_round_trip() {
+    for pattern_type in [
+        PatternType::Code,
+        PatternType::Routing,
+        PatternType::ErrorHandling,
+        PatternType::Communication,
+        PatternType::Optimization,
+        PatternType::Custom("solution".to_string()),
+    ] {
+        assert_eq!(PatternType::from(pattern_type.as_str()), pattern_type);
+    }
+}
+
+#[tokio::test]
+async fn test_pattern_store_create_and_get() {
+    let store: Box<dyn PatternStore> = Box::new(InMemoryPatternStore::new());
+    let pattern = Pattern::new(PatternType::Code, "Use a connection pool");
+
+    let id = store.create(pattern.clone()).await.unwrap();
+    assert_eq!(id, pattern.id);
+
+    let stored = store.get(id).await.unwrap().unwrap();
+    assert_eq!(stored.content, "Use a connection pool");
+    assert!(store.get(PatternId::new()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_pattern_store_text_search() {
+    let store: Box<dyn PatternStore> = Box::new(InMemoryPatternStore::new());
+    let mut proven = Pattern::new(PatternType::Code, "Retry with BACKOFF");
+    proven.record_success();
+    store.create(proven.clone()).await.unwrap();
+    store.create(Pattern::new(PatternType::Code, "backoff on 429")).await.unwrap();
+    store.create(Pattern::new(PatternType::Routing, "backoff routing")).await.unwrap();
+    store.create(Pattern::new(PatternType::Code, "unrelated")).await.unwrap();
+
+    let matches = store.search(&PatternQuery::text("backoff", 10)).await.unwrap();
+    assert_eq!(matches.len(), 3);
+    assert_eq!(matches[0].pattern.id, proven.id);
+    assert!((matches[0].score - 1.0).abs() < f64::EPSILON);
+
+    let query = PatternQuery::text("backoff", 10).with_pattern_type(PatternType::Routing);
+    let matches = store.search(&query).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].pattern.content, "backoff routing");
+
+    let matches = store.search(&PatternQuery::text("backoff", 1)).await.unwrap();
+    assert_eq!(matches.len(), 1);
+}
+
+#[tokio::test]
+async fn test_pattern_store_embedding_search() {
+    let store: Box<dyn PatternStore> = Box::new(InMemoryPatternStore::new());
+    let mut near = Pattern::new(PatternType::Code, "near");
+    near.embedding = Some(vec![1.0, 0.1]);
+    let mut far = Pattern::new(PatternType::Code, "far");
+    far.embedding = Some(vec![0.0, 1.0]);
+    store.create(near).await.unwrap();
+    store.create(far).await.unwrap();
+    store.create(Pattern::new(PatternType::Code, "no embedding")).await.unwrap();
+
+    let query = PatternQuery::text("ignored", 10).with_embedding(vec![1.0, 0.0], 0.5);
+    let matches = store.search(&query).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].pattern.content, "near");
+    assert!(matches[0].score > 0.99);
+
+    let query = PatternQuery::text("ignored", 10).with_embedding(vec![1.0, 0.0], 0.0);
+    let matches = store.search(&query).await.unwrap();
+    let contents: Vec<_> = matches.iter().map(|m| m.pattern.content.as_str()).collect();
+    assert_eq!(contents, ["near", "far"]);
+}
@@ -374,7 +374,7 @@ fn bench_priority_scheduling(c: &mut Criterion) {
 
             // Sort by priority (simulating priority queue)
             let mut sorted_tasks = tasks.clone();
-            sorted_tasks.sort_by(|a, b| b.priority.cmp(&a.priority));
+            sorted_tasks.sort_by_key(|t| std::cmp::Reverse(t.priority));
 
             for task in &sorted_tasks {
                 let _ = orchestrator.route_task(black_box(task));
@@ -7,14 +7,17 @@
 //! Note: Some methods are infrastructure for future features and not yet called.
 #![allow(dead_code)]
 
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::process::Stdio;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
+use serde::Deserialize;
 use tokio::process::Command;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
@@ -22,7 +25,7 @@ use tracing::{debug, error, info, warn};
 use cca_core::{Agent, AgentId, AgentRole, AgentState};
 use cca_core::util::{safe_truncate, safe_truncate_with_ellipsis};
 
-use crate::config::{Config, PermissionsConfig};
+use crate::config::{AgentsConfig, Config, PermissionsConfig};
 
 /// `SEC-007`: Apply permission configuration to a tokio Command
 /// This replaces the blanket `--dangerously-skip-permissions` with granular control
@@ -122,15 +125,118 @@ pub fn apply_permissions_to_pty_command(cmd: &mut CommandBuilder, permissions: &
     }
 }
 
+/// Error for a Claude Code process that failed to start
+///
+/// A missing binary gets an actionable message instead of the raw OS error.
+pub fn claude_spawn_error(claude_path: &str, err: &std::io::Error) -> String {
+    if err.kind() == std::io::ErrorKind::NotFound {
+        format!("Claude Code binary not found at {claude_path}; set CCA__AGENTS__CLAUDE_PATH")
+    } else {
+        format!("Failed to start Claude Code at {claude_path}: {err}")
+    }
+}
+
+/// Variables every agent process gets from the daemon's environment
+const AGENT_BASE_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "LC_ALL", "TMPDIR"];
+
+/// Environment for an agent process of `role`: the base variables plus the
+/// configured passthrough list, read through `lookup`. Unset names are skipped.
+pub fn agent_env(
+    agents: &AgentsConfig,
+    role: &str,
+    lookup: impl Fn(&str) -> Option<OsString>,
+) -> Vec<(String, OsString)> {
+    let mut env: Vec<(String, OsString)> = Vec::new();
+    for name in AGENT_BASE_ENV.iter().copied().chain(agents.env_passthrough_for_role(role)) {
+        if env.iter().any(|(existing, _)| existing == name) {
+            continue;
+        }
+        if let Some(value) = lookup(name) {
+            env.push((name.to_string(), value));
+        }
+    }
+    env
+}
+
+/// Replace a command's inherited environment with [`agent_env`]
+pub fn apply_env_to_command(cmd: &mut Command, agents: &AgentsConfig, role: &str) {
+    cmd.env_clear();
+    cmd.envs(agent_env(agents, role, |name| std::env::var_os(name)));
+}
+
+/// PTY variant of [`apply_env_to_command`]
+pub fn apply_env_to_pty_command(cmd: &mut CommandBuilder, agents: &AgentsConfig, role: &str) {
+    cmd.env_clear();
+    for (name, value) in agent_env(agents, role, |name| std::env::var_os(name)) {
+        cmd.env(name, value);
+    }
+}
+
+/// Response text and token usage of a `claude --print` run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaudeOutput {
+    pub text: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl ClaudeOutput {
+    pub fn tokens_used(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Result object printed by `claude --print --output-format json`
+#[derive(Deserialize)]
+struct ClaudeJsonResult {
+    result: String,
+    #[serde(default)]
+    usage: ClaudeJsonUsage,
+}
+
+#[derive(Default, Deserialize)]
+struct ClaudeJsonUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// Parse `--output-format json` output
+///
+/// Output that isn't a JSON result (e.g. from an older binary that ignored the
+/// flag) is returned as plain text with no usage.
+pub fn parse_claude_output(stdout: &str) -> ClaudeOutput {
+    match serde_json::from_str::<ClaudeJsonResult>(stdout.trim()) {
+        Ok(parsed) => ClaudeOutput {
+            text: parsed.result,
+            input_tokens: parsed.usage.input_tokens,
+            output_tokens: parsed.usage.output_tokens,
+        },
+        Err(_) => ClaudeOutput {
+            text: stdout.to_string(),
+            ..ClaudeOutput::default()
+        },
+    }
+}
+
 /// Manages Claude Code agent instances
 pub struct AgentManager {
     agents: HashMap<AgentId, ManagedAgent>,
     config: Config,
+    /// Optional durable archive that receives a copy of every log entry
+    log_sink: Option<mpsc::Sender<(AgentId, LogEntry)>>,
 }
 
 /// Maximum number of log entries to keep per agent
 const MAX_LOG_ENTRIES: usize = 100;
 
+/// Maximum number of PTY output lines kept per agent for the attach UI
+pub const MAX_SESSION_OUTPUT_LINES: usize = 1000;
+
+/// Longest PTY output line kept; longer lines are truncated
+const MAX_SESSION_LINE_LEN: usize = 4096;
+
 /// A managed agent with optional interactive PTY session
 struct ManagedAgent {
     agent: Agent,
@@ -140,16 +246,90 @@ struct ManagedAgent {
     current_task: Option<String>,
     /// Recent log entries for this agent
     logs: Vec<LogEntry>,
+    /// Recent output of the interactive session (kept after it stops)
+    session_output: SessionOutput,
+}
+
+/// Bounded ring buffer of interactive session output lines
+///
+/// Shared with the PTY reader thread, which appends every line it reads.
+/// Once full, the oldest lines are dropped.
+#[derive(Clone, Default)]
+pub struct SessionOutput {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl SessionOutput {
+    fn push(&self, line: &str) {
+        let line = safe_truncate(line, MAX_SESSION_LINE_LEN).to_string();
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() >= MAX_SESSION_OUTPUT_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `n` lines, oldest first
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines.iter().skip(lines.len().saturating_sub(n)).cloned().collect()
+    }
+
+    fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
 }
 
 /// A log entry for an agent
 #[derive(Clone)]
 pub struct LogEntry {
+    /// Position in the agent's log, starting at 1; assigned when the entry is pushed
+    pub seq: u64,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub level: String,
     pub message: String,
 }
 
+impl LogEntry {
+    pub fn new(level: &str, message: String) -> Self {
+        Self {
+            seq: 0,
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            message,
+        }
+    }
+
+    /// Whether the entry is at or above `min_level` (unrecognised levels always are)
+    fn at_least(&self, min_level: Option<LogLevel>) -> bool {
+        min_level.map_or(true, |min| LogLevel::parse(&self.level).map_or(true, |l| l >= min))
+    }
+}
+
+/// Severity of a log entry, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Parse a level name case-insensitively (`WARNING` is accepted for `WARN`)
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" | "WARNING" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration needed to execute a task for an agent
 #[derive(Clone)]
 pub struct TaskConfig {
@@ -159,6 +339,51 @@ pub struct TaskConfig {
     pub system_prompt: Option<String>,
 }
 
+/// Append a log entry, keeping only the last `MAX_LOG_ENTRIES`, and copy it to the sink
+///
+/// Sequence numbers keep counting up after old entries are dropped, so they
+/// can be used as a cursor.
+fn push_log(
+    logs: &mut Vec<LogEntry>,
+    sink: Option<&mpsc::Sender<(AgentId, LogEntry)>>,
+    agent_id: AgentId,
+    mut entry: LogEntry,
+) {
+    entry.seq = logs.last().map_or(1, |last| last.seq + 1);
+    if let Some(sink) = sink {
+        if let Err(mpsc::error::TrySendError::Full(_)) = sink.try_send((agent_id, entry.clone())) {
+            debug!("Agent log sink is full, entry not archived");
+        }
+    }
+
+    logs.push(entry);
+    if logs.len() > MAX_LOG_ENTRIES {
+        logs.remove(0);
+    }
+}
+
+/// Read PTY output line by line into the capture buffer and the response channel
+///
+/// The capture buffer sees every line. Lines nobody is waiting for are dropped
+/// from the channel once it fills up, so an idle session never stalls capture.
+fn pump_session_output(reader: impl Read, output: &SessionOutput, stdout_tx: &mpsc::Sender<String>) {
+    for line in BufReader::new(reader).lines() {
+        match line {
+            Ok(line) => {
+                debug!("Read from PTY: {}", line);
+                output.push(&line);
+                if let Err(mpsc::error::TrySendError::Closed(_)) = stdout_tx.try_send(line) {
+                    break;
+                }
+            }
+            Err(e) => {
+                error!("Failed to read from PTY: {}", e);
+                break;
+            }
+        }
+    }
+}
+
 /// Handles for interactive PTY communication
 struct InteractiveSession {
     /// Sender to write to PTY stdin
@@ -175,9 +400,18 @@ impl AgentManager {
         Self {
             agents: HashMap::new(),
             config: config.clone(),
+            log_sink: None,
         }
     }
 
+    /// Forward a copy of every log entry to `sink` (e.g. the Postgres archive)
+    ///
+    /// The in-memory buffer stays the source for `get_logs`; entries are
+    /// dropped from the sink rather than blocking when it falls behind.
+    pub fn set_log_sink(&mut self, sink: mpsc::Sender<(AgentId, LogEntry)>) {
+        self.log_sink = Some(sink);
+    }
+
     /// Spawn a new agent with the given role
     /// This registers the agent - actual Claude Code processes are spawned
     /// per-task in `send()` or on-demand via `start_interactive_session()`
@@ -203,6 +437,7 @@ impl AgentManager {
                 interactive_session: None,
                 current_task: None,
                 logs: Vec::new(),
+                session_output: SessionOutput::default(),
             },
         );
 
@@ -248,6 +483,7 @@ impl AgentManager {
         // SEC-007: Apply permission configuration instead of blanket --dangerously-skip-permissions
         let role_str = role.to_string();
         apply_permissions_to_pty_command(&mut cmd, &self.config.agents.permissions, &role_str);
+        apply_env_to_pty_command(&mut cmd, &self.config.agents, &role_str);
 
         cmd.env("CLAUDE_MD", &claude_md_path);
         cmd.env("TERM", "dumb");
@@ -271,7 +507,7 @@ impl AgentManager {
 
         // Create channels for async communication
         let (stdin_tx, mut stdin_rx) = mpsc::channel::<String>(32);
-        let (stdout_tx, stdout_rx) = mpsc::channel::<String>(32);
+        let (stdout_tx, stdout_rx) = mpsc::channel::<String>(256);
 
         // Spawn blocking task to write to PTY stdin
         tokio::task::spawn_blocking(move || {
@@ -289,23 +525,9 @@ impl AgentManager {
         });
 
         // Spawn blocking task to read from PTY stdout
-        tokio::task::spawn_blocking(move || {
-            let buf_reader = BufReader::new(reader);
-            for line in buf_reader.lines() {
-                match line {
-                    Ok(line) => {
-                        debug!("Read from PTY: {}", line);
-                        if stdout_tx.blocking_send(line).is_err() {
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to read from PTY: {}", e);
-                        break;
-                    }
-                }
-            }
-        });
+        managed.session_output.clear();
+        let output = managed.session_output.clone();
+        tokio::task::spawn_blocking(move || pump_session_output(reader, &output, &stdout_tx));
 
         managed.interactive_session = Some(InteractiveSession {
             stdin_tx,
@@ -431,6 +653,14 @@ impl AgentManager {
         self.agents.get(&agent_id).map(|m| &m.agent)
     }
 
+    /// Get the last `lines` lines of an agent's interactive session output
+    pub fn get_session_output(&self, agent_id: AgentId, lines: usize) -> Result<Vec<String>> {
+        self.agents
+            .get(&agent_id)
+            .map(|m| m.session_output.tail(lines))
+            .ok_or_else(|| anyhow!("Agent {agent_id} not found"))
+    }
+
     /// Check if agent has an interactive session
     pub fn has_interactive_session(&self, agent_id: AgentId) -> bool {
         self.agents
@@ -457,15 +687,8 @@ impl AgentManager {
         managed.current_task = Some(task_preview.clone());
 
         // Add log entry for task start
-        let entry = LogEntry {
-            timestamp: Utc::now(),
-            level: "INFO".to_string(),
-            message: format!("Starting task: {task_preview}"),
-        };
-        managed.logs.push(entry);
-        if managed.logs.len() > MAX_LOG_ENTRIES {
-            managed.logs.remove(0);
-        }
+        let entry = LogEntry::new("INFO", format!("Starting task: {task_preview}"));
+        push_log(&mut managed.logs, self.log_sink.as_ref(), agent_id, entry);
 
         Ok(TaskConfig {
             role,
@@ -481,35 +704,17 @@ impl AgentManager {
             managed.current_task = None;
 
             let entry = if success {
-                LogEntry {
-                    timestamp: Utc::now(),
-                    level: "INFO".to_string(),
-                    message: format!("Task completed successfully ({} bytes)", output.len()),
-                }
+                LogEntry::new("INFO", format!("Task completed successfully ({} bytes)", output.len()))
             } else {
-                LogEntry {
-                    timestamp: Utc::now(),
-                    level: "ERROR".to_string(),
-                    message: format!("Task failed: {}", error.unwrap_or("unknown error")),
-                }
+                LogEntry::new("ERROR", format!("Task failed: {}", error.unwrap_or("unknown error")))
             };
-            managed.logs.push(entry);
-            if managed.logs.len() > MAX_LOG_ENTRIES {
-                managed.logs.remove(0);
-            }
+            push_log(&mut managed.logs, self.log_sink.as_ref(), agent_id, entry);
 
             // Add output preview for successful tasks
             if success {
                 let output_preview = safe_truncate_with_ellipsis(output, 200);
-                let debug_entry = LogEntry {
-                    timestamp: Utc::now(),
-                    level: "DEBUG".to_string(),
-                    message: format!("Output: {}", output_preview.replace('\n', "\\n")),
-                };
-                managed.logs.push(debug_entry);
-                if managed.logs.len() > MAX_LOG_ENTRIES {
-                    managed.logs.remove(0);
-                }
+                let debug_entry = LogEntry::new("DEBUG", format!("Output: {}", output_preview.replace('\n', "\\n")));
+                push_log(&mut managed.logs, self.log_sink.as_ref(), agent_id, debug_entry);
             }
         }
     }
@@ -535,6 +740,7 @@ impl AgentManager {
         // Apply permission configuration (replaces blanket --dangerously-skip-permissions)
         let role_str = config.role.to_string();
         apply_permissions_to_command(&mut cmd, &self.config.agents.permissions, &role_str);
+        apply_env_to_command(&mut cmd, &self.config.agents, &role_str);
 
         // Non-interactive mode
         let output = cmd
@@ -546,7 +752,8 @@ impl AgentManager {
             .env("NO_COLOR", "1")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()?
+            .spawn()
+            .map_err(|e| anyhow!(claude_spawn_error(&config.claude_path, &e)))?
             .wait_with_output()
             .await?;
 
@@ -565,17 +772,8 @@ impl AgentManager {
     /// Add a log entry for an agent (public for external use)
     pub fn add_log(&mut self, agent_id: AgentId, level: &str, message: &str) {
         if let Some(managed) = self.agents.get_mut(&agent_id) {
-            let entry = LogEntry {
-                timestamp: Utc::now(),
-                level: level.to_string(),
-                message: message.to_string(),
-            };
-            managed.logs.push(entry);
-
-            // Keep only the last MAX_LOG_ENTRIES
-            if managed.logs.len() > MAX_LOG_ENTRIES {
-                managed.logs.remove(0);
-            }
+            let entry = LogEntry::new(level, message.to_string());
+            push_log(&mut managed.logs, self.log_sink.as_ref(), agent_id, entry);
         }
     }
 
@@ -591,21 +789,64 @@ impl AgentManager {
         }
     }
 
-    /// Get logs for an agent
-    pub fn get_logs(&self, agent_id: AgentId, limit: usize) -> Vec<LogEntry> {
+    /// Get the last `limit` logs for an agent at or above `min_level`
+    ///
+    /// Entries with an unrecognised level are always included.
+    pub fn get_logs(&self, agent_id: AgentId, limit: usize, min_level: Option<LogLevel>) -> Vec<LogEntry> {
         self.agents
             .get(&agent_id)
             .map(|m| {
-                let logs = &m.logs;
-                if logs.len() > limit {
-                    logs[logs.len() - limit..].to_vec()
-                } else {
-                    logs.clone()
-                }
+                let logs: Vec<&LogEntry> = m
+                    .logs
+                    .iter()
+                    .filter(|entry| entry.at_least(min_level))
+                    .collect();
+                logs[logs.len().saturating_sub(limit)..]
+                    .iter()
+                    .map(|entry| (*entry).clone())
+                    .collect()
             })
             .unwrap_or_default()
     }
 
+    /// Get up to `limit` logs written after sequence number `since`, oldest first
+    ///
+    /// Also returns the cursor to pass as `since` next time: the last returned
+    /// entry when `limit` cut the batch short, otherwise the newest entry in the
+    /// buffer (so entries skipped by `min_level` aren't scanned again).
+    pub fn get_logs_since(
+        &self,
+        agent_id: AgentId,
+        since: u64,
+        limit: usize,
+        min_level: Option<LogLevel>,
+    ) -> (Vec<LogEntry>, u64) {
+        let Some(managed) = self.agents.get(&agent_id) else {
+            return (Vec::new(), since);
+        };
+
+        let mut matching = managed
+            .logs
+            .iter()
+            .filter(|entry| entry.seq > since && entry.at_least(min_level));
+        let logs: Vec<LogEntry> = matching.by_ref().take(limit).cloned().collect();
+
+        let cursor = if matching.next().is_some() {
+            logs.last().map_or(since, |last| last.seq)
+        } else {
+            managed.logs.last().map_or(since, |newest| newest.seq)
+        };
+        (logs, cursor)
+    }
+
+    /// Sequence number of the newest log entry for an agent (0 if none)
+    pub fn latest_log_seq(&self, agent_id: AgentId) -> u64 {
+        self.agents
+            .get(&agent_id)
+            .and_then(|m| m.logs.last())
+            .map_or(0, |entry| entry.seq)
+    }
+
     /// Send a task to an agent with custom timeout
     pub async fn send_with_timeout(
         &mut self,
@@ -626,3 +867,207 @@ impl AgentManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_env_forwards_only_allowlisted_vars() {
+        let mut agents = Config::default().agents;
+        agents.env_passthrough = vec!["ANTHROPIC_API_KEY".to_string()];
+        agents
+            .role_env_passthrough
+            .insert("dba".to_string(), vec!["DATABASE_URL".to_string()]);
+        let daemon_env = |name: &str| {
+            matches!(name, "PATH" | "ANTHROPIC_API_KEY" | "DATABASE_URL" | "AWS_SECRET_ACCESS_KEY")
+                .then(|| OsString::from(format!("{name}-value")))
+        };
+
+        let names = |role: &str| -> Vec<String> {
+            agent_env(&agents, role, daemon_env).into_iter().map(|(name, _)| name).collect()
+        };
+        assert_eq!(names("backend"), vec!["PATH", "ANTHROPIC_API_KEY"]);
+        assert_eq!(names("dba"), vec!["PATH", "ANTHROPIC_API_KEY", "DATABASE_URL"]);
+    }
+
+    #[tokio::test]
+    async fn test_agent_process_gets_no_other_env() {
+        let mut agents = Config::default().agents;
+        agents.env_passthrough.clear();
+
+        let mut cmd = Command::new("env");
+        apply_env_to_command(&mut cmd, &agents, "backend");
+        let output = cmd.output().await.unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for line in stdout.lines() {
+            let name = line.split('=').next().unwrap_or_default();
+            assert!(AGENT_BASE_ENV.contains(&name), "{name} leaked into the agent environment");
+        }
+    }
+
+    #[test]
+    fn test_parse_claude_json_output_with_usage() {
+        let stdout = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":2140,
+            "num_turns":1,"result":"Added the endpoint.","session_id":"0f5c",
+            "total_cost_usd":0.0123,
+            "usage":{"input_tokens":1200,"cache_creation_input_tokens":0,
+                     "cache_read_input_tokens":3400,"output_tokens":345}}"#;
+        let parsed = parse_claude_output(stdout);
+        assert_eq!(parsed.text, "Added the endpoint.");
+        assert_eq!(parsed.input_tokens, 1200);
+        assert_eq!(parsed.output_tokens, 345);
+        assert_eq!(parsed.tokens_used(), 1545);
+
+        // No usage block: the result still comes through
+        let parsed = parse_claude_output(r#"{"result":"done"}"#);
+        assert_eq!(parsed.text, "done");
+        assert_eq!(parsed.tokens_used(), 0);
+    }
+
+    #[test]
+    fn test_parse_claude_output_falls_back_to_text() {
+        let parsed = parse_claude_output("Plain text answer\n");
+        assert_eq!(parsed.text, "Plain text answer\n");
+        assert_eq!(parsed.tokens_used(), 0);
+
+        // JSON that isn't a result object is treated as text too
+        assert_eq!(parse_claude_output(r#"{"foo":1}"#).text, r#"{"foo":1}"#);
+    }
+
+    #[test]
+    fn test_claude_spawn_error_explains_missing_binary() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(
+            claude_spawn_error("/opt/claude", &missing),
+            "Claude Code binary not found at /opt/claude; set CCA__AGENTS__CLAUDE_PATH"
+        );
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(claude_spawn_error("/opt/claude", &denied).starts_with("Failed to start Claude Code at /opt/claude: "));
+    }
+
+    #[tokio::test]
+    async fn test_session_output_returns_last_lines() {
+        let mut manager = AgentManager::new(&Config::default());
+        let agent_id = manager.spawn(AgentRole::Backend).await.unwrap();
+        assert!(manager.get_session_output(agent_id, 10).unwrap().is_empty());
+
+        // Stand in for the PTY with canned output
+        let pty = std::io::Cursor::new("line 1\nline 2\nline 3\nline 4\n");
+        let (stdout_tx, mut stdout_rx) = mpsc::channel(256);
+        let output = manager.agents[&agent_id].session_output.clone();
+        pump_session_output(pty, &output, &stdout_tx);
+
+        assert_eq!(
+            manager.get_session_output(agent_id, 2).unwrap(),
+            vec!["line 3".to_string(), "line 4".to_string()]
+        );
+        assert_eq!(manager.get_session_output(agent_id, 100).unwrap().len(), 4);
+        assert_eq!(stdout_rx.recv().await.as_deref(), Some("line 1"));
+
+        assert!(manager.get_session_output(AgentId::new(), 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_filters_by_level() {
+        let mut manager = AgentManager::new(&Config::default());
+        let agent_id = manager.spawn(AgentRole::Backend).await.unwrap();
+        for level in ["DEBUG", "INFO", "WARN", "ERROR", "debug"] {
+            manager.add_log(agent_id, level, &format!("{level} message"));
+        }
+
+        let levels = |min_level, limit| -> Vec<String> {
+            manager
+                .get_logs(agent_id, limit, min_level)
+                .into_iter()
+                .map(|e| e.level)
+                .collect()
+        };
+
+        assert_eq!(levels(Some(LogLevel::Info), 10), vec!["INFO", "WARN", "ERROR"]);
+        assert_eq!(levels(Some(LogLevel::Error), 10), vec!["ERROR"]);
+        assert_eq!(levels(None, 10).len(), 5);
+        // The limit applies after filtering
+        assert_eq!(levels(Some(LogLevel::Info), 2), vec!["WARN", "ERROR"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_since_pages_by_cursor() {
+        let mut manager = AgentManager::new(&Config::default());
+        let agent_id = manager.spawn(AgentRole::Backend).await.unwrap();
+        for i in 1..=5 {
+            manager.add_log(agent_id, if i == 4 { "DEBUG" } else { "INFO" }, &format!("line {i}"));
+        }
+        assert_eq!(manager.latest_log_seq(agent_id), 5);
+
+        let messages = |logs: Vec<LogEntry>| logs.into_iter().map(|e| e.message).collect::<Vec<_>>();
+
+        // A short limit stops the cursor at the last entry returned
+        let (logs, cursor) = manager.get_logs_since(agent_id, 1, 2, None);
+        assert_eq!(messages(logs), vec!["line 2", "line 3"]);
+        assert_eq!(cursor, 3);
+
+        // Filtered-out entries still move the cursor forward
+        let (logs, cursor) = manager.get_logs_since(agent_id, cursor, 10, Some(LogLevel::Info));
+        assert_eq!(messages(logs), vec!["line 5"]);
+        assert_eq!(cursor, 5);
+
+        let (logs, cursor) = manager.get_logs_since(agent_id, cursor, 10, None);
+        assert!(logs.is_empty());
+        assert_eq!(cursor, 5);
+    }
+
+    #[tokio::test]
+    async fn test_log_sink_receives_entries() {
+        let mut manager = AgentManager::new(&Config::default());
+        let agent_id = manager.spawn(AgentRole::Backend).await.unwrap();
+        let (sink, mut archived) = mpsc::channel(8);
+        manager.set_log_sink(sink);
+
+        manager.add_log(agent_id, "WARN", "disk almost full");
+        let (id, entry) = archived.recv().await.unwrap();
+        assert_eq!(id, agent_id);
+        assert_eq!((entry.level.as_str(), entry.message.as_str()), ("WARN", "disk almost full"));
+
+        // The in-memory buffer is still the fast path
+        assert_eq!(manager.get_logs(agent_id, 10, None).len(), 1);
+    }
+
+    #[test]
+    fn test_log_level_parse_and_order() {
+        assert_eq!(LogLevel::parse("warning"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("Error"), Some(LogLevel::Error));
+        assert_eq!(LogLevel::parse("loud"), None);
+        assert!(LogLevel::Debug < LogLevel::Info && LogLevel::Warn < LogLevel::Error);
+    }
+
+    #[test]
+    fn test_session_output_is_bounded() {
+        let output = SessionOutput::default();
+        let long_line = "x".repeat(MAX_SESSION_LINE_LEN * 2);
+        output.push(&long_line);
+        for i in 0..MAX_SESSION_OUTPUT_LINES {
+            output.push(&format!("line {i}"));
+        }
+
+        let all = output.tail(usize::MAX);
+        assert_eq!(all.len(), MAX_SESSION_OUTPUT_LINES);
+        assert_eq!(all[0], "line 0");
+        assert_eq!(all.last().unwrap(), &format!("line {}", MAX_SESSION_OUTPUT_LINES - 1));
+
+        // Overlong lines are truncated before they're stored
+        output.clear();
+        output.push(&long_line);
+        assert_eq!(output.tail(1)[0].len(), MAX_SESSION_LINE_LEN);
+    }
+
+    #[test]
+    fn test_pump_keeps_capturing_when_channel_is_full() {
+        let output = SessionOutput::default();
+        let (stdout_tx, _stdout_rx) = mpsc::channel(1);
+        let pty = std::io::Cursor::new("a\nb\nc\n");
+        pump_session_output(pty, &output, &stdout_tx);
+        assert_eq!(output.tail(3), vec!["a", "b", "c"]);
+    }
+}
@@ -8,7 +8,8 @@
 //! `SEC-004`: Per-IP rate limiting to prevent DoS attacks.
 //!
 //! Hot-reload support: The auth middleware can use SharedReloadableConfig
-//! for dynamic API key updates without service restart.
+//! for dynamic API key updates without service restart, and the rate limiter
+//! is held in a `SharedRateLimiter` that is rebuilt when limits are reloaded.
 
 use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroU32;
@@ -17,11 +18,12 @@ use std::sync::Arc;
 use axum::{
     body::Body,
     extract::{ConnectInfo, State},
-    http::{header, Request, StatusCode},
+    http::{header, Method, Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use cca_core::util::constant_time_eq;
+use ipnet::IpNet;
 use governor::{
     clock::DefaultClock,
     middleware::NoOpMiddleware,
@@ -33,7 +35,57 @@ use tracing::{debug, warn};
 use crate::config::SharedReloadableConfig;
 
 /// Paths that bypass authentication
-const BYPASS_PATHS: &[&str] = &["/health", "/api/v1/health"];
+const BYPASS_PATHS: &[&str] = &["/health", "/healthz", "/readyz", "/api/v1/health"];
+
+/// Prometheus endpoint, public unless `metrics_require_auth` is set
+const METRICS_PATH: &str = "/metrics";
+
+/// Scope required to call an endpoint, or `None` for the bypass paths
+///
+/// Reads need `<resource>:read`, mutations `<resource>:write`. Destructive
+/// endpoints (RL changes, disconnecting agents, config reload) need `:admin`.
+/// Anything not mapped here fails closed and needs `config:admin`.
+fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    if BYPASS_PATHS.contains(&path) {
+        return None;
+    }
+    if path == METRICS_PATH {
+        return Some("metrics:read");
+    }
+    let Some(rest) = path.strip_prefix("/api/v1/") else {
+        return Some("config:admin");
+    };
+    let resource = rest.split('/').next().unwrap_or_default();
+    let write = *method != Method::GET && *method != Method::HEAD;
+
+    let scope = match (resource, write) {
+        ("admin", _) | ("config", true) => "config:admin",
+        ("config", false) => "config:read",
+        ("rl", true) => "rl:admin",
+        ("rl", false) => "rl:read",
+        ("agents" | "orchestrator", true) if *method == Method::DELETE => "agents:admin",
+        // Relays speak for an arbitrary `from` agent
+        ("acp", true) if matches!(rest, "acp/disconnect" | "acp/disconnect-slow" | "acp/relay") => {
+            "agents:admin"
+        }
+        ("agents" | "orchestrator" | "acp" | "broadcast" | "pubsub" | "workloads", true) => {
+            "agents:write"
+        }
+        ("agents" | "orchestrator" | "acp" | "broadcast" | "pubsub" | "workloads", false) => {
+            "agents:read"
+        }
+        ("tasks" | "delegate" | "activity", true) => "tasks:write",
+        ("tasks" | "delegate" | "activity", false) => "tasks:read",
+        // Searches are POSTs but don't modify anything
+        ("memory" | "code", true) if rest.ends_with("/search") => "memory:read",
+        ("memory" | "code", true) => "memory:write",
+        ("memory" | "code", false) => "memory:read",
+        ("tokens", _) => "tokens:read",
+        ("status" | "redis" | "postgres", _) => "status:read",
+        _ => "config:admin",
+    };
+    Some(scope)
+}
 
 /// Dynamic authentication configuration that reads from SharedReloadableConfig
 /// This allows hot-reloading of API keys without restarting the daemon
@@ -43,6 +95,8 @@ pub struct DynamicAuthConfig {
     pub config: SharedReloadableConfig,
     /// Whether auth is required (from static config, not reloadable for security)
     pub required: bool,
+    /// Whether `/metrics` is authenticated like the API instead of bypassed
+    pub metrics_require_auth: bool,
 }
 
 /// Authentication middleware with hot-reload support
@@ -61,14 +115,14 @@ pub async fn dynamic_auth_middleware(
 
     // Check bypass paths
     let path = request.uri().path();
-    if BYPASS_PATHS.contains(&path) {
+    if BYPASS_PATHS.contains(&path) || (path == METRICS_PATH && !config.metrics_require_auth) {
         return Ok(next.run(request).await);
     }
 
     // Read API keys from reloadable config (hot-reload enabled)
-    let api_keys = {
+    let (api_keys, api_key_configs) = {
         let reload_config = config.config.read().await;
-        reload_config.api_keys.clone()
+        (reload_config.api_keys.clone(), reload_config.api_key_configs.clone())
     };
 
     // Check X-API-Key header
@@ -84,20 +138,39 @@ pub async fn dynamic_auth_middleware(
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.strip_prefix("Bearer "));
 
-    // Validate API key using constant-time comparison to prevent timing attacks
-    if let Some(key) = api_key {
-        if api_keys.iter().any(|k| constant_time_eq(k, key)) {
-            return Ok(next.run(request).await);
+    // Validate API key, then bearer token, using constant-time comparison to
+    // prevent timing attacks. Legacy keys are unrestricted; configured keys
+    // must hold the scope the route requires.
+    let required = required_scope(request.method(), path);
+    let mut authenticated = false;
+    for credential in [api_key, bearer_token].into_iter().flatten() {
+        if api_keys.iter().any(|k| constant_time_eq(k, credential)) {
+            authenticated = true;
+            break;
         }
-    }
 
-    // Validate bearer token using constant-time comparison to prevent timing attacks
-    if let Some(token) = bearer_token {
-        if api_keys.iter().any(|k| constant_time_eq(k, token)) {
-            return Ok(next.run(request).await);
+        if let Some(key_config) = api_key_configs
+            .iter()
+            .find(|c| constant_time_eq(&c.key, credential))
+        {
+            if let Some(scope) = required.filter(|scope| !key_config.has_scope(scope)) {
+                warn!(
+                    "Forbidden API request to {} - key {} lacks scope {}",
+                    path,
+                    key_config.key_id.as_deref().unwrap_or("<unnamed>"),
+                    scope
+                );
+                return Err(scope_forbidden_response(scope));
+            }
+            authenticated = true;
+            break;
         }
     }
 
+    if authenticated {
+        return Ok(next.run(request).await);
+    }
+
     warn!(
         "Unauthorized API request to {} - missing or invalid credentials",
         path
@@ -137,6 +210,10 @@ pub struct RateLimitConfig {
     pub api_key_rps: u32,
     /// Burst size for API key rate limiting
     pub api_key_burst: u32,
+    /// Networks that bypass all rate limits
+    pub allowlist: Vec<IpNet>,
+    /// Networks that are always rejected with 403 (takes precedence over `allowlist`)
+    pub denylist: Vec<IpNet>,
 }
 
 impl Default for RateLimitConfig {
@@ -148,6 +225,8 @@ impl Default for RateLimitConfig {
             trust_proxy: false,
             api_key_rps: 200,    // Higher limit for authenticated clients
             api_key_burst: 100,  // Higher burst for authenticated clients
+            allowlist: Vec::new(),
+            denylist: Vec::new(),
         }
     }
 }
@@ -164,6 +243,20 @@ pub struct RateLimiterState {
     pub global: Option<Arc<GlobalRateLimiter>>,
     /// Whether to trust X-Forwarded-For header
     pub trust_proxy: bool,
+    /// Networks that bypass all rate limits
+    pub allowlist: Arc<Vec<IpNet>>,
+    /// Networks that are always rejected
+    pub denylist: Arc<Vec<IpNet>>,
+}
+
+impl RateLimiterState {
+    fn is_denied(&self, ip: IpAddr) -> bool {
+        self.denylist.iter().any(|net| net.contains(&ip))
+    }
+
+    fn is_allowed(&self, ip: IpAddr) -> bool {
+        self.allowlist.iter().any(|net| net.contains(&ip))
+    }
 }
 
 /// Create a new rate limiter with the specified requests per second (legacy)
@@ -223,6 +316,8 @@ pub fn create_rate_limiter_state(config: &RateLimitConfig) -> RateLimiterState {
         per_api_key,
         global,
         trust_proxy: config.trust_proxy,
+        allowlist: Arc::new(config.allowlist.clone()),
+        denylist: Arc::new(config.denylist.clone()),
     }
 }
 
@@ -288,6 +383,7 @@ fn extract_client_ip(request: &Request<Body>, trust_proxy: bool) -> Option<IpAdd
 /// `SEC-004`: Per-client rate limiting to prevent DoS attacks
 ///
 /// Rate limiting strategy:
+/// 0. Denylisted IPs are rejected with 403; allowlisted IPs skip all limits
 /// 1. Global rate limit - absolute cap on all requests
 /// 2. Per-API-key rate limit - for authenticated requests (higher limits)
 /// 3. Per-IP rate limit - for all requests (fallback for unauthenticated)
@@ -312,6 +408,16 @@ pub async fn rate_limit_middleware(
             IpAddr::V4(std::net::Ipv4Addr::LOCALHOST)
         });
 
+    // SEC-004: Access lists are checked before any token bucket
+    if limiter.is_denied(client_ip) {
+        warn!("Rejected request to {} from denylisted IP {}", path, client_ip);
+        return Err(forbidden_response());
+    }
+    if limiter.is_allowed(client_ip) {
+        debug!("Skipping rate limits for {} from allowlisted IP {}", path, client_ip);
+        return Ok(next.run(request).await);
+    }
+
     // Extract API key for per-key rate limiting
     let api_key = extract_api_key(&request);
 
@@ -388,6 +494,43 @@ pub async fn rate_limit_middleware(
     }
 }
 
+/// Rate limiter that is swapped out when limits are reloaded (`None` = disabled)
+pub type SharedRateLimiter = Arc<std::sync::RwLock<Option<RateLimiterState>>>;
+
+/// Rate limiting middleware that reads the current limiter on each request
+///
+/// Replacing the limiter starts every client with a fresh bucket.
+pub async fn reloadable_rate_limit_middleware(
+    State(shared): State<SharedRateLimiter>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, Response> {
+    let limiter = shared.read().unwrap().clone();
+    match limiter {
+        Some(limiter) => rate_limit_middleware(State(limiter), request, next).await,
+        None => Ok(next.run(request).await),
+    }
+}
+
+/// Create a response for a key missing the scope a route requires
+fn scope_forbidden_response(scope: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        format!("Forbidden: API key lacks required scope '{scope}'"),
+    )
+        .into_response()
+}
+
+/// Create a response for a denylisted client
+fn forbidden_response() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        [("Content-Type", "application/json")],
+        r#"{"error":"Forbidden","message":"Access denied"}"#,
+    )
+        .into_response()
+}
+
 /// Create a rate limit exceeded response with proper headers
 fn rate_limit_response(retry_after: u64, remaining: u32) -> Response {
     rate_limit_response_with_type(retry_after, remaining, "global")
@@ -419,15 +562,17 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_auth_config_default() {
-        let config = AuthConfig::default();
-        assert!(!config.required);
+    fn test_daemon_config_requires_auth_by_default() {
+        let config = crate::config::DaemonConfig::default();
+        assert!(config.require_auth);
         assert!(config.api_keys.is_empty());
     }
 
     #[test]
     fn test_bypass_paths() {
         assert!(BYPASS_PATHS.contains(&"/health"));
+        assert!(BYPASS_PATHS.contains(&"/healthz"));
+        assert!(BYPASS_PATHS.contains(&"/readyz"));
     }
 
     #[test]
@@ -488,6 +633,7 @@ mod tests {
             trust_proxy: false,
             api_key_rps: 0,
             api_key_burst: 0,
+            ..Default::default()
         };
         let limiter = create_per_ip_rate_limiter(&config);
 
@@ -532,6 +678,7 @@ mod tests {
             trust_proxy: false,
             api_key_rps: 10,
             api_key_burst: 5,
+            ..Default::default()
         };
         let limiter = create_per_api_key_rate_limiter(&config);
 
@@ -584,6 +731,7 @@ mod tests {
             trust_proxy: false,
             api_key_rps: 10,
             api_key_burst: 3,
+            ..Default::default()
         };
 
         let ip_limiter = create_per_ip_rate_limiter(&config);
@@ -604,4 +752,272 @@ mod tests {
         }
         assert!(api_key_limiter.check_key(&key).is_err());
     }
+
+    /// Router behind the auth middleware with one legacy and two scoped keys
+    fn scoped_router() -> axum::Router {
+        scoped_router_with_metrics(false)
+    }
+
+    fn scoped_router_with_metrics(metrics_require_auth: bool) -> axum::Router {
+        use axum::routing::{get, post};
+
+        let mut config = crate::config::Config::default();
+        config.daemon.api_keys = vec!["legacy-key".to_string()];
+        config.daemon.api_key_configs = vec![
+            crate::config::ApiKeyConfig {
+                key: "read-only-key".to_string(),
+                allowed_roles: vec![],
+                key_id: Some("dashboard".to_string()),
+                allowed_scopes: vec!["tasks:read".to_string(), "rl:read".to_string()],
+            },
+            crate::config::ApiKeyConfig {
+                key: "operator-key".to_string(),
+                allowed_roles: vec![],
+                key_id: Some("operator".to_string()),
+                allowed_scopes: vec!["tasks:write".to_string(), "rl:write".to_string()],
+            },
+            crate::config::ApiKeyConfig {
+                key: "scraper-key".to_string(),
+                allowed_roles: vec![],
+                key_id: Some("prometheus".to_string()),
+                allowed_scopes: vec!["metrics:read".to_string()],
+            },
+        ];
+        let auth = DynamicAuthConfig {
+            config: Arc::new(tokio::sync::RwLock::new(config.to_reloadable())),
+            required: true,
+            metrics_require_auth,
+        };
+
+        axum::Router::new()
+            .route("/metrics", get(|| async { "cca_up 1" }))
+            .route("/api/v1/tasks", get(|| async { "list" }).post(|| async { "created" }))
+            .route("/api/v1/rl/train", post(|| async { "trained" }))
+            .route("/api/v1/unmapped", get(|| async { "unmapped" }))
+            .layer(axum::middleware::from_fn_with_state(auth, dynamic_auth_middleware))
+    }
+
+    async fn call(router: &axum::Router, method: Method, path: &str, key: &str) -> StatusCode {
+        use tower::ServiceExt;
+
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("X-API-Key", key)
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_required_scope_mapping() {
+        assert_eq!(required_scope(&Method::GET, "/api/v1/tasks"), Some("tasks:read"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/tasks"), Some("tasks:write"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/rl/train"), Some("rl:admin"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/memory/search"), Some("memory:read"));
+        assert_eq!(
+            required_scope(&Method::DELETE, "/api/v1/orchestrator/agents/abc"),
+            Some("agents:admin")
+        );
+        assert_eq!(required_scope(&Method::DELETE, "/api/v1/agents/abc"), Some("agents:admin"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/agents"), Some("agents:write"));
+        assert_eq!(
+            required_scope(&Method::POST, "/api/v1/acp/disconnect-slow"),
+            Some("agents:admin")
+        );
+        assert_eq!(required_scope(&Method::POST, "/api/v1/acp/relay"), Some("agents:admin"));
+        assert_eq!(
+            required_scope(&Method::POST, "/api/v1/admin/config/reload"),
+            Some("config:admin")
+        );
+        assert_eq!(required_scope(&Method::GET, "/api/v1/config"), Some("config:read"));
+        assert_eq!(required_scope(&Method::GET, "/metrics"), Some("metrics:read"));
+        assert_eq!(required_scope(&Method::GET, "/health"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/v1/health"), None);
+    }
+
+    #[test]
+    fn test_unmapped_routes_fail_closed() {
+        assert_eq!(required_scope(&Method::GET, "/api/v1/unmapped"), Some("config:admin"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/unmapped/thing"), Some("config:admin"));
+        assert_eq!(required_scope(&Method::GET, "/api/v1/"), Some("config:admin"));
+        assert_eq!(required_scope(&Method::GET, "/internal"), Some("config:admin"));
+    }
+
+    #[tokio::test]
+    async fn test_unmapped_route_rejects_scoped_keys() {
+        let router = scoped_router();
+        assert_eq!(
+            call(&router, Method::GET, "/api/v1/unmapped", "read-only-key").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call(&router, Method::GET, "/api/v1/unmapped", "legacy-key").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_key_blocked_from_write_route() {
+        let router = scoped_router();
+
+        assert_eq!(call(&router, Method::GET, "/api/v1/tasks", "read-only-key").await, StatusCode::OK);
+        assert_eq!(
+            call(&router, Method::POST, "/api/v1/tasks", "read-only-key").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call(&router, Method::POST, "/api/v1/tasks", "operator-key").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_destructive_route_requires_admin_scope() {
+        let router = scoped_router();
+
+        // rl:write is not enough to retrain the model
+        assert_eq!(
+            call(&router, Method::POST, "/api/v1/rl/train", "operator-key").await,
+            StatusCode::FORBIDDEN
+        );
+        // Legacy keys have no scope restrictions
+        assert_eq!(
+            call(&router, Method::POST, "/api/v1/rl/train", "legacy-key").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            call(&router, Method::POST, "/api/v1/rl/train", "unknown-key").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    async fn call_without_key(router: &axum::Router, path: &str) -> StatusCode {
+        use tower::ServiceExt;
+
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_public_by_default() {
+        let router = scoped_router();
+        assert_eq!(call_without_key(&router, "/metrics").await, StatusCode::OK);
+        assert_eq!(
+            call_without_key(&router, "/api/v1/tasks").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_require_auth_rejects_missing_key() {
+        let router = scoped_router_with_metrics(true);
+
+        assert_eq!(call_without_key(&router, "/metrics").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            call(&router, Method::GET, "/metrics", "unknown-key").await,
+            StatusCode::UNAUTHORIZED
+        );
+        // Scoped keys need metrics:read; legacy keys are unrestricted
+        assert_eq!(
+            call(&router, Method::GET, "/metrics", "read-only-key").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(call(&router, Method::GET, "/metrics", "scraper-key").await, StatusCode::OK);
+        assert_eq!(call(&router, Method::GET, "/metrics", "legacy-key").await, StatusCode::OK);
+    }
+
+    /// Router with a 1-request burst behind the rate limit middleware
+    fn limited_router(config: &RateLimitConfig) -> axum::Router {
+        axum::Router::new()
+            .route("/api/v1/status", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                create_rate_limiter_state(config),
+                rate_limit_middleware,
+            ))
+    }
+
+    fn request_from(ip: &str, forwarded_for: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().uri("/api/v1/status");
+        if let Some(forwarded) = forwarded_for {
+            request = request.header("X-Forwarded-For", forwarded);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        let addr: SocketAddr = format!("{ip}:40000").parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(addr));
+        request
+    }
+
+    fn strict_config() -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_second: 1,
+            burst_size: 1,
+            global_rps: 0,
+            api_key_rps: 0,
+            allowlist: vec!["10.1.0.0/16".parse().unwrap()],
+            denylist: vec!["203.0.113.7/32".parse().unwrap()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_ip_bypasses_rate_limit() {
+        use tower::ServiceExt;
+
+        let router = limited_router(&strict_config());
+
+        for _ in 0..5 {
+            let response = router.clone().oneshot(request_from("10.1.2.3", None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // A non-listed IP is limited after its 1-request burst
+        let first = router.clone().oneshot(request_from("10.2.0.1", None)).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let second = router.oneshot(request_from("10.2.0.1", None)).await.unwrap();
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_denylisted_ip_is_rejected() {
+        use tower::ServiceExt;
+
+        let router = limited_router(&strict_config());
+        let response = router.oneshot(request_from("203.0.113.7", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_access_lists_honor_trust_proxy() {
+        use tower::ServiceExt;
+
+        // Without trust_proxy the forwarded header is ignored
+        let router = limited_router(&strict_config());
+        let response = router
+            .oneshot(request_from("192.0.2.1", Some("203.0.113.7")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Behind a trusted proxy the forwarded client IP is checked
+        let router = limited_router(&RateLimitConfig {
+            trust_proxy: true,
+            ..strict_config()
+        });
+        let response = router
+            .clone()
+            .oneshot(request_from("192.0.2.1", Some("203.0.113.7, 192.0.2.1")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        for _ in 0..3 {
+            let response = router
+                .clone()
+                .oneshot(request_from("192.0.2.1", Some("10.1.9.9")))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
 }
@@ -0,0 +1,238 @@
+//! Task cancellation
+//!
+//! Every `send_task` call made on behalf of an API task runs on its own tokio
+//! task whose abort handle is registered here under the API task id. Cancelling
+//! the task aborts those in-flight sends and reports which agents were working
+//! on it, so the daemon can tell them to stop as well. Sends registered after
+//! a task was cancelled are aborted immediately.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use cca_core::{AgentId, TaskRunStatus};
+use chrono::Utc;
+use tokio::sync::RwLock;
+use tokio::task::AbortHandle;
+
+use crate::daemon::TaskState;
+
+/// Result of a cancellation request
+#[derive(Debug, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// Task marked cancelled; these agents had sends aborted
+    Cancelled(Vec<AgentId>),
+    NotFound,
+    /// Task had already reached the given terminal status
+    AlreadyFinished(TaskRunStatus),
+}
+
+#[derive(Default)]
+struct Entry {
+    cancelled: bool,
+    in_flight: Vec<(u64, AgentId, AbortHandle)>,
+}
+
+/// In-flight `send_task` handles keyed by API task id
+#[derive(Default)]
+pub struct TaskCancellation {
+    entries: Mutex<HashMap<String, Entry>>,
+    next_token: AtomicU64,
+}
+
+impl TaskCancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a send to `agent_id`; returns a token for [`Self::deregister`]
+    ///
+    /// If the task was already cancelled the send is aborted right away.
+    pub fn register(&self, task_id: &str, agent_id: AgentId, handle: AbortHandle) -> u64 {
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(task_id.to_string()).or_default();
+        if entry.cancelled {
+            handle.abort();
+        } else {
+            entry.in_flight.push((token, agent_id, handle));
+        }
+        token
+    }
+
+    /// Stop tracking a send once it has finished
+    pub fn deregister(&self, task_id: &str, token: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(task_id) {
+            entry.in_flight.retain(|(t, _, _)| *t != token);
+            if entry.in_flight.is_empty() && !entry.cancelled {
+                entries.remove(task_id);
+            }
+        }
+    }
+
+    /// Abort every in-flight send for the task and block future ones
+    ///
+    /// Returns the agents whose sends were aborted.
+    pub fn cancel(&self, task_id: &str) -> Vec<AgentId> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(task_id.to_string()).or_default();
+        entry.cancelled = true;
+
+        let mut agents = Vec::new();
+        for (_, agent_id, handle) in entry.in_flight.drain(..) {
+            handle.abort();
+            if !agents.contains(&agent_id) {
+                agents.push(agent_id);
+            }
+        }
+        agents
+    }
+
+    pub fn is_cancelled(&self, task_id: &str) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .is_some_and(|e| e.cancelled)
+    }
+
+    /// Drop bookkeeping for tasks that no longer exist
+    pub fn retain(&self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.lock().unwrap().retain(|id, _| keep(id));
+    }
+}
+
+/// Mark a task cancelled and abort its in-flight sends
+///
+/// The status is switched while holding the task map's write lock, so a
+/// handler finishing concurrently either sees the cancellation or had
+/// already recorded its terminal status.
+pub async fn cancel_task(
+    tasks: &RwLock<HashMap<String, TaskState>>,
+    registry: &TaskCancellation,
+    task_id: &str,
+) -> CancelOutcome {
+    let mut tasks = tasks.write().await;
+    let Some(task) = tasks.get_mut(task_id) else {
+        return CancelOutcome::NotFound;
+    };
+    if task.status.is_terminal() {
+        return CancelOutcome::AlreadyFinished(task.status);
+    }
+
+    task.status = TaskRunStatus::Cancelled;
+    task.error = Some("Cancelled by request".to_string());
+    task.updated_at = Utc::now();
+
+    CancelOutcome::Cancelled(registry.cancel(task_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn pending_send() -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async { tokio::time::sleep(Duration::from_secs(3600)).await })
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_in_flight_sends() {
+        let registry = TaskCancellation::new();
+        let (a, b) = (AgentId::new(), AgentId::new());
+
+        let first = pending_send();
+        let second = pending_send();
+        registry.register("task-1", a, first.abort_handle());
+        registry.register("task-1", b, second.abort_handle());
+
+        let other = pending_send();
+        registry.register("task-2", a, other.abort_handle());
+
+        let agents = registry.cancel("task-1");
+        assert_eq!(agents.len(), 2);
+        assert!(agents.contains(&a) && agents.contains(&b));
+        assert!(registry.is_cancelled("task-1"));
+
+        assert!(first.await.unwrap_err().is_cancelled());
+        assert!(second.await.unwrap_err().is_cancelled());
+
+        // Other tasks are untouched
+        assert!(!registry.is_cancelled("task-2"));
+        assert!(!other.is_finished());
+        other.abort();
+    }
+
+    fn task(task_id: &str, status: TaskRunStatus) -> TaskState {
+        TaskState {
+            task_id: task_id.to_string(),
+            description: "Refactor the auth module".to_string(),
+            status,
+            priority: "normal".to_string(),
+            output: None,
+            error: None,
+            assigned_agent: None,
+            delegations: Vec::new(),
+            progress: None,
+            content_hash: None,
+            use_delegation_cache: false,
+            deadline: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_task_marks_cancelled_and_aborts() {
+        let tasks = RwLock::new(HashMap::from([
+            ("running".to_string(), task("running", TaskRunStatus::Running)),
+            ("done".to_string(), task("done", TaskRunStatus::Completed)),
+        ]));
+        let registry = TaskCancellation::new();
+        let agent = AgentId::new();
+        let send = pending_send();
+        registry.register("running", agent, send.abort_handle());
+
+        let outcome = cancel_task(&tasks, &registry, "running").await;
+        assert_eq!(outcome, CancelOutcome::Cancelled(vec![agent]));
+        assert_eq!(tasks.read().await["running"].status, TaskRunStatus::Cancelled);
+        assert!(send.await.unwrap_err().is_cancelled());
+
+        // Finished and unknown tasks are left alone
+        assert_eq!(
+            cancel_task(&tasks, &registry, "done").await,
+            CancelOutcome::AlreadyFinished(TaskRunStatus::Completed)
+        );
+        assert_eq!(tasks.read().await["done"].status, TaskRunStatus::Completed);
+        assert_eq!(cancel_task(&tasks, &registry, "missing").await, CancelOutcome::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_sends_registered_after_cancel_are_aborted() {
+        let registry = TaskCancellation::new();
+        assert!(registry.cancel("task-1").is_empty());
+
+        let late = pending_send();
+        registry.register("task-1", AgentId::new(), late.abort_handle());
+        assert!(late.await.unwrap_err().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_deregister_and_retain_clean_up() {
+        let registry = TaskCancellation::new();
+        let send = pending_send();
+        let token = registry.register("task-1", AgentId::new(), send.abort_handle());
+        registry.deregister("task-1", token);
+        assert!(registry.entries.lock().unwrap().is_empty());
+
+        // Nothing left to abort, but the task still counts as cancelled
+        assert!(registry.cancel("task-1").is_empty());
+        assert!(!send.is_finished());
+        send.abort();
+
+        registry.retain(|id| id != "task-1");
+        assert!(!registry.is_cancelled("task-1"));
+    }
+}
@@ -0,0 +1,220 @@
+//! Chaos fault injection for HTTP testing
+//!
+//! Only compiled with the `chaos` feature. When latency injection is switched
+//! on through `POST /api/v1/admin/chaos/latency`, the middleware holds back a
+//! fraction of requests for `delay_ms` before handling them, so client timeout
+//! handling can be exercised against a real daemon. The toggle endpoint itself
+//! is never delayed.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Latency injection settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencySettings {
+    pub enabled: bool,
+    pub delay_ms: u64,
+    /// Fraction of requests to delay, 0.0 to 1.0
+    pub fraction: f64,
+}
+
+impl Default for LatencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_ms: 0,
+            fraction: 1.0,
+        }
+    }
+}
+
+/// Runtime toggle for request latency injection
+#[derive(Default)]
+pub struct LatencyInjector {
+    settings: RwLock<LatencySettings>,
+    requests: AtomicU64,
+}
+
+pub type SharedLatencyInjector = Arc<LatencyInjector>;
+
+impl LatencyInjector {
+    pub fn settings(&self) -> LatencySettings {
+        *self.settings.read().unwrap()
+    }
+
+    pub fn set(&self, settings: LatencySettings) {
+        *self.settings.write().unwrap() = settings;
+        self.requests.store(0, Ordering::Relaxed);
+    }
+
+    /// Delay to apply to the next request, if any
+    ///
+    /// Requests are picked deterministically so that exactly `fraction` of
+    /// them are delayed over any run, e.g. every fourth one at 0.25.
+    pub fn next_delay(&self) -> Option<Duration> {
+        let settings = self.settings();
+        if !settings.enabled || settings.delay_ms == 0 || settings.fraction <= 0.0 {
+            return None;
+        }
+        let n = self.requests.fetch_add(1, Ordering::Relaxed) as f64;
+        let picked = ((n + 1.0) * settings.fraction).floor() > (n * settings.fraction).floor();
+        picked.then(|| Duration::from_millis(settings.delay_ms))
+    }
+}
+
+/// Hold back the request when latency injection picks it
+pub async fn latency_injection_middleware(
+    State(injector): State<SharedLatencyInjector>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(delay) = injector.next_delay() {
+        tokio::time::sleep(delay).await;
+    }
+    next.run(request).await
+}
+
+/// Routes for `/api/v1/admin/chaos`
+pub fn router(injector: SharedLatencyInjector) -> Router {
+    Router::new()
+        .route("/latency", get(get_latency).post(set_latency))
+        .with_state(injector)
+}
+
+async fn get_latency(State(injector): State<SharedLatencyInjector>) -> Json<LatencySettings> {
+    Json(injector.settings())
+}
+
+async fn set_latency(
+    State(injector): State<SharedLatencyInjector>,
+    Json(settings): Json<LatencySettings>,
+) -> Response {
+    if !(0.0..=1.0).contains(&settings.fraction) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "success": false,
+                "error": "fraction must be between 0.0 and 1.0"
+            })),
+        )
+            .into_response();
+    }
+
+    if settings.enabled {
+        warn!(
+            "Chaos latency injection enabled: {}ms on {:.0}% of requests",
+            settings.delay_ms,
+            settings.fraction * 100.0
+        );
+    } else {
+        info!("Chaos latency injection disabled");
+    }
+    injector.set(settings);
+    Json(settings).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tower::ServiceExt;
+
+    fn app(injector: SharedLatencyInjector) -> Router {
+        Router::new()
+            .route("/api/v1/tasks", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                injector.clone(),
+                latency_injection_middleware,
+            ))
+            .nest_service("/api/v1/admin/chaos", router(injector))
+    }
+
+    async fn send(app: &Router, method: &str, path: &str, body: Option<&str>) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_fraction_picks_matching_share_of_requests() {
+        let injector = LatencyInjector::default();
+        assert_eq!(injector.next_delay(), None);
+
+        injector.set(LatencySettings {
+            enabled: true,
+            delay_ms: 50,
+            fraction: 0.25,
+        });
+        let delayed = (0..100).filter(|_| injector.next_delay().is_some()).count();
+        assert_eq!(delayed, 25);
+    }
+
+    #[tokio::test]
+    async fn test_delayed_responses_exceed_configured_delay() {
+        let injector = SharedLatencyInjector::default();
+        let app = app(injector.clone());
+
+        let status = send(
+            &app,
+            "POST",
+            "/api/v1/admin/chaos/latency",
+            Some(r#"{"enabled":true,"delay_ms":200,"fraction":1.0}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(injector.settings().enabled);
+
+        let start = Instant::now();
+        assert_eq!(
+            send(&app, "GET", "/api/v1/tasks", None).await,
+            StatusCode::OK
+        );
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // The toggle endpoint is not delayed, so latency can always be switched off
+        let start = Instant::now();
+        let status = send(
+            &app,
+            "POST",
+            "/api/v1/admin/chaos/latency",
+            Some(r#"{"enabled":false,"delay_ms":0,"fraction":1.0}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        let start = Instant::now();
+        send(&app, "GET", "/api/v1/tasks", None).await;
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_out_of_range_fraction() {
+        let app = app(SharedLatencyInjector::default());
+        let status = send(
+            &app,
+            "POST",
+            "/api/v1/admin/chaos/latency",
+            Some(r#"{"enabled":true,"delay_ms":10,"fraction":1.5}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,161 @@
+//! Per-role circuit breaker for agent dispatch
+//!
+//! After `threshold` consecutive failures for a role the breaker opens and
+//! dispatch to that role is refused for `cooldown`. Once the cooldown passes
+//! the breaker half-opens and lets a single probe task through: success closes
+//! it again, failure re-opens it for another cooldown.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+/// Default consecutive failures before a role's breaker opens
+pub const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+
+/// Default time an open breaker refuses dispatch
+pub const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed,
+    Open { until: Instant },
+    /// A probe was let through at `probe_started`; another is allowed if it
+    /// never reports back within a cooldown
+    HalfOpen { probe_started: Instant },
+}
+
+/// Breaker for one agent role
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    state: State,
+}
+
+/// Point-in-time view of a breaker, for `/api/v1/workloads`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BreakerSnapshot {
+    /// `closed`, `open` or `half_open`
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+    /// Seconds until an open breaker half-opens
+    pub retry_after_secs: Option<f64>,
+}
+
+impl CircuitBreaker {
+    /// A closed breaker (`threshold` 0 never opens)
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            consecutive_failures: 0,
+            state: State::Closed,
+        }
+    }
+
+    /// Whether a task may be dispatched now
+    ///
+    /// Returns how long to wait when the breaker is open. Letting a task
+    /// through an expired open breaker half-opens it.
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.state {
+            State::Closed => Ok(()),
+            State::Open { until } if now < until => Err(until - now),
+            State::HalfOpen { probe_started } if now < probe_started + self.cooldown => {
+                Err(probe_started + self.cooldown - now)
+            }
+            State::Open { .. } | State::HalfOpen { .. } => {
+                self.state = State::HalfOpen { probe_started: now };
+                Ok(())
+            }
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.state = State::Closed;
+    }
+
+    pub fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let trips = match self.state {
+            State::HalfOpen { .. } => true,
+            State::Closed => self.threshold > 0 && self.consecutive_failures >= self.threshold,
+            State::Open { .. } => false,
+        };
+        if trips {
+            self.state = State::Open { until: now + self.cooldown };
+        }
+    }
+
+    pub fn snapshot(&self, now: Instant) -> BreakerSnapshot {
+        let (state, retry_after) = match self.state {
+            State::Closed => ("closed", None),
+            State::Open { until } if now < until => ("open", Some(until - now)),
+            // Expired open breakers half-open on the next dispatch
+            State::Open { .. } | State::HalfOpen { .. } => ("half_open", None),
+        };
+        BreakerSnapshot {
+            state,
+            consecutive_failures: self.consecutive_failures,
+            retry_after_secs: retry_after.map(|d| d.as_secs_f64()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_threshold_and_recovers() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            breaker.record_failure(start);
+        }
+        assert!(breaker.try_acquire(start).is_ok());
+
+        breaker.record_failure(start);
+        assert_eq!(breaker.snapshot(start).state, "open");
+        assert_eq!(breaker.try_acquire(start), Err(Duration::from_secs(30)));
+
+        // Cooldown over: one probe goes through, the next caller waits on it
+        let later = start + Duration::from_secs(30);
+        assert_eq!(breaker.snapshot(later).state, "half_open");
+        assert!(breaker.try_acquire(later).is_ok());
+        assert!(breaker.try_acquire(later).is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.snapshot(later).state, "closed");
+        assert_eq!(breaker.snapshot(later).consecutive_failures, 0);
+        assert!(breaker.try_acquire(later).is_ok());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens_breaker() {
+        let mut breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        let start = Instant::now();
+
+        breaker.record_failure(start);
+        let probe_at = start + Duration::from_secs(10);
+        assert!(breaker.try_acquire(probe_at).is_ok());
+        breaker.record_failure(probe_at);
+
+        assert_eq!(breaker.snapshot(probe_at).state, "open");
+        assert_eq!(breaker.try_acquire(probe_at), Err(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_zero_threshold_never_opens() {
+        let mut breaker = CircuitBreaker::new(0, Duration::from_secs(10));
+        let now = Instant::now();
+        for _ in 0..100 {
+            breaker.record_failure(now);
+        }
+        assert!(breaker.try_acquire(now).is_ok());
+    }
+}
@@ -5,49 +5,64 @@
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use axum::extract::{Path, State};
-use axum::http::{HeaderValue, Method};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
 use axum::{
-    routing::{get, post},
-    Json, Router,
+    routing::{delete, get, post},
+    Extension, Json, Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
-use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::cors::{AllowCredentials, AllowOrigin, CorsLayer};
 use axum::extract::DefaultBodyLimit;
 use tower_http::set_header::SetResponseHeaderLayer;
 use validator::Validate;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
 use cca_acp::AcpServer;
-use cca_core::{AgentRole, AgentId, TaskId};
+use cca_core::{AgentRole, AgentId, CCAError, PatternId, TaskId, TaskRunStatus};
+use cca_core::memory::{Pattern, PatternQuery, PatternStore, PatternType as MemoryPatternType, SearchMatch};
 use cca_core::util::safe_truncate;
 use cca_rl::{Action, Experience, State as RLState, state::AgentState as RLAgentState};
 
 use crate::rl::compute_reward;
 
-use crate::agent_manager::{AgentManager, apply_permissions_to_command};
+use crate::agent_manager::{
+    AgentManager, ClaudeOutput, LogLevel, apply_env_to_command, apply_permissions_to_command, claude_spawn_error,
+    parse_claude_output,
+};
+use crate::cancellation::{cancel_task, CancelOutcome, TaskCancellation};
 use crate::auth::{
-    create_rate_limiter_state, dynamic_auth_middleware, rate_limit_middleware,
-    DynamicAuthConfig, RateLimitConfig,
+    create_rate_limiter_state, dynamic_auth_middleware, reloadable_rate_limit_middleware,
+    DynamicAuthConfig, RateLimitConfig, RateLimiterState, SharedRateLimiter,
+};
+use crate::config::{
+    Config, DaemonConfig, ReloadChanges, ReloadResult, ReloadableConfig, SharedReloadableConfig,
 };
-use crate::config::{Config, ReloadResult, SharedReloadableConfig};
+use crate::dispatch::{DispatchQueue, QueueFull, QueueTicket, TaskPriority};
+use crate::delegation_cache::DelegationCache;
+use crate::idempotency::{body_hash, caller_api_key, request_key, IdempotencyStore};
 use crate::orchestrator::Orchestrator;
 use crate::postgres::PostgresServices;
 use crate::redis::{PubSubMessage, RedisAgentState, RedisServices};
+use crate::request_id::{request_id_middleware, RequestId};
+use crate::service_error::{require_service, ServiceError, ServiceResult};
 use crate::rl::{RLConfig, RLService};
-use crate::tokens::TokenService;
+use crate::tokens::{BudgetDecision, TokenService};
 use crate::embeddings::{EmbeddingConfig, EmbeddingService};
 use crate::indexing::{IndexingService, StartIndexingRequest};
 use crate::validation::{
+    screen_agent_content, validate_broadcast, validate_pattern_filters, validate_query, validate_task_request,
+    ValidationError,
     DEFAULT_BODY_LIMIT,
-    MAX_TASK_DESCRIPTION_LEN, MAX_BROADCAST_MESSAGE_LEN, MAX_CONTENT_LEN,
-    MAX_QUERY_LEN, MAX_ROLE_LEN, MAX_ALGORITHM_LEN, MAX_PATH_LEN,
+    MAX_TASK_DESCRIPTION_LEN, MAX_CONTENT_LEN,
+    MAX_ROLE_LEN, MAX_ALGORITHM_LEN, MAX_PATH_LEN,
     MAX_PRIORITY_LEN, MAX_TIMEOUT_SECONDS, MIN_TIMEOUT_SECONDS,
     VALID_PRIORITIES, VALID_RL_ALGORITHMS,
 };
@@ -59,19 +74,39 @@ pub struct DaemonState {
     /// Hot-reloadable configuration (API keys, rate limits, agent settings)
     /// This allows updating these values without restarting the daemon
     pub reloadable_config: SharedReloadableConfig,
+    /// SEC-004: Current rate limiter, rebuilt when limits are reloaded
+    pub rate_limiter: SharedRateLimiter,
+    /// SEC-010: Current CORS origins, replaced when they are reloaded
+    pub cors_policy: SharedCorsPolicy,
+    /// Applies a reloaded log level (`None` when `RUST_LOG` controls logging)
+    pub log_reloader: Option<LogLevelReloader>,
     pub agent_manager: Arc<RwLock<AgentManager>>,
     pub orchestrator: Arc<RwLock<Orchestrator>>,
     pub tasks: Arc<RwLock<HashMap<String, TaskState>>>,
+    /// Abort handles for in-flight agent sends, keyed by task id
+    pub cancellation: Arc<TaskCancellation>,
     pub redis: Option<Arc<RedisServices>>,
     pub postgres: Option<Arc<PostgresServices>>,
+    /// ReasoningBank patterns (backed by `postgres.patterns` when PostgreSQL is up)
+    pub pattern_store: Option<Arc<dyn PatternStore>>,
     pub acp_server: Arc<AcpServer>,
     pub rl_service: Arc<RLService>,
     pub token_service: Arc<TokenService>,
     pub tmux_manager: Arc<crate::tmux::TmuxManager>,
     /// Track which agents are currently busy with a task
     pub busy_agents: Arc<RwLock<HashMap<AgentId, String>>>,
+    /// Delegations waiting for a busy agent, in priority order
+    pub dispatch_queue: Arc<DispatchQueue>,
+    /// Responses to replay for requests carrying an `Idempotency-Key`
+    pub idempotency: Arc<IdempotencyStore>,
+    /// Redis task locks this daemon holds, by lock key, shared by identical tasks
+    task_claims: Arc<tokio::sync::Mutex<HashMap<String, std::sync::Weak<TaskClaim>>>>,
+    /// Outputs of successful delegations, for tasks that opt into reuse
+    pub delegation_cache: Arc<DelegationCache>,
     /// Cached health check result - PERF-003
     health_cache: Arc<RwLock<Option<CachedHealthCheck>>>,
+    /// Set once `run` has started every background service, for `/readyz`
+    pub started: Arc<AtomicBool>,
     /// Embedding service for semantic search (optional, requires Ollama)
     pub embedding_service: Option<Arc<EmbeddingService>>,
     /// Indexing service for codebase indexing (optional, requires embeddings + postgres)
@@ -83,15 +118,33 @@ pub struct DaemonState {
 pub struct TaskState {
     pub task_id: String,
     pub description: String,
-    pub status: String,
+    pub status: TaskRunStatus,
     pub priority: String,
     pub output: Option<String>,
     pub error: Option<String>,
     pub assigned_agent: Option<String>,
+    /// Per-agent results of the task's delegations
+    #[serde(default)]
+    pub delegations: Vec<DelegationResult>,
+    /// Latest progress reported by a worker (`task.progress`)
+    #[serde(default)]
+    pub progress: Option<TaskProgress>,
+    /// Hash of the normalized description, for `dedupe` requests
+    #[serde(skip)]
+    pub content_hash: Option<u64>,
+    /// Reuse cached outputs of identical delegations (`use_delegation_cache`)
+    #[serde(skip)]
+    pub use_delegation_cache: bool,
+    /// When the task's delegations must be done by (`agents.task_deadline_seconds`)
+    #[serde(skip)]
+    pub deadline: Option<tokio::time::Instant>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Swaps the daemon's log filter to a new `daemon.log_level`
+pub type LogLevelReloader = Arc<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
 /// Main CCA Daemon
 pub struct CCADaemon {
     config: Config,
@@ -121,6 +174,8 @@ impl CCADaemon {
         // Initialize PostgreSQL services
         let postgres = match PostgresServices::new(&config.postgres).await {
             Ok(services) => {
+                // Fail fast on a stale schema rather than erroring on queries at runtime
+                services.db.check_schema().await?;
                 info!("PostgreSQL services initialized");
                 Some(Arc::new(services))
             }
@@ -148,6 +203,7 @@ impl CCADaemon {
                 key: cfg.key.clone(),
                 allowed_roles: cfg.allowed_roles.clone(),
                 key_id: cfg.key_id.clone(),
+                allowed_scopes: cfg.allowed_scopes.clone(),
             })
             .collect();
 
@@ -156,7 +212,9 @@ impl CCADaemon {
             api_key_metadata,
             require_auth: config.daemon.is_auth_required(),
         };
-        let acp_server = Arc::new(AcpServer::with_auth(acp_addr, acp_auth_config));
+        let acp_server = Arc::new(
+            AcpServer::with_auth(acp_addr, acp_auth_config).with_max_message_size(config.acp.max_message_size),
+        );
         info!(
             "ACP server configured on port {} (auth: {})",
             config.acp.websocket_port,
@@ -175,7 +233,11 @@ impl CCADaemon {
         info!("RL service initialized with algorithm: q_learning");
 
         // Initialize Orchestrator with all dependencies
-        let mut orchestrator = Orchestrator::new();
+        let mut orchestrator = Orchestrator::new()
+            .with_circuit_breaker(
+                config.agents.circuit_breaker_threshold,
+                std::time::Duration::from_secs(config.agents.circuit_breaker_cooldown_seconds),
+            );
         if let Some(ref r) = redis {
             orchestrator = orchestrator.with_redis(r.clone());
         }
@@ -185,11 +247,28 @@ impl CCADaemon {
         info!("Orchestrator initialized with RL-based task routing");
 
         // Initialize Token efficiency service
-        let token_service = Arc::new(TokenService::new());
+        let token_counter = crate::tokens::counter_for(&config.token_efficiency.tokenizer)?;
+        let mut token_service = TokenService::new()
+            .with_counter(token_counter)
+            .with_budget(
+                config.agents.token_budget_per_agent,
+                std::time::Duration::from_secs(config.agents.token_budget_window_seconds),
+            );
+        if config.token_efficiency.preserve_debug_lines {
+            token_service = token_service
+                .with_preserve_patterns(crate::tokens::ContextCompressor::default_preserve_patterns());
+        }
+        let token_service = Arc::new(token_service);
         info!("Token efficiency service initialized");
 
         // Initialize Tmux manager for auto-spawning agents
-        let tmux_manager = Arc::new(crate::tmux::TmuxManager::new());
+        let spawn_command = crate::tmux::SpawnCommand::new(
+            &config.agents.spawn_command,
+            spawn_vars(&config),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid agents.spawn_command: {e}"))?;
+        let tmux_manager =
+            Arc::new(crate::tmux::TmuxManager::new().with_spawn_command(spawn_command));
         if tmux_manager.is_available() {
             info!("Tmux auto-spawn enabled (max {} agents)", crate::tmux::MAX_AUTO_AGENTS);
         }
@@ -236,22 +315,48 @@ impl CCADaemon {
         };
 
         // Create hot-reloadable config wrapper
-        let reloadable_config = Arc::new(RwLock::new(config.to_reloadable()));
+        let reloadable = config.to_reloadable();
+        let rate_limiter = Arc::new(std::sync::RwLock::new(build_rate_limiter(&config.daemon, &reloadable)));
+        let cors_policy = Arc::new(std::sync::RwLock::new(CorsPolicy::new(
+            &reloadable.cors_origins,
+            reloadable.cors_allow_credentials,
+        )));
+        let reloadable_config = Arc::new(RwLock::new(reloadable));
 
         let state = DaemonState {
             config: config.clone(),
             reloadable_config,
+            rate_limiter,
+            cors_policy,
+            log_reloader: None,
             agent_manager: agent_manager.clone(),
             orchestrator: orchestrator.clone(),
             tasks: Arc::new(RwLock::new(HashMap::new())),
+            cancellation: Arc::new(TaskCancellation::new()),
             redis,
+            pattern_store: postgres
+                .as_ref()
+                .map(|pg| Arc::clone(&pg.patterns) as Arc<dyn PatternStore>),
             postgres,
             acp_server,
             rl_service,
             token_service,
             tmux_manager,
             busy_agents: Arc::new(RwLock::new(HashMap::new())),
+            dispatch_queue: Arc::new(DispatchQueue::new(
+                config.agents.dispatch_queue_capacity as usize,
+                config.agents.priority_aging_rate,
+            )),
+            idempotency: Arc::new(IdempotencyStore::new(
+                std::time::Duration::from_secs(config.daemon.idempotency_ttl_secs),
+                config.daemon.idempotency_max_entries,
+            )),
+            task_claims: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            delegation_cache: Arc::new(DelegationCache::new(std::time::Duration::from_secs(
+                config.agents.delegation_cache_ttl_secs,
+            ))),
             health_cache: Arc::new(RwLock::new(None)),
+            started: Arc::new(AtomicBool::new(false)),
             embedding_service,
             indexing_service,
         };
@@ -263,6 +368,12 @@ impl CCADaemon {
         })
     }
 
+    /// Let config reloads change the log level
+    pub fn with_log_reloader(mut self, reloader: Option<LogLevelReloader>) -> Self {
+        self.state.log_reloader = reloader;
+        self
+    }
+
     /// Run the daemon main loop
     pub async fn run(&self) -> Result<()> {
         info!("CCA Daemon running on {}", self.config.daemon.bind_address);
@@ -294,20 +405,60 @@ impl CCADaemon {
 
         // Start task cleanup background job (STABILITY: prevent unbounded task HashMap growth)
         let tasks_ref = self.state.tasks.clone();
+        let cancellation_ref = self.state.cancellation.clone();
         let cleanup_task = tokio::spawn(async move {
-            task_cleanup_job(tasks_ref).await;
+            task_cleanup_job(tasks_ref, cancellation_ref).await;
+        });
+
+        // Stop tracking tmux-spawned agents whose panes died
+        let tmux_ref = self.state.tmux_manager.clone();
+        let tmux_health_task = tokio::spawn(async move {
+            tmux_health_job(tmux_ref).await;
+        });
+
+        // Record task.progress notifications from workers
+        let progress_rx = self.state.acp_server.subscribe();
+        let progress_tasks = self.state.tasks.clone();
+        let progress_task = tokio::spawn(async move {
+            task_progress_job(progress_rx, progress_tasks).await;
+        });
+
+        // Sample per-role workload for /api/v1/workloads/history
+        let sampler_state = self.state.clone();
+        let workload_sampler_task = tokio::spawn(async move {
+            workload_sampler_job(sampler_state).await;
         });
 
+        // Archive agent logs to PostgreSQL and prune them past the retention window
+        let mut agent_log_tasks = Vec::new();
+        if let Some(postgres) = self.state.postgres.clone() {
+            if self.config.postgres.persist_agent_logs {
+                let (sink, entries) = tokio::sync::mpsc::channel(AGENT_LOG_SINK_CAPACITY);
+                self.state.agent_manager.write().await.set_log_sink(sink);
+                let writer_pg = postgres.clone();
+                agent_log_tasks.push(tokio::spawn(async move {
+                    agent_log_writer(entries, writer_pg).await;
+                }));
+                let retention_days = self.config.postgres.agent_log_retention_days;
+                agent_log_tasks.push(tokio::spawn(async move {
+                    agent_log_retention_job(postgres, retention_days).await;
+                }));
+                info!("Agent log archive enabled");
+            }
+        }
+
         // Start SIGHUP handler for config reload (Unix only)
         #[cfg(unix)]
         {
-            let reloadable_config = self.state.reloadable_config.clone();
+            let state = self.state.clone();
             tokio::spawn(async move {
-                sighup_handler(reloadable_config).await;
+                sighup_handler(state).await;
             });
             info!("SIGHUP handler enabled for config reload (systemd compatible)");
         }
 
+        self.state.started.store(true, Ordering::Release);
+
         // Serve HTTP API with graceful shutdown
         axum::serve(listener, app)
             .with_graceful_shutdown(async move {
@@ -319,6 +470,12 @@ impl CCADaemon {
         self.state.acp_server.shutdown();
         acp_task.abort();
         cleanup_task.abort();
+        tmux_health_task.abort();
+        workload_sampler_task.abort();
+        progress_task.abort();
+        for task in agent_log_tasks {
+            task.abort();
+        }
 
         Ok(())
     }
@@ -351,33 +508,31 @@ fn create_router(state: DaemonState) -> Router {
     let auth_config = DynamicAuthConfig {
         config: state.reloadable_config.clone(),
         required: state.config.daemon.is_auth_required(),
+        metrics_require_auth: state.config.daemon.metrics_require_auth,
     };
 
-    // SEC-004: Create per-IP and per-API-key rate limiter from config
-    let rate_limit_config = RateLimitConfig {
-        requests_per_second: state.config.daemon.rate_limit_rps,
-        burst_size: state.config.daemon.rate_limit_burst,
-        global_rps: state.config.daemon.rate_limit_global_rps,
-        trust_proxy: state.config.daemon.rate_limit_trust_proxy,
-        api_key_rps: state.config.daemon.rate_limit_api_key_rps,
-        api_key_burst: state.config.daemon.rate_limit_api_key_burst,
-    };
-    let rate_limiter = create_rate_limiter_state(&rate_limit_config);
-
     let mut router = Router::new()
         .route("/health", get(health_check))
+        .route("/healthz", get(health_check))
+        .route("/readyz", get(readiness_check))
         .route("/metrics", get(prometheus_metrics))
         .route("/api/v1/health", get(health_check))
         .route("/api/v1/status", get(get_status))
         .route("/api/v1/agents", get(list_agents))
         .route("/api/v1/agents", post(spawn_agent))
+        .route("/api/v1/agents/:agent_id", delete(stop_agent))
         .route("/api/v1/agents/:agent_id/send", post(send_to_agent))
         .route("/api/v1/agents/:agent_id/attach", post(start_agent_session))
         .route("/api/v1/agents/:agent_id/logs", get(get_agent_logs))
+        .route("/api/v1/agents/:agent_id/session/output", get(get_agent_session_output))
         .route("/api/v1/delegate", post(delegate_task))
         .route("/api/v1/tasks", get(list_tasks))
         .route("/api/v1/tasks", post(create_task))
+        .route("/api/v1/tasks/from-template", post(create_task_from_template))
+        .route("/api/v1/tasks/history", get(get_task_history))
+        .route("/api/v1/tasks/stats", get(get_task_stats))
         .route("/api/v1/tasks/:task_id", get(get_task))
+        .route("/api/v1/tasks/:task_id/cancel", post(cancel_task_endpoint))
         .route("/api/v1/activity", get(get_activity))
         .route("/api/v1/redis/status", get(redis_status))
         .route("/api/v1/postgres/status", get(postgres_status))
@@ -393,9 +548,14 @@ fn create_router(state: DaemonState) -> Router {
         .route("/api/v1/pubsub/broadcast", post(pubsub_broadcast))
         .route("/api/v1/acp/status", get(acp_status))
         .route("/api/v1/acp/disconnect", post(acp_disconnect))
+        .route("/api/v1/acp/disconnect-slow", post(acp_disconnect_slow))
         .route("/api/v1/acp/send", post(acp_send_task))
+        .route("/api/v1/acp/relay", post(acp_relay))
         .route("/api/v1/broadcast", post(broadcast_all))
         .route("/api/v1/workloads", get(get_workloads))
+        .route("/api/v1/workloads/history", get(get_workload_history))
+        .route("/api/v1/orchestrator/agents", post(register_orchestrator_agent))
+        .route("/api/v1/orchestrator/agents/:agent_id", delete(unregister_orchestrator_agent))
         .route("/api/v1/rl/stats", get(rl_stats))
         .route("/api/v1/rl/train", post(rl_train))
         .route("/api/v1/rl/algorithm", post(rl_set_algorithm))
@@ -409,40 +569,60 @@ fn create_router(state: DaemonState) -> Router {
         // Admin endpoints for configuration management
         .route("/api/v1/admin/config/reload", post(reload_config))
         .route("/api/v1/admin/config/reloadable", get(get_reloadable_config))
+        .route("/api/v1/config", get(get_config));
+
+    // Chaos latency injection wraps the routes above; the toggle endpoints are
+    // added afterwards so they are never delayed
+    #[cfg(feature = "chaos")]
+    {
+        warn!("Chaos feature enabled: latency injection available at /api/v1/admin/chaos/latency");
+        let injector = crate::chaos::SharedLatencyInjector::default();
+        router = router
+            .layer(axum::middleware::from_fn_with_state(
+                injector.clone(),
+                crate::chaos::latency_injection_middleware,
+            ))
+            .nest_service("/api/v1/admin/chaos", crate::chaos::router(injector));
+    }
+
+    router = router
         // Apply auth middleware (bypasses /health automatically)
         .layer(axum::middleware::from_fn_with_state(auth_config, dynamic_auth_middleware));
 
-    // SEC-004: Apply per-IP and per-API-key rate limiting if configured (rate_limit_rps > 0)
+    // SEC-004: Apply per-IP and per-API-key rate limiting. The limiter is read per
+    // request so reloaded limits apply without rebuilding the router.
     if state.config.daemon.rate_limit_rps > 0 {
         info!(
             "Rate limiting enabled: {} req/s per IP (burst: {}), {} req/s per API key (burst: {}), global: {} req/s",
-            rate_limit_config.requests_per_second,
-            rate_limit_config.burst_size,
-            rate_limit_config.api_key_rps,
-            rate_limit_config.api_key_burst,
-            rate_limit_config.global_rps
+            state.config.daemon.rate_limit_rps,
+            state.config.daemon.rate_limit_burst,
+            state.config.daemon.rate_limit_api_key_rps,
+            state.config.daemon.rate_limit_api_key_burst,
+            state.config.daemon.rate_limit_global_rps
         );
-        router = router.layer(axum::middleware::from_fn_with_state(rate_limiter, rate_limit_middleware));
     }
+    router = router.layer(axum::middleware::from_fn_with_state(
+        state.rate_limiter.clone(),
+        reloadable_rate_limit_middleware,
+    ));
 
-    // SEC-010: Apply CORS middleware if origins are configured
+    // SEC-010: Apply CORS middleware. Origins are checked against the current
+    // policy, so an empty list denies every cross-origin request.
     let cors_origins = &state.config.daemon.cors_origins;
     if !cors_origins.is_empty() {
-        let cors = build_cors_layer(
-            cors_origins,
-            state.config.daemon.cors_allow_credentials,
-            state.config.daemon.cors_max_age_secs,
-        );
         info!(
             "CORS enabled for {} origin(s), credentials: {}, max_age: {}s",
             cors_origins.len(),
             state.config.daemon.cors_allow_credentials,
             state.config.daemon.cors_max_age_secs
         );
-        router = router.layer(cors);
     } else {
         debug!("CORS disabled (no origins configured)");
     }
+    router = router.layer(build_cors_layer(
+        state.cors_policy.clone(),
+        state.config.daemon.cors_max_age_secs,
+    ));
 
     // SEC-011: Apply security headers middleware
     // These headers protect against common web vulnerabilities
@@ -454,63 +634,107 @@ fn create_router(state: DaemonState) -> Router {
     router = router.layer(DefaultBodyLimit::max(DEFAULT_BODY_LIMIT));
     info!("Request body size limit: {} bytes", DEFAULT_BODY_LIMIT);
 
+    // Outermost layer so rejections from auth and rate limiting carry the id too
+    router = router.layer(axum::middleware::from_fn(request_id_middleware));
+
     router.with_state(state)
 }
 
-/// SEC-010: Build CORS layer with explicit allowed origins configuration
+/// SEC-004: Build the rate limiter from the reloadable limits (`None` when disabled)
 ///
-/// SECURITY: This function enforces secure CORS defaults:
-/// - Only allows explicitly configured origins (no wildcards in production)
-/// - Restricts allowed methods to safe API operations (GET, POST, OPTIONS)
-/// - Restricts allowed headers to standard API headers
-/// - Warns if credentials are enabled with wildcard origins
-fn build_cors_layer(
-    origins: &[String],
-    allow_credentials: bool,
-    max_age_secs: u64,
-) -> CorsLayer {
-    // SEC-010: Check for wildcard origin - warn if credentials enabled
-    let has_wildcard = origins.iter().any(|o| o == "*");
-    if has_wildcard && allow_credentials {
-        warn!(
-            "SEC-010: CORS credentials enabled with wildcard origin '*' is insecure! \
-             Credentials will be DISABLED. Use explicit origins instead."
-        );
+/// The trust-proxy flag and access lists are startup-only settings.
+fn build_rate_limiter(daemon: &DaemonConfig, limits: &ReloadableConfig) -> Option<RateLimiterState> {
+    if limits.rate_limit_rps == 0 {
+        return None;
     }
 
-    // Build allowed origins
-    let allow_origin = if has_wildcard {
-        // Wildcard - for development only
-        warn!("SEC-010: Using wildcard CORS origin '*' - this should only be used in development!");
-        AllowOrigin::any()
-    } else {
-        // Explicit origins - parse and validate each one
-        let parsed_origins: Vec<HeaderValue> = origins
+    let rate_limit_config = RateLimitConfig {
+        requests_per_second: limits.rate_limit_rps,
+        burst_size: limits.rate_limit_burst,
+        global_rps: limits.rate_limit_global_rps,
+        trust_proxy: daemon.rate_limit_trust_proxy,
+        api_key_rps: limits.rate_limit_api_key_rps,
+        api_key_burst: limits.rate_limit_api_key_burst,
+        allowlist: daemon.rate_limit_allowlist.clone(),
+        denylist: daemon.rate_limit_denylist.clone(),
+    };
+    Some(create_rate_limiter_state(&rate_limit_config))
+}
+
+/// SEC-010: Allowed CORS origins, swapped when they are reloaded
+#[derive(Debug, Default)]
+pub struct CorsPolicy {
+    any_origin: bool,
+    origins: Vec<HeaderValue>,
+    allow_credentials: bool,
+}
+
+/// Shared CORS policy read by the CORS layer on every request
+pub type SharedCorsPolicy = Arc<std::sync::RwLock<CorsPolicy>>;
+
+impl CorsPolicy {
+    /// Parse configured origins, dropping (and warning about) invalid ones
+    ///
+    /// SECURITY: Credentials are never allowed with the wildcard origin.
+    pub fn new(origins: &[String], allow_credentials: bool) -> Self {
+        let any_origin = origins.iter().any(|o| o == "*");
+        if any_origin {
+            warn!("SEC-010: Using wildcard CORS origin '*' - this should only be used in development!");
+            if allow_credentials {
+                warn!(
+                    "SEC-010: CORS credentials enabled with wildcard origin '*' is insecure! \
+                     Credentials will be DISABLED. Use explicit origins instead."
+                );
+            }
+        }
+
+        let origins = origins
             .iter()
-            .filter_map(|origin| {
-                match origin.parse::<HeaderValue>() {
-                    Ok(hv) => Some(hv),
-                    Err(e) => {
-                        warn!("SEC-010: Invalid CORS origin '{}': {}", origin, e);
-                        None
-                    }
+            .filter(|o| *o != "*")
+            .filter_map(|origin| match origin.parse::<HeaderValue>() {
+                Ok(hv) => Some(hv),
+                Err(e) => {
+                    warn!("SEC-010: Invalid CORS origin '{}': {}", origin, e);
+                    None
                 }
             })
             .collect();
 
-        if parsed_origins.is_empty() {
-            warn!("SEC-010: No valid CORS origins after parsing, using restrictive default");
-            AllowOrigin::exact("https://localhost".parse().unwrap())
-        } else {
-            AllowOrigin::list(parsed_origins)
+        Self {
+            any_origin,
+            origins,
+            allow_credentials: allow_credentials && !any_origin,
         }
-    };
+    }
+
+    fn allows_origin(&self, origin: &HeaderValue) -> bool {
+        self.any_origin || self.origins.contains(origin)
+    }
+
+    fn allows_credentials(&self, origin: &HeaderValue) -> bool {
+        self.allow_credentials && self.allows_origin(origin)
+    }
+}
 
-    // Build the CORS layer with secure defaults
-    let mut cors = CorsLayer::new()
-        .allow_origin(allow_origin)
+/// SEC-010: Build CORS layer backed by the shared, reloadable policy
+///
+/// SECURITY: This function enforces secure CORS defaults:
+/// - Only allows explicitly configured origins (`*` for development only)
+/// - Restricts allowed methods to safe API operations (GET, POST, DELETE, OPTIONS)
+/// - Restricts allowed headers to standard API headers
+/// - Never allows credentials with the wildcard origin
+fn build_cors_layer(policy: SharedCorsPolicy, max_age_secs: u64) -> CorsLayer {
+    let origin_policy = policy.clone();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origin_policy.read().unwrap().allows_origin(origin)
+        }))
+        .allow_credentials(AllowCredentials::predicate(move |origin, _| {
+            policy.read().unwrap().allows_credentials(origin)
+        }))
         // SEC-010: Only allow safe HTTP methods for API
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
         // SEC-010: Allow standard API headers
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
@@ -519,16 +743,15 @@ fn build_cors_layer(
             axum::http::header::ORIGIN,
             // Custom header for API key auth
             axum::http::header::HeaderName::from_static("x-api-key"),
+            axum::http::header::HeaderName::from_static(crate::request_id::REQUEST_ID_HEADER),
+            axum::http::header::HeaderName::from_static("idempotency-key"),
         ])
+        // Let browser clients read the id for bug reports
+        .expose_headers([axum::http::header::HeaderName::from_static(
+            crate::request_id::REQUEST_ID_HEADER,
+        )])
         // SEC-010: Cache preflight requests
-        .max_age(std::time::Duration::from_secs(max_age_secs));
-
-    // SEC-010: Only allow credentials with explicit origins (not wildcard)
-    if allow_credentials && !has_wildcard {
-        cors = cors.allow_credentials(true);
-    }
-
-    cors
+        .max_age(std::time::Duration::from_secs(max_age_secs))
 }
 
 /// SEC-011: Apply security headers to all HTTP responses
@@ -661,7 +884,10 @@ const MAX_TASKS: usize = 10_000;
 const TASK_CLEANUP_INTERVAL_SECS: u64 = 300;
 
 /// Background job to clean up old tasks and prevent unbounded memory growth
-async fn task_cleanup_job(tasks: Arc<RwLock<HashMap<String, TaskState>>>) {
+async fn task_cleanup_job(
+    tasks: Arc<RwLock<HashMap<String, TaskState>>>,
+    cancellation: Arc<TaskCancellation>,
+) {
     use tokio::time::{interval, Duration};
 
     let mut cleanup_interval = interval(Duration::from_secs(TASK_CLEANUP_INTERVAL_SECS));
@@ -669,47 +895,199 @@ async fn task_cleanup_job(tasks: Arc<RwLock<HashMap<String, TaskState>>>) {
     loop {
         cleanup_interval.tick().await;
 
-        let now = Utc::now();
-        let cutoff = now - chrono::Duration::seconds(TASK_TTL_SECS);
+        let cutoff = Utc::now() - chrono::Duration::seconds(TASK_TTL_SECS);
 
         let mut tasks = tasks.write().await;
         let before_count = tasks.len();
 
-        // Remove completed/failed tasks older than TTL
-        tasks.retain(|_id, task| {
-            // Keep pending/in_progress tasks
-            if task.status == "pending" || task.status == "in_progress" {
-                return true;
-            }
-            // Remove old completed/failed tasks
-            task.updated_at > cutoff
-        });
+        prune_tasks(&mut tasks, cutoff);
 
-        // If still over limit, remove oldest completed tasks
-        if tasks.len() > MAX_TASKS {
-            let mut completed_tasks: Vec<_> = tasks
-                .iter()
-                .filter(|(_, t)| t.status == "completed" || t.status == "failed")
-                .map(|(id, t)| (id.clone(), t.updated_at))
-                .collect();
+        cancellation.retain(|id| tasks.contains_key(id));
+
+        let removed = before_count.saturating_sub(tasks.len());
+        if removed > 0 {
+            info!("Task cleanup: removed {} old tasks, {} remaining", removed, tasks.len());
+        }
+    }
+}
+
+/// Drop finished tasks last updated before `cutoff`, then the oldest finished
+/// ones until at most `MAX_TASKS` remain
+///
+/// Tasks that are still pending or running are always kept. With PostgreSQL
+/// configured, pruned tasks can still be fetched from the task history.
+fn prune_tasks(tasks: &mut HashMap<String, TaskState>, cutoff: DateTime<Utc>) {
+    // Remove finished tasks older than TTL
+    tasks.retain(|_id, task| {
+        // Keep pending/running tasks
+        if !task.status.is_terminal() {
+            return true;
+        }
+        // Remove old finished tasks
+        task.updated_at > cutoff
+    });
 
-            // Sort by updated_at (oldest first)
-            completed_tasks.sort_by_key(|(_, updated_at)| *updated_at);
+    // If still over limit, remove oldest finished tasks
+    if tasks.len() > MAX_TASKS {
+        let mut completed_tasks: Vec<_> = tasks
+            .iter()
+            .filter(|(_, t)| t.status.is_terminal())
+            .map(|(id, t)| (id.clone(), t.updated_at))
+            .collect();
+
+        // Sort by updated_at (oldest first)
+        completed_tasks.sort_by_key(|(_, updated_at)| *updated_at);
+
+        // Remove oldest tasks until under limit
+        let to_remove = tasks.len().saturating_sub(MAX_TASKS);
+        for (id, _) in completed_tasks.into_iter().take(to_remove) {
+            tasks.remove(&id);
+        }
+    }
+}
 
-            // Remove oldest tasks until under limit
-            let to_remove = tasks.len().saturating_sub(MAX_TASKS);
-            for (id, _) in completed_tasks.into_iter().take(to_remove) {
-                tasks.remove(&id);
+/// Values for the tmux spawn command's placeholders
+///
+/// Workers run on this host, so wildcard bind addresses become loopback.
+fn spawn_vars(config: &Config) -> crate::tmux::SpawnVars {
+    let localhost = std::net::IpAddr::from(std::net::Ipv4Addr::LOCALHOST);
+    let (daemon_url, host) = match config.daemon.bind_address.parse::<SocketAddr>() {
+        Ok(mut addr) => {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(localhost);
             }
+            (format!("http://{addr}"), addr.ip())
         }
+        Err(_) => (format!("http://{}", config.daemon.bind_address), localhost),
+    };
 
-        let removed = before_count.saturating_sub(tasks.len());
-        if removed > 0 {
-            info!("Task cleanup: removed {} old tasks, {} remaining", removed, tasks.len());
+    crate::tmux::SpawnVars {
+        daemon_url,
+        acp_url: format!("ws://{}", SocketAddr::new(host, config.acp.websocket_port)),
+        api_key: config.daemon.api_keys.first().cloned().unwrap_or_default(),
+    }
+}
+
+/// Agent log entries buffered for the Postgres archive before new ones are dropped
+const AGENT_LOG_SINK_CAPACITY: usize = 1024;
+/// How often to delete archived agent logs past retention (1 hour)
+const AGENT_LOG_RETENTION_INTERVAL_SECS: u64 = 3600;
+
+/// Background job writing agent log entries to the Postgres archive
+async fn agent_log_writer(
+    mut entries: tokio::sync::mpsc::Receiver<(AgentId, crate::agent_manager::LogEntry)>,
+    postgres: Arc<PostgresServices>,
+) {
+    while let Some((agent_id, entry)) = entries.recv().await {
+        if let Err(e) = postgres
+            .agent_logs
+            .insert(agent_id.0, &entry.level, &entry.message, entry.timestamp)
+            .await
+        {
+            warn!("Failed to archive log entry for agent {}: {}", agent_id, e);
+        }
+    }
+}
+
+/// Background job deleting archived agent logs older than the retention window
+async fn agent_log_retention_job(postgres: Arc<PostgresServices>, retention_days: u32) {
+    if retention_days == 0 {
+        return;
+    }
+
+    let mut retention_interval =
+        tokio::time::interval(std::time::Duration::from_secs(AGENT_LOG_RETENTION_INTERVAL_SECS));
+    loop {
+        retention_interval.tick().await;
+        let cutoff = Utc::now() - chrono::Duration::days(i64::from(retention_days));
+        match postgres.agent_logs.delete_older_than(cutoff).await {
+            Ok(0) => {}
+            Ok(removed) => info!("Agent log retention: removed {} archived entries", removed),
+            Err(e) => warn!("Agent log retention failed: {}", e),
+        }
+    }
+}
+
+/// How often to check tmux-spawned agents for dead panes
+const TMUX_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Background job pruning tmux-spawned agents whose panes have died
+async fn tmux_health_job(tmux_manager: Arc<crate::tmux::TmuxManager>) {
+    if !tmux_manager.is_available() {
+        return;
+    }
+
+    let mut check_interval =
+        tokio::time::interval(std::time::Duration::from_secs(TMUX_HEALTH_CHECK_INTERVAL_SECS));
+    loop {
+        check_interval.tick().await;
+        let pruned = tmux_manager.prune_dead_agents().await;
+        if pruned > 0 {
+            info!("Tmux health check: pruned {} dead agent(s)", pruned);
+        }
+    }
+}
+
+/// Background job applying workers' `task.progress` notifications to task state
+async fn task_progress_job(
+    mut messages: tokio::sync::broadcast::Receiver<cca_acp::AcpMessage>,
+    tasks: Arc<RwLock<HashMap<String, TaskState>>>,
+) {
+    loop {
+        match messages.recv().await {
+            Ok(message) => {
+                if let Some(progress) = cca_acp::TaskProgressParams::from_notification(&message) {
+                    apply_task_progress(&mut *tasks.write().await, progress);
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                debug!("Task progress listener skipped {} ACP messages", skipped);
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
         }
     }
 }
 
+/// Record a progress report on an unfinished task; returns whether it applied
+fn apply_task_progress(
+    tasks: &mut HashMap<String, TaskState>,
+    progress: cca_acp::TaskProgressParams,
+) -> bool {
+    let Some(task) = tasks
+        .get_mut(&progress.task_id)
+        .filter(|t| !t.status.is_terminal())
+    else {
+        return false;
+    };
+
+    let now = Utc::now();
+    task.progress = Some(TaskProgress {
+        percent: progress.percent,
+        message: safe_truncate(&progress.message, MAX_PROGRESS_MESSAGE_LEN).to_string(),
+        updated_at: now,
+    });
+    task.updated_at = now;
+    true
+}
+
+/// Max length kept of a worker's progress message
+const MAX_PROGRESS_MESSAGE_LEN: usize = 1000;
+
+/// How often the orchestrator's workload is sampled for history
+const WORKLOAD_SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Background job recording orchestrator workload samples
+async fn workload_sampler_job(state: DaemonState) {
+    let mut sample_interval =
+        tokio::time::interval(std::time::Duration::from_secs(WORKLOAD_SAMPLE_INTERVAL_SECS));
+    loop {
+        sample_interval.tick().await;
+        let busy: Vec<AgentId> = state.busy_agents.read().await.keys().copied().collect();
+        let queued = state.dispatch_queue.waiting_by_role();
+        state.orchestrator.read().await.record_workload_sample(&busy, &queued).await;
+    }
+}
+
 // API Request/Response types
 // Note: Validation constants (MAX_*, VALID_*) are imported from crate::validation
 
@@ -717,10 +1095,22 @@ async fn task_cleanup_job(tasks: Arc<RwLock<HashMap<String, TaskState>>>) {
 const MAX_LOG_LINES: usize = 10_000;
 /// Max file extensions to filter (specific to indexing)
 const MAX_EXTENSIONS: usize = 100;
-/// Max exclude glob patterns (specific to indexing)
+/// Max glob patterns per include/exclude list (specific to indexing)
 const MAX_EXCLUDE_PATTERNS: usize = 100;
 /// Max JSON params size (specific to RL params endpoint)
 const MAX_JSON_PARAMS_SIZE: usize = 10_000;
+/// Max length of a single capability name
+const MAX_CAPABILITY_LEN: usize = 64;
+/// How often a held task re-checks its dependencies
+const DEPENDENCY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+/// How often a delegation waiting for a busy agent re-checks it
+const BUSY_AGENT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+/// Max parameters passed to a task template
+const MAX_TEMPLATE_PARAMS: usize = 32;
+/// Max length of a single template parameter value
+const MAX_TEMPLATE_PARAM_LEN: usize = 10_000;
+/// Max previous-step output forwarded as context to the next template step
+const MAX_TEMPLATE_STEP_CONTEXT_LEN: usize = 20_000;
 
 /// SEC-009: Sanitize broadcast message content to prevent injection attacks
 /// Removes or escapes potentially dangerous content before forwarding to agents
@@ -762,7 +1152,8 @@ fn sanitize_broadcast_message(message: &str) -> String {
 }
 
 /// Coordinator system prompt - enforces JSON delegation output
-const COORDINATOR_SYSTEM_PROMPT: &str = r#"You are a COORDINATOR agent. You do NOT execute tasks yourself.
+/// Default for `agents.coordinator_system_prompt`
+pub const DEFAULT_COORDINATOR_SYSTEM_PROMPT: &str = r#"You are a COORDINATOR agent. You do NOT execute tasks yourself.
 
 Your ONLY job is to analyze tasks, decompose complex ones into parallel subtasks, and delegate to specialists.
 
@@ -806,15 +1197,87 @@ pub struct CreateTaskRequest {
     #[serde(default)]
     #[validate(length(max = 16, message = "Priority must be at most 16 characters"))]
     pub priority: Option<String>,
+    /// Task IDs that must complete before this task runs
+    #[serde(default)]
+    #[validate(length(max = 32, message = "At most 32 dependencies allowed"))]
+    pub depends_on: Vec<String>,
+    /// Run even if a dependency fails (default: fail this task too)
+    #[serde(default)]
+    pub run_on_dependency_failure: bool,
+    /// Return an unfinished task with the same description created within
+    /// `daemon.task_dedup_window_secs` instead of starting a new one
+    #[serde(default)]
+    pub dedupe: bool,
+    /// Reuse the output of an identical delegation (same role, task and context)
+    /// that succeeded within `agents.delegation_cache_ttl_secs`
+    #[serde(default)]
+    pub use_delegation_cache: bool,
+    /// Replay the response of an earlier request with the same key instead of
+    /// creating another task (the `Idempotency-Key` header takes precedence)
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+/// Request to create a task from a configured template
+/// SEC-012: Validated with max lengths and parameter count
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct TemplateTaskRequest {
+    #[validate(length(min = 1, max = 64, message = "Template name must be 1-64 characters"))]
+    pub template: String,
+    /// Values for the template's `{{param}}` placeholders
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(default)]
+    #[validate(length(max = 16, message = "Priority must be at most 16 characters"))]
+    pub priority: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskResponse {
     pub task_id: String,
-    pub status: String,
+    pub status: TaskRunStatus,
+    /// Markdown report combining every delegation's output
     pub output: Option<String>,
     pub error: Option<String>,
     pub assigned_agent: Option<String>,
+    /// Structured per-agent results (same data as `output`, one entry per delegation)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<DelegationResult>,
+    /// Latest `task.progress` report from a worker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgress>,
+}
+
+/// Progress a worker reported for a running task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub percent: u8,
+    pub message: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outcome of a single delegation within a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegationResult {
+    pub role: String,
+    pub success: bool,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub tokens_used: u64,
+}
+
+impl From<&DelegateTaskResponse> for DelegationResult {
+    fn from(response: &DelegateTaskResponse) -> Self {
+        Self {
+            role: response.role.clone(),
+            success: response.success,
+            output: response.output.clone(),
+            error: response.error.clone(),
+            duration_ms: response.duration_ms,
+            tokens_used: response.tokens_used,
+        }
+    }
 }
 
 /// Request to spawn a new agent
@@ -843,6 +1306,10 @@ pub struct DelegateTaskRequest {
     #[serde(default = "default_delegate_timeout")]
     #[validate(range(min = 1, max = 3600, message = "Timeout must be 1-3600 seconds"))]
     pub timeout_seconds: u64,
+    /// Replay the response of an earlier request with the same key instead of
+    /// delegating again (the `Idempotency-Key` header takes precedence)
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
 fn default_delegate_timeout() -> u64 {
@@ -850,7 +1317,7 @@ fn default_delegate_timeout() -> u64 {
 }
 
 /// Response from task delegation
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DelegateTaskResponse {
     pub success: bool,
     pub agent_id: String,
@@ -942,7 +1409,22 @@ struct CachedHealthCheck {
 }
 
 /// Prometheus metrics endpoint
-async fn prometheus_metrics() -> ([(axum::http::header::HeaderName, &'static str); 1], String) {
+///
+/// The live state gauges are refreshed from the daemon state before encoding.
+async fn prometheus_metrics(
+    State(state): State<DaemonState>,
+) -> ([(axum::http::header::HeaderName, &'static str); 1], String) {
+    let tasks_in_flight = state
+        .tasks
+        .read()
+        .await
+        .values()
+        .filter(|t| !t.status.is_terminal())
+        .count();
+    let agents_busy = state.busy_agents.read().await.len();
+    let acp_connections = state.acp_server.connection_count().await;
+    crate::metrics::set_live_state(tasks_in_flight, agents_busy, acp_connections);
+
     let metrics = crate::metrics::encode_metrics();
     (
         [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
@@ -950,7 +1432,44 @@ async fn prometheus_metrics() -> ([(axum::http::header::HeaderName, &'static str
     )
 }
 
-async fn health_check(State(state): State<DaemonState>) -> Json<HealthResponse> {
+/// Query parameters for `GET /health`
+#[derive(Debug, Default, Deserialize)]
+pub struct HealthQuery {
+    /// Ping Redis and Postgres instead of reporting whether they were configured
+    #[serde(default)]
+    pub deep: bool,
+}
+
+/// How long a deep health check waits for each service to answer
+const DEEP_HEALTH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Whether a liveness probe succeeds within `timeout`
+async fn probe_service(
+    name: &str,
+    probe: impl std::future::Future<Output = Result<()>>,
+    timeout: std::time::Duration,
+) -> bool {
+    match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!("Deep health check: {} is unreachable: {}", name, e);
+            false
+        }
+        Err(_) => {
+            warn!("Deep health check: {} did not answer within {:?}", name, timeout);
+            false
+        }
+    }
+}
+
+async fn health_check(
+    State(state): State<DaemonState>,
+    axum::extract::Query(query): axum::extract::Query<HealthQuery>,
+) -> Json<HealthResponse> {
+    if query.deep {
+        return Json(deep_health_check(&state).await);
+    }
+
     // PERF-003: Check cache first
     {
         let cache = state.health_cache.read().await;
@@ -1002,12 +1521,78 @@ async fn health_check(State(state): State<DaemonState>) -> Json<HealthResponse>
     Json(response)
 }
 
+/// Health check that pings each configured service; never cached
+async fn deep_health_check(state: &DaemonState) -> HealthResponse {
+    let redis = async {
+        match &state.redis {
+            Some(redis) => probe_service("redis", redis.client.ping(), DEEP_HEALTH_TIMEOUT).await,
+            None => false,
+        }
+    };
+    let postgres = async {
+        match &state.postgres {
+            Some(postgres) => probe_service("postgres", postgres.db.ping(), DEEP_HEALTH_TIMEOUT).await,
+            None => false,
+        }
+    };
+    let embeddings = async {
+        match &state.embedding_service {
+            Some(emb_service) => emb_service.health_check().await,
+            None => false,
+        }
+    };
+    let (redis_ok, postgres_ok, embeddings_ok) = tokio::join!(redis, postgres, embeddings);
+
+    HealthResponse {
+        status: if redis_ok && postgres_ok { "healthy" } else { "degraded" },
+        version: env!("CARGO_PKG_VERSION"),
+        services: ServiceHealth {
+            redis: redis_ok,
+            postgres: postgres_ok,
+            acp: true,
+            embeddings: embeddings_ok,
+        },
+    }
+}
+
+/// Readiness probe response
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// What the daemon is still waiting for: `startup`, `redis` or `postgres`
+    pub waiting_on: Vec<&'static str>,
+    pub services: ServiceHealth,
+}
+
+/// Readiness probe: 200 once startup has finished and every configured
+/// service answers a deep check, 503 otherwise
+///
+/// Redis and PostgreSQL count as required when they are configured.
+async fn readiness_check(State(state): State<DaemonState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let health = deep_health_check(&state).await;
+
+    let mut waiting_on = Vec::new();
+    if !state.started.load(Ordering::Acquire) {
+        waiting_on.push("startup");
+    }
+    if state.config.redis.is_configured() && !health.services.redis {
+        waiting_on.push("redis");
+    }
+    if !state.config.postgres.url.is_empty() && !health.services.postgres {
+        waiting_on.push("postgres");
+    }
+
+    let ready = waiting_on.is_empty();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(ReadinessResponse { ready, waiting_on, services: health.services }))
+}
+
 async fn get_status(State(state): State<DaemonState>) -> Json<serde_json::Value> {
     let tasks = state.tasks.read().await;
     let agents = state.agent_manager.read().await;
 
-    let pending = tasks.values().filter(|t| t.status == "pending").count();
-    let completed = tasks.values().filter(|t| t.status == "completed").count();
+    let pending = tasks.values().filter(|t| t.status == TaskRunStatus::Pending).count();
+    let completed = tasks.values().filter(|t| t.status == TaskRunStatus::Completed).count();
 
     // Get auto-spawned tmux agents info
     let tmux_agents = state.tmux_manager.list_agents().await;
@@ -1045,7 +1630,10 @@ async fn get_status(State(state): State<DaemonState>) -> Json<serde_json::Value>
         "tmux": {
             "available": state.tmux_manager.is_available(),
             "target_session": state.tmux_manager.target_session(),
-            "auto_spawned_agents": tmux_agents_info
+            "auto_spawned_agents": tmux_agents_info,
+            "auto_spawned_count": state.tmux_manager.spawned_count().await,
+            "max_auto_agents": crate::tmux::MAX_AUTO_AGENTS,
+            "pruned_dead_agents": state.tmux_manager.pruned_total()
         },
         "embeddings": embeddings_info
     }))
@@ -1235,19 +1823,20 @@ async fn send_to_agent(
     let result = tokio::time::timeout(timeout, async {
         let mut cmd = tokio::process::Command::new(&config.claude_path);
 
-        // Apply permission configuration
+        // Apply permission configuration and the environment allowlist
         apply_permissions_to_command(&mut cmd, &permissions, &role_str);
+        apply_env_to_command(&mut cmd, &state.config.agents, &role_str);
 
         cmd.arg("--print")
             .arg("--output-format")
-            .arg("text")
+            .arg("json")
             .arg(&request.message)
             .env("CLAUDE_MD", &config.claude_md_path)
             .env("NO_COLOR", "1")
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
-            .map_err(|e| e.to_string())?
+            .map_err(|e| claude_spawn_error(&config.claude_path, &e))?
             .wait_with_output()
             .await
             .map_err(|e| e.to_string())
@@ -1257,18 +1846,19 @@ async fn send_to_agent(
     // Step 3: Briefly acquire lock to record result
     match result {
         Ok(Ok(output)) if output.status.success() => {
-            let response = String::from_utf8_lossy(&output.stdout).to_string();
+            let parsed = parse_claude_output(&String::from_utf8_lossy(&output.stdout));
             {
                 let mut manager = state.agent_manager.write().await;
-                manager.record_task_result(agent_id, true, &response, None);
+                manager.record_task_result(agent_id, true, &parsed.text, None);
             }
+            record_claude_usage(&state, agent_id, &parsed).await;
             info!("Message sent to agent {} successfully", agent_id);
             Json(SendToAgentResponse {
                 success: true,
-                output: Some(response),
+                tokens_used: parsed.tokens_used(),
+                output: Some(parsed.text),
                 error: None,
                 duration_ms: start.elapsed().as_millis() as u64,
-                tokens_used: 0,
             })
         }
         Ok(Ok(output)) => {
@@ -1321,6 +1911,24 @@ async fn send_to_agent(
     }
 }
 
+/// Record a Claude Code run's token usage in the agent's metrics and budget
+async fn record_claude_usage(state: &DaemonState, agent_id: AgentId, output: &ClaudeOutput) {
+    let total = output.tokens_used();
+    if total == 0 {
+        return;
+    }
+    let usage = crate::tokens::TokenUsage {
+        agent_id,
+        input_tokens: u32::try_from(output.input_tokens).unwrap_or(u32::MAX),
+        output_tokens: u32::try_from(output.output_tokens).unwrap_or(u32::MAX),
+        total_tokens: u32::try_from(total).unwrap_or(u32::MAX),
+        context_tokens: 0,
+        timestamp: Utc::now().timestamp(),
+    };
+    state.token_service.metrics.record(usage).await;
+    state.token_service.budgets.record(agent_id, total).await;
+}
+
 /// Start an interactive PTY session for an agent (for attach functionality)
 async fn start_agent_session(
     State(state): State<DaemonState>,
@@ -1358,43 +1966,172 @@ async fn start_agent_session(
     }
 }
 
-/// Query parameters for logs endpoint
+/// Query parameters for stopping an agent
 #[derive(Debug, Deserialize)]
-pub struct LogsQuery {
-    #[serde(default = "default_log_lines")]
-    lines: usize,
-}
-
-fn default_log_lines() -> usize {
-    50
+pub struct StopAgentQuery {
+    /// Stop the agent even if it is working on a task
+    #[serde(default)]
+    force: bool,
 }
 
-/// Get logs for a specific agent
-async fn get_agent_logs(
+/// Stop an agent and remove it from everything the daemon tracks it in
+///
+/// Covers the local agent manager, the ACP connection, the orchestrator's
+/// routing pool, the busy set, Redis agent state and tmux. Agents with a task
+/// in progress are only stopped with `?force=true`.
+async fn stop_agent(
     State(state): State<DaemonState>,
     Path(agent_id): Path<String>,
-    axum::extract::Query(query): axum::extract::Query<LogsQuery>,
-) -> Json<serde_json::Value> {
-    // Parse agent ID
+    axum::extract::Query(query): axum::extract::Query<StopAgentQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     let agent_id = match Uuid::parse_str(&agent_id) {
         Ok(uuid) => AgentId(uuid),
         Err(_) => {
-            return Json(serde_json::json!({
-                "error": format!("Invalid agent ID: {}", agent_id)
-            }));
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Invalid agent ID: {}", agent_id)
+                })),
+            ));
         }
     };
 
-    // SEC-008: Validate lines parameter to prevent excessive memory usage
-    let lines = query.lines.min(MAX_LOG_LINES);
+    let is_local = state.agent_manager.read().await.get(agent_id).is_some();
+    let acp_role = state
+        .acp_server
+        .agents_with_roles()
+        .await
+        .into_iter()
+        .find(|(id, _)| *id == agent_id)
+        .map(|(_, role)| role);
+    let worker_id = state.acp_server.worker_ids().await.remove(&agent_id);
+    let in_orchestrator = state.orchestrator.read().await.is_agent_registered(agent_id).await;
+
+    if !is_local && acp_role.is_none() && !in_orchestrator {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "success": false,
+                "error": format!("Agent {} not found", agent_id)
+            })),
+        ));
+    }
+
+    let busy_task = state.busy_agents.read().await.get(&agent_id).cloned();
+    let current_task = match busy_task {
+        Some(task) => Some(task),
+        None => state.agent_manager.read().await.get_current_task(agent_id),
+    };
+    if let Some(task) = &current_task {
+        if !query.force {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Agent {} is busy; use ?force=true to stop it anyway", agent_id),
+                    "current_task": safe_truncate(task, 100)
+                })),
+            ));
+        }
+        warn!("Force-stopping agent {} mid-task", agent_id);
+    }
+
+    let mut cleaned_up = Vec::new();
+    if is_local && state.agent_manager.write().await.stop(agent_id).await.is_ok() {
+        cleaned_up.push("agent_manager");
+    }
+    if acp_role.is_some() && state.acp_server.disconnect(agent_id).await.is_ok() {
+        cleaned_up.push("acp_connection");
+    }
+    if state.orchestrator.read().await.unregister_agent(agent_id).await {
+        cleaned_up.push("orchestrator");
+    }
+    if state.busy_agents.write().await.remove(&agent_id).is_some() {
+        cleaned_up.push("busy_agents");
+    }
+    if let Some(ref redis) = state.redis {
+        match redis.agent_states.remove(agent_id).await {
+            Ok(()) => cleaned_up.push("redis"),
+            Err(e) => warn!("Failed to remove Redis state for agent {}: {}", agent_id, e),
+        }
+    }
+    // Only workers we spawned in tmux have a pane recorded under their worker ID
+    let tmux_pane = match worker_id {
+        Some(worker_id) => state.tmux_manager.stop_agent_by_worker_id(&worker_id).await,
+        None => None,
+    };
+    if tmux_pane.is_some() {
+        cleaned_up.push("tmux");
+    }
+
+    info!("Agent {} stopped via API (cleaned up: {})", agent_id, cleaned_up.join(", "));
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "agent_id": agent_id.to_string(),
+        "was_busy": current_task.is_some(),
+        "cleaned_up": cleaned_up,
+        "tmux_pane": tmux_pane
+    })))
+}
+
+/// Query parameters for logs endpoint
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    #[serde(default = "default_log_lines")]
+    lines: usize,
+    /// Minimum level to return (e.g. `ERROR`); all levels when omitted
+    #[serde(default)]
+    level: Option<String>,
+    /// Only return entries after this cursor, oldest first (for `--follow`)
+    #[serde(default)]
+    since: Option<u64>,
+}
+
+fn default_log_lines() -> usize {
+    50
+}
+
+/// Get logs for a specific agent
+async fn get_agent_logs(
+    State(state): State<DaemonState>,
+    Path(agent_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<LogsQuery>,
+) -> Json<serde_json::Value> {
+    // Parse agent ID
+    let agent_id = match Uuid::parse_str(&agent_id) {
+        Ok(uuid) => AgentId(uuid),
+        Err(_) => {
+            return Json(serde_json::json!({
+                "error": format!("Invalid agent ID: {}", agent_id)
+            }));
+        }
+    };
+
+    // SEC-008: Validate lines parameter to prevent excessive memory usage
+    let lines = query.lines.min(MAX_LOG_LINES);
+
+    let min_level = match query.level.as_deref().map(|l| (l, LogLevel::parse(l))) {
+        None => None,
+        Some((_, Some(level))) => Some(level),
+        Some((raw, None)) => {
+            return Json(serde_json::json!({
+                "error": format!("Invalid log level: {raw} (expected TRACE, DEBUG, INFO, WARN or ERROR)")
+            }));
+        }
+    };
 
     let manager = state.agent_manager.read().await;
-    let logs = manager.get_logs(agent_id, lines);
+    let (logs, cursor) = match query.since {
+        Some(since) => manager.get_logs_since(agent_id, since, lines, min_level),
+        None => (manager.get_logs(agent_id, lines, min_level), manager.latest_log_seq(agent_id)),
+    };
 
     let log_entries: Vec<serde_json::Value> = logs
         .iter()
         .map(|entry| {
             serde_json::json!({
+                "seq": entry.seq,
                 "timestamp": entry.timestamp.to_rfc3339(),
                 "level": entry.level,
                 "message": entry.message
@@ -1404,16 +2141,78 @@ async fn get_agent_logs(
 
     Json(serde_json::json!({
         "agent_id": agent_id.to_string(),
-        "logs": log_entries
+        "logs": log_entries,
+        "cursor": cursor
     }))
 }
 
+/// Get recent interactive session output for an agent (for the attach UI)
+async fn get_agent_session_output(
+    State(state): State<DaemonState>,
+    Path(agent_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<LogsQuery>,
+) -> Json<serde_json::Value> {
+    let agent_id = match Uuid::parse_str(&agent_id) {
+        Ok(uuid) => AgentId(uuid),
+        Err(_) => {
+            return Json(serde_json::json!({
+                "error": format!("Invalid agent ID: {}", agent_id)
+            }));
+        }
+    };
+
+    let manager = state.agent_manager.read().await;
+    match manager.get_session_output(agent_id, query.lines) {
+        Ok(lines) => Json(serde_json::json!({
+            "agent_id": agent_id.to_string(),
+            "session_active": manager.has_interactive_session(agent_id),
+            "lines": lines
+        })),
+        Err(e) => Json(serde_json::json!({
+            "error": e.to_string()
+        })),
+    }
+}
+
 /// Delegate a task to a specialist agent
 /// This endpoint is used by the coordinator to delegate tasks to sub-agents
 async fn delegate_task(
     State(state): State<DaemonState>,
+    headers: HeaderMap,
     Json(request): Json<DelegateTaskRequest>,
-) -> Json<DelegateTaskResponse> {
+) -> Result<Json<DelegateTaskResponse>, ServiceError> {
+    let key = match request_key(&headers, request.idempotency_key.as_deref()) {
+        Ok(key) => key,
+        Err(e) => {
+            return Ok(Json(DelegateTaskResponse {
+                success: false,
+                agent_id: String::new(),
+                role: request.role.clone(),
+                output: None,
+                error: Some(e),
+                duration_ms: 0,
+                tokens_used: 0,
+            }));
+        }
+    };
+
+    let hash = body_hash(&(&request.role, &request.task, &request.context, request.timeout_seconds));
+    let idempotency = state.idempotency.clone();
+    let response = idempotency
+        .run(
+            &caller_api_key(&headers),
+            "delegate",
+            key.as_deref(),
+            hash,
+            |response: &DelegateTaskResponse| response.success,
+            async { run_delegate_task(state, request).await.0 },
+        )
+        .await?;
+    Ok(Json(response))
+}
+
+/// Validate and run a delegation (the uncached body of [`delegate_task`])
+async fn run_delegate_task(state: DaemonState, request: DelegateTaskRequest) -> Json<DelegateTaskResponse> {
     let start = std::time::Instant::now();
 
     // SEC-008: Input validation - check task length
@@ -1484,6 +2283,26 @@ async fn delegate_task(
         });
     }
 
+    // SEC-009: Optional shell/prompt injection screening
+    let action = state.config.daemon.suspicious_content_action;
+    let screened = screen_agent_content("task", &request.task, action).and_then(|()| {
+        request
+            .context
+            .as_deref()
+            .map_or(Ok(()), |ctx| screen_agent_content("context", ctx, action))
+    });
+    if let Err(e) = screened {
+        return Json(DelegateTaskResponse {
+            success: false,
+            agent_id: String::new(),
+            role: request.role.clone(),
+            output: None,
+            error: Some(e.to_string()),
+            duration_ms: start.elapsed().as_millis() as u64,
+            tokens_used: 0,
+        });
+    }
+
     // Parse role
     let role = match request.role.to_lowercase().as_str() {
         "frontend" => AgentRole::Frontend,
@@ -1588,19 +2407,20 @@ async fn delegate_task(
     let result = tokio::time::timeout(timeout, async {
         let mut cmd = tokio::process::Command::new(&config.claude_path);
 
-        // Apply permission configuration
+        // Apply permission configuration and the environment allowlist
         apply_permissions_to_command(&mut cmd, &permissions, &role_str);
+        apply_env_to_command(&mut cmd, &state.config.agents, &role_str);
 
         cmd.arg("--print")
             .arg("--output-format")
-            .arg("text")
+            .arg("json")
             .arg(&message)
             .env("CLAUDE_MD", &config.claude_md_path)
             .env("NO_COLOR", "1")
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
-            .map_err(|e| e.to_string())?
+            .map_err(|e| claude_spawn_error(&config.claude_path, &e))?
             .wait_with_output()
             .await
             .map_err(|e| e.to_string())
@@ -1610,20 +2430,26 @@ async fn delegate_task(
     // Step 3: Briefly acquire lock to record result
     match result {
         Ok(Ok(output)) if output.status.success() => {
-            let response = String::from_utf8_lossy(&output.stdout).to_string();
+            let parsed = parse_claude_output(&String::from_utf8_lossy(&output.stdout));
             {
                 let mut manager = state.agent_manager.write().await;
-                manager.record_task_result(agent_id, true, &response, None);
+                manager.record_task_result(agent_id, true, &parsed.text, None);
             }
-            info!("Task completed by {} agent in {}ms", request.role, start.elapsed().as_millis());
+            record_claude_usage(&state, agent_id, &parsed).await;
+            info!(
+                "Task completed by {} agent in {}ms (tokens: {})",
+                request.role,
+                start.elapsed().as_millis(),
+                parsed.tokens_used()
+            );
             Json(DelegateTaskResponse {
                 success: true,
                 agent_id: agent_id.to_string(),
                 role: request.role.clone(),
-                output: Some(response),
+                tokens_used: parsed.tokens_used(),
+                output: Some(parsed.text),
                 error: None,
                 duration_ms: start.elapsed().as_millis() as u64,
-                tokens_used: 0,
             })
         }
         Ok(Ok(output)) => {
@@ -1685,10 +2511,12 @@ async fn list_tasks(State(state): State<DaemonState>) -> Json<serde_json::Value>
         .values()
         .map(|t| TaskResponse {
             task_id: t.task_id.clone(),
-            status: t.status.clone(),
+            status: t.status,
             output: t.output.clone(),
             error: t.error.clone(),
             assigned_agent: t.assigned_agent.clone(),
+            delegations: t.delegations.clone(),
+            progress: t.progress.clone(),
         })
         .collect();
 
@@ -1697,76 +2525,351 @@ async fn list_tasks(State(state): State<DaemonState>) -> Json<serde_json::Value>
     }))
 }
 
+/// Whether a dependency with `status` still blocks its dependent
+///
+/// Errors if the dependency failed or was cancelled, unless `run_on_failure`.
+fn dependency_blocks(dep: &str, status: TaskRunStatus, run_on_failure: bool) -> Result<bool, String> {
+    match status {
+        TaskRunStatus::Completed => Ok(false),
+        TaskRunStatus::Failed | TaskRunStatus::Cancelled if run_on_failure => Ok(false),
+        TaskRunStatus::Failed | TaskRunStatus::Cancelled => Err(format!("Dependency {dep} {status}")),
+        _ => Ok(true),
+    }
+}
+
+/// Status of a task in the PostgreSQL task history, if it has a record there
+async fn task_history_status(state: &DaemonState, task_id: &str) -> Option<TaskRunStatus> {
+    match (&state.postgres, Uuid::parse_str(task_id)) {
+        (Some(postgres), Ok(id)) => match postgres.tasks.get(id).await {
+            Ok(record) => record.map(|record| task_response_from_record(record).status),
+            Err(e) => {
+                warn!("Failed to look up task {} in PostgreSQL: {}", task_id, e);
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Final status of a task that was pruned from memory
+///
+/// Only finished tasks are pruned, so without a (finished) task history
+/// record the task counts as completed.
+async fn pruned_task_status(state: &DaemonState, task_id: &str) -> TaskRunStatus {
+    task_history_status(state, task_id)
+        .await
+        .filter(|status| status.is_terminal())
+        .unwrap_or(TaskRunStatus::Completed)
+}
+
+/// Wait until every dependency reaches a terminal status
+///
+/// Returns an error message if a dependency failed or was cancelled (unless
+/// `run_on_failure`) or didn't finish within
+/// `agents.dependency_wait_timeout_seconds` (when set), or if the waiting task
+/// itself was cancelled. A dependency pruned from memory while waiting is
+/// judged by its task history status.
+async fn wait_for_dependencies(
+    state: &DaemonState,
+    task_id: &str,
+    depends_on: &[String],
+    run_on_failure: bool,
+) -> Result<(), String> {
+    let timeout = std::time::Duration::from_secs(state.config.agents.dependency_wait_timeout_seconds);
+    let deadline = (!timeout.is_zero()).then(|| tokio::time::Instant::now() + timeout);
+    let mut pruned: HashMap<&str, TaskRunStatus> = HashMap::new();
+
+    loop {
+        let mut waiting = false;
+        let mut missing = Vec::new();
+        {
+            let tasks = state.tasks.read().await;
+            if tasks.get(task_id).is_some_and(|t| t.status == TaskRunStatus::Cancelled) {
+                return Err("Cancelled".to_string());
+            }
+            for dep in depends_on {
+                match tasks.get(dep).map(|t| t.status).or_else(|| pruned.get(dep.as_str()).copied()) {
+                    Some(status) => waiting |= dependency_blocks(dep, status, run_on_failure)?,
+                    None => missing.push(dep.as_str()),
+                }
+            }
+        }
+        for dep in missing {
+            let status = pruned_task_status(state, dep).await;
+            pruned.insert(dep, status);
+            dependency_blocks(dep, status, run_on_failure)?;
+        }
+
+        if !waiting {
+            return Ok(());
+        }
+        if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+            return Err(format!(
+                "Timed out after {}s waiting for dependencies",
+                timeout.as_secs()
+            ));
+        }
+        tokio::time::sleep(DEPENDENCY_POLL_INTERVAL).await;
+    }
+}
+
 async fn create_task(
     State(state): State<DaemonState>,
+    Extension(request_id): Extension<RequestId>,
+    headers: HeaderMap,
     Json(request): Json<CreateTaskRequest>,
-) -> Json<TaskResponse> {
-    // SEC-008: Input validation - check description length
-    if request.description.len() > MAX_TASK_DESCRIPTION_LEN {
-        return Json(TaskResponse {
-            task_id: String::new(),
-            status: "error".to_string(),
-            output: None,
-            error: Some(format!(
-                "Description too long: {} bytes (max: {} bytes)",
-                request.description.len(),
-                MAX_TASK_DESCRIPTION_LEN
-            )),
-            assigned_agent: None,
-        });
+) -> Result<(StatusCode, Json<TaskResponse>), ServiceError> {
+    // SEC-008: Input validation - description length, priority whitelist and
+    // dependency count
+    validate_task_request(&request.description, request.priority.as_deref(), request.depends_on.len())?;
+    let key = request_key(&headers, request.idempotency_key.as_deref())
+        .map_err(|e| ValidationError::new("idempotency_key", "invalid_value", e))?;
+    let priority = request.priority.clone().unwrap_or_else(|| "normal".to_string());
+
+    // Dependencies must already exist, in memory or (once pruned) in the task
+    // history. Since the new task gets a fresh ID, this also rules out cycles.
+    let not_in_memory: Vec<&String> = {
+        let tasks = state.tasks.read().await;
+        request.depends_on.iter().filter(|id| !tasks.contains_key(*id)).collect()
+    };
+    for dep in not_in_memory {
+        if task_history_status(&state, dep).await.is_none() {
+            return Err(ValidationError::new(
+                "depends_on",
+                "unknown_task",
+                format!("Unknown dependency: {}", safe_truncate(dep, 64)),
+            )
+            .into());
+        }
     }
 
-    // SEC-008: Input validation - validate priority against whitelist
-    let priority = request.priority.as_deref().unwrap_or("normal");
-    if priority.len() > MAX_PRIORITY_LEN {
-        return Json(TaskResponse {
-            task_id: String::new(),
-            status: "error".to_string(),
-            output: None,
-            error: Some(format!(
-                "Priority too long: {} bytes (max: {} bytes)",
-                priority.len(), MAX_PRIORITY_LEN
-            )),
-            assigned_agent: None,
-        });
-    }
-    if !VALID_PRIORITIES.contains(&priority) {
-        return Json(TaskResponse {
-            task_id: String::new(),
-            status: "error".to_string(),
-            output: None,
-            error: Some(format!(
-                "Invalid priority '{}'. Must be one of: {}",
-                priority, VALID_PRIORITIES.join(", ")
-            )),
-            assigned_agent: None,
-        });
+    let hash = body_hash(&(
+        &request.description,
+        &priority,
+        &request.depends_on,
+        request.run_on_dependency_failure,
+        request.dedupe,
+        request.use_delegation_cache,
+    ));
+    let idempotency = state.idempotency.clone();
+    let response = idempotency
+        .run(
+            &caller_api_key(&headers),
+            "tasks",
+            key.as_deref(),
+            hash,
+            // A failure that left no finished task behind is worth retrying
+            |response: &TaskResponse| response.error.is_none() || response.status.is_terminal(),
+            async { run_new_task(state, request_id, request, priority).await.0 },
+        )
+        .await?;
+    // Tasks waiting on dependencies run in the background
+    let status = if response.status == TaskRunStatus::Pending {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(response)))
+}
+
+/// Hash a task description after trimming, collapsing whitespace and lowercasing
+fn task_content_hash(description: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let normalized = description
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Find the newest unfinished task with `content_hash` created at or after `since`
+fn find_duplicate_task(
+    tasks: &HashMap<String, TaskState>,
+    content_hash: u64,
+    since: DateTime<Utc>,
+) -> Option<&TaskState> {
+    tasks
+        .values()
+        .filter(|task| {
+            task.content_hash == Some(content_hash)
+                && task.created_at >= since
+                && !task.status.is_terminal()
+        })
+        .max_by_key(|task| task.created_at)
+}
+
+/// Create, route and run a task whose request has already been validated
+///
+/// Once the task finishes, its result is saved to the task history.
+async fn run_new_task(
+    state: DaemonState,
+    request_id: RequestId,
+    request: CreateTaskRequest,
+    priority: String,
+) -> Json<TaskResponse> {
+    let response = execute_new_task(state.clone(), request_id, request, priority).await;
+    // A deduplicated request returns a task that is still running elsewhere
+    if response.status.is_terminal() {
+        persist_task_result(&state, &response.task_id).await;
     }
-    let priority = priority.to_string();
+    response
+}
 
+async fn execute_new_task(
+    state: DaemonState,
+    request_id: RequestId,
+    request: CreateTaskRequest,
+    priority: String,
+) -> Json<TaskResponse> {
     let task_id = Uuid::new_v4().to_string();
     let now = Utc::now();
+    let content_hash = task_content_hash(&request.description);
 
     // Create task state
     let task = TaskState {
         task_id: task_id.clone(),
         description: request.description.clone(),
-        status: "pending".to_string(),
+        status: TaskRunStatus::Pending,
         priority,
         output: None,
         error: None,
         assigned_agent: None,
+        delegations: Vec::new(),
+        progress: None,
+        content_hash: Some(content_hash),
+        use_delegation_cache: request.use_delegation_cache,
+        deadline: task_deadline(&state.config.agents),
         created_at: now,
         updated_at: now,
     };
 
-    // Store task
+    // Store task, unless it duplicates one that's still running. Checking and
+    // inserting under one lock keeps concurrent duplicates from both starting.
     {
         let mut tasks = state.tasks.write().await;
+        if request.dedupe {
+            let window = chrono::Duration::seconds(
+                i64::try_from(state.config.daemon.task_dedup_window_secs).unwrap_or(i64::MAX),
+            );
+            if let Some(existing) = find_duplicate_task(&tasks, content_hash, now - window) {
+                info!("Task deduplicated: returning {} for identical description", existing.task_id);
+                return Json(TaskResponse {
+                    task_id: existing.task_id.clone(),
+                    status: existing.status,
+                    output: existing.output.clone(),
+                    error: existing.error.clone(),
+                    assigned_agent: existing.assigned_agent.clone(),
+                    delegations: existing.delegations.clone(),
+                    progress: existing.progress.clone(),
+                });
+            }
+        }
         tasks.insert(task_id.clone(), task);
     }
 
     info!("Task created: {} - {}", task_id, request.description);
+    persist_task_created(&state, &task_id, &request.description, TaskRunStatus::Pending).await;
+
+    // Dependencies can take as long as whole tasks, so don't hold the request
+    // open for them: answer "pending" and run the task once they finish
+    if !request.depends_on.is_empty() {
+        let background_state = state.clone();
+        let background_task_id = task_id.clone();
+        tokio::spawn(
+            async move {
+                let response =
+                    run_created_task(background_state.clone(), request_id, background_task_id, request).await;
+                if response.status.is_terminal() {
+                    persist_task_result(&background_state, &response.task_id).await;
+                }
+            }
+            .in_current_span(),
+        );
+        return Json(TaskResponse {
+            task_id,
+            status: TaskRunStatus::Pending,
+            output: None,
+            error: None,
+            assigned_agent: None,
+            delegations: Vec::new(),
+            progress: None,
+        });
+    }
+
+    // Boxed: the run's future is large enough to overflow small (test) stacks
+    Box::pin(run_created_task(state, request_id, task_id, request)).await
+}
+
+/// Run a task that is already stored, once its dependencies have finished
+async fn run_created_task(
+    state: DaemonState,
+    request_id: RequestId,
+    task_id: String,
+    request: CreateTaskRequest,
+) -> Json<TaskResponse> {
+    // Hold the task (status "pending") until its dependencies finish
+    if !request.depends_on.is_empty() {
+        if let Err(error_msg) = wait_for_dependencies(
+            &state,
+            &task_id,
+            &request.depends_on,
+            request.run_on_dependency_failure,
+        )
+        .await
+        {
+            if let Some(response) = cancelled_task_response(&state, &task_id).await {
+                return response;
+            }
+            warn!("Task {} not run: {}", task_id, error_msg);
+            {
+                let mut tasks = state.tasks.write().await;
+                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                    task.status = TaskRunStatus::Failed;
+                    task.error = Some(error_msg.clone());
+                    task.updated_at = Utc::now();
+                }
+            }
+            return Json(TaskResponse {
+                task_id,
+                status: TaskRunStatus::Failed,
+                output: None,
+                error: Some(error_msg),
+                assigned_agent: None,
+                delegations: Vec::new(),
+                progress: None,
+            });
+        }
+    }
+
+    // Only one daemon sharing this Redis runs the task; the claim is released
+    // when this function returns
+    let _claim = match claim_task(&state, &task_id, task_content_hash(&request.description)).await {
+        Ok(claim) => claim,
+        Err(error_msg) => {
+            warn!("{}", error_msg);
+            {
+                let mut tasks = state.tasks.write().await;
+                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                    task.status = TaskRunStatus::Failed;
+                    task.error = Some(error_msg.clone());
+                    task.updated_at = Utc::now();
+                }
+            }
+            return Json(TaskResponse {
+                task_id,
+                status: TaskRunStatus::Failed,
+                output: None,
+                error: Some(error_msg),
+                assigned_agent: None,
+                delegations: Vec::new(),
+                progress: None,
+            });
+        }
+    };
 
     // Step 1: Find connected coordinator worker via WebSocket
     let coordinator_id = match state.acp_server.find_agent_by_role("coordinator").await {
@@ -1779,18 +2882,20 @@ async fn create_task(
             warn!("{}", error_msg);
             {
                 let mut tasks = state.tasks.write().await;
-                if let Some(task) = tasks.get_mut(&task_id) {
-                    task.status = "failed".to_string();
+                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                    task.status = TaskRunStatus::Failed;
                     task.error = Some(error_msg.clone());
                     task.updated_at = Utc::now();
                 }
             }
             return Json(TaskResponse {
                 task_id,
-                status: "failed".to_string(),
+                status: TaskRunStatus::Failed,
                 output: None,
                 error: Some(error_msg),
                 assigned_agent: None,
+                delegations: Vec::new(),
+                progress: None,
             });
         }
     };
@@ -1798,8 +2903,8 @@ async fn create_task(
     // Update task to running status
     {
         let mut tasks = state.tasks.write().await;
-        if let Some(task) = tasks.get_mut(&task_id) {
-            task.status = "running".to_string();
+        if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+            task.status = TaskRunStatus::Running;
             task.assigned_agent = Some(coordinator_id.to_string());
             task.updated_at = Utc::now();
         }
@@ -1812,57 +2917,41 @@ async fn create_task(
     );
 
     // Step 2: Get available workers to inform coordinator
-    let agents_with_roles = state.acp_server.agents_with_roles().await;
-    let available_roles: Vec<String> = agents_with_roles
-        .iter()
-        .filter_map(|(_, role_opt)| {
-            let role = role_opt.as_ref()?;
-            if role != "coordinator" && role != "unregistered" {
-                Some(role.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
+    let available_roles = describe_workers(&state.acp_server.agents_with_capabilities().await);
 
     // Build context with system prompt and available workers
-    let workers_info = if available_roles.is_empty() {
-        if state.tmux_manager.is_available() {
-            // Tmux available - allow delegation, workers will be auto-spawned
-            "No workers are currently connected, but auto-spawn is enabled. \
-             You may delegate to any role (backend, frontend, devops, dba, security, qa) and workers will be spawned automatically.".to_string()
-        } else {
-            "IMPORTANT: No specialist workers are currently connected. \
-             You MUST return an error response telling the user to start the required worker(s).\n\
-             Example: {\"action\":\"error\",\"error\":\"No workers available. Start required workers with: cca agent worker <role>\",\"required_workers\":[\"backend\"]}".to_string()
+    let context = coordinator_context(&state, &available_roles);
+
+    // Send task to coordinator via WebSocket, re-asking if it doesn't answer in JSON
+    let timeout = std::time::Duration::from_secs(state.reloadable_config.read().await.default_timeout_seconds);
+    let mut ask_coordinator = |task: String| {
+        let (state, task_id, context, request_id) = (&state, &task_id, &context, &request_id);
+        async move {
+            send_task_cancellable(
+                state,
+                task_id,
+                coordinator_id,
+                &task,
+                Some(context),
+                request_id,
+                timeout,
+            )
+            .await
         }
-    } else {
-        let auto_spawn_note = if state.tmux_manager.is_available() {
-            " Additional workers can be auto-spawned if needed."
-        } else {
-            ""
-        };
-        format!(
-            "Available workers: {}.{} \
-             If a required role is not available, return an error response listing the missing workers.",
-            available_roles.join(", "),
-            auto_spawn_note
-        )
     };
-    let context = format!("{COORDINATOR_SYSTEM_PROMPT}\n\n{workers_info}");
-
-    // Send task to coordinator via WebSocket
-    let timeout = std::time::Duration::from_secs(state.config.agents.default_timeout_seconds);
-    let result = state.acp_server.send_task(
-        coordinator_id,
+    let result = request_coordinator_decision(
         &request.description,
-        Some(&context),
-        timeout,
-    ).await;
+        state.config.agents.coordinator_json_retries,
+        &mut ask_coordinator,
+    )
+    .await;
+    if let Some(response) = cancelled_task_response(&state, &task_id).await {
+        return response;
+    }
 
     // Step 3: Process coordinator's response
     match result {
-        Ok(coordinator_response) => {
+        Ok((coordinator_response, decision)) => {
             let coordinator_output = coordinator_response.output;
             let _coordinator_tokens = coordinator_response.tokens_used; // Available for future use
 
@@ -1873,47 +2962,46 @@ async fn create_task(
                 task_id
             );
 
-            // Try to parse coordinator's JSON response
-            // Extract JSON from output (coordinator might include markdown or other text)
-            let json_str = extract_json_from_output(&coordinator_output);
-
-            match json_str.and_then(|s| serde_json::from_str::<CoordinatorResponse>(&s).ok()) {
+            match decision {
                 Some(coord_response) => {
                     info!("Coordinator decision: action={}, summary={:?}",
                           coord_response.action, coord_response.summary);
 
                     match coord_response.action.as_str() {
                         "delegate" => {
-                            // Execute delegations to specialist agents
-                            let delegation_results = execute_delegations(
-                                &state,
-                                &coord_response.delegations,
-                            ).await;
-
-                            // Aggregate results
-                            let mut combined_output = String::new();
-                            let mut all_success = true;
-                            let mut errors = Vec::new();
-
-                            if let Some(summary) = &coord_response.summary {
-                                combined_output.push_str(&format!("## Coordinator Summary\n{summary}\n\n"));
+                            // Execute delegations to specialist agents, feeding results back
+                            // to the coordinator for further rounds when enabled
+                            let summary = coord_response.summary.clone();
+                            let outcome = run_coordination_rounds(
+                                &request.description,
+                                coord_response,
+                                state.config.agents.coordinator_max_rounds,
+                                state.config.agents.coordinator_json_retries,
+                                &mut ask_coordinator,
+                                |delegations| {
+                                    let (state, task_id, request_id) = (&state, &task_id, &request_id);
+                                    async move { execute_delegations(state, task_id, &delegations, request_id).await }
+                                },
+                            )
+                            .await;
+                            if let Some(response) = cancelled_task_response(&state, &task_id).await {
+                                return response;
                             }
+                            let delegation_results = outcome.results;
 
-                            for (delegation, result) in coord_response.delegations.iter().zip(delegation_results.iter()) {
-                                combined_output.push_str(&format!("## {} Agent\n", delegation.role));
-                                if result.success {
-                                    if let Some(ref out) = result.output {
-                                        combined_output.push_str(out);
-                                    }
-                                } else {
-                                    all_success = false;
-                                    if let Some(ref err) = result.error {
-                                        errors.push(format!("{}: {}", delegation.role, err));
-                                        combined_output.push_str(&format!("Error: {err}\n"));
-                                    }
-                                }
-                                combined_output.push_str("\n\n");
-                            }
+                            // Aggregate results
+                            let heading = match (&outcome.final_response, &summary) {
+                                (Some(answer), _) => Some(("Coordinator Answer", answer.as_str())),
+                                (None, Some(summary)) => Some(("Coordinator Summary", summary.as_str())),
+                                (None, None) => None,
+                            };
+                            let (combined_output, all_success, errors) = combine_delegation_results(
+                                heading,
+                                &outcome.delegations,
+                                &delegation_results,
+                            );
+                            let delegations: Vec<DelegationResult> =
+                                delegation_results.iter().map(DelegationResult::from).collect();
 
                             // FIX 1: Record RL experiences for each delegation result
                             for result in &delegation_results {
@@ -1965,9 +3053,10 @@ async fn create_task(
                             // Update task state
                             {
                                 let mut tasks = state.tasks.write().await;
-                                if let Some(task) = tasks.get_mut(&task_id) {
-                                    task.status = if all_success { "completed" } else { "partial" }.to_string();
+                                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                                    task.status = if all_success { TaskRunStatus::Completed } else { TaskRunStatus::Partial };
                                     task.output = Some(combined_output.clone());
+                                    task.delegations = delegations.clone();
                                     if !errors.is_empty() {
                                         task.error = Some(errors.join("; "));
                                     }
@@ -1976,10 +3065,11 @@ async fn create_task(
                             }
 
                             info!(
-                                "Task {} {}: {} delegation(s), {} succeeded, {} failed",
+                                "Task {} {}: {} delegation(s) over {} round(s), {} succeeded, {} failed",
                                 task_id,
                                 if all_success { "completed" } else { "partially completed" },
-                                coord_response.delegations.len(),
+                                outcome.delegations.len(),
+                                outcome.rounds,
                                 delegation_results.iter().filter(|r| r.success).count(),
                                 delegation_results.iter().filter(|r| !r.success).count()
                             );
@@ -1997,10 +3087,12 @@ async fn create_task(
 
                             Json(TaskResponse {
                                 task_id,
-                                status: if all_success { "completed" } else { "partial" }.to_string(),
+                                status: if all_success { TaskRunStatus::Completed } else { TaskRunStatus::Partial },
                                 output: Some(combined_output),
                                 error: if errors.is_empty() { None } else { Some(errors.join("; ")) },
                                 assigned_agent: Some(coordinator_id.to_string()),
+                                delegations,
+                                progress: None,
                             })
                         }
                         "direct" => {
@@ -2012,8 +3104,8 @@ async fn create_task(
                             // Update task state
                             {
                                 let mut tasks = state.tasks.write().await;
-                                if let Some(task) = tasks.get_mut(&task_id) {
-                                    task.status = "failed".to_string();
+                                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                                    task.status = TaskRunStatus::Failed;
                                     task.error = Some(error_msg.to_string());
                                     task.updated_at = Utc::now();
                                 }
@@ -2021,10 +3113,12 @@ async fn create_task(
 
                             Json(TaskResponse {
                                 task_id,
-                                status: "failed".to_string(),
+                                status: TaskRunStatus::Failed,
                                 output: None,
                                 error: Some(error_msg.to_string()),
                                 assigned_agent: Some(coordinator_id.to_string()),
+                                delegations: Vec::new(),
+                                progress: None,
                             })
                         }
                         "error" => {
@@ -2033,8 +3127,8 @@ async fn create_task(
                             // Update task state
                             {
                                 let mut tasks = state.tasks.write().await;
-                                if let Some(task) = tasks.get_mut(&task_id) {
-                                    task.status = "failed".to_string();
+                                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                                    task.status = TaskRunStatus::Failed;
                                     task.error = Some(error_msg.clone());
                                     task.updated_at = Utc::now();
                                 }
@@ -2042,10 +3136,12 @@ async fn create_task(
 
                             Json(TaskResponse {
                                 task_id,
-                                status: "failed".to_string(),
+                                status: TaskRunStatus::Failed,
                                 output: None,
                                 error: Some(error_msg),
                                 assigned_agent: Some(coordinator_id.to_string()),
+                                delegations: Vec::new(),
+                                progress: None,
                             })
                         }
                         _ => {
@@ -2055,8 +3151,8 @@ async fn create_task(
                             // Update task state
                             {
                                 let mut tasks = state.tasks.write().await;
-                                if let Some(task) = tasks.get_mut(&task_id) {
-                                    task.status = "completed".to_string();
+                                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                                    task.status = TaskRunStatus::Completed;
                                     task.output = Some(coordinator_output.clone());
                                     task.updated_at = Utc::now();
                                 }
@@ -2064,10 +3160,12 @@ async fn create_task(
 
                             Json(TaskResponse {
                                 task_id,
-                                status: "completed".to_string(),
+                                status: TaskRunStatus::Completed,
                                 output: Some(coordinator_output),
                                 error: None,
                                 assigned_agent: Some(coordinator_id.to_string()),
+                                delegations: Vec::new(),
+                                progress: None,
                             })
                         }
                     }
@@ -2083,8 +3181,8 @@ async fn create_task(
                     // Update task state
                     {
                         let mut tasks = state.tasks.write().await;
-                        if let Some(task) = tasks.get_mut(&task_id) {
-                            task.status = "completed".to_string();
+                        if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                            task.status = TaskRunStatus::Completed;
                             task.output = Some(coordinator_output.clone());
                             task.updated_at = Utc::now();
                         }
@@ -2102,10 +3200,12 @@ async fn create_task(
 
                     Json(TaskResponse {
                         task_id,
-                        status: "completed".to_string(),
+                        status: TaskRunStatus::Completed,
                         output: Some(coordinator_output),
                         error: None,
                         assigned_agent: Some(coordinator_id.to_string()),
+                        delegations: Vec::new(),
+                        progress: None,
                     })
                 }
             }
@@ -2120,8 +3220,8 @@ async fn create_task(
             // Update task state
             {
                 let mut tasks = state.tasks.write().await;
-                if let Some(task) = tasks.get_mut(&task_id) {
-                    task.status = "failed".to_string();
+                if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+                    task.status = TaskRunStatus::Failed;
                     task.error = Some(error_msg.clone());
                     task.updated_at = Utc::now();
                 }
@@ -2129,52 +3229,195 @@ async fn create_task(
 
             Json(TaskResponse {
                 task_id,
-                status: "failed".to_string(),
+                status: TaskRunStatus::Failed,
                 output: None,
                 error: Some(error_msg),
                 assigned_agent: Some(coordinator_id.to_string()),
+                delegations: Vec::new(),
+                progress: None,
             })
         }
     }
 }
 
+/// Reminder appended to the task when the coordinator's reply wasn't valid JSON
+const COORDINATOR_JSON_REMINDER: &str =
+    "Your last response was not valid JSON. Respond with ONLY the JSON object.";
+
+/// Parse the coordinator's decision out of its output
+///
+/// The coordinator might wrap the JSON in markdown or other text.
+fn parse_coordinator_response(output: &str) -> Option<CoordinatorResponse> {
+    extract_json_from_output(output).and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Send a task to the coordinator, retrying with a stricter reminder when its
+/// reply can't be parsed as a decision
+///
+/// `send` delivers the task text and returns the coordinator's reply. Makes at
+/// most `max_retries` extra attempts. Returns the last reply together with its
+/// decision, or `None` if no attempt produced valid JSON (the caller then falls
+/// back to treating the reply as a direct response). A failed retry falls back
+/// to the previous reply; only a failure of the first send is an error.
+async fn request_coordinator_decision<F, Fut>(
+    description: &str,
+    max_retries: u32,
+    mut send: F,
+) -> Result<(cca_acp::TaskResponse, Option<CoordinatorResponse>)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<cca_acp::TaskResponse>>,
+{
+    let mut response = send(description.to_string()).await?;
+    for attempt in 1..=max_retries {
+        if let Some(decision) = parse_coordinator_response(&response.output) {
+            return Ok((response, Some(decision)));
+        }
+
+        warn!(
+            "Coordinator returned invalid JSON, retrying ({}/{})",
+            attempt, max_retries
+        );
+        match send(format!("{description}\n\n{COORDINATOR_JSON_REMINDER}")).await {
+            Ok(retry) => response = retry,
+            Err(e) => {
+                warn!("Coordinator retry failed: {}", e);
+                return Ok((response, None));
+            }
+        }
+    }
+
+    let decision = parse_coordinator_response(&response.output);
+    Ok((response, decision))
+}
+
+/// Appended to the results of a round when asking the coordinator what's next
+const COORDINATOR_FOLLOW_UP: &str = "The delegations above have finished. If the task is done, \
+respond with ONLY {\"action\":\"final\",\"response\":\"answer for the user, based on the results\"}. \
+If more work is needed, respond with ONLY a new {\"action\":\"delegate\",...} object for the remaining \
+work. Do not repeat delegations that already succeeded.";
+
+/// Everything delegated for a task across coordinator rounds
+struct CoordinationOutcome {
+    delegations: Vec<CoordinatorDelegation>,
+    /// One result per delegation, in the same order
+    results: Vec<DelegateTaskResponse>,
+    /// The coordinator's `final` answer, if it gave one
+    final_response: Option<String>,
+    rounds: u32,
+}
+
+/// Run a coordinator's delegations, then keep showing it the results until it
+/// gives a final answer
+///
+/// `first` is the coordinator's initial `delegate` decision. After each round
+/// the coordinator sees the round's results and may delegate more work or
+/// reply `final`. Stops after `max_rounds` rounds, when the coordinator gives
+/// anything but new delegations, or when it repeats the previous round's
+/// delegations verbatim. With `max_rounds` 1 the coordinator is never asked
+/// again.
+async fn run_coordination_rounds<S, SFut, D, DFut>(
+    description: &str,
+    first: CoordinatorResponse,
+    max_rounds: u32,
+    json_retries: u32,
+    mut ask_coordinator: S,
+    mut delegate: D,
+) -> CoordinationOutcome
+where
+    S: FnMut(String) -> SFut,
+    SFut: std::future::Future<Output = Result<cca_acp::TaskResponse>>,
+    D: FnMut(Vec<CoordinatorDelegation>) -> DFut,
+    DFut: std::future::Future<Output = Vec<DelegateTaskResponse>>,
+{
+    let mut outcome = CoordinationOutcome {
+        delegations: Vec::new(),
+        results: Vec::new(),
+        final_response: None,
+        rounds: 0,
+    };
+    let mut round_delegations = first.delegations;
+
+    loop {
+        outcome.rounds += 1;
+        let round_results = delegate(round_delegations.clone()).await;
+        if outcome.rounds >= max_rounds {
+            outcome.delegations.extend(round_delegations);
+            outcome.results.extend(round_results);
+            break;
+        }
+
+        let (round_output, _, _) = combine_delegation_results(None, &round_delegations, &round_results);
+        let follow_up = format!(
+            "{description}\n\n# Results of round {}\n\n{round_output}{COORDINATOR_FOLLOW_UP}",
+            outcome.rounds
+        );
+        let previous = std::mem::take(&mut round_delegations);
+        outcome.delegations.extend(previous.iter().cloned());
+        outcome.results.extend(round_results);
+
+        let decision = match request_coordinator_decision(&follow_up, json_retries, &mut ask_coordinator).await {
+            Ok((_, Some(decision))) => decision,
+            Ok((_, None)) => {
+                warn!("Coordinator gave no decision after round {}, stopping", outcome.rounds);
+                break;
+            }
+            Err(e) => {
+                warn!("Coordinator follow-up after round {} failed: {}", outcome.rounds, e);
+                break;
+            }
+        };
+
+        match decision.action.as_str() {
+            "final" => {
+                outcome.final_response = decision.response.or(decision.summary);
+                break;
+            }
+            "delegate" if decision.delegations.is_empty() => break,
+            "delegate" if same_delegations(&decision.delegations, &previous) => {
+                warn!("Coordinator repeated the delegations of round {}, stopping", outcome.rounds);
+                break;
+            }
+            "delegate" => round_delegations = decision.delegations,
+            other => {
+                warn!("Coordinator answered round {} with action '{}', stopping", outcome.rounds, other);
+                break;
+            }
+        }
+    }
+
+    outcome
+}
+
+fn same_delegations(a: &[CoordinatorDelegation], b: &[CoordinatorDelegation]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.role == y.role && x.task == y.task)
+}
+
+/// Longest stretch of coordinator output scanned for a balanced JSON object
+const MAX_JSON_SCAN_LEN: usize = 1024 * 1024;
+
 /// Extract JSON object from coordinator output (may contain markdown or other text)
+///
+/// Returns `None` rather than a truncated object when the braces never balance
+/// within `MAX_JSON_SCAN_LEN` bytes.
 fn extract_json_from_output(output: &str) -> Option<String> {
     // Try to find JSON object in the output
     // Look for { ... } pattern, handling nested braces
     let trimmed = output.trim();
 
     // If the output starts with {, try to parse directly
-    if trimmed.starts_with('{') {
-        // Find matching closing brace
-        let mut depth = 0;
-        let mut end_pos = 0;
-        for (i, c) in trimmed.char_indices() {
-            match c {
-                '{' => depth += 1,
-                '}' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_pos = i + 1;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-        if end_pos > 0 {
-            return Some(trimmed[..end_pos].to_string());
-        }
+    if let Some(end) = balanced_object_len(trimmed) {
+        return Some(trimmed[..end].to_string());
     }
 
     // Look for ```json code blocks
     // Safety: "```json" is 7 ASCII bytes, so start + 7 is always a valid UTF-8 boundary
     if let Some(start) = trimmed.find("```json") {
         let json_start = start + 7; // "```json".len()
-        if json_start <= trimmed.len() {
-            if let Some(end) = trimmed[json_start..].find("```") {
-                let json_end = json_start + end;
-                return Some(trimmed[json_start..json_end].trim().to_string());
+        if let Some(end) = trimmed[json_start..].find("```") {
+            let block = trimmed[json_start..json_start + end].trim();
+            if let Some(len) = balanced_object_len(block) {
+                return Some(block[..len].to_string());
             }
         }
     }
@@ -2183,38 +3426,57 @@ fn extract_json_from_output(output: &str) -> Option<String> {
     // Safety: "```\n" is 4 ASCII bytes, so start + 4 is always a valid UTF-8 boundary
     if let Some(start) = trimmed.find("```\n{") {
         let json_start = start + 4; // "```\n".len()
-        if json_start <= trimmed.len() {
-            if let Some(end) = trimmed[json_start..].find("```") {
-                let json_end = json_start + end;
-                return Some(trimmed[json_start..json_end].trim().to_string());
+        if let Some(end) = trimmed[json_start..].find("```") {
+            let block = trimmed[json_start..json_start + end].trim();
+            if let Some(len) = balanced_object_len(block) {
+                return Some(block[..len].to_string());
             }
         }
     }
 
     // Look for first { in the output
     // Safety: '{' is ASCII, so `start` is always a valid UTF-8 boundary
-    if let Some(start) = trimmed.find('{') {
-        let json_part = &trimmed[start..];
-        let mut depth = 0;
-        let mut end_pos = 0;
-        for (i, c) in json_part.char_indices() {
-            match c {
-                '{' => depth += 1,
-                '}' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_pos = i + 1;
-                        break;
-                    }
-                }
+    let start = trimmed.find('{')?;
+    let json_part = &trimmed[start..];
+    balanced_object_len(json_part).map(|end| json_part[..end].to_string())
+}
+
+/// Byte length of the JSON object at the start of `text`, if its braces balance
+///
+/// Braces inside string literals (including ones with escaped quotes) don't
+/// count. Gives up after `MAX_JSON_SCAN_LEN` bytes.
+fn balanced_object_len(text: &str) -> Option<usize> {
+    if !text.starts_with('{') {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    // Only ASCII bytes are inspected, and UTF-8 continuation bytes are never
+    // ASCII, so the returned length always falls on a char boundary
+    for (i, byte) in text.bytes().take(MAX_JSON_SCAN_LEN).enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
                 _ => {}
             }
+            continue;
         }
-        if end_pos > 0 {
-            return Some(json_part[..end_pos].to_string());
+        match byte {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
         }
     }
-
     None
 }
 
@@ -2227,8 +3489,8 @@ async fn store_task_as_pattern(
     output: &str,
     duration_ms: u64,
 ) {
-    // Only store patterns if PostgreSQL is configured
-    let Some(postgres_services) = &state.postgres else {
+    // Only store patterns if a pattern store (PostgreSQL) is configured
+    let Some(patterns) = &state.pattern_store else {
         return;
     };
 
@@ -2264,20 +3526,18 @@ async fn store_task_as_pattern(
     };
 
     // Store the pattern with Solution type
-    match postgres_services
-        .patterns
-        .create(
-            Some(agent_id.0), // Extract Uuid from AgentId
-            crate::postgres::PatternType::Solution,
-            output,
-            embedding.as_deref(),
-            metadata,
-        )
-        .await
-    {
-        Ok(pattern_id) => {
-            let with_emb = if embedding.is_some() { " (with embedding)" } else { "" };
-            debug!("Stored pattern {}{} for {} agent {} ({}ms)", pattern_id, with_emb, role, agent_id, duration_ms);
+    let mut pattern = Pattern::new(crate::postgres::PatternType::Solution.into(), output);
+    pattern.agent_id = Some(agent_id);
+    pattern.embedding = embedding;
+    pattern.metadata = metadata;
+    let with_emb = if pattern.embedding.is_some() { " (with embedding)" } else { "" };
+
+    match store_or_merge_pattern(patterns.as_ref(), pattern, state.config.embeddings.dedup_threshold).await {
+        Ok(StoredPattern::Created(pattern_id)) => {
+            debug!("Stored pattern {}{} for {} agent {} ({}ms)", pattern_id.0, with_emb, role, agent_id, duration_ms);
+        }
+        Ok(StoredPattern::Merged(pattern_id)) => {
+            debug!("Merged {} agent {} result into existing pattern {}", role, agent_id, pattern_id.0);
         }
         Err(e) => {
             warn!("Failed to store pattern for {} agent {}: {}", role, agent_id, e);
@@ -2285,137 +3545,582 @@ async fn store_task_as_pattern(
     }
 }
 
-/// Execute delegations to specialist agents IN PARALLEL
+/// Outcome of `store_or_merge_pattern`
+#[derive(Debug, PartialEq, Eq)]
+enum StoredPattern {
+    Created(PatternId),
+    /// A near-duplicate already existed and had its success count bumped
+    Merged(PatternId),
+}
+
+/// Store a pattern unless an existing one is at least `dedup_threshold` similar
 ///
-/// This is the core of CCA's value - multiple agents working simultaneously.
-/// All delegations are spawned concurrently and awaited together.
-async fn execute_delegations(
-    state: &DaemonState,
-    delegations: &[CoordinatorDelegation],
-) -> Vec<DelegateTaskResponse> {
-    use futures_util::future::join_all;
+/// Patterns without an embedding, or a threshold of 0, always insert.
+async fn store_or_merge_pattern(
+    patterns: &dyn PatternStore,
+    pattern: Pattern,
+    dedup_threshold: f64,
+) -> cca_core::Result<StoredPattern> {
+    if dedup_threshold > 0.0 {
+        if let Some(ref embedding) = pattern.embedding {
+            if let Some(existing) = patterns.find_duplicate(embedding, dedup_threshold).await? {
+                patterns.record_success(existing).await?;
+                return Ok(StoredPattern::Merged(existing));
+            }
+        }
+    }
+    Ok(StoredPattern::Created(patterns.create(pattern).await?))
+}
 
-    if delegations.is_empty() {
-        return Vec::new();
+/// Create a task from a named template in config
+///
+/// Expands the template's steps with the request parameters and delegates them
+/// directly, skipping the coordinator. Steps run in order (each receiving the
+/// previous step's output as context, stopping at the first failure) unless the
+/// template sets `parallel = true`.
+async fn create_task_from_template(
+    State(state): State<DaemonState>,
+    Extension(request_id): Extension<RequestId>,
+    Json(request): Json<TemplateTaskRequest>,
+) -> Result<Json<TaskResponse>, ValidationError> {
+    request.validate()?;
+
+    let error_response = |error: String| {
+        Ok(Json(TaskResponse {
+            task_id: String::new(),
+            status: TaskRunStatus::Error,
+            output: None,
+            error: Some(error),
+            assigned_agent: None,
+            delegations: Vec::new(),
+            progress: None,
+        }))
+    };
+
+    // SEC-008: Input validation - check parameter count and sizes
+    if request.params.len() > MAX_TEMPLATE_PARAMS {
+        return error_response(format!(
+            "Too many parameters: {} (max: {})",
+            request.params.len(), MAX_TEMPLATE_PARAMS
+        ));
+    }
+    if let Some((name, _)) = request
+        .params
+        .iter()
+        .find(|(k, v)| k.len() > MAX_ROLE_LEN || v.len() > MAX_TEMPLATE_PARAM_LEN)
+    {
+        return error_response(format!(
+            "Parameter '{}' too long (max: {} bytes)",
+            safe_truncate(name, MAX_ROLE_LEN), MAX_TEMPLATE_PARAM_LEN
+        ));
     }
 
-    info!("Executing {} delegations IN PARALLEL", delegations.len());
+    // SEC-008: Input validation - validate priority against whitelist
+    let priority = request.priority.as_deref().unwrap_or("normal");
+    if !VALID_PRIORITIES.contains(&priority) {
+        return error_response(format!(
+            "Invalid priority '{}'. Must be one of: {}",
+            safe_truncate(priority, MAX_PRIORITY_LEN), VALID_PRIORITIES.join(", ")
+        ));
+    }
 
-    // Phase 1: Prepare all delegations - validate roles and find/spawn agents
-    // This phase is sequential to avoid race conditions when spawning agents
-    let mut prepared: Vec<(CoordinatorDelegation, AgentId)> = Vec::new();
-    let mut errors: Vec<DelegateTaskResponse> = Vec::new();
+    // Config keys are case-insensitive
+    let template_name = request.template.to_lowercase();
+    let Some(template) = state.config.templates.get(&template_name) else {
+        let mut available: Vec<&str> = state.config.templates.keys().map(String::as_str).collect();
+        available.sort_unstable();
+        return error_response(format!(
+            "Unknown template '{}'. Available: {}",
+            safe_truncate(&request.template, 64),
+            if available.is_empty() { "none".to_string() } else { available.join(", ") }
+        ));
+    };
 
-    for delegation in delegations {
-        info!("Preparing delegation to {}: {}", delegation.role,
-              safe_truncate(&delegation.task, 50));
+    let steps = match template.expand(&request.params) {
+        Ok(steps) => steps,
+        Err(e) => return error_response(format!("Template '{template_name}': {e}")),
+    };
+    if let Some(step) = steps.iter().find(|s| s.task.len() > MAX_TASK_DESCRIPTION_LEN) {
+        return error_response(format!(
+            "Expanded task for {} too long: {} bytes (max: {} bytes)",
+            step.role, step.task.len(), MAX_TASK_DESCRIPTION_LEN
+        ));
+    }
 
-        // Validate role
-        let valid_role = matches!(
-            delegation.role.to_lowercase().as_str(),
-            "frontend" | "backend" | "dba" | "devops" | "security" | "qa"
+    let task_id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let description = if template.description.is_empty() {
+        format!("Template '{template_name}'")
+    } else {
+        format!("Template '{template_name}': {}", template.description)
+    };
+
+    {
+        let mut tasks = state.tasks.write().await;
+        tasks.insert(
+            task_id.clone(),
+            TaskState {
+                task_id: task_id.clone(),
+                description: description.clone(),
+                status: TaskRunStatus::Running,
+                priority: priority.to_string(),
+                output: None,
+                error: None,
+                assigned_agent: None,
+                delegations: Vec::new(),
+                progress: None,
+                content_hash: None,
+                use_delegation_cache: false,
+                deadline: task_deadline(&state.config.agents),
+                created_at: now,
+                updated_at: now,
+            },
         );
+    }
 
-        if !valid_role {
-            errors.push(DelegateTaskResponse {
+    persist_task_created(&state, &task_id, &description, TaskRunStatus::Running).await;
+
+    info!(
+        "Task {} created from template '{}' ({} steps, {})",
+        task_id,
+        template_name,
+        steps.len(),
+        if template.parallel { "parallel" } else { "sequential" }
+    );
+
+    let mut delegations: Vec<CoordinatorDelegation> = steps
+        .into_iter()
+        .map(|step| CoordinatorDelegation {
+            role: step.role,
+            task: step.task,
+            context: step.context,
+        })
+        .collect();
+
+    let results = if template.parallel {
+        execute_delegations(&state, &task_id, &delegations, &request_id).await
+    } else {
+        let mut results: Vec<DelegateTaskResponse> = Vec::with_capacity(delegations.len());
+        for i in 0..delegations.len() {
+            if state.cancellation.is_cancelled(&task_id) {
+                break;
+            }
+            // Hand the previous step's output to the next step
+            if let Some(prev) = results.last().and_then(|r| r.output.as_deref()) {
+                let handoff = format!(
+                    "Output from previous step ({}):\n{}",
+                    delegations[i - 1].role,
+                    safe_truncate(prev, MAX_TEMPLATE_STEP_CONTEXT_LEN)
+                );
+                let delegation = &mut delegations[i];
+                delegation.context = Some(match delegation.context.take() {
+                    Some(ctx) => format!("{ctx}\n\n{handoff}"),
+                    None => handoff,
+                });
+            }
+
+            let result = execute_delegations(
+                &state,
+                &task_id,
+                std::slice::from_ref(&delegations[i]),
+                &request_id,
+            )
+            .await
+            .pop();
+            let Some(result) = result else { break };
+            let failed = !result.success;
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+
+        // Report steps skipped after a failure
+        for delegation in &delegations[results.len()..] {
+            results.push(DelegateTaskResponse {
                 success: false,
                 agent_id: String::new(),
                 role: delegation.role.clone(),
                 output: None,
-                error: Some(format!("Unknown role: {}", delegation.role)),
+                error: Some("Skipped: previous step failed".to_string()),
                 duration_ms: 0,
                 tokens_used: 0,
             });
-            continue;
         }
+        results
+    };
 
-        // Find an available agent (not already assigned in this batch)
-        let already_assigned: Vec<AgentId> = prepared.iter().map(|(_, id)| *id).collect();
-        let agent_id = find_available_agent_excluding(state, &delegation.role, &already_assigned).await;
+    if let Some(response) = cancelled_task_response(&state, &task_id).await {
+        persist_task_result(&state, &task_id).await;
+        return Ok(response);
+    }
 
-        let agent_id = match agent_id {
-            Some(id) => {
-                info!("Found available {} agent: {}", delegation.role, id);
-                id
+    let heading = (!template.description.is_empty())
+        .then_some(("Template", template.description.as_str()));
+    let (combined_output, all_success, errors) =
+        combine_delegation_results(heading, &delegations, &results);
+    let delegation_results: Vec<DelegationResult> =
+        results.iter().map(DelegationResult::from).collect();
+    let status = if all_success { TaskRunStatus::Completed } else { TaskRunStatus::Partial };
+
+    {
+        let mut tasks = state.tasks.write().await;
+        if let Some(task) = live_task_mut(&mut tasks, &task_id) {
+            task.status = status;
+            task.output = Some(combined_output.clone());
+            task.delegations = delegation_results.clone();
+            if !errors.is_empty() {
+                task.error = Some(errors.join("; "));
             }
-            None => {
-                // No available agent - try to spawn one via tmux
-                if state.tmux_manager.is_available() {
-                    let existing_tmux_agents = state.tmux_manager.agents_by_role(&delegation.role).await;
-                    // Allow more agents for parallel work (up to 5 per role)
-                    if existing_tmux_agents.len() >= 5 {
-                        errors.push(DelegateTaskResponse {
-                            success: false,
-                            agent_id: String::new(),
-                            role: delegation.role.clone(),
-                            output: None,
-                            error: Some(format!(
-                                "No available {} agent. {} agents spawned but all busy.",
-                                delegation.role, existing_tmux_agents.len()
-                            )),
-                            duration_ms: 0,
-                            tokens_used: 0,
-                        });
-                        continue;
-                    }
+            task.updated_at = Utc::now();
+        }
+    }
+    persist_task_result(&state, &task_id).await;
 
-                    info!("No available {} agent, spawning via tmux (existing: {})",
-                          delegation.role, existing_tmux_agents.len());
-                    match state.tmux_manager.spawn_agent(&delegation.role).await {
-                        Ok(pane_id) => {
-                            info!("Spawned {} agent in tmux pane {}", delegation.role, pane_id);
-                            // Wait for the agent to connect with retries
-                            let mut new_agent_id = None;
-                            for attempt in 1..=5 {
-                                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                                // Find an available agent that's NOT already assigned
-                                if let Some(id) = find_available_agent_excluding(
-                                    state,
-                                    &delegation.role,
-                                    &already_assigned,
-                                ).await {
-                                    new_agent_id = Some(id);
-                                    break;
-                                }
-                                info!("Waiting for {} agent to connect (attempt {}/5)", delegation.role, attempt);
-                            }
-                            match new_agent_id {
-                                Some(id) => id,
-                                None => {
-                                    warn!("Spawned agent hasn't connected after 10 seconds");
-                                    errors.push(DelegateTaskResponse {
-                                        success: false,
-                                        agent_id: String::new(),
-                                        role: delegation.role.clone(),
-                                        output: None,
-                                        error: Some("Agent spawned but not connected. Try again.".to_string()),
-                                        duration_ms: 0,
-                                        tokens_used: 0,
-                                    });
-                                    continue;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to spawn {} agent via tmux: {}", delegation.role, e);
-                            errors.push(DelegateTaskResponse {
-                                success: false,
-                                agent_id: String::new(),
-                                role: delegation.role.clone(),
-                                output: None,
-                                error: Some(format!(
-                                    "No {} agent available. Start one with: cca agent worker {}",
-                                    delegation.role, delegation.role
-                                )),
-                                duration_ms: 0,
-                                tokens_used: 0,
-                            });
-                            continue;
+    Ok(Json(TaskResponse {
+        task_id,
+        status,
+        output: Some(combined_output),
+        error: if errors.is_empty() { None } else { Some(errors.join("; ")) },
+        assigned_agent: None,
+        delegations: delegation_results,
+        progress: None,
+    }))
+}
+
+/// Combine delegation results into a markdown report
+///
+/// Returns `(output, all_success, errors)`; `heading` is an optional
+/// `(title, body)` section placed before the per-agent sections.
+fn combine_delegation_results(
+    heading: Option<(&str, &str)>,
+    delegations: &[CoordinatorDelegation],
+    results: &[DelegateTaskResponse],
+) -> (String, bool, Vec<String>) {
+    let mut combined_output = String::new();
+    let mut all_success = true;
+    let mut errors = Vec::new();
+
+    if let Some((title, body)) = heading {
+        combined_output.push_str(&format!("## {title}\n{body}\n\n"));
+    }
+
+    for (delegation, result) in delegations.iter().zip(results.iter()) {
+        combined_output.push_str(&format!("## {} Agent\n", delegation.role));
+        if result.success {
+            if let Some(ref out) = result.output {
+                combined_output.push_str(out);
+            }
+        } else {
+            all_success = false;
+            if let Some(ref err) = result.error {
+                errors.push(format!("{}: {}", delegation.role, err));
+                combined_output.push_str(&format!("Error: {err}\n"));
+            }
+        }
+        combined_output.push_str("\n\n");
+    }
+
+    (combined_output, all_success, errors)
+}
+
+/// Coordinator system prompt followed by a note on which workers can take delegations
+fn coordinator_context(state: &DaemonState, available_roles: &[String]) -> String {
+    let workers_info = if available_roles.is_empty() {
+        if state.tmux_manager.is_available() {
+            // Tmux available - allow delegation, workers will be auto-spawned
+            "No workers are currently connected, but auto-spawn is enabled. \
+             You may delegate to any role (backend, frontend, devops, dba, security, qa) and workers will be spawned automatically.".to_string()
+        } else {
+            "IMPORTANT: No specialist workers are currently connected. \
+             You MUST return an error response telling the user to start the required worker(s).\n\
+             Example: {\"action\":\"error\",\"error\":\"No workers available. Start required workers with: cca agent worker <role>\",\"required_workers\":[\"backend\"]}".to_string()
+        }
+    } else {
+        let auto_spawn_note = if state.tmux_manager.is_available() {
+            " Additional workers can be auto-spawned if needed."
+        } else {
+            ""
+        };
+        format!(
+            "Available workers: {}.{} \
+             If a required role is not available, return an error response listing the missing workers.",
+            available_roles.join(", "),
+            auto_spawn_note
+        )
+    };
+    format!("{}\n\n{workers_info}", state.config.agents.coordinator_system_prompt)
+}
+
+/// Describe connected specialist workers for the coordinator prompt
+///
+/// Each entry is the worker's role, followed by whatever capabilities it
+/// advertised at registration, e.g. `backend (model: x, tools: Read, Grep)`.
+fn describe_workers(
+    agents: &[(AgentId, Option<String>, Option<cca_acp::AgentCapabilities>)],
+) -> Vec<String> {
+    agents
+        .iter()
+        .filter_map(|(_, role, capabilities)| {
+            let role = role.as_deref()?;
+            if role == "coordinator" || role == "unregistered" {
+                return None;
+            }
+
+            let mut details = Vec::new();
+            if let Some(caps) = capabilities {
+                if let Some(model) = &caps.model {
+                    details.push(format!("model: {model}"));
+                }
+                if let Some(max_context) = caps.max_context {
+                    details.push(format!("max context: {max_context} tokens"));
+                }
+                if !caps.supported_tools.is_empty() {
+                    details.push(format!("tools: {}", caps.supported_tools.join(", ")));
+                }
+            }
+
+            Some(if details.is_empty() {
+                role.to_string()
+            } else {
+                format!("{role} ({})", details.join(", "))
+            })
+        })
+        .collect()
+}
+
+/// Execute delegations to specialist agents IN PARALLEL
+///
+/// This is the core of CCA's value - multiple agents working simultaneously.
+/// Delegations run concurrently, at most `agents.max_concurrent_delegations`
+/// at a time. Results are returned in the same order as `delegations`.
+async fn execute_delegations(
+    state: &DaemonState,
+    task_id: &str,
+    delegations: &[CoordinatorDelegation],
+    request_id: &RequestId,
+) -> Vec<DelegateTaskResponse> {
+    if delegations.is_empty() {
+        return Vec::new();
+    }
+
+    info!("Executing {} delegations IN PARALLEL", delegations.len());
+
+    // Phase 1: Prepare all delegations - validate roles and find/spawn agents
+    // This phase is sequential to avoid race conditions when spawning agents
+    // Entries carry the delegation's index so results can be returned in input order
+    let mut prepared: Vec<(usize, CoordinatorDelegation, AgentId)> = Vec::new();
+    // Budget tokens reserved per delegation index, settled once it finishes
+    let mut reservations: HashMap<usize, (u64, tokio::time::Instant)> = HashMap::new();
+    // Delegations answered without dispatch: failures and cache hits
+    let mut settled: Vec<(usize, DelegateTaskResponse)> = Vec::new();
+    let (use_cache, deadline, priority) = state
+        .tasks
+        .read()
+        .await
+        .get(task_id)
+        .map_or((false, None, TaskPriority::Normal), |t| {
+            (t.use_delegation_cache, t.deadline, t.priority.parse().unwrap_or_default())
+        });
+
+    // Out of time already: fail everything before looking for agents
+    if let Err(e) = delegation_timeout(std::time::Duration::MAX, deadline, tokio::time::Instant::now()) {
+        warn!("Task {} is past its deadline, skipping {} delegations", task_id, delegations.len());
+        return delegations
+            .iter()
+            .map(|delegation| DelegateTaskResponse {
+                success: false,
+                agent_id: String::new(),
+                role: delegation.role.clone(),
+                output: None,
+                error: Some(e.to_string()),
+                duration_ms: 0,
+                tokens_used: 0,
+            })
+            .collect();
+    }
+
+    for (index, delegation) in delegations.iter().enumerate() {
+        info!("Preparing delegation to {}: {}", delegation.role,
+              safe_truncate(&delegation.task, 50));
+
+        // Validate role
+        let valid_role = matches!(
+            delegation.role.to_lowercase().as_str(),
+            "frontend" | "backend" | "dba" | "devops" | "security" | "qa"
+        );
+
+        if !valid_role {
+            settled.push((index, DelegateTaskResponse {
+                success: false,
+                agent_id: String::new(),
+                role: delegation.role.clone(),
+                output: None,
+                error: Some(format!("Unknown role: {}", delegation.role)),
+                duration_ms: 0,
+                tokens_used: 0,
+            }));
+            continue;
+        }
+
+        if use_cache {
+            let cached = state.delegation_cache.get(
+                &delegation.role,
+                &delegation.task,
+                delegation.context.as_deref(),
+            );
+            if let Some(output) = cached {
+                info!("Reusing cached {} output for: {}", delegation.role,
+                      safe_truncate(&delegation.task, 50));
+                settled.push((index, DelegateTaskResponse {
+                    success: true,
+                    agent_id: String::new(),
+                    role: delegation.role.clone(),
+                    output: Some(output),
+                    error: None,
+                    duration_ms: 0,
+                    tokens_used: 0,
+                }));
+                continue;
+            }
+        }
+
+        // Fail fast while the role keeps failing
+        let breaker = state
+            .orchestrator
+            .read()
+            .await
+            .check_role_breaker(&delegation.role.to_lowercase())
+            .await;
+        if let Err(e) = breaker {
+            settled.push((index, DelegateTaskResponse {
+                success: false,
+                agent_id: String::new(),
+                role: delegation.role.clone(),
+                output: None,
+                error: Some(e.to_string()),
+                duration_ms: 0,
+                tokens_used: 0,
+            }));
+            continue;
+        }
+
+        // Find an available agent (not already assigned in this batch). While
+        // other delegations are queued for this role, a freed agent is theirs.
+        let already_assigned: Vec<AgentId> = prepared.iter().map(|(_, _, id)| *id).collect();
+        let found = if state.dispatch_queue.has_waiters(&delegation.role) {
+            None
+        } else {
+            find_available_agent_excluding(state, &delegation.role, &already_assigned).await
+        };
+        // Held until the agent is marked busy so the next waiter can't take it too
+        let mut _queue_ticket = None;
+        let agent_id = match found {
+            Some(id) => Some(id),
+            // Every agent of this role is busy - give one a chance to free up before spawning
+            None => match wait_for_busy_agent(state, &delegation.role, priority, &already_assigned).await {
+                Ok(waited) => waited.map(|(id, ticket)| {
+                    _queue_ticket = Some(ticket);
+                    id
+                }),
+                Err(e) => {
+                    settled.push((index, DelegateTaskResponse {
+                        success: false,
+                        agent_id: String::new(),
+                        role: delegation.role.clone(),
+                        output: None,
+                        error: Some(e.to_string()),
+                        duration_ms: 0,
+                        tokens_used: 0,
+                    }));
+                    continue;
+                }
+            },
+        };
+
+        let agent_id = match agent_id {
+            Some(id) => {
+                info!("Found available {} agent: {}", delegation.role, id);
+                id
+            }
+            None => {
+                // No available agent - try to spawn one via tmux
+                if state.tmux_manager.is_available() {
+                    let existing_tmux_agents = state.tmux_manager.agents_by_role(&delegation.role).await;
+                    // Allow more agents for parallel work (up to 5 per role)
+                    if existing_tmux_agents.len() >= 5 {
+                        settled.push((index, DelegateTaskResponse {
+                            success: false,
+                            agent_id: String::new(),
+                            role: delegation.role.clone(),
+                            output: None,
+                            error: Some(format!(
+                                "No available {} agent. {} agents spawned but all busy.",
+                                delegation.role, existing_tmux_agents.len()
+                            )),
+                            duration_ms: 0,
+                            tokens_used: 0,
+                        }));
+                        continue;
+                    }
+
+                    info!("No available {} agent, spawning via tmux (existing: {})",
+                          delegation.role, existing_tmux_agents.len());
+                    match state.tmux_manager.spawn_agent(&delegation.role).await {
+                        Ok(pane_id) => {
+                            info!("Spawned {} agent in tmux pane {}", delegation.role, pane_id);
+                            // Wait for the agent to connect with retries
+                            let mut new_agent_id = None;
+                            for attempt in 1..=5 {
+                                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                                // Find an available agent that's NOT already assigned
+                                if let Some(id) = find_available_agent_excluding(
+                                    state,
+                                    &delegation.role,
+                                    &already_assigned,
+                                ).await {
+                                    new_agent_id = Some(id);
+                                    break;
+                                }
+                                info!("Waiting for {} agent to connect (attempt {}/5)", delegation.role, attempt);
+                            }
+                            match new_agent_id {
+                                Some(id) => id,
+                                None => {
+                                    warn!("Spawned agent hasn't connected after 10 seconds");
+                                    settled.push((index, DelegateTaskResponse {
+                                        success: false,
+                                        agent_id: String::new(),
+                                        role: delegation.role.clone(),
+                                        output: None,
+                                        error: Some("Agent spawned but not connected. Try again.".to_string()),
+                                        duration_ms: 0,
+                                        tokens_used: 0,
+                                    }));
+                                    continue;
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to spawn {} agent via tmux: {}", delegation.role, e);
+                            settled.push((index, DelegateTaskResponse {
+                                success: false,
+                                agent_id: String::new(),
+                                role: delegation.role.clone(),
+                                output: None,
+                                error: Some(format!(
+                                    "No {} agent available and auto-spawn failed ({}). Start one with: cca agent worker {}",
+                                    delegation.role, e, delegation.role
+                                )),
+                                duration_ms: 0,
+                                tokens_used: 0,
+                            }));
+                            continue;
                         }
                     }
                 } else {
                     warn!("No {} agent connected and tmux not available", delegation.role);
-                    errors.push(DelegateTaskResponse {
+                    settled.push((index, DelegateTaskResponse {
                         success: false,
                         agent_id: String::new(),
                         role: delegation.role.clone(),
@@ -2426,29 +4131,57 @@ async fn execute_delegations(
                         )),
                         duration_ms: 0,
                         tokens_used: 0,
-                    });
+                    }));
                     continue;
                 }
             }
         };
 
-        prepared.push((delegation.clone(), agent_id));
-    }
+        // Reserve the input estimate against the agent's token budget before dispatching
+        let requested_tokens = u64::from(state.token_service.count(&delegation.task))
+            + delegation
+                .context
+                .as_deref()
+                .map_or(0, |c| u64::from(state.token_service.count(c)));
+        let reserved_at = tokio::time::Instant::now();
+        match state.token_service.check_budget(agent_id, requested_tokens).await {
+            BudgetDecision::Allow => {}
+            BudgetDecision::Throttle { remaining } => {
+                warn!(
+                    "{} agent {} is near its token budget ({} tokens left after this task)",
+                    delegation.role, agent_id, remaining
+                );
+            }
+            BudgetDecision::Deny { used, limit } => {
+                settled.push((index, DelegateTaskResponse {
+                    success: false,
+                    agent_id: agent_id.to_string(),
+                    role: delegation.role.clone(),
+                    output: None,
+                    error: Some(format!(
+                        "Token budget exceeded for {} agent {}: {} of {} tokens used this window",
+                        delegation.role, agent_id, used, limit
+                    )),
+                    duration_ms: 0,
+                    tokens_used: 0,
+                }));
+                continue;
+            }
+        }
 
-    if prepared.is_empty() {
-        return errors;
+        reservations.insert(index, (requested_tokens, reserved_at));
+        // Mark the agent busy as soon as it's taken so other tasks and queued
+        // delegations look elsewhere
+        state.busy_agents.write().await.insert(agent_id, delegation.task.clone());
+        prepared.push((index, delegation.clone(), agent_id));
     }
 
-    // Phase 2: Mark all agents as busy BEFORE spawning tasks
-    {
-        let mut busy = state.busy_agents.write().await;
-        for (delegation, agent_id) in &prepared {
-            busy.insert(*agent_id, delegation.task.clone());
-        }
+    if prepared.is_empty() {
+        return settled.into_iter().map(|(_, response)| response).collect();
     }
 
-    // Update Redis state for all agents
-    for (delegation, agent_id) in &prepared {
+    // Phase 2: Update Redis state for all agents BEFORE spawning tasks
+    for (_, delegation, agent_id) in &prepared {
         update_agent_redis_state(
             &state.redis,
             *agent_id,
@@ -2458,40 +4191,55 @@ async fn execute_delegations(
         ).await;
     }
 
-    // Phase 3: Spawn ALL tasks concurrently
-    info!("Spawning {} tasks concurrently", prepared.len());
-    let timeout = std::time::Duration::from_secs(state.config.agents.default_timeout_seconds);
+    // Phase 3: Spawn tasks concurrently, bounded by max_concurrent_delegations
+    let max_concurrent = state.config.agents.max_concurrent_delegations as usize;
+    info!(
+        "Spawning {} tasks concurrently (limit: {})",
+        prepared.len(),
+        if max_concurrent == 0 { "none".to_string() } else { max_concurrent.to_string() }
+    );
+    let timeout = std::time::Duration::from_secs(state.reloadable_config.read().await.default_timeout_seconds);
 
     let task_futures: Vec<_> = prepared
         .iter()
-        .map(|(delegation, agent_id)| {
+        .map(|(index, delegation, agent_id)| {
+            let index = *index;
             let state = state.clone();
             let delegation = delegation.clone();
             let agent_id = *agent_id;
 
             async move {
                 let start = std::time::Instant::now();
-                info!("Sending task to {} agent {} via WebSocket", delegation.role, agent_id);
 
-                let result = state.acp_server.send_task(
-                    agent_id,
-                    &delegation.task,
-                    delegation.context.as_deref(),
-                    timeout,
-                ).await;
+                // Whatever is left of the task's deadline caps this delegation
+                let result = match delegation_timeout(timeout, deadline, tokio::time::Instant::now()) {
+                    Ok(timeout) => {
+                        info!("Sending task to {} agent {} via WebSocket", delegation.role, agent_id);
+                        send_task_cancellable(
+                            &state,
+                            task_id,
+                            agent_id,
+                            &delegation.task,
+                            delegation.context.as_deref(),
+                            request_id,
+                            timeout,
+                        ).await
+                    }
+                    Err(e) => Err(e.into()),
+                };
 
-                (delegation, agent_id, start, result)
+                (index, delegation, agent_id, start, result)
             }
         })
         .collect();
 
     // Phase 4: Await ALL tasks together (this is where parallelism happens)
-    let task_results = join_all(task_futures).await;
+    let task_results = join_bounded(task_futures, max_concurrent).await;
 
     // Phase 5: Process results and cleanup
-    let mut results = errors; // Start with any errors from preparation phase
+    let mut results = settled; // Start with what the preparation phase settled
 
-    for (delegation, agent_id, start, result) in task_results {
+    for (index, delegation, agent_id, start, result) in task_results {
         // Unmark agent as busy
         {
             let mut busy = state.busy_agents.write().await;
@@ -2507,6 +4255,36 @@ async fn execute_delegations(
             None,
         ).await;
 
+        // Cancellations and an expired task deadline say nothing about the
+        // role's health
+        let counts_for_breaker = match &result {
+            Ok(_) => true,
+            Err(e) => {
+                e.downcast_ref::<TaskDeadlineExceeded>().is_none()
+                    && !state.cancellation.is_cancelled(task_id)
+            }
+        };
+        // Swap the budget reservation for the tokens the agent reported
+        if let Some((reserved, reserved_at)) = reservations.remove(&index) {
+            let actual = result.as_ref().map_or(0, |response| response.tokens_used);
+            state
+                .token_service
+                .budgets
+                .reconcile(agent_id, reserved, actual, reserved_at)
+                .await;
+        }
+
+        if counts_for_breaker {
+            let orchestrator = state.orchestrator.read().await;
+            orchestrator
+                .record_role_outcome(&delegation.role.to_lowercase(), result.is_ok())
+                .await;
+            // Feeds success rates back into agent selection
+            orchestrator
+                .record_agent_outcome(agent_id, result.is_ok(), start.elapsed().as_millis() as u64)
+                .await;
+        }
+
         match result {
             Ok(task_response) => {
                 let duration_ms = start.elapsed().as_millis() as u64;
@@ -2540,7 +4318,16 @@ async fn execute_delegations(
                     duration_ms,
                 ).await;
 
-                results.push(DelegateTaskResponse {
+                if use_cache {
+                    state.delegation_cache.insert(
+                        &delegation.role,
+                        &delegation.task,
+                        delegation.context.as_deref(),
+                        output.clone(),
+                    );
+                }
+
+                results.push((index, DelegateTaskResponse {
                     success: true,
                     agent_id: agent_id.to_string(),
                     role: delegation.role.clone(),
@@ -2548,12 +4335,12 @@ async fn execute_delegations(
                     error: None,
                     duration_ms,
                     tokens_used,
-                });
+                }));
             }
             Err(e) => {
                 let error_msg = e.to_string();
                 warn!("{} agent {} error: {}", delegation.role, agent_id, error_msg);
-                results.push(DelegateTaskResponse {
+                results.push((index, DelegateTaskResponse {
                     success: false,
                     agent_id: agent_id.to_string(),
                     role: delegation.role.clone(),
@@ -2561,17 +4348,250 @@ async fn execute_delegations(
                     error: Some(error_msg),
                     duration_ms: start.elapsed().as_millis() as u64,
                     tokens_used: 0,
-                });
+                }));
             }
         }
     }
 
     info!("All {} delegations completed", results.len());
-    results
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, response)| response).collect()
 }
 
-/// Find an available (not busy) agent with the specified role
-async fn find_available_agent(state: &DaemonState, role: &str) -> Option<AgentId> {
+/// A task used up its `agents.task_deadline_seconds` before a delegation was sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskDeadlineExceeded;
+
+impl std::fmt::Display for TaskDeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Task deadline exceeded")
+    }
+}
+
+impl std::error::Error for TaskDeadlineExceeded {}
+
+/// Deadline for a task created now, if `agents.task_deadline_seconds` is set
+fn task_deadline(agents: &crate::config::AgentsConfig) -> Option<tokio::time::Instant> {
+    (agents.task_deadline_seconds > 0).then(|| {
+        tokio::time::Instant::now() + std::time::Duration::from_secs(agents.task_deadline_seconds)
+    })
+}
+
+/// Timeout for a delegation sent at `now`: `default`, cut down to what is left
+/// before the task's deadline
+fn delegation_timeout(
+    default: std::time::Duration,
+    deadline: Option<tokio::time::Instant>,
+    now: tokio::time::Instant,
+) -> Result<std::time::Duration, TaskDeadlineExceeded> {
+    match deadline {
+        None => Ok(default),
+        Some(deadline) if deadline <= now => Err(TaskDeadlineExceeded),
+        Some(deadline) => Ok(default.min(deadline - now)),
+    }
+}
+
+/// Await futures concurrently, running at most `limit` at a time (0 = no limit)
+///
+/// Outputs are returned in input order, regardless of completion order.
+async fn join_bounded<F: std::future::Future>(
+    futures: impl IntoIterator<Item = F>,
+    limit: usize,
+) -> Vec<F::Output> {
+    use futures_util::future::join_all;
+
+    let semaphore = (limit > 0).then(|| tokio::sync::Semaphore::new(limit));
+    join_all(futures.into_iter().map(|future| {
+        let semaphore = semaphore.as_ref();
+        async move {
+            // The semaphore is never closed, so acquiring can't fail
+            let _permit = match semaphore {
+                Some(semaphore) => semaphore.acquire().await.ok(),
+                None => None,
+            };
+            future.await
+        }
+    }))
+    .await
+}
+
+/// Send a task to an agent on its own tokio task so it can be cancelled
+///
+/// The abort handle is registered under `task_id`; cancelling the task
+/// aborts the send and this returns an error.
+async fn send_task_cancellable(
+    state: &DaemonState,
+    task_id: &str,
+    agent_id: AgentId,
+    task: &str,
+    context: Option<&str>,
+    request_id: &RequestId,
+    timeout: std::time::Duration,
+) -> Result<cca_acp::TaskResponse> {
+    let acp_server = state.acp_server.clone();
+    let task = task.to_string();
+    let context = context.map(str::to_string);
+    let request_id = request_id.to_string();
+    let api_task_id = task_id.to_string();
+
+    // Keep the request's span (and its request ID) on the spawned send
+    let handle = tokio::spawn(
+        async move {
+            acp_server
+                .send_task(
+                    agent_id,
+                    Some(&api_task_id),
+                    &task,
+                    context.as_deref(),
+                    Some(&request_id),
+                    timeout,
+                )
+                .await
+        }
+        .in_current_span(),
+    );
+    let token = state.cancellation.register(task_id, agent_id, handle.abort_handle());
+    let result = handle.await;
+    state.cancellation.deregister(task_id, token);
+
+    match result {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => Err(anyhow::anyhow!("Task cancelled")),
+        Err(e) => Err(anyhow::anyhow!("Task send failed: {e}")),
+    }
+}
+
+/// Look up a task for a status update, skipping it if it was cancelled
+///
+/// Keeps a handler that finishes after a cancellation from overwriting the
+/// "cancelled" status.
+fn live_task_mut<'a>(
+    tasks: &'a mut HashMap<String, TaskState>,
+    task_id: &str,
+) -> Option<&'a mut TaskState> {
+    tasks.get_mut(task_id).filter(|t| t.status != TaskRunStatus::Cancelled)
+}
+
+/// Build the response for a task that was cancelled while it ran
+async fn cancelled_task_response(state: &DaemonState, task_id: &str) -> Option<Json<TaskResponse>> {
+    if !state.cancellation.is_cancelled(task_id) {
+        return None;
+    }
+    let tasks = state.tasks.read().await;
+    let task = tasks.get(task_id)?;
+    Some(Json(TaskResponse {
+        task_id: task.task_id.clone(),
+        status: TaskRunStatus::Cancelled,
+        output: task.output.clone(),
+        error: task.error.clone(),
+        assigned_agent: task.assigned_agent.clone(),
+        delegations: task.delegations.clone(),
+        progress: task.progress.clone(),
+    }))
+}
+
+/// A daemon's Redis lock on a task, renewed while held and released when dropped
+struct TaskClaim {
+    redis: Arc<RedisServices>,
+    key: String,
+    token: String,
+    /// Keeps extending the lock so it outlives tasks longer than its TTL
+    renewal: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for TaskClaim {
+    fn drop(&mut self) {
+        self.renewal.abort();
+        let redis = self.redis.clone();
+        let key = std::mem::take(&mut self.key);
+        let token = std::mem::take(&mut self.token);
+        tokio::spawn(async move {
+            match redis.release_lock(&key, &token).await {
+                Ok(true) => debug!("Released lock {}", key),
+                Ok(false) => warn!("Lock {} expired before its task finished", key),
+                Err(e) => warn!("Failed to release lock {}: {}", key, e),
+            }
+        });
+    }
+}
+
+/// Lock task `task_id` so no other daemon sharing this Redis processes it
+///
+/// The lock is keyed on `content_hash` (see [`task_content_hash`]) rather than
+/// the task ID, which each daemon generates itself, so every daemon that
+/// receives the same task competes for the same lock. Tasks on this daemon
+/// with the same description share its claim instead of failing.
+///
+/// Returns `Ok(None)` without Redis, or when Redis can't be reached: the lock
+/// only guards against running a task twice, so it doesn't stop the task from
+/// running at all. The claim is renewed while held; if the daemon dies, the
+/// lock expires after `redis.task_lock_ttl_seconds` and another daemon can
+/// take the task over.
+async fn claim_task(
+    state: &DaemonState,
+    task_id: &str,
+    content_hash: u64,
+) -> Result<Option<Arc<TaskClaim>>, String> {
+    let Some(redis) = &state.redis else {
+        return Ok(None);
+    };
+    let key = format!("task:{content_hash:016x}");
+
+    // Held across the Redis call so identical local tasks don't race each other
+    let mut claims = state.task_claims.lock().await;
+    claims.retain(|_, claim| claim.strong_count() > 0);
+    if let Some(claim) = claims.get(&key).and_then(std::sync::Weak::upgrade) {
+        return Ok(Some(claim));
+    }
+
+    let ttl = std::time::Duration::from_secs(state.config.redis.task_lock_ttl_seconds);
+    match redis.try_acquire_lock(&key, ttl).await {
+        Ok(Some(token)) => {
+            let renewal = tokio::spawn(renew_task_lock(redis.clone(), key.clone(), token.clone(), ttl));
+            let claim = Arc::new(TaskClaim {
+                redis: redis.clone(),
+                key: key.clone(),
+                token,
+                renewal,
+            });
+            claims.insert(key, Arc::downgrade(&claim));
+            Ok(Some(claim))
+        }
+        Ok(None) => Err(format!(
+            "Task {task_id} duplicates a task already being processed by another daemon"
+        )),
+        Err(e) => {
+            warn!("Could not lock task {} in Redis, running it unlocked: {}", task_id, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Extend a task lock every third of its TTL until its claim is dropped
+async fn renew_task_lock(
+    redis: Arc<RedisServices>,
+    key: String,
+    token: String,
+    ttl: std::time::Duration,
+) {
+    let mut renew = tokio::time::interval(ttl / 3);
+    // The first tick completes immediately, right after the lock was taken
+    renew.tick().await;
+    loop {
+        renew.tick().await;
+        match redis.extend_lock(&key, &token, ttl).await {
+            Ok(true) => debug!("Renewed lock {}", key),
+            Ok(false) => {
+                warn!("Lost lock {}; another daemon may take over its task", key);
+                return;
+            }
+            Err(e) => warn!("Failed to renew lock {}: {}", key, e),
+        }
+    }
+}
+
+/// Find an available (not busy) agent with the specified role
+async fn find_available_agent(state: &DaemonState, role: &str) -> Option<AgentId> {
     find_available_agent_excluding(state, role, &[]).await
 }
 
@@ -2587,51 +4607,43 @@ async fn find_available_agent_excluding(
 ) -> Option<AgentId> {
     let agents_with_roles = state.acp_server.agents_with_roles().await;
 
-    // Find matching agent that's not busy AND not in the exclusion list
-    let found_agent = {
+    // Matching agents that are not busy AND not in the exclusion list
+    let idle: Vec<(AgentId, String)> = {
         let busy_agents = state.busy_agents.read().await;
-        agents_with_roles.into_iter().find(|(agent_id, agent_role)| {
-            if let Some(r) = agent_role {
-                r.to_lowercase() == role.to_lowercase()
-                    && !busy_agents.contains_key(agent_id)
-                    && !exclude.contains(agent_id)
-            } else {
-                false
-            }
-        })
+        agents_with_roles
+            .into_iter()
+            .filter_map(|(agent_id, agent_role)| {
+                let r = agent_role?;
+                (r.to_lowercase() == role.to_lowercase()
+                    && !busy_agents.contains_key(&agent_id)
+                    && !exclude.contains(&agent_id))
+                    .then_some((agent_id, r))
+            })
+            .collect()
     }; // busy_agents lock released here
 
-    if let Some((agent_id, agent_role)) = found_agent {
-        let role_name = agent_role.unwrap_or_default();
+    // Prefer the agent with the best track record (round-robin on ties)
+    let candidates: Vec<AgentId> = idle.iter().map(|(id, _)| *id).collect();
+    let chosen = state.orchestrator.read().await.pick_preferred_agent(&candidates).await;
 
-        // Ensure agent is registered in orchestrator for workload tracking
-        let orchestrator = state.orchestrator.read().await;
-        let workloads = orchestrator.get_agent_workloads().await;
+    if let Some(agent_id) = chosen {
+        let role_name = idle
+            .into_iter()
+            .find_map(|(id, r)| (id == agent_id).then_some(r))
+            .unwrap_or_default();
 
-        if !workloads.iter().any(|w| w.agent_id == agent_id) {
-            orchestrator.register_agent(
-                agent_id,
-                role_name.clone(),
-                vec![role_name.clone()],
-                5,
-            ).await;
+        // Fallback: ensure agent is registered in orchestrator for workload tracking
+        if ensure_orchestrator_registration(state, agent_id, &role_name).await {
             info!("Auto-registered {} agent {} in orchestrator for workload tracking", role_name, agent_id);
 
             // Also register in PostgreSQL for pattern FK references
-            if let Some(ref postgres) = state.postgres {
-                if let Err(e) = postgres
-                    .agents
-                    .register_with_id(
-                        agent_id.0,
-                        &role_name,
-                        None,
-                        serde_json::json!({"auto_registered": true}),
-                    )
-                    .await
-                {
-                    warn!("Failed to register agent {} in PostgreSQL: {}", agent_id, e);
-                }
-            }
+            register_agent_in_postgres(
+                state,
+                agent_id,
+                &role_name,
+                serde_json::json!({"auto_registered": true}),
+            )
+            .await;
         }
 
         return Some(agent_id);
@@ -2640,22 +4652,396 @@ async fn find_available_agent_excluding(
     None
 }
 
+/// Wait up to `agents.busy_agent_wait_seconds` for a busy agent of `role` to free up
+///
+/// Returns immediately if waiting is disabled or no agent of the role is
+/// connected (nothing to wait for; the caller spawns one instead). Waiting
+/// delegations queue in the dispatch queue, so a freed agent goes to the
+/// highest-priority one. The returned ticket keeps that place until the
+/// agent is marked busy.
+async fn wait_for_busy_agent(
+    state: &DaemonState,
+    role: &str,
+    priority: TaskPriority,
+    exclude: &[AgentId],
+) -> Result<Option<(AgentId, QueueTicket)>, QueueFull> {
+    let wait = std::time::Duration::from_secs(state.config.agents.busy_agent_wait_seconds);
+    if wait.is_zero() {
+        return Ok(None);
+    }
+
+    let candidates: Vec<AgentId> = state
+        .acp_server
+        .agents_with_roles()
+        .await
+        .into_iter()
+        .filter(|(id, r)| {
+            r.as_deref().is_some_and(|r| r.eq_ignore_ascii_case(role)) && !exclude.contains(id)
+        })
+        .map(|(id, _)| id)
+        .collect();
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let ticket = state.dispatch_queue.enqueue(role, priority)?;
+    info!(
+        "All {} {} agent(s) busy, waiting up to {}s for one to free up",
+        candidates.len(), role, wait.as_secs()
+    );
+    if wait_for_idle_agent(&state.busy_agents, &candidates, &ticket, wait).await.is_none() {
+        return Ok(None);
+    }
+    // Re-check through the normal path so the agent gets registered for tracking
+    Ok(find_available_agent_excluding(state, role, exclude)
+        .await
+        .map(|id| (id, ticket)))
+}
+
+/// Poll `busy_agents` until one of `candidates` is idle while `ticket` is
+/// first in line, or `wait` elapses
+async fn wait_for_idle_agent(
+    busy_agents: &RwLock<HashMap<AgentId, String>>,
+    candidates: &[AgentId],
+    ticket: &QueueTicket,
+    wait: std::time::Duration,
+) -> Option<AgentId> {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        if ticket.is_next() {
+            let busy = busy_agents.read().await;
+            if let Some(id) = candidates.iter().find(|id| !busy.contains_key(*id)) {
+                return Some(*id);
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(BUSY_AGENT_POLL_INTERVAL).await;
+    }
+}
+
+/// Implicitly register a connected agent in the orchestrator if it isn't known yet
+///
+/// Explicit registrations via `POST /api/v1/orchestrator/agents` take precedence;
+/// this only fills in agents that were never registered. A worker that
+/// registered a stable `worker_id` keeps the stats of its earlier connections.
+/// Returns true if the agent was newly registered.
+async fn ensure_orchestrator_registration(
+    state: &DaemonState,
+    agent_id: AgentId,
+    role: &str,
+) -> bool {
+    let orchestrator = state.orchestrator.read().await;
+    let registered = orchestrator
+        .register_agent_if_absent(
+            agent_id,
+            role.to_string(),
+            vec![role.to_string()],
+            state.config.agents.max_tasks_for_role(role),
+        )
+        .await;
+    if registered {
+        if let Some(worker_id) = state.acp_server.worker_ids().await.remove(&agent_id) {
+            orchestrator.link_worker(agent_id, &worker_id).await;
+        }
+    }
+    registered
+}
+
+/// Register an agent in PostgreSQL for pattern FK references (best effort)
+async fn register_agent_in_postgres(
+    state: &DaemonState,
+    agent_id: AgentId,
+    role: &str,
+    metadata: serde_json::Value,
+) {
+    if let Some(ref postgres) = state.postgres {
+        if let Err(e) = postgres
+            .agents
+            .register_with_id(agent_id.0, role, None, metadata)
+            .await
+        {
+            warn!("Failed to register agent {} in PostgreSQL: {}", agent_id, e);
+        }
+    }
+}
+
 async fn get_task(
     State(state): State<DaemonState>,
     axum::extract::Path(task_id): axum::extract::Path<String>,
-) -> Result<Json<TaskResponse>, axum::http::StatusCode> {
-    let tasks = state.tasks.read().await;
-
-    match tasks.get(&task_id) {
-        Some(task) => Ok(Json(TaskResponse {
+) -> Result<Json<TaskResponse>, ServiceError> {
+    if let Some(task) = state.tasks.read().await.get(&task_id) {
+        return Ok(Json(TaskResponse {
             task_id: task.task_id.clone(),
-            status: task.status.clone(),
+            status: task.status,
             output: task.output.clone(),
             error: task.error.clone(),
             assigned_agent: task.assigned_agent.clone(),
-        })),
-        None => Err(axum::http::StatusCode::NOT_FOUND),
+            delegations: task.delegations.clone(),
+            progress: task.progress.clone(),
+        }));
+    }
+
+    // Finished tasks are pruned from memory, but stay in the task history
+    if let (Some(postgres), Ok(id)) = (&state.postgres, Uuid::parse_str(&task_id)) {
+        if let Some(record) = postgres.tasks.get(id).await.context("Failed to get task")? {
+            return Ok(Json(task_response_from_record(record)));
+        }
+    }
+    Err(CCAError::NotFound(format!("Task {task_id}")).into())
+}
+
+/// Query parameters for the task history endpoint
+#[derive(Debug, Deserialize)]
+pub struct TaskHistoryQuery {
+    /// Only tasks with this status
+    #[serde(default)]
+    status: Option<String>,
+    /// Only tasks assigned to this agent ID
+    #[serde(default)]
+    agent: Option<String>,
+    #[serde(default = "default_task_history_limit")]
+    limit: u32,
+    #[serde(default)]
+    offset: u32,
+}
+
+fn default_task_history_limit() -> u32 {
+    50
+}
+
+/// Max tasks per task history page
+const MAX_TASK_HISTORY_LIMIT: u32 = 200;
+
+/// One task in `/api/v1/tasks/history`
+#[derive(Debug, Serialize)]
+struct TaskHistoryEntry {
+    #[serde(flatten)]
+    task: TaskResponse,
+    description: String,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+/// Tasks recorded in PostgreSQL, newest first, beyond the in-memory window
+async fn get_task_history(
+    State(state): State<DaemonState>,
+    axum::extract::Query(query): axum::extract::Query<TaskHistoryQuery>,
+) -> ServiceResult {
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<TaskRunStatus>)
+        .transpose()
+        .map_err(|e| ValidationError::new("status", "invalid_value", e))?;
+    if query.agent.as_ref().is_some_and(|a| a.is_empty() || a.len() > MAX_ROLE_LEN) {
+        return Err(ValidationError::new(
+            "agent",
+            "invalid_value",
+            format!("agent must be 1-{MAX_ROLE_LEN} characters"),
+        )
+        .into());
+    }
+    if query.limit == 0 || query.limit > MAX_TASK_HISTORY_LIMIT {
+        return Err(ValidationError::new(
+            "limit",
+            "out_of_range",
+            format!("limit must be between 1 and {MAX_TASK_HISTORY_LIMIT}"),
+        )
+        .into());
+    }
+
+    let postgres = require_service(state.postgres.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "PostgreSQL not available"
+        })
+    })?;
+
+    let records = postgres
+        .tasks
+        .list_filtered(
+            status.map(TaskRunStatus::as_str),
+            query.agent.as_deref(),
+            i64::from(query.limit),
+            i64::from(query.offset),
+        )
+        .await?;
+    let tasks: Vec<TaskHistoryEntry> = records
+        .into_iter()
+        .map(|record| TaskHistoryEntry {
+            description: record.description.clone(),
+            created_at: record.created_at,
+            completed_at: record.completed_at,
+            task: task_response_from_record(record),
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "tasks": tasks,
+        "limit": query.limit,
+        "offset": query.offset
+    })))
+}
+
+/// Aggregate statistics over the task history
+async fn get_task_stats(State(state): State<DaemonState>) -> ServiceResult {
+    let postgres = require_service(state.postgres.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "PostgreSQL not available"
+        })
+    })?;
+
+    let (stats, by_role) =
+        tokio::try_join!(postgres.tasks.get_stats(), postgres.tasks.get_role_stats())?;
+    Ok(Json(task_stats_json(&stats, &by_role)))
+}
+
+/// Body of `/api/v1/tasks/stats`
+fn task_stats_json(
+    stats: &crate::postgres::TaskStats,
+    by_role: &[crate::postgres::RoleTaskStats],
+) -> serde_json::Value {
+    let by_role: Vec<serde_json::Value> = by_role
+        .iter()
+        .map(|role| {
+            serde_json::json!({
+                "role": role.role,
+                "delegations": role.delegations,
+                "successful": role.successful,
+                "failed": role.delegations - role.successful,
+                "total_tokens": role.total_tokens,
+                "avg_duration_ms": role.avg_duration_ms
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "success": true,
+        "total_tasks": stats.total_tasks,
+        "completed_tasks": stats.completed_tasks,
+        "failed_tasks": stats.failed_tasks,
+        "total_tokens": stats.total_tokens,
+        "avg_duration_ms": stats.avg_duration_ms,
+        "by_role": by_role
+    })
+}
+
+/// Record a new API task in the task history, if PostgreSQL is configured
+async fn persist_task_created(
+    state: &DaemonState,
+    task_id: &str,
+    description: &str,
+    status: TaskRunStatus,
+) {
+    let (Some(postgres), Ok(id)) = (&state.postgres, Uuid::parse_str(task_id)) else {
+        return;
+    };
+    if let Err(e) = postgres
+        .tasks
+        .create_with_id(id, description, status.as_str())
+        .await
+    {
+        warn!("Failed to record task {} in PostgreSQL: {}", task_id, e);
+    }
+}
+
+/// Save a finished API task's result to the task history, if PostgreSQL is configured
+async fn persist_task_result(state: &DaemonState, task_id: &str) {
+    let (Some(postgres), Ok(id)) = (&state.postgres, Uuid::parse_str(task_id)) else {
+        return;
+    };
+    let Some(task) = state.tasks.read().await.get(task_id).cloned() else {
+        return;
+    };
+    let tokens_used: u64 = task.delegations.iter().map(|d| d.tokens_used).sum();
+    let duration_ms = (task.updated_at - task.created_at).num_milliseconds();
+    if let Err(e) = postgres
+        .tasks
+        .complete(
+            id,
+            task.status.as_str(),
+            task_result_json(&task),
+            i32::try_from(tokens_used).unwrap_or(i32::MAX),
+            i32::try_from(duration_ms.max(0)).unwrap_or(i32::MAX),
+        )
+        .await
+    {
+        warn!("Failed to save result of task {} to PostgreSQL: {}", task_id, e);
+    }
+}
+
+/// The `result` column of a task history record
+fn task_result_json(task: &TaskState) -> serde_json::Value {
+    serde_json::json!({
+        "priority": task.priority,
+        "output": task.output,
+        "error": task.error,
+        "assigned_agent": task.assigned_agent,
+        "delegations": task.delegations,
+    })
+}
+
+/// Rebuild an API task from its task history record
+fn task_response_from_record(record: crate::postgres::TaskRecord) -> TaskResponse {
+    let result = record.result.unwrap_or_default();
+    let text = |field: &str| result.get(field).and_then(|v| v.as_str()).map(str::to_string);
+    TaskResponse {
+        task_id: record.id.to_string(),
+        // The column only ever holds statuses written by `persist_task_*`
+        status: serde_json::from_value(serde_json::Value::String(record.status))
+            .unwrap_or(TaskRunStatus::Failed),
+        output: text("output"),
+        error: text("error"),
+        assigned_agent: text("assigned_agent"),
+        delegations: result
+            .get("delegations")
+            .and_then(|d| serde_json::from_value(d.clone()).ok())
+            .unwrap_or_default(),
+        progress: None,
+    }
+}
+
+/// Cancel a task, aborting its in-flight agent sends
+///
+/// Agents whose sends were aborted get a `task.cancel` notification.
+/// Returns 404 for unknown tasks and 409 for tasks that already finished.
+async fn cancel_task_endpoint(
+    State(state): State<DaemonState>,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<Json<TaskResponse>, ServiceError> {
+    let agents = match cancel_task(&state.tasks, &state.cancellation, &task_id).await {
+        CancelOutcome::Cancelled(agents) => agents,
+        CancelOutcome::NotFound => return Err(CCAError::NotFound(format!("Task {task_id}")).into()),
+        CancelOutcome::AlreadyFinished(status) => {
+            debug!("Task {} not cancelled: already {}", task_id, status);
+            return Err(CCAError::Task(format!("Task {task_id} already {status}")).into());
+        }
+    };
+
+    info!("Task {} cancelled ({} in-flight sends aborted)", task_id, agents.len());
+    for agent_id in agents {
+        if !state.acp_server.cancel_task(agent_id, &task_id).await {
+            warn!("Could not notify agent {} that task {} was cancelled", agent_id, task_id);
+        }
     }
+
+    let tasks = state.tasks.read().await;
+    let task = tasks
+        .get(&task_id)
+        .ok_or_else(|| CCAError::NotFound(format!("Task {task_id}")))?;
+    Ok(Json(TaskResponse {
+        task_id: task.task_id.clone(),
+        status: task.status,
+        output: task.output.clone(),
+        error: task.error.clone(),
+        assigned_agent: task.assigned_agent.clone(),
+        delegations: task.delegations.clone(),
+        progress: task.progress.clone(),
+    }))
 }
 
 async fn get_activity(State(state): State<DaemonState>) -> Json<serde_json::Value> {
@@ -2717,45 +5103,46 @@ async fn get_activity(State(state): State<DaemonState>) -> Json<serde_json::Valu
 }
 
 /// Redis status endpoint
-async fn redis_status(State(state): State<DaemonState>) -> Json<serde_json::Value> {
-    match &state.redis {
-        Some(redis) => {
-            // Try to get stats
-            let agent_count = redis.agent_states.get_all().await.map(|v| v.len()).unwrap_or(0);
-
-            Json(serde_json::json!({
-                "connected": true,
-                "pool_size": state.config.redis.pool_size,
-                "context_ttl_seconds": state.config.redis.context_ttl_seconds,
-                "agents_tracked": agent_count
-            }))
-        }
-        None => Json(serde_json::json!({
+async fn redis_status(State(state): State<DaemonState>) -> ServiceResult {
+    let redis = require_service(state.redis.as_ref(), || {
+        serde_json::json!({
             "connected": false,
             "error": "Redis not available"
-        })),
-    }
+        })
+    })?;
+
+    // Try to get stats
+    let agent_count = redis.agent_states.get_all().await.map(|v| v.len()).unwrap_or(0);
+
+    Ok(Json(serde_json::json!({
+        "connected": true,
+        "pool_size": state.config.redis.pool_size,
+        "context_ttl_seconds": state.config.redis.context_ttl_seconds,
+        "agents_tracked": agent_count
+    })))
 }
 
 /// PostgreSQL status endpoint
-async fn postgres_status(State(state): State<DaemonState>) -> Json<serde_json::Value> {
-    match &state.postgres {
-        Some(postgres) => {
-            // Get pattern count
-            let pattern_count = postgres.patterns.count().await.unwrap_or(0);
-
-            Json(serde_json::json!({
-                "connected": true,
-                "pool_size": state.config.postgres.max_connections,
-                "patterns_count": pattern_count
-                // SECURITY: Database URL intentionally omitted - never expose connection strings
-            }))
-        }
-        None => Json(serde_json::json!({
+async fn postgres_status(State(state): State<DaemonState>) -> ServiceResult {
+    let postgres = require_service(state.postgres.as_ref(), || {
+        serde_json::json!({
             "connected": false,
             "error": "PostgreSQL not available"
-        })),
-    }
+        })
+    })?;
+
+    // Get pattern count
+    let pattern_count = postgres.patterns.count().await.unwrap_or(0);
+    let pool = postgres.db.pool_stats();
+
+    Ok(Json(serde_json::json!({
+        "connected": true,
+        "pool_size": state.config.postgres.max_connections,
+        "connections_active": pool.active,
+        "connections_idle": pool.idle,
+        "patterns_count": pattern_count
+        // SECURITY: Database URL intentionally omitted - never expose connection strings
+    })))
 }
 
 /// Memory search request
@@ -2767,102 +5154,70 @@ pub struct MemorySearchRequest {
     #[serde(default = "default_limit")]
     #[validate(range(min = 1, max = 100, message = "Limit must be 1-100"))]
     pub limit: i32,
+    /// Minimum cosine similarity (0-1) for semantic results; defaults to 0.3
+    #[serde(default)]
+    pub min_similarity: Option<f64>,
+    /// Only return patterns of this type (e.g. `error_recovery`)
+    #[serde(default)]
+    pub pattern_type: Option<String>,
 }
 
 fn default_limit() -> i32 {
     10
 }
 
+/// Similarity threshold used when a memory search doesn't specify one
+const DEFAULT_MIN_SIMILARITY: f64 = 0.3;
+
 /// Memory search endpoint - query ReasoningBank patterns
 /// Uses semantic search (embeddings) when available, falls back to text search
 async fn memory_search(
     State(state): State<DaemonState>,
     Json(request): Json<MemorySearchRequest>,
-) -> Json<serde_json::Value> {
-    // Input validation: check query length
-    if request.query.len() > MAX_QUERY_LEN {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": format!(
-                "Query too long: {} bytes (max: {} bytes)",
-                request.query.len(),
-                MAX_QUERY_LEN
-            )
-        }));
-    }
+) -> ServiceResult {
+    validate_query(&request.query)?;
+    validate_pattern_filters(request.min_similarity, request.pattern_type.as_deref())?;
 
-    let postgres = match &state.postgres {
-        Some(pg) => pg,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "PostgreSQL not available"
-            }));
-        }
-    };
+    let patterns = require_service(state.pattern_store.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "PostgreSQL not available"
+        })
+    })?;
 
     // Clamp limit to prevent resource exhaustion
     let limit = request.limit.clamp(1, 100);
+    let min_similarity = request.min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY);
+    let mut query = PatternQuery::text(request.query.as_str(), limit as usize);
+    if let Some(ref pattern_type) = request.pattern_type {
+        query = query.with_pattern_type(MemoryPatternType::from(pattern_type.as_str()));
+    }
 
-    // Try semantic search if embedding service is available
-    if let Some(ref emb_service) = state.embedding_service {
-        match emb_service.embed(&request.query).await {
-            Ok(query_embedding) => {
-                // Use cosine similarity search with minimum threshold of 0.3
-                match postgres.patterns.search_similar(&query_embedding, limit, 0.3).await {
-                    Ok(patterns) => {
-                        let results: Vec<serde_json::Value> = patterns
-                            .iter()
-                            .map(|pw| {
-                                serde_json::json!({
-                                    "id": pw.pattern.id.to_string(),
-                                    "pattern_type": pw.pattern.pattern_type,
-                                    "content": pw.pattern.content,
-                                    "success_rate": pw.pattern.success_rate,
-                                    "success_count": pw.pattern.success_count,
-                                    "failure_count": pw.pattern.failure_count,
-                                    "similarity": pw.similarity,
-                                    "created_at": pw.pattern.created_at.to_rfc3339()
-                                })
-                            })
-                            .collect();
-
-                        return Json(serde_json::json!({
-                            "success": true,
-                            "patterns": results,
-                            "count": results.len(),
-                            "query": request.query,
-                            "search_type": "semantic"
-                        }));
-                    }
-                    Err(e) => {
-                        warn!("Semantic search failed, falling back to text: {}", e);
-                        // Fall through to text search
-                    }
-                }
-            }
+    // Semantic search needs a query embedding; without one only text search runs
+    let embedding = match state.embedding_service {
+        Some(ref emb_service) => match emb_service.embed(&request.query).await {
+            Ok(query_embedding) => Some(query_embedding),
             Err(e) => {
                 warn!("Failed to generate query embedding, falling back to text: {}", e);
-                // Fall through to text search
+                None
             }
-        }
-    }
+        },
+        None => None,
+    };
 
-    // Fallback: text search (when embeddings not available or semantic search fails)
-    match postgres.patterns.search_text(&request.query, limit).await {
-        Ok(patterns) => {
-            let results: Vec<serde_json::Value> = patterns
+    let weight = state.config.embeddings.success_rate_weight;
+    let searched =
+        search_patterns(patterns.as_ref(), &query, embedding, min_similarity, weight).await;
+    Ok(match searched {
+        Ok((matches, search_type)) => {
+            let results: Vec<serde_json::Value> = matches
                 .iter()
-                .map(|p| {
-                    serde_json::json!({
-                        "id": p.id.to_string(),
-                        "pattern_type": p.pattern_type,
-                        "content": p.content,
-                        "success_rate": p.success_rate,
-                        "success_count": p.success_count,
-                        "failure_count": p.failure_count,
-                        "created_at": p.created_at.to_rfc3339()
-                    })
+                .map(|m| {
+                    let mut result = pattern_result(&m.pattern);
+                    if search_type == "semantic" {
+                        result["similarity"] = serde_json::json!(m.score);
+                    }
+                    result
                 })
                 .collect();
 
@@ -2871,59 +5226,114 @@ async fn memory_search(
                 "patterns": results,
                 "count": results.len(),
                 "query": request.query,
-                "search_type": "text"
+                "search_type": search_type
             }))
         }
         Err(e) => Json(serde_json::json!({
             "success": false,
             "error": format!("Failed to search patterns: {}", e)
         })),
+    })
+}
+
+/// Semantic search when a query embedding is available, falling back to text
+/// search if there is none or the semantic search fails
+///
+/// Returns the matches and which search produced them (`"semantic"` or `"text"`).
+async fn search_patterns(
+    patterns: &dyn PatternStore,
+    query: &PatternQuery,
+    embedding: Option<Vec<f32>>,
+    min_similarity: f64,
+    success_rate_weight: f64,
+) -> cca_core::Result<(Vec<SearchMatch>, &'static str)> {
+    if let Some(embedding) = embedding {
+        let mut semantic = query.clone().with_embedding(embedding, min_similarity);
+        if success_rate_weight > 0.0 {
+            // Over-fetch so well-proven patterns just past the cutoff can move up
+            semantic.limit = query.limit * RERANK_CANDIDATE_FACTOR;
+        }
+        match patterns.search(&semantic).await {
+            Ok(mut matches) => {
+                rerank_by_success_rate(&mut matches, success_rate_weight);
+                matches.truncate(query.limit);
+                return Ok((matches, "semantic"));
+            }
+            Err(e) => warn!("Semantic search failed, falling back to text: {}", e),
+        }
+    }
+
+    Ok((patterns.search(query).await?, "text"))
+}
+
+/// Candidates fetched per requested result when semantic matches are re-ranked
+const RERANK_CANDIDATE_FACTOR: usize = 3;
+
+/// Order semantic matches by similarity blended with success rate
+///
+/// Each match ranks by `(1 - weight) * similarity + weight * success_rate`,
+/// with similarity clamped to 0-1. Scores keep the raw similarity, and a
+/// weight of 0 leaves the similarity order untouched.
+fn rerank_by_success_rate(matches: &mut [SearchMatch], weight: f64) {
+    if weight <= 0.0 {
+        return;
     }
+    let rank = |m: &SearchMatch| {
+        (1.0 - weight) * m.score.clamp(0.0, 1.0) + weight * m.pattern.success_rate()
+    };
+    matches.sort_by(|a, b| rank(b).total_cmp(&rank(a)));
+}
+
+/// JSON shape of a pattern in memory search results
+fn pattern_result(pattern: &Pattern) -> serde_json::Value {
+    serde_json::json!({
+        "id": pattern.id.0.to_string(),
+        "pattern_type": pattern.pattern_type.as_str(),
+        "content": pattern.content,
+        "success_rate": pattern.success_rate(),
+        "success_count": pattern.success_count,
+        "failure_count": pattern.failure_count,
+        "created_at": pattern.created_at.to_rfc3339()
+    })
 }
 
 /// Backfill embeddings for patterns that don't have them
 /// Uses embed_batch for efficient bulk processing
 async fn backfill_embeddings(
     State(state): State<DaemonState>,
-) -> Json<serde_json::Value> {
+) -> ServiceResult {
     // Check prerequisites
-    let emb_service = match &state.embedding_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Embedding service not configured"
-            }));
-        }
-    };
+    let emb_service = require_service(state.embedding_service.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "Embedding service not configured"
+        })
+    })?;
 
-    let postgres = match &state.postgres {
-        Some(pg) => pg,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "PostgreSQL not available"
-            }));
-        }
-    };
+    let postgres = require_service(state.postgres.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "PostgreSQL not available"
+        })
+    })?;
 
     // Get patterns without embeddings (batch of 10)
     let patterns = match postgres.patterns.get_without_embeddings(10).await {
         Ok(p) => p,
         Err(e) => {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": format!("Failed to get patterns: {}", e)
-            }));
+            })));
         }
     };
 
     if patterns.is_empty() {
-        return Json(serde_json::json!({
+        return Ok(Json(serde_json::json!({
             "success": true,
             "message": "No patterns need embedding backfill",
             "processed": 0
-        }));
+        })));
     }
 
     // Prepare texts for batch embedding
@@ -2936,32 +5346,48 @@ async fn backfill_embeddings(
     let embeddings = match emb_service.embed_batch(&texts).await {
         Ok(embs) => embs,
         Err(e) => {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": format!("Failed to generate embeddings: {}", e)
-            }));
+            })));
         }
     };
 
-    // Update patterns with embeddings
-    let mut updated = 0;
-    let mut errors = 0;
-    for (pattern, embedding) in patterns.iter().zip(embeddings.iter()) {
-        match postgres.patterns.update_embedding(pattern.id, embedding).await {
-            Ok(()) => updated += 1,
-            Err(e) => {
-                warn!("Failed to update embedding for pattern {}: {}", pattern.id, e);
-                errors += 1;
-            }
+    // Update all patterns in one transaction (rolled back on any failure)
+    let updates: Vec<(Uuid, Vec<f32>)> = patterns
+        .iter()
+        .map(|p| p.id)
+        .zip(embeddings)
+        .collect();
+
+    let result = postgres.patterns.update_embeddings_batch(&updates).await;
+    // Patterns still missing an embedding, not just the rest of this batch
+    let remaining = match postgres.patterns.count_without_embeddings().await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            warn!("Failed to count patterns without embeddings: {}", e);
+            None
         }
-    }
+    };
 
-    Json(serde_json::json!({
-        "success": true,
-        "processed": updated,
-        "errors": errors,
-        "remaining": patterns.len() as i32 - updated - errors
-    }))
+    Ok(match result {
+        Ok(updated) => Json(serde_json::json!({
+            "success": true,
+            "processed": updated,
+            "errors": 0,
+            "remaining": remaining
+        })),
+        Err(e) => {
+            warn!("Failed to update embeddings batch of {} patterns: {}", updates.len(), e);
+            Json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to update embeddings: {}", e),
+                "processed": 0,
+                "errors": updates.len(),
+                "remaining": remaining
+            }))
+        }
+    })
 }
 
 // ============================================================================
@@ -2972,87 +5398,90 @@ async fn backfill_embeddings(
 async fn start_indexing(
     State(state): State<DaemonState>,
     Json(request): Json<StartIndexingRequest>,
-) -> Json<serde_json::Value> {
+) -> ServiceResult {
     // SEC-008: Input validation - check path length
     if request.path.len() > MAX_PATH_LEN {
-        return Json(serde_json::json!({
+        return Ok(Json(serde_json::json!({
             "job_id": "",
             "status": "error",
             "message": format!("Path too long: {} bytes (max: {} bytes)", request.path.len(), MAX_PATH_LEN)
-        }));
+        })));
     }
 
     // SEC-008: Input validation - prevent path traversal attacks
     let path = std::path::Path::new(&request.path);
     if request.path.contains("..") {
-        return Json(serde_json::json!({
+        return Ok(Json(serde_json::json!({
             "job_id": "",
             "status": "error",
             "message": "Path traversal not allowed (contains '..')"
-        }));
+        })));
     }
 
     // SEC-008: Ensure path is absolute (security: prevent relative path confusion)
     if !path.is_absolute() {
-        return Json(serde_json::json!({
+        return Ok(Json(serde_json::json!({
             "job_id": "",
             "status": "error",
             "message": "Path must be absolute"
-        }));
+        })));
     }
 
     // SEC-008: Input validation - check extension count and lengths
     if let Some(ref exts) = request.extensions {
         if exts.len() > MAX_EXTENSIONS {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "job_id": "",
                 "status": "error",
                 "message": format!("Too many extensions: {} (max: {})", exts.len(), MAX_EXTENSIONS)
-            }));
+            })));
         }
         for ext in exts {
             if ext.len() > 32 {
-                return Json(serde_json::json!({
+                return Ok(Json(serde_json::json!({
                     "job_id": "",
                     "status": "error",
                     "message": format!("Extension too long: '{}' (max: 32 chars)", ext)
-                }));
+                })));
             }
         }
     }
 
-    // SEC-008: Input validation - check exclude pattern count and lengths
-    if let Some(ref patterns) = request.exclude_patterns {
+    // SEC-008: Input validation - check glob pattern counts and lengths
+    let glob_lists = [
+        ("exclude patterns", &request.exclude_patterns),
+        ("include globs", &request.include_globs),
+        ("exclude globs", &request.exclude_globs),
+    ];
+    for (kind, patterns) in glob_lists {
+        let Some(patterns) = patterns else { continue };
         if patterns.len() > MAX_EXCLUDE_PATTERNS {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "job_id": "",
                 "status": "error",
-                "message": format!("Too many exclude patterns: {} (max: {})", patterns.len(), MAX_EXCLUDE_PATTERNS)
-            }));
+                "message": format!("Too many {}: {} (max: {})", kind, patterns.len(), MAX_EXCLUDE_PATTERNS)
+            })));
         }
         for pattern in patterns {
             if pattern.len() > 256 {
-                return Json(serde_json::json!({
+                return Ok(Json(serde_json::json!({
                     "job_id": "",
                     "status": "error",
-                    "message": format!("Exclude pattern too long: '{}' (max: 256 chars)", pattern)
-                }));
+                    "message": format!("Glob pattern too long: '{}' (max: 256 chars)", pattern)
+                })));
             }
         }
     }
 
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "job_id": "",
-                "status": "error",
-                "message": "Indexing service not available (requires embeddings + postgres)"
-            }));
-        }
-    };
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
+            "job_id": "",
+            "status": "error",
+            "message": "Indexing service not available (requires embeddings + postgres)"
+        })
+    })?;
 
-    match indexing_service.start_indexing(request).await {
+    Ok(match indexing_service.start_indexing(request).await {
         Ok(job_id) => Json(serde_json::json!({
             "job_id": job_id.to_string(),
             "status": "started",
@@ -3063,35 +5492,32 @@ async fn start_indexing(
             "status": "error",
             "message": format!("Failed to start indexing: {}", e)
         })),
-    }
+    })
 }
 
 /// Get indexing job status
 async fn get_indexing_status(
     State(state): State<DaemonState>,
     Path(job_id): Path<String>,
-) -> Json<serde_json::Value> {
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Indexing service not available"
-            }));
-        }
-    };
+) -> ServiceResult {
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "Indexing service not available"
+        })
+    })?;
 
     let job_uuid = match Uuid::parse_str(&job_id) {
         Ok(id) => id,
         Err(_) => {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": "Invalid job ID format"
-            }));
+            })));
         }
     };
 
-    match indexing_service.get_job_status(job_uuid).await {
+    Ok(match indexing_service.get_job_status(job_uuid).await {
         Ok(Some(status)) => Json(serde_json::json!({
             "success": true,
             "job": status
@@ -3104,35 +5530,32 @@ async fn get_indexing_status(
             "success": false,
             "error": format!("Failed to get job status: {}", e)
         })),
-    }
+    })
 }
 
 /// Cancel a running indexing job
 async fn cancel_indexing(
     State(state): State<DaemonState>,
     Path(job_id): Path<String>,
-) -> Json<serde_json::Value> {
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Indexing service not available"
-            }));
-        }
-    };
+) -> ServiceResult {
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "Indexing service not available"
+        })
+    })?;
 
     let job_uuid = match Uuid::parse_str(&job_id) {
         Ok(id) => id,
         Err(_) => {
-            return Json(serde_json::json!({
+            return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": "Invalid job ID format"
-            }));
+            })));
         }
     };
 
-    match indexing_service.cancel_job(job_uuid).await {
+    Ok(match indexing_service.cancel_job(job_uuid).await {
         Ok(true) => Json(serde_json::json!({
             "success": true,
             "message": "Job cancelled"
@@ -3145,34 +5568,33 @@ async fn cancel_indexing(
             "success": false,
             "error": format!("Failed to cancel job: {}", e)
         })),
-    }
+    })
 }
 
 /// List recent indexing jobs
 async fn list_indexing_jobs(
     State(state): State<DaemonState>,
-) -> Json<serde_json::Value> {
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Indexing service not available"
-            }));
-        }
-    };
+) -> ServiceResult {
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "Indexing service not available"
+        })
+    })?;
 
-    match indexing_service.list_jobs(20).await {
+    Ok(match indexing_service.list_jobs(20).await {
         Ok(jobs) => Json(serde_json::json!({
             "success": true,
             "jobs": jobs,
-            "count": jobs.len()
+            "count": jobs.len(),
+            "running": indexing_service.running_jobs(),
+            "max_concurrent_jobs": indexing_service.max_concurrent_jobs()
         })),
         Err(e) => Json(serde_json::json!({
             "success": false,
             "error": format!("Failed to list jobs: {}", e)
         })),
-    }
+    })
 }
 
 /// Search request for indexed code
@@ -3187,36 +5609,33 @@ struct SearchCodeRequest {
     #[serde(default)]
     #[validate(length(max = 32, message = "Language must be at most 32 characters"))]
     language: Option<String>,
+    /// Only return chunks from files under this path (matched literally)
+    #[serde(default)]
+    #[validate(length(max = 4096, message = "Path prefix must be at most 4096 characters"))]
+    path_prefix: Option<String>,
 }
 
 /// Search indexed code chunks
 async fn search_code(
     State(state): State<DaemonState>,
     Json(request): Json<SearchCodeRequest>,
-) -> Json<serde_json::Value> {
-    if request.query.len() > MAX_QUERY_LEN {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": format!(
-                "Query too long: {} bytes (max: {} bytes)",
-                request.query.len(),
-                MAX_QUERY_LEN
-            )
-        }));
-    }
-
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Indexing service not available"
-            }));
-        }
-    };
+) -> ServiceResult {
+    validate_query(&request.query)?;
 
-    match indexing_service
-        .search_code(&request.query, request.limit, request.language.as_deref())
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
+            "success": false,
+            "error": "Indexing service not available"
+        })
+    })?;
+
+    Ok(match indexing_service
+        .search_code(
+            &request.query,
+            request.limit,
+            request.language.as_deref(),
+            request.path_prefix.as_deref(),
+        )
         .await
     {
         Ok(results) => Json(serde_json::json!({
@@ -3229,45 +5648,48 @@ async fn search_code(
             "success": false,
             "error": format!("Search failed: {}", e)
         })),
-    }
+    })
 }
 
 /// Get code indexing statistics
-async fn code_stats(State(state): State<DaemonState>) -> Json<serde_json::Value> {
-    let indexing_service = match &state.indexing_service {
-        Some(svc) => svc,
-        None => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": "Indexing service not available"
-            }));
-        }
-    };
-
-    match indexing_service.get_stats().await {
-        Ok(stats) => Json(serde_json::json!({
-            "success": true,
-            "stats": stats
-        })),
-        Err(e) => Json(serde_json::json!({
+async fn code_stats(State(state): State<DaemonState>) -> ServiceResult {
+    let indexing_service = require_service(state.indexing_service.as_ref(), || {
+        serde_json::json!({
             "success": false,
-            "error": format!("Failed to get stats: {}", e)
-        })),
-    }
+            "error": "Indexing service not available"
+        })
+    })?;
+
+    let stats = indexing_service.get_stats().await.context("Failed to get stats")?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "stats": stats
+    })))
 }
 
 /// ACP WebSocket status endpoint
 async fn acp_status(State(state): State<DaemonState>) -> Json<serde_json::Value> {
-    let agents_with_roles = state.acp_server.agents_with_roles().await;
+    let agents_with_capabilities = state.acp_server.agents_with_capabilities().await;
+    let worker_ids = state.acp_server.worker_ids().await;
     let connection_count = state.acp_server.connection_count().await;
+    let backpressure: HashMap<AgentId, cca_acp::ConnectionBackpressureInfo> = state
+        .acp_server
+        .get_all_backpressure_metrics()
+        .await
+        .into_iter()
+        .map(|info| (info.agent_id, info))
+        .collect();
 
-    let workers: Vec<serde_json::Value> = agents_with_roles
+    let workers: Vec<serde_json::Value> = agents_with_capabilities
         .iter()
-        .map(|(id, role)| {
-            serde_json::json!({
-                "agent_id": id.to_string(),
-                "role": role.clone().unwrap_or_else(|| "unregistered".to_string())
-            })
+        .map(|(id, role, capabilities)| {
+            acp_worker_json(
+                *id,
+                role.as_deref(),
+                worker_ids.get(id).map(String::as_str),
+                capabilities.as_ref(),
+                backpressure.get(id),
+            )
         })
         .collect();
 
@@ -3279,6 +5701,25 @@ async fn acp_status(State(state): State<DaemonState>) -> Json<serde_json::Value>
     }))
 }
 
+/// One worker's entry in `/api/v1/acp/status`, including its send queue state
+fn acp_worker_json(
+    agent_id: AgentId,
+    role: Option<&str>,
+    worker_id: Option<&str>,
+    capabilities: Option<&cca_acp::AgentCapabilities>,
+    backpressure: Option<&cca_acp::ConnectionBackpressureInfo>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "agent_id": agent_id.to_string(),
+        "role": role.unwrap_or("unregistered"),
+        "worker_id": worker_id,
+        "capabilities": capabilities,
+        "channel_fullness": backpressure.map_or(0.0, |b| b.channel_fullness),
+        "messages_dropped": backpressure.map_or(0, |b| b.messages_dropped),
+        "is_warning": backpressure.is_some_and(|b| b.is_warning)
+    })
+}
+
 /// ACP disconnect request
 /// SEC-012: Validated with UUID format
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -3302,27 +5743,9 @@ async fn acp_disconnect(
         }
     };
 
-    // Get the agent's role before disconnecting (for tmux tracking cleanup)
-    let agent_role = state
-        .acp_server
-        .agents_with_roles()
-        .await
-        .into_iter()
-        .find(|(id, _)| *id == agent_id)
-        .and_then(|(_, role)| role);
-
-    match state.acp_server.disconnect(agent_id).await {
+    match disconnect_acp_agent(&state, agent_id).await {
         Ok(()) => {
             info!("Agent {} disconnected via API", agent_id);
-
-            // If this was a tmux-spawned agent, remove it from tracking
-            if let Some(role) = agent_role {
-                state.tmux_manager.remove_agent_by_role(&role).await;
-            }
-
-            // Also remove from busy agents if it was marked busy
-            state.busy_agents.write().await.remove(&agent_id);
-
             Json(serde_json::json!({
                 "success": true,
                 "message": format!("Agent {} disconnected", agent_id)
@@ -3337,6 +5760,87 @@ async fn acp_disconnect(
     }
 }
 
+/// Disconnect an ACP worker and drop the daemon's tracking for it
+async fn disconnect_acp_agent(state: &DaemonState, agent_id: AgentId) -> anyhow::Result<()> {
+    // Get the worker ID before disconnecting (for tmux tracking cleanup)
+    let worker_id = state.acp_server.worker_ids().await.remove(&agent_id);
+
+    state.acp_server.disconnect(agent_id).await?;
+
+    // If this was a tmux-spawned agent, remove its pane from tracking
+    if let Some(worker_id) = worker_id {
+        state.tmux_manager.remove_agent_by_worker_id(&worker_id).await;
+    }
+
+    // Also remove from busy agents if it was marked busy
+    state.busy_agents.write().await.remove(&agent_id);
+    Ok(())
+}
+
+/// Thresholds for `POST /api/v1/acp/disconnect-slow`; at least one is required
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcpDisconnectSlowRequest {
+    /// Disconnect workers with at least this many consecutive dropped messages
+    pub consecutive_drops: Option<u32>,
+    /// Disconnect workers whose send queue is at least this full (0.0 to 1.0)
+    pub channel_fullness: Option<f32>,
+}
+
+/// Workers at or above either threshold
+fn slow_consumers(
+    metrics: &[cca_acp::ConnectionBackpressureInfo],
+    consecutive_drops: Option<u32>,
+    channel_fullness: Option<f32>,
+) -> Vec<AgentId> {
+    metrics
+        .iter()
+        .filter(|info| {
+            consecutive_drops.is_some_and(|min| info.consecutive_drops >= min)
+                || channel_fullness.is_some_and(|min| info.channel_fullness >= min)
+        })
+        .map(|info| info.agent_id)
+        .collect()
+}
+
+/// Disconnect every worker whose send queue is over the given thresholds
+async fn acp_disconnect_slow(
+    State(state): State<DaemonState>,
+    Json(request): Json<AcpDisconnectSlowRequest>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    if request.consecutive_drops.is_none() && request.channel_fullness.is_none() {
+        return Err(ValidationError::new(
+            "consecutive_drops",
+            "required",
+            "Provide consecutive_drops or channel_fullness",
+        ));
+    }
+    if request.channel_fullness.is_some_and(|f| !(0.0..=1.0).contains(&f)) {
+        return Err(ValidationError::new(
+            "channel_fullness",
+            "out_of_range",
+            "channel_fullness must be between 0.0 and 1.0",
+        ));
+    }
+
+    let metrics = state.acp_server.get_all_backpressure_metrics().await;
+    let mut disconnected = Vec::new();
+    for agent_id in slow_consumers(&metrics, request.consecutive_drops, request.channel_fullness) {
+        match disconnect_acp_agent(&state, agent_id).await {
+            Ok(()) => {
+                info!("Slow consumer {} disconnected via API", agent_id);
+                disconnected.push(agent_id.to_string());
+            }
+            // Usually the worker went away on its own in the meantime
+            Err(e) => warn!("Failed to disconnect slow consumer {}: {}", agent_id, e),
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "disconnected": disconnected
+    })))
+}
+
 /// ACP send task request
 /// SEC-012: Validated with UUID format and max lengths
 #[derive(Debug, Clone, Deserialize, Validate)]
@@ -3352,6 +5856,7 @@ pub struct AcpSendTaskRequest {
 /// Send a task to a specific worker
 async fn acp_send_task(
     State(state): State<DaemonState>,
+    Extension(request_id): Extension<RequestId>,
     Json(request): Json<AcpSendTaskRequest>,
 ) -> Json<serde_json::Value> {
     // Input validation - check task length
@@ -3390,12 +5895,14 @@ async fn acp_send_task(
         }
     };
 
-    let timeout = std::time::Duration::from_secs(state.config.agents.default_timeout_seconds);
+    let timeout = std::time::Duration::from_secs(state.reloadable_config.read().await.default_timeout_seconds);
 
     match state.acp_server.send_task(
         agent_id,
+        None,
         &request.task,
         request.context.as_deref(),
+        Some(request_id.as_str()),
         timeout,
     ).await {
         Ok(output) => {
@@ -3413,41 +5920,106 @@ async fn acp_send_task(
     }
 }
 
-/// Broadcast request
-/// SEC-012: Validated with max message length
-#[derive(Debug, Clone, Deserialize, Validate)]
-pub struct BroadcastRequest {
-    #[validate(length(min = 1, max = 10000, message = "Message must be 1-10000 characters"))]
+/// ACP relay request
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcpRelayRequest {
+    /// Sending agent, which must be connected
+    pub from: String,
+    /// Receiving agent
+    pub to: String,
     pub message: String,
 }
 
-/// Pub/Sub broadcast endpoint
-async fn pubsub_broadcast(
+/// Forward a message from one connected agent to another
+///
+/// The caller names the sender, so the route needs `agents:admin` and the
+/// delivered message is marked as relayed by the daemon.
+async fn acp_relay(
     State(state): State<DaemonState>,
-    Json(request): Json<BroadcastRequest>,
-) -> Json<serde_json::Value> {
-    // Input validation: check message length
-    if request.message.len() > MAX_BROADCAST_MESSAGE_LEN {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": format!(
-                "Message too long: {} bytes (max: {} bytes)",
-                request.message.len(),
-                MAX_BROADCAST_MESSAGE_LEN
-            )
-        }));
+    Json(request): Json<AcpRelayRequest>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let parse_agent_id = |field: &'static str, value: &str| {
+        Uuid::parse_str(value).map(AgentId).map_err(|_| {
+            ValidationError::new(field, "invalid_agent_id", format!("Invalid agent ID: {value}"))
+        })
+    };
+    let from = parse_agent_id("from", &request.from)?;
+    let to = parse_agent_id("to", &request.to)?;
+    if from == to {
+        return Err(ValidationError::new("to", "same_agent", "Cannot relay a message to its sender"));
+    }
+
+    validate_broadcast(&request.message)?;
+    // SEC-009: Sanitize message content before forwarding to the agent
+    let sanitized_message = sanitize_broadcast_message(&request.message);
+    if sanitized_message.is_empty() {
+        return Err(ValidationError::new(
+            "message",
+            "empty",
+            "Message is empty after sanitization",
+        ));
+    }
+    screen_agent_content("message", &sanitized_message, state.config.daemon.suspicious_content_action)?;
+
+    if !state.acp_server.connected_agents().await.contains(&from) {
+        return Err(ValidationError::new(
+            "from",
+            "not_connected",
+            format!("Agent not connected: {from}"),
+        ));
     }
 
+    let params = cca_acp::SendMessageParams {
+        from: Some(from.to_string()),
+        to: to.to_string(),
+        content: sanitized_message,
+        metadata: serde_json::json!({ "relayed_by": "daemon" }),
+    };
+    let message = cca_acp::AcpMessage::notification(
+        cca_acp::methods::SEND_MESSAGE,
+        serde_json::to_value(params).unwrap_or_default(),
+    );
+
+    let delivery = state.acp_server.send_to(to, message).await;
+    if let Err(e) = &delivery {
+        warn!("Failed to relay message from {} to {}: {}", from, to, e);
+    }
+    Ok(Json(serde_json::json!({
+        "success": delivery.is_ok(),
+        "delivered": delivery.is_ok(),
+        "from": from.to_string(),
+        "to": to.to_string(),
+        "error": delivery.err().map(|e| e.to_string())
+    })))
+}
+
+/// Broadcast request
+/// SEC-012: Validated with max message length
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct BroadcastRequest {
+    #[validate(length(min = 1, max = 10000, message = "Message must be 1-10000 characters"))]
+    pub message: String,
+}
+
+/// Pub/Sub broadcast endpoint
+async fn pubsub_broadcast(
+    State(state): State<DaemonState>,
+    Json(request): Json<BroadcastRequest>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    validate_broadcast(&request.message)?;
+
     // SEC-009: Sanitize message content before forwarding to agents
     let sanitized_message = sanitize_broadcast_message(&request.message);
     if sanitized_message.is_empty() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Message is empty after sanitization"
-        }));
+        return Err(ValidationError::new(
+            "message",
+            "empty",
+            "Message is empty after sanitization",
+        ));
     }
+    screen_agent_content("message", &sanitized_message, state.config.daemon.suspicious_content_action)?;
 
-    match &state.redis {
+    let response = match &state.redis {
         Some(redis) => {
             let msg = PubSubMessage::Broadcast {
                 from: AgentId::new(), // System broadcast
@@ -3455,51 +6027,41 @@ async fn pubsub_broadcast(
             };
 
             match redis.pubsub.broadcast(&msg).await {
-                Ok(()) => Json(serde_json::json!({
+                Ok(()) => serde_json::json!({
                     "success": true,
                     "message": "Broadcast sent"
-                })),
-                Err(e) => Json(serde_json::json!({
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
                     "error": format!("Failed to broadcast: {}", e)
-                })),
+                }),
             }
         }
-        None => Json(serde_json::json!({
+        None => serde_json::json!({
             "success": false,
             "error": "Redis not available"
-        })),
-    }
+        }),
+    };
+    Ok(Json(response))
 }
 
 /// Broadcast to all agents via ACP and Redis
 async fn broadcast_all(
     State(state): State<DaemonState>,
     Json(request): Json<BroadcastRequest>,
-) -> Json<serde_json::Value> {
-    // Input validation: check message length
-    if request.message.len() > MAX_BROADCAST_MESSAGE_LEN {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": format!(
-                "Message too long: {} bytes (max: {} bytes)",
-                request.message.len(),
-                MAX_BROADCAST_MESSAGE_LEN
-            )
-        }));
-    }
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    validate_broadcast(&request.message)?;
 
     // SEC-009: Sanitize message content before forwarding to agents
     let sanitized_message = sanitize_broadcast_message(&request.message);
     if sanitized_message.is_empty() {
-        return Json(serde_json::json!({
-            "success": false,
-            "error": "Message is empty after sanitization"
-        }));
+        return Err(ValidationError::new(
+            "message",
+            "empty",
+            "Message is empty after sanitization",
+        ));
     }
-
-    let mut acp_count = 0;
-    let mut redis_success = false;
+    screen_agent_content("message", &sanitized_message, state.config.daemon.suspicious_content_action)?;
 
     // Broadcast via ACP WebSocket
     let acp_message = cca_acp::AcpMessage::notification(
@@ -3510,60 +6072,88 @@ async fn broadcast_all(
         }),
     );
 
-    match state.acp_server.broadcast(acp_message).await {
-        Ok(result) => {
-            acp_count = result.sent;
-            if result.had_backpressure() {
-                warn!("Broadcast had backpressure: {}", result);
-            }
-        }
-        Err(e) => {
-            warn!("Failed to broadcast via ACP: {}", e);
-        }
+    let acp = state
+        .acp_server
+        .broadcast(acp_message)
+        .await
+        .map_err(|e| e.to_string());
+    match &acp {
+        Ok(result) if result.had_backpressure() => warn!("Broadcast had backpressure: {}", result),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to broadcast via ACP: {}", e),
     }
 
     // Also broadcast via Redis pub/sub
-    if let Some(redis) = &state.redis {
-        let msg = PubSubMessage::Broadcast {
-            from: AgentId::new(),
-            message: sanitized_message.clone(),
-        };
-
-        if redis.pubsub.broadcast(&msg).await.is_ok() {
-            redis_success = true;
+    let redis = match &state.redis {
+        Some(redis) => {
+            let msg = PubSubMessage::Broadcast {
+                from: AgentId::new(),
+                message: sanitized_message.clone(),
+            };
+            let published = redis.pubsub.broadcast(&msg).await.map_err(|e| e.to_string());
+            if let Err(e) = &published {
+                warn!("Failed to broadcast via Redis: {}", e);
+            }
+            Some(published)
         }
-    }
+        None => None,
+    };
 
-    Json(serde_json::json!({
-        "success": true,
-        "agents_notified": acp_count,
-        "message": format!("Broadcast sent to {} agents via ACP, Redis: {}", acp_count, redis_success)
-    }))
+    Ok(Json(broadcast_result_json(&acp, redis.as_ref())))
+}
+
+/// Body of `POST /api/v1/broadcast`
+///
+/// `redis` is `None` when Redis isn't configured. The broadcast only counts as
+/// a success if at least one agent got it over ACP or Redis accepted it.
+fn broadcast_result_json(
+    acp: &Result<cca_acp::BroadcastResult, String>,
+    redis: Option<&Result<(), String>>,
+) -> serde_json::Value {
+    let (sent, dropped, disconnected, acp_error) = match acp {
+        Ok(result) => (
+            result.sent,
+            result.dropped,
+            result.disconnected.iter().map(ToString::to_string).collect(),
+            None,
+        ),
+        Err(e) => (0, 0, Vec::new(), Some(e.as_str())),
+    };
+    let redis_published = matches!(redis, Some(Ok(())));
+    let redis_error = redis.and_then(|r| r.as_ref().err());
+
+    serde_json::json!({
+        "success": sent > 0 || redis_published,
+        "agents_notified": sent,
+        "message": format!("Broadcast sent to {} agents via ACP, Redis: {}", sent, redis_published),
+        "acp": {
+            "sent": sent,
+            "dropped": dropped,
+            "disconnected": disconnected,
+            "error": acp_error
+        },
+        "redis": {
+            "enabled": redis.is_some(),
+            "published": redis_published,
+            "error": redis_error
+        }
+    })
 }
 
 /// Get workload distribution across agents
 async fn get_workloads(State(state): State<DaemonState>) -> Json<serde_json::Value> {
     let tasks = state.tasks.read().await;
 
-    // Sync ACP-connected agents to orchestrator
-    let orchestrator = state.orchestrator.read().await;
+    // Fallback: sync ACP-connected agents that were never registered explicitly
     let agents_with_roles = state.acp_server.agents_with_roles().await;
     for (agent_id, role_opt) in agents_with_roles {
         if let Some(role) = role_opt {
-            let workloads = orchestrator.get_agent_workloads().await;
-            if !workloads.iter().any(|w| w.agent_id == agent_id) {
-                orchestrator.register_agent(
-                    agent_id,
-                    role.clone(),
-                    vec![role.clone()],
-                    5,
-                ).await;
-            }
+            ensure_orchestrator_registration(&state, agent_id, &role).await;
         }
     }
 
     // Get workloads from orchestrator (includes ACP-connected workers)
-    let orchestrator_workloads = orchestrator.get_agent_workloads().await;
+    let orchestrator_workloads = state.orchestrator.read().await.get_agent_workloads().await;
 
     let agents: Vec<serde_json::Value> = orchestrator_workloads
         .iter()
@@ -3583,15 +6173,195 @@ async fn get_workloads(State(state): State<DaemonState>) -> Json<serde_json::Val
         .collect();
 
     let total_tasks = tasks.len();
-    let pending_tasks = tasks.values().filter(|t| t.status == "pending").count();
+    let pending_tasks = tasks.values().filter(|t| t.status == TaskRunStatus::Pending).count();
+
+    // Oldest pending work across API tasks and delegations queued for a busy agent
+    let now = Utc::now();
+    let oldest_api_pending = tasks
+        .values()
+        .filter(|t| t.status == TaskRunStatus::Pending)
+        .filter_map(|t| (now - t.created_at).to_std().ok())
+        .max();
+    let circuit_breakers = state.orchestrator.read().await.role_breakers().await;
+    let oldest_pending_age_secs = oldest_api_pending
+        .max(state.dispatch_queue.oldest_wait())
+        .map(|age| age.as_secs_f64());
 
     Json(serde_json::json!({
         "agents": agents,
         "total_tasks": total_tasks,
-        "pending_tasks": pending_tasks
+        "pending_tasks": pending_tasks,
+        "queued_tasks": state.dispatch_queue.waiting(),
+        "circuit_breakers": circuit_breakers,
+        "oldest_pending_age_secs": oldest_pending_age_secs
     }))
 }
 
+/// Query parameters for the workload history endpoint
+#[derive(Debug, Deserialize)]
+pub struct WorkloadHistoryQuery {
+    /// How far back to return samples (default 60, max 1440)
+    #[serde(default = "default_history_minutes")]
+    minutes: u32,
+}
+
+fn default_history_minutes() -> u32 {
+    60
+}
+
+/// Max minutes of workload history (matches the orchestrator's ring buffer)
+const MAX_HISTORY_MINUTES: u32 =
+    (crate::orchestrator::WORKLOAD_HISTORY_CAPACITY as u64 * WORKLOAD_SAMPLE_INTERVAL_SECS / 60) as u32;
+
+/// Per-role workload samples from the last `minutes`
+async fn get_workload_history(
+    State(state): State<DaemonState>,
+    axum::extract::Query(query): axum::extract::Query<WorkloadHistoryQuery>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    if query.minutes == 0 || query.minutes > MAX_HISTORY_MINUTES {
+        return Err(ValidationError::new(
+            "minutes",
+            "out_of_range",
+            format!("minutes must be between 1 and {MAX_HISTORY_MINUTES}"),
+        ));
+    }
+
+    let since = Utc::now() - chrono::Duration::minutes(i64::from(query.minutes));
+    let samples = state.orchestrator.read().await.workload_history(since).await;
+
+    Ok(Json(serde_json::json!({
+        "minutes": query.minutes,
+        "interval_secs": WORKLOAD_SAMPLE_INTERVAL_SECS,
+        "samples": samples
+    })))
+}
+
+/// Request to register an agent in the orchestrator's routing pool
+/// SEC-012: Validated with UUID format, max lengths and capacity bounds
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RegisterOrchestratorAgentRequest {
+    #[validate(custom(function = "crate::validation::validate_uuid"))]
+    pub agent_id: String,
+    #[validate(length(min = 1, max = 64, message = "Role must be 1-64 characters"))]
+    pub role: String,
+    /// Capabilities advertised for routing (defaults to the role name)
+    #[serde(default)]
+    #[validate(length(max = 64, message = "At most 64 capabilities allowed"))]
+    #[validate(custom(function = "validate_capability_names"))]
+    pub capabilities: Vec<String>,
+    /// Maximum concurrent tasks (defaults to the role's configured `max_tasks`)
+    #[serde(default)]
+    #[validate(range(min = 1, max = 100, message = "max_tasks must be 1-100"))]
+    pub max_tasks: Option<u32>,
+}
+
+/// Every capability name must be at most `MAX_CAPABILITY_LEN` bytes
+fn validate_capability_names(capabilities: &[String]) -> Result<(), validator::ValidationError> {
+    match capabilities.iter().find(|c| c.len() > MAX_CAPABILITY_LEN) {
+        Some(cap) => {
+            let mut err = validator::ValidationError::new("too_long");
+            err.message = Some(
+                format!(
+                    "Capability too long: '{}' (max: {} chars)",
+                    safe_truncate(cap, MAX_CAPABILITY_LEN),
+                    MAX_CAPABILITY_LEN
+                )
+                .into(),
+            );
+            Err(err)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Explicitly register an agent in the orchestrator
+///
+/// Replaces any existing registration (including the implicit one created when
+/// routing first discovers a connected worker).
+async fn register_orchestrator_agent(
+    State(state): State<DaemonState>,
+    Json(request): Json<RegisterOrchestratorAgentRequest>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    // SEC-008: Input validation - role, agent ID, capabilities and capacity
+    request.validate()?;
+    let agent_id = Uuid::parse_str(&request.agent_id)
+        .map(AgentId)
+        .map_err(|_| ValidationError::new("agent_id", "invalid_uuid", "Invalid UUID format"))?;
+
+    let role = request.role.to_lowercase();
+    let max_tasks = request
+        .max_tasks
+        .unwrap_or_else(|| state.config.agents.max_tasks_for_role(&role));
+    let capabilities = if request.capabilities.is_empty() {
+        vec![role.clone()]
+    } else {
+        request.capabilities
+    };
+
+    let replaced = state
+        .orchestrator
+        .read()
+        .await
+        .register_agent(agent_id, role.clone(), capabilities.clone(), max_tasks)
+        .await;
+
+    register_agent_in_postgres(
+        &state,
+        agent_id,
+        &role,
+        serde_json::json!({"manually_registered": true}),
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "agent_id": agent_id.to_string(),
+        "role": role,
+        "capabilities": capabilities,
+        "max_tasks": max_tasks,
+        "replaced": replaced
+    })))
+}
+
+/// Remove an agent from the orchestrator's routing pool
+///
+/// Connected agents are refused with 409: the implicit fallback would
+/// re-register them with default settings as soon as routing or the workloads
+/// view encountered them. Stop or disconnect the agent instead.
+async fn unregister_orchestrator_agent(
+    State(state): State<DaemonState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let error = |status: StatusCode, message: String| {
+        (status, Json(serde_json::json!({ "success": false, "error": message })))
+    };
+    let agent_id = match Uuid::parse_str(&agent_id) {
+        Ok(uuid) => AgentId(uuid),
+        Err(_) => {
+            return Err(error(StatusCode::BAD_REQUEST, format!("Invalid agent ID: {}", agent_id)));
+        }
+    };
+
+    if state.acp_server.connected_agents().await.contains(&agent_id) {
+        return Err(error(
+            StatusCode::CONFLICT,
+            format!("Agent {} is still connected; stop or disconnect it instead", agent_id),
+        ));
+    }
+
+    let removed = state.orchestrator.read().await.unregister_agent(agent_id).await;
+    if !removed {
+        return Err(error(
+            StatusCode::NOT_FOUND,
+            format!("Agent {} is not registered in orchestrator", agent_id),
+        ));
+    }
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "message": format!("Agent {} unregistered from orchestrator", agent_id)
+    })))
+}
+
 /// Helper to publish task events to Redis
 async fn publish_task_event(redis: &Option<Arc<RedisServices>>, msg: PubSubMessage) {
     if let Some(redis) = redis {
@@ -3791,7 +6561,9 @@ async fn tokens_analyze(
         "code_blocks": analysis.code_block_count,
         "long_lines": analysis.long_line_count,
         "compression_potential": format!("{:.1}%", analysis.compression_potential * 100.0),
-        "repeated_lines": analysis.repeated_lines.len()
+        "repeated_lines": analysis.repeated_lines.len(),
+        "sections": analysis.sections,
+        "top_repeated_blocks": analysis.top_repeated_blocks
     }))
 }
 
@@ -3832,7 +6604,7 @@ async fn tokens_compress(
         }));
     }
 
-    let original_tokens = state.token_service.counter.count(&request.content);
+    let original_tokens = state.token_service.count(&request.content);
 
     // Apply compression strategies
     let mut compressed = request.content.clone();
@@ -3849,7 +6621,7 @@ async fn tokens_compress(
         }
     }
 
-    let final_tokens = state.token_service.counter.count(&compressed);
+    let final_tokens = state.token_service.count(&compressed);
     let tokens_saved = original_tokens.saturating_sub(final_tokens);
     let reduction = if original_tokens > 0 {
         (tokens_saved as f64 / original_tokens as f64) * 100.0
@@ -3960,7 +6732,7 @@ async fn tokens_recommendations(State(state): State<DaemonState>) -> Json<serde_
 /// This allows systemd and other service managers to trigger config reload
 /// using `systemctl reload ccad` or `kill -HUP <pid>`.
 #[cfg(unix)]
-async fn sighup_handler(reloadable_config: SharedReloadableConfig) {
+async fn sighup_handler(state: DaemonState) {
     use tokio::signal::unix::{signal, SignalKind};
 
     let mut stream = match signal(SignalKind::hangup()) {
@@ -3984,28 +6756,58 @@ async fn sighup_handler(reloadable_config: SharedReloadableConfig) {
             }
         };
 
-        // Extract reloadable parts and compare
-        let new_reloadable = new_config.to_reloadable();
+        match apply_config_reload(&state, &new_config).await {
+            Ok(changes) if changes.applied.is_empty() => {
+                info!("SIGHUP reload: no changes detected");
+            }
+            Ok(changes) => {
+                info!("SIGHUP reload successful. Changed fields: {:?}", changes.applied);
+            }
+            Err(e) => error!("SIGHUP reload failed: {}", e),
+        }
+    }
+}
+
+/// Validate a freshly loaded config and swap its reloadable fields into the daemon
+///
+/// Rate limits, CORS origins and the log level are applied immediately; other
+/// reloadable fields are read by handlers as they run. Changes to startup-only
+/// settings are logged and ignored.
+async fn apply_config_reload(state: &DaemonState, new_config: &Config) -> Result<ReloadChanges, String> {
+    if let Err(errors) = new_config.validate() {
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        return Err(format!("Invalid configuration: {}", errors.join("; ")));
+    }
 
-        let current_config = reloadable_config.read().await;
-        let changed_fields = current_config.diff(&new_reloadable);
-        drop(current_config);
+    let mut reloadable = state.reloadable_config.write().await;
+    let changes = reloadable.merge(&state.config, new_config);
 
-        if changed_fields.is_empty() {
-            info!("SIGHUP reload: no changes detected");
-            continue;
+    if changes.applied.iter().any(|f| f.starts_with("rate_limit_")) {
+        *state.rate_limiter.write().unwrap() = build_rate_limiter(&state.config.daemon, &reloadable);
+    }
+    if changes.applied.iter().any(|f| f.starts_with("cors_")) {
+        *state.cors_policy.write().unwrap() =
+            CorsPolicy::new(&reloadable.cors_origins, reloadable.cors_allow_credentials);
+    }
+    if changes.applied.iter().any(|f| f == "log_level") {
+        match &state.log_reloader {
+            Some(reload) => {
+                if let Err(e) = reload(&reloadable.log_level) {
+                    warn!("Failed to apply log level '{}': {}", reloadable.log_level, e);
+                }
+            }
+            None => warn!("log_level changed but RUST_LOG is set; keeping the RUST_LOG filter"),
         }
+    }
+    drop(reloadable);
 
-        // Apply the new configuration
-        let mut config = reloadable_config.write().await;
-        *config = new_reloadable;
-        drop(config);
-
-        info!(
-            "SIGHUP reload successful. Changed fields: {:?}",
-            changed_fields
+    if !changes.restart_required.is_empty() {
+        warn!(
+            "Config changes that need a restart were not applied: {:?}",
+            changes.restart_required
         );
     }
+    Ok(changes)
 }
 
 // ============================================================================
@@ -4018,6 +6820,8 @@ async fn sighup_handler(reloadable_config: SharedReloadableConfig) {
 /// The following are reloadable:
 /// - API keys (daemon.api_keys, daemon.api_key_configs)
 /// - Rate limits (daemon.rate_limit_*)
+/// - CORS origins and credentials (daemon.cors_origins, daemon.cors_allow_credentials)
+/// - Log level (daemon.log_level, unless `RUST_LOG` is set)
 /// - Agent settings (agents.default_timeout_seconds, agents.permissions)
 /// - Learning settings (learning.enabled, learning.training_batch_size)
 ///
@@ -4041,43 +6845,40 @@ async fn reload_config(State(state): State<DaemonState>) -> Json<ReloadResult> {
                 success: false,
                 config_file: config_file_str,
                 changed_fields: vec![],
+                restart_required: vec![],
                 error: Some(format!("Failed to load configuration: {}", e)),
             });
         }
     };
 
-    // Extract reloadable parts
-    let new_reloadable = new_config.to_reloadable();
-
-    // Compare with current config and get list of changes
-    let current_config = state.reloadable_config.read().await;
-    let changed_fields = current_config.diff(&new_reloadable);
-    drop(current_config);
+    let changes = match apply_config_reload(&state, &new_config).await {
+        Ok(changes) => changes,
+        Err(e) => {
+            error!("Configuration reload failed: {}", e);
+            return Json(ReloadResult {
+                success: false,
+                config_file: config_file_str,
+                changed_fields: vec![],
+                restart_required: vec![],
+                error: Some(e),
+            });
+        }
+    };
 
-    if changed_fields.is_empty() {
+    if changes.applied.is_empty() {
         info!("Configuration reload: no changes detected");
-        return Json(ReloadResult {
-            success: true,
-            config_file: config_file_str,
-            changed_fields: vec![],
-            error: None,
-        });
+    } else {
+        info!(
+            "Configuration reloaded successfully. Changed fields: {:?}",
+            changes.applied
+        );
     }
 
-    // Apply the new configuration
-    let mut config = state.reloadable_config.write().await;
-    *config = new_reloadable;
-    drop(config);
-
-    info!(
-        "Configuration reloaded successfully. Changed fields: {:?}",
-        changed_fields
-    );
-
     Json(ReloadResult {
         success: true,
         config_file: config_file_str,
-        changed_fields,
+        changed_fields: changes.applied,
+        restart_required: changes.restart_required,
         error: None,
     })
 }
@@ -4108,6 +6909,9 @@ async fn get_reloadable_config(State(state): State<DaemonState>) -> Json<Reloada
         "rate_limit_global_rps".to_string(),
         "rate_limit_api_key_rps".to_string(),
         "rate_limit_api_key_burst".to_string(),
+        "cors_origins".to_string(),
+        "cors_allow_credentials".to_string(),
+        "log_level".to_string(),
         "default_timeout_seconds".to_string(),
         "permissions.mode".to_string(),
         "permissions.allowed_tools".to_string(),
@@ -4127,6 +6931,9 @@ async fn get_reloadable_config(State(state): State<DaemonState>) -> Json<Reloada
         "rate_limit_global_rps": config.rate_limit_global_rps,
         "rate_limit_api_key_rps": config.rate_limit_api_key_rps,
         "rate_limit_api_key_burst": config.rate_limit_api_key_burst,
+        "cors_origins": config.cors_origins,
+        "cors_allow_credentials": config.cors_allow_credentials,
+        "log_level": config.log_level,
         "default_timeout_seconds": config.default_timeout_seconds,
         "permissions": {
             "mode": config.permissions.mode,
@@ -4146,3 +6953,1795 @@ async fn get_reloadable_config(State(state): State<DaemonState>) -> Json<Reloada
         current_values,
     })
 }
+
+/// Get the effective configuration with secrets redacted
+///
+/// Starts from the startup config with the current reloadable values on top.
+/// API keys and connection URLs are masked here, so they never leave the daemon.
+async fn get_config(State(state): State<DaemonState>) -> Json<serde_json::Value> {
+    let mut config = state.config.clone();
+    state.reloadable_config.read().await.overlay(&mut config);
+    let config_file = Config::find_config_file_path().map(|p| p.display().to_string());
+
+    match config.redacted() {
+        Ok(redacted) => Json(serde_json::json!({
+            "success": true,
+            "config_file": config_file,
+            "config": redacted
+        })),
+        Err(e) => Json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to serialize configuration: {}", e)
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cca_chaos_tests::pattern_store_chaos_tests::ChaosPatternStore;
+
+    fn delegation(role: &str, task: &str) -> CoordinatorDelegation {
+        CoordinatorDelegation {
+            role: role.to_string(),
+            task: task.to_string(),
+            context: None,
+        }
+    }
+
+    /// Daemon state with no external services, for handler tests
+    fn test_state() -> DaemonState {
+        let config = Config::default();
+        DaemonState {
+            reloadable_config: Arc::new(RwLock::new(config.to_reloadable())),
+            rate_limiter: Arc::new(std::sync::RwLock::new(None)),
+            cors_policy: Arc::new(std::sync::RwLock::new(CorsPolicy::default())),
+            log_reloader: None,
+            agent_manager: Arc::new(RwLock::new(AgentManager::new(&config))),
+            orchestrator: Arc::new(RwLock::new(Orchestrator::new())),
+            tasks: Arc::new(RwLock::new(HashMap::new())),
+            cancellation: Arc::new(TaskCancellation::new()),
+            redis: None,
+            postgres: None,
+            pattern_store: None,
+            acp_server: Arc::new(AcpServer::new("127.0.0.1:0".parse().unwrap())),
+            rl_service: Arc::new(RLService::new(RLConfig::default())),
+            token_service: Arc::new(TokenService::new()),
+            tmux_manager: Arc::new(crate::tmux::TmuxManager::new()),
+            busy_agents: Arc::new(RwLock::new(HashMap::new())),
+            dispatch_queue: Arc::new(DispatchQueue::new(0, 0.0)),
+            idempotency: Arc::new(IdempotencyStore::new(std::time::Duration::from_secs(60), 100)),
+            task_claims: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            delegation_cache: Arc::new(DelegationCache::new(std::time::Duration::from_secs(60))),
+            health_cache: Arc::new(RwLock::new(None)),
+            started: Arc::new(AtomicBool::new(false)),
+            embedding_service: None,
+            indexing_service: None,
+            config,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claim_task_without_redis_runs_unlocked() {
+        let hash = task_content_hash("Fix the login bug");
+        assert!(claim_task(&test_state(), "task-1", hash).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_claimed_task_is_not_run_by_a_second_daemon() {
+        let config = crate::config::RedisConfig {
+            url: std::env::var("CCA_TEST_REDIS_URL").expect("CCA_TEST_REDIS_URL not set"),
+            ..Default::default()
+        };
+        let redis = Arc::new(RedisServices::new(&config).await.unwrap());
+        let first = DaemonState {
+            redis: Some(redis.clone()),
+            ..test_state()
+        };
+        let second = DaemonState {
+            redis: Some(redis.clone()),
+            ..test_state()
+        };
+        // Each daemon gives the task its own ID; the lock follows the description
+        let description = format!("Fix the login bug {}", Uuid::new_v4());
+        let hash = task_content_hash(&description);
+        assert_eq!(hash, task_content_hash(&format!("  {}", description.to_uppercase())));
+
+        let claim = claim_task(&first, "task-a", hash).await.unwrap().unwrap();
+        let err = claim_task(&second, "task-b", hash).await.err().unwrap();
+        assert!(err.contains("another daemon"));
+
+        // An identical task on the same daemon shares the claim
+        let shared = claim_task(&first, "task-c", hash).await.unwrap().unwrap();
+        assert!(Arc::ptr_eq(&claim, &shared));
+
+        // Dropping the last reference releases the lock in the background
+        drop(claim);
+        drop(shared);
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            if let Ok(Some(_)) = claim_task(&second, "task-b", hash).await {
+                break;
+            }
+            assert!(tokio::time::Instant::now() < deadline, "claim was not released");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_task_claim_is_renewed_past_its_ttl() {
+        let config = crate::config::RedisConfig {
+            url: std::env::var("CCA_TEST_REDIS_URL").expect("CCA_TEST_REDIS_URL not set"),
+            task_lock_ttl_seconds: 1,
+            ..Default::default()
+        };
+        let redis = Arc::new(RedisServices::new(&config).await.unwrap());
+        let mut first = DaemonState {
+            redis: Some(redis.clone()),
+            ..test_state()
+        };
+        first.config.redis = config;
+        let second = DaemonState {
+            redis: Some(redis.clone()),
+            ..test_state()
+        };
+        let hash = task_content_hash(&format!("Long migration {}", Uuid::new_v4()));
+
+        let _claim = claim_task(&first, "task-a", hash).await.unwrap().unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+        assert!(claim_task(&second, "task-b", hash).await.is_err());
+    }
+
+    fn pending_task(task_id: &str, description: &str, created_at: DateTime<Utc>) -> TaskState {
+        TaskState {
+            task_id: task_id.to_string(),
+            description: description.to_string(),
+            status: TaskRunStatus::Running,
+            priority: "normal".to_string(),
+            output: None,
+            error: None,
+            assigned_agent: None,
+            delegations: Vec::new(),
+            progress: None,
+            content_hash: Some(task_content_hash(description)),
+            use_delegation_cache: false,
+            deadline: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[test]
+    fn test_prune_tasks_keeps_unfinished_tasks() {
+        let old = Utc::now() - chrono::Duration::hours(2);
+        let mut tasks = HashMap::new();
+        for (id, status) in [
+            ("running", TaskRunStatus::Running),
+            ("pending", TaskRunStatus::Pending),
+            ("done", TaskRunStatus::Completed),
+            ("cancelled", TaskRunStatus::Cancelled),
+        ] {
+            let mut task = pending_task(id, id, old);
+            task.status = status;
+            tasks.insert(id.to_string(), task);
+        }
+        tasks.insert("recent".to_string(), {
+            let mut task = pending_task("recent", "recent", Utc::now());
+            task.status = TaskRunStatus::Partial;
+            task
+        });
+
+        prune_tasks(&mut tasks, Utc::now() - chrono::Duration::hours(1));
+        let mut kept: Vec<&str> = tasks.keys().map(String::as_str).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, vec!["pending", "recent", "running"]);
+    }
+
+    #[test]
+    fn test_task_record_round_trips_result() {
+        let mut task = pending_task("unused", "Write docs", Utc::now());
+        task.status = TaskRunStatus::Partial;
+        task.output = Some("## Summary".to_string());
+        task.error = Some("qa: timed out".to_string());
+        task.assigned_agent = Some("coordinator-1".to_string());
+        task.delegations = vec![DelegationResult {
+            role: "qa".to_string(),
+            success: false,
+            output: None,
+            error: Some("timed out".to_string()),
+            duration_ms: 10,
+            tokens_used: 5,
+        }];
+
+        let id = Uuid::new_v4();
+        let response = task_response_from_record(crate::postgres::TaskRecord {
+            id,
+            agent_id: None,
+            description: task.description.clone(),
+            status: task.status.to_string(),
+            result: Some(task_result_json(&task)),
+            tokens_used: Some(5),
+            duration_ms: Some(10),
+            created_at: task.created_at,
+            completed_at: Some(task.updated_at),
+        });
+        assert_eq!(response.task_id, id.to_string());
+        assert_eq!(response.status, TaskRunStatus::Partial);
+        assert_eq!(response.output, task.output);
+        assert_eq!(response.error, task.error);
+        assert_eq!(response.assigned_agent, task.assigned_agent);
+        assert_eq!(response.delegations.len(), 1);
+        assert_eq!(response.delegations[0].error.as_deref(), Some("timed out"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires PostgreSQL (set CCA_TEST_DATABASE_URL)"]
+    async fn test_completed_task_retrievable_after_cleanup() {
+        let config = crate::config::PostgresConfig {
+            url: std::env::var("CCA_TEST_DATABASE_URL").expect("CCA_TEST_DATABASE_URL not set"),
+            ..Default::default()
+        };
+        let state = DaemonState {
+            postgres: Some(Arc::new(PostgresServices::new(&config).await.unwrap())),
+            ..test_state()
+        };
+
+        let task_id = Uuid::new_v4().to_string();
+        let mut task = pending_task(&task_id, "Add a health check", Utc::now());
+        persist_task_created(&state, &task_id, &task.description, task.status).await;
+        task.status = TaskRunStatus::Completed;
+        task.output = Some("Added /healthz".to_string());
+        state.tasks.write().await.insert(task_id.clone(), task);
+        persist_task_result(&state, &task_id).await;
+
+        // Cleanup with a cutoff in the future drops every finished task
+        prune_tasks(&mut *state.tasks.write().await, Utc::now() + chrono::Duration::hours(1));
+        assert!(state.tasks.read().await.is_empty());
+
+        let Json(response) = get_task(State(state.clone()), Path(task_id.clone())).await.unwrap();
+        assert_eq!(response.task_id, task_id);
+        assert_eq!(response.status, TaskRunStatus::Completed);
+        assert_eq!(response.output.as_deref(), Some("Added /healthz"));
+
+        let missing = get_task(State(state), Path(Uuid::new_v4().to_string())).await;
+        assert!(missing.is_err());
+    }
+
+    fn history_query(status: Option<&str>, agent: Option<&str>, limit: u32) -> TaskHistoryQuery {
+        TaskHistoryQuery {
+            status: status.map(str::to_string),
+            agent: agent.map(str::to_string),
+            limit,
+            offset: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_history_validates_filters() {
+        use axum::response::IntoResponse;
+
+        let history = |query| get_task_history(State(test_state()), axum::extract::Query(query));
+
+        let err = history(history_query(Some("done"), None, 10)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+        let err = history(history_query(None, Some(""), 10)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+        let err = history(history_query(None, None, MAX_TASK_HISTORY_LIMIT + 1)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+
+        // Valid filters without PostgreSQL
+        let err = history(history_query(Some("failed"), None, 10)).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires PostgreSQL (set CCA_TEST_DATABASE_URL)"]
+    async fn test_task_history_filters_by_status_and_agent() {
+        let config = crate::config::PostgresConfig {
+            url: std::env::var("CCA_TEST_DATABASE_URL").expect("CCA_TEST_DATABASE_URL not set"),
+            ..Default::default()
+        };
+        let state = DaemonState {
+            postgres: Some(Arc::new(PostgresServices::new(&config).await.unwrap())),
+            ..test_state()
+        };
+
+        // Unique agents keep rows from other runs out of the results
+        let (agent_a, agent_b) = (Uuid::new_v4().to_string(), Uuid::new_v4().to_string());
+        for (agent, status) in [
+            (&agent_a, TaskRunStatus::Completed),
+            (&agent_a, TaskRunStatus::Failed),
+            (&agent_a, TaskRunStatus::Completed),
+            (&agent_b, TaskRunStatus::Completed),
+        ] {
+            let task_id = Uuid::new_v4().to_string();
+            let mut task = pending_task(&task_id, "History test", Utc::now());
+            persist_task_created(&state, &task_id, &task.description, TaskRunStatus::Pending).await;
+            task.status = status;
+            task.assigned_agent = Some(agent.clone());
+            state.tasks.write().await.insert(task_id.clone(), task);
+            persist_task_result(&state, &task_id).await;
+        }
+
+        let history = |status: Option<&str>, agent: &str, limit: u32, offset: u32| {
+            let mut query = history_query(status, Some(agent), limit);
+            query.offset = offset;
+            let state = state.clone();
+            async move {
+                let Json(body) = get_task_history(State(state), axum::extract::Query(query))
+                    .await
+                    .unwrap();
+                body["tasks"].as_array().unwrap().clone()
+            }
+        };
+
+        let all_a = history(None, &agent_a, 50, 0).await;
+        assert_eq!(all_a.len(), 3);
+        assert!(all_a.iter().all(|t| t["assigned_agent"] == agent_a.as_str()));
+        assert_eq!(history(None, &agent_b, 50, 0).await.len(), 1);
+
+        let completed = history(Some("completed"), &agent_a, 50, 0).await;
+        assert_eq!(completed.len(), 2);
+        assert!(completed.iter().all(|t| t["status"] == "completed"));
+        let failed = history(Some("failed"), &agent_a, 50, 0).await;
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0]["description"], "History test");
+        assert!(history(Some("cancelled"), &agent_a, 50, 0).await.is_empty());
+
+        // Pages don't overlap
+        let first = history(None, &agent_a, 2, 0).await;
+        let rest = history(None, &agent_a, 2, 2).await;
+        assert_eq!((first.len(), rest.len()), (2, 1));
+        assert!(first.iter().all(|t| t["task_id"] != rest[0]["task_id"]));
+    }
+
+    /// Connect a WebSocket client to `acp` and return it with its agent id
+    async fn connect_acp_client(
+        acp: &AcpServer,
+        addr: std::net::SocketAddr,
+    ) -> (
+        tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+        AgentId,
+    ) {
+        let before = acp.connected_agents().await;
+        let mut attempts = 0;
+        let ws = loop {
+            match tokio_tungstenite::connect_async(format!("ws://{addr}")).await {
+                Ok((ws, _)) => break ws,
+                // The server may still be binding
+                Err(_) if attempts < 50 => {
+                    attempts += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                }
+                Err(e) => panic!("failed to connect to ACP server: {e}"),
+            }
+        };
+        for _ in 0..100 {
+            if let Some(id) = acp
+                .connected_agents()
+                .await
+                .into_iter()
+                .find(|id| !before.contains(id))
+            {
+                return (ws, id);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("ACP connection was never registered");
+    }
+
+    #[tokio::test]
+    async fn test_acp_relay_delivers_between_agents() {
+        use futures_util::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let acp = Arc::new(AcpServer::new(addr));
+        let server = {
+            let acp = acp.clone();
+            tokio::spawn(async move { acp.run().await })
+        };
+
+        let (_sender_ws, sender) = connect_acp_client(&acp, addr).await;
+        let (mut receiver_ws, receiver) = connect_acp_client(&acp, addr).await;
+        let state = DaemonState {
+            acp_server: acp.clone(),
+            ..test_state()
+        };
+
+        let request = AcpRelayRequest {
+            from: sender.to_string(),
+            to: receiver.to_string(),
+            message: "Schema migration is done, you can start on the API".to_string(),
+        };
+        let Json(json) = acp_relay(State(state.clone()), Json(request)).await.unwrap();
+        assert_eq!(json["success"], true);
+        assert_eq!(json["delivered"], true);
+
+        let relayed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                match receiver_ws.next().await {
+                    Some(Ok(Message::Text(text))) => {
+                        let msg: cca_acp::AcpMessage = serde_json::from_str(&text).unwrap();
+                        if msg.method.as_deref() == Some(cca_acp::methods::SEND_MESSAGE) {
+                            break msg;
+                        }
+                    }
+                    Some(Ok(_)) => continue,
+                    other => panic!("expected the relayed message, got {other:?}"),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        let params: cca_acp::SendMessageParams =
+            serde_json::from_value(relayed.params.unwrap()).unwrap();
+        assert_eq!(params.from, Some(sender.to_string()));
+        assert_eq!(params.to, receiver.to_string());
+        assert_eq!(params.content, "Schema migration is done, you can start on the API");
+        assert_eq!(params.metadata["relayed_by"], "daemon");
+
+        // A disconnected target is reported as undelivered
+        let missing = AgentId::new();
+        let request = AcpRelayRequest {
+            from: sender.to_string(),
+            to: missing.to_string(),
+            message: "hello".to_string(),
+        };
+        let Json(json) = acp_relay(State(state), Json(request)).await.unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["delivered"], false);
+        assert!(json["error"].as_str().unwrap().contains("not connected"));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_acp_relay_validation() {
+        let relay = |from: String, to: String, message: &str| {
+            acp_relay(
+                State(test_state()),
+                Json(AcpRelayRequest {
+                    from,
+                    to,
+                    message: message.to_string(),
+                }),
+            )
+        };
+        let (a, b) = (AgentId::new().to_string(), AgentId::new().to_string());
+
+        assert_eq!(relay("nope".into(), b.clone(), "hi").await.unwrap_err().field, "from");
+        assert_eq!(relay(a.clone(), a.clone(), "hi").await.unwrap_err().code, "same_agent");
+        let too_long = "x".repeat(crate::validation::MAX_BROADCAST_MESSAGE_LEN + 1);
+        assert_eq!(relay(a.clone(), b.clone(), &too_long).await.unwrap_err().field, "message");
+        assert_eq!(relay(a.clone(), b.clone(), "\u{0}\u{1}").await.unwrap_err().code, "empty");
+        // The sender must be connected
+        assert_eq!(relay(a, b, "hi").await.unwrap_err().code, "not_connected");
+    }
+
+    fn acp_broadcast(sent: usize, dropped: usize, disconnected: Vec<AgentId>) -> cca_acp::BroadcastResult {
+        cca_acp::BroadcastResult {
+            sent,
+            dropped,
+            disconnected,
+        }
+    }
+
+    #[test]
+    fn test_broadcast_result_acp_only_success() {
+        let slow = AgentId::new();
+        let acp = Ok(acp_broadcast(2, 1, vec![slow]));
+        let json = broadcast_result_json(&acp, None);
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["agents_notified"], 2);
+        assert_eq!(json["acp"]["dropped"], 1);
+        assert_eq!(json["acp"]["disconnected"][0], slow.to_string());
+        assert!(json["acp"]["error"].is_null());
+        assert_eq!(json["redis"]["enabled"], false);
+        assert_eq!(json["redis"]["published"], false);
+    }
+
+    #[test]
+    fn test_broadcast_result_redis_only_success() {
+        let acp = Ok(acp_broadcast(0, 0, Vec::new()));
+        let json = broadcast_result_json(&acp, Some(&Ok(())));
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["agents_notified"], 0);
+        assert_eq!(json["redis"]["enabled"], true);
+        assert_eq!(json["redis"]["published"], true);
+        assert!(json["redis"]["error"].is_null());
+    }
+
+    #[test]
+    fn test_broadcast_result_total_failure() {
+        let acp = Err("serialization failed".to_string());
+        let json = broadcast_result_json(&acp, Some(&Err("connection refused".to_string())));
+
+        assert_eq!(json["success"], false);
+        assert_eq!(json["agents_notified"], 0);
+        assert_eq!(json["acp"]["error"], "serialization failed");
+        assert_eq!(json["redis"]["published"], false);
+        assert_eq!(json["redis"]["error"], "connection refused");
+
+        // Every ACP send dropped and no Redis is a failure too
+        let acp = Ok(acp_broadcast(0, 3, Vec::new()));
+        let json = broadcast_result_json(&acp, None);
+        assert_eq!(json["success"], false);
+        assert_eq!(json["acp"]["dropped"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_with_no_channels_reports_failure() {
+        let request = BroadcastRequest {
+            message: "Deploy starting".to_string(),
+        };
+        let Json(json) = broadcast_all(State(test_state()), Json(request)).await.unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["acp"]["sent"], 0);
+        assert_eq!(json["redis"]["enabled"], false);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_gauges_reflect_live_state() {
+        let state = test_state();
+        {
+            let mut tasks = state.tasks.write().await;
+            tasks.insert("t1".to_string(), pending_task("t1", "In flight", Utc::now()));
+            let mut done = pending_task("t2", "Done", Utc::now());
+            done.status = TaskRunStatus::Completed;
+            tasks.insert("t2".to_string(), done);
+        }
+        state
+            .busy_agents
+            .write()
+            .await
+            .insert(AgentId::new(), "t1".to_string());
+
+        let (_, body) = prometheus_metrics(State(state)).await;
+        assert!(body.contains("cca_tasks_in_flight 1"), "{body}");
+        assert!(body.contains("cca_agents_busy 1"), "{body}");
+        assert!(body.contains("cca_acp_connections 0"), "{body}");
+    }
+
+    #[test]
+    fn test_task_stats_json_shape() {
+        let stats = crate::postgres::TaskStats {
+            total_tasks: 10,
+            completed_tasks: 7,
+            failed_tasks: 2,
+            total_tokens: 12_000,
+            avg_duration_ms: 1500.0,
+        };
+        let by_role = vec![crate::postgres::RoleTaskStats {
+            role: "backend".to_string(),
+            delegations: 5,
+            successful: 4,
+            total_tokens: 8_000,
+            avg_duration_ms: 900.0,
+        }];
+
+        let json = task_stats_json(&stats, &by_role);
+        assert_eq!(json["success"], true);
+        assert_eq!(json["total_tasks"], 10);
+        assert_eq!(json["completed_tasks"], 7);
+        assert_eq!(json["failed_tasks"], 2);
+        assert_eq!(json["total_tokens"], 12_000);
+        assert_eq!(json["avg_duration_ms"], 1500.0);
+        assert_eq!(json["by_role"][0]["role"], "backend");
+        assert_eq!(json["by_role"][0]["delegations"], 5);
+        assert_eq!(json["by_role"][0]["failed"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_task_stats_requires_postgres() {
+        use axum::response::IntoResponse;
+
+        let err = get_task_stats(State(test_state())).await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires PostgreSQL (set CCA_TEST_DATABASE_URL)"]
+    async fn test_task_stats_returns_repository_stats() {
+        let config = crate::config::PostgresConfig {
+            url: std::env::var("CCA_TEST_DATABASE_URL").expect("CCA_TEST_DATABASE_URL not set"),
+            ..Default::default()
+        };
+        let postgres = Arc::new(PostgresServices::new(&config).await.unwrap());
+        let state = DaemonState {
+            postgres: Some(postgres.clone()),
+            ..test_state()
+        };
+
+        // A role unique to this run, so its breakdown is exact
+        let role = format!("stats-{}", Uuid::new_v4());
+        let task_id = Uuid::new_v4().to_string();
+        let mut task = pending_task(&task_id, "Stats test", Utc::now());
+        persist_task_created(&state, &task_id, &task.description, TaskRunStatus::Pending).await;
+        task.status = TaskRunStatus::Partial;
+        task.delegations = [(true, 100), (false, 50)]
+            .into_iter()
+            .map(|(success, tokens_used)| DelegationResult {
+                role: role.clone(),
+                success,
+                output: None,
+                error: None,
+                duration_ms: 200,
+                tokens_used,
+            })
+            .collect();
+        state.tasks.write().await.insert(task_id.clone(), task);
+        persist_task_result(&state, &task_id).await;
+
+        let Json(body) = get_task_stats(State(state)).await.unwrap();
+        let stats = postgres.tasks.get_stats().await.unwrap();
+        assert_eq!(body["success"], true);
+        assert_eq!(body["total_tasks"], stats.total_tasks);
+        assert_eq!(body["completed_tasks"], stats.completed_tasks);
+        assert_eq!(body["failed_tasks"], stats.failed_tasks);
+        assert_eq!(body["total_tokens"], stats.total_tokens);
+        assert!(body["avg_duration_ms"].is_number());
+
+        let entry = body["by_role"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["role"] == role.as_str())
+            .unwrap();
+        assert_eq!(entry["delegations"], 2);
+        assert_eq!(entry["successful"], 1);
+        assert_eq!(entry["failed"], 1);
+        assert_eq!(entry["total_tokens"], 150);
+        assert_eq!(entry["avg_duration_ms"], 200.0);
+    }
+
+    fn dedupe_request(description: &str) -> CreateTaskRequest {
+        CreateTaskRequest {
+            description: description.to_string(),
+            priority: None,
+            depends_on: Vec::new(),
+            run_on_dependency_failure: false,
+            dedupe: true,
+            use_delegation_cache: false,
+            idempotency_key: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_returns_running_duplicate() {
+        let state = test_state();
+        let existing = pending_task("task-1", "Fix the login   bug", Utc::now());
+        state.tasks.write().await.insert("task-1".to_string(), existing);
+
+        let request = dedupe_request("  fix the LOGIN bug ");
+        let Json(response) =
+            run_new_task(state.clone(), RequestId::new(), request, "normal".to_string()).await;
+        assert_eq!(response.task_id, "task-1");
+        assert_eq!(response.status, TaskRunStatus::Running);
+        assert_eq!(state.tasks.read().await.len(), 1);
+    }
+
+    #[test]
+    fn test_task_progress_updates_running_tasks_only() {
+        let mut tasks = HashMap::from([
+            ("task-1".to_string(), pending_task("task-1", "Build it", Utc::now())),
+            ("task-2".to_string(), pending_task("task-2", "Done already", Utc::now())),
+        ]);
+        tasks.get_mut("task-2").unwrap().status = TaskRunStatus::Completed;
+
+        let report = |task_id: &str| cca_acp::TaskProgressParams {
+            task_id: task_id.to_string(),
+            percent: 40,
+            message: "Compiling".to_string(),
+        };
+        assert!(apply_task_progress(&mut tasks, report("task-1")));
+        assert!(!apply_task_progress(&mut tasks, report("task-2")));
+        assert!(!apply_task_progress(&mut tasks, report("missing")));
+
+        let progress = tasks["task-1"].progress.as_ref().unwrap();
+        assert_eq!((progress.percent, progress.message.as_str()), (40, "Compiling"));
+        assert!(tasks["task-2"].progress.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_replays_create_task() {
+        let state = test_state();
+        let create = |key: &'static str| {
+            let state = state.clone();
+            let mut headers = HeaderMap::new();
+            headers.insert("X-API-Key", HeaderValue::from_static("client-a"));
+            headers.insert("Idempotency-Key", HeaderValue::from_static(key));
+            let mut request = dedupe_request("Refactor the billing module");
+            request.dedupe = false;
+            async move {
+                create_task(State(state), Extension(RequestId::new()), headers, Json(request))
+                    .await
+                    .unwrap()
+                    .1
+                    .0
+            }
+        };
+
+        let first = create("retry-1").await;
+        let replay = create("retry-1").await;
+        assert_eq!(
+            serde_json::to_value(&replay).unwrap(),
+            serde_json::to_value(&first).unwrap()
+        );
+        assert_eq!(state.tasks.read().await.len(), 1);
+
+        let other = create("retry-2").await;
+        assert_ne!(other.task_id, first.task_id);
+        assert_eq!(state.tasks.read().await.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_task_with_dependencies_is_accepted_and_waits_in_background() {
+        let state = test_state();
+        state
+            .tasks
+            .write()
+            .await
+            .insert("dep".to_string(), pending_task("dep", "Migrate the schema", Utc::now()));
+
+        let mut request = dedupe_request("Backfill the new column");
+        request.depends_on = vec!["dep".to_string()];
+        let (status, Json(response)) =
+            create_task(State(state.clone()), Extension(RequestId::new()), HeaderMap::new(), Json(request))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(response.status, TaskRunStatus::Pending);
+
+        tokio::time::sleep(DEPENDENCY_POLL_INTERVAL * 2).await;
+        assert_eq!(state.tasks.read().await[&response.task_id].status, TaskRunStatus::Pending);
+
+        state.tasks.write().await.get_mut("dep").unwrap().status = TaskRunStatus::Failed;
+        tokio::time::sleep(DEPENDENCY_POLL_INTERVAL * 2).await;
+        let tasks = state.tasks.read().await;
+        let task = &tasks[&response.task_id];
+        assert_eq!(task.status, TaskRunStatus::Failed);
+        assert_eq!(task.error.as_deref(), Some("Dependency dep failed"));
+    }
+
+    #[tokio::test]
+    async fn test_register_orchestrator_agent_validates_and_reports_replacement() {
+        let state = test_state();
+        let agent_id = Uuid::new_v4().to_string();
+        let request = |capabilities: Vec<String>, max_tasks| RegisterOrchestratorAgentRequest {
+            agent_id: agent_id.clone(),
+            role: "Backend".to_string(),
+            capabilities,
+            max_tasks,
+        };
+
+        let err = register_orchestrator_agent(State(state.clone()), Json(request(vec!["x".repeat(65)], None)))
+            .await
+            .unwrap_err();
+        assert_eq!((err.field.as_ref(), err.code.as_ref()), ("capabilities", "too_long"));
+        let err = register_orchestrator_agent(State(state.clone()), Json(request(Vec::new(), Some(0))))
+            .await
+            .unwrap_err();
+        assert_eq!(err.field, "max_tasks");
+
+        let Json(first) = register_orchestrator_agent(State(state.clone()), Json(request(Vec::new(), Some(3))))
+            .await
+            .unwrap();
+        assert_eq!((first["role"].as_str(), first["replaced"].as_bool()), (Some("backend"), Some(false)));
+        let Json(second) = register_orchestrator_agent(State(state), Json(request(Vec::new(), None)))
+            .await
+            .unwrap();
+        assert_eq!(second["replaced"], true);
+    }
+
+    #[tokio::test]
+    async fn test_template_task_request_is_validated() {
+        let request = TemplateTaskRequest {
+            template: String::new(),
+            params: HashMap::new(),
+            priority: None,
+        };
+        let err = create_task_from_template(State(test_state()), Extension(RequestId::new()), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(err.field, "template");
+    }
+
+    #[tokio::test]
+    async fn test_unregister_orchestrator_agent_statuses() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let acp = Arc::new(AcpServer::new(addr));
+        let server = {
+            let acp = acp.clone();
+            tokio::spawn(async move { acp.run().await })
+        };
+        let (_ws, connected) = connect_acp_client(&acp, addr).await;
+        let state = DaemonState {
+            acp_server: acp,
+            ..test_state()
+        };
+        let unregister = |agent_id: String| unregister_orchestrator_agent(State(state.clone()), Path(agent_id));
+
+        assert_eq!(unregister("nope".into()).await.unwrap_err().0, StatusCode::BAD_REQUEST);
+        let offline = AgentId::new();
+        assert_eq!(unregister(offline.to_string()).await.unwrap_err().0, StatusCode::NOT_FOUND);
+
+        let orchestrator = state.orchestrator.read().await;
+        orchestrator.register_agent(offline, "backend".to_string(), vec![], 5).await;
+        orchestrator.register_agent(connected, "backend".to_string(), vec![], 5).await;
+        drop(orchestrator);
+
+        // A connected agent would just be re-registered, so it is refused
+        assert_eq!(unregister(connected.to_string()).await.unwrap_err().0, StatusCode::CONFLICT);
+        assert!(state.orchestrator.read().await.is_agent_registered(connected).await);
+
+        let Json(json) = unregister(offline.to_string()).await.unwrap();
+        assert_eq!(json["success"], true);
+        assert!(!state.orchestrator.read().await.is_agent_registered(offline).await);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pruned_dependency_counts_as_finished() {
+        let state = test_state();
+        state
+            .tasks
+            .write()
+            .await
+            .insert("task".to_string(), pending_task("task", "Deploy", Utc::now()));
+
+        // Without a task history, a dependency that is no longer in memory
+        // was pruned after finishing
+        let result = wait_for_dependencies(&state, "task", &["pruned".to_string()], false).await;
+        assert_eq!(result, Ok(()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dependency_wait_outlasts_agent_timeout_unless_capped() {
+        let mut state = test_state();
+        {
+            let mut tasks = state.tasks.write().await;
+            tasks.insert("task".to_string(), pending_task("task", "Run tests", Utc::now()));
+            tasks.insert("dep".to_string(), pending_task("dep", "Long migration", Utc::now()));
+        }
+        let agent_timeout = state.reloadable_config.read().await.default_timeout_seconds;
+
+        // A long-running dependency is waited for past the agent timeout
+        let deps = ["dep".to_string()];
+        let wait = wait_for_dependencies(&state, "task", &deps, false);
+        let waited = tokio::time::timeout(std::time::Duration::from_secs(agent_timeout * 2), wait).await;
+        assert!(waited.is_err(), "still waiting while the dependency runs");
+
+        state.config.agents.dependency_wait_timeout_seconds = 10;
+        let result = wait_for_dependencies(&state, "task", &deps, false).await;
+        assert_eq!(result, Err("Timed out after 10s waiting for dependencies".to_string()));
+    }
+
+    #[test]
+    fn test_dedupe_misses_outside_window_and_finished_tasks() {
+        let now = Utc::now();
+        let hash = task_content_hash("Fix the login bug");
+        let since = now - chrono::Duration::seconds(300);
+
+        // Created before the window opened
+        let old = pending_task("old", "Fix the login bug", now - chrono::Duration::seconds(600));
+        let mut tasks = HashMap::from([("old".to_string(), old)]);
+        assert!(find_duplicate_task(&tasks, hash, since).is_none());
+
+        // Inside the window but already finished
+        let mut done = pending_task("done", "Fix the login bug", now);
+        done.status = TaskRunStatus::Completed;
+        tasks.insert("done".to_string(), done);
+        assert!(find_duplicate_task(&tasks, hash, since).is_none());
+
+        // Different description
+        tasks.insert("other".to_string(), pending_task("other", "Fix the signup bug", now));
+        assert!(find_duplicate_task(&tasks, hash, since).is_none());
+
+        tasks.insert("new".to_string(), pending_task("new", "fix the login bug", now));
+        assert_eq!(find_duplicate_task(&tasks, hash, since).unwrap().task_id, "new");
+    }
+
+    async fn delete_agent(
+        state: &DaemonState,
+        agent_id: AgentId,
+        force: bool,
+    ) -> Result<serde_json::Value, (StatusCode, serde_json::Value)> {
+        stop_agent(
+            State(state.clone()),
+            Path(agent_id.to_string()),
+            axum::extract::Query(StopAgentQuery { force }),
+        )
+        .await
+        .map(|Json(body)| body)
+        .map_err(|(status, Json(body))| (status, body))
+    }
+
+    #[tokio::test]
+    async fn test_config_reload_swaps_rate_limiter_and_cors() {
+        let state = test_state();
+        let origin = HeaderValue::from_static("https://app.example.com");
+        assert!(!state.cors_policy.read().unwrap().allows_origin(&origin));
+
+        let mut new_config = state.config.clone();
+        new_config.daemon.api_keys = vec!["test-key".to_string()];
+        new_config.daemon.rate_limit_rps = 5;
+        new_config.daemon.cors_origins = vec!["https://app.example.com".to_string()];
+        new_config.daemon.bind_address = "0.0.0.0:9999".to_string();
+
+        let changes = apply_config_reload(&state, &new_config).await.unwrap();
+        assert!(changes.applied.contains(&"rate_limit_rps".to_string()));
+        assert_eq!(changes.restart_required, vec!["daemon.bind_address"]);
+        assert!(state.rate_limiter.read().unwrap().is_some());
+        assert!(state.cors_policy.read().unwrap().allows_origin(&origin));
+
+        // Invalid configs are rejected without touching the running settings
+        new_config.daemon.rate_limit_rps = 0;
+        new_config.daemon.cors_origins.clear();
+        assert!(apply_config_reload(&state, &new_config).await.is_err());
+        assert_eq!(state.reloadable_config.read().await.rate_limit_rps, 5);
+        assert!(state.rate_limiter.read().unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stop_agent_refuses_busy_agent_without_force() {
+        let state = test_state();
+        let agent_id = state.agent_manager.write().await.spawn(AgentRole::Backend).await.unwrap();
+        state.busy_agents.write().await.insert(agent_id, "Migrate the schema".to_string());
+
+        let (status, body) = delete_agent(&state, agent_id, false).await.unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["current_task"], "Migrate the schema");
+
+        // Nothing was torn down
+        assert!(state.agent_manager.read().await.get(agent_id).is_some());
+        assert!(state.busy_agents.read().await.contains_key(&agent_id));
+    }
+
+    #[tokio::test]
+    async fn test_stop_agent_with_force_tears_down_busy_agent() {
+        let state = test_state();
+        let agent_id = state.agent_manager.write().await.spawn(AgentRole::Backend).await.unwrap();
+        state.busy_agents.write().await.insert(agent_id, "Migrate the schema".to_string());
+
+        let body = delete_agent(&state, agent_id, true).await.unwrap();
+        assert_eq!(body["success"], true);
+        assert_eq!(body["was_busy"], true);
+        assert_eq!(body["cleaned_up"], serde_json::json!(["agent_manager", "busy_agents"]));
+
+        assert!(state.agent_manager.read().await.get(agent_id).is_none());
+        assert!(state.busy_agents.read().await.is_empty());
+
+        // Already gone
+        let (status, _) = delete_agent(&state, agent_id, true).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_stop_idle_agent_needs_no_force() {
+        let state = test_state();
+        let agent_id = state.agent_manager.write().await.spawn(AgentRole::QA).await.unwrap();
+
+        let body = delete_agent(&state, agent_id, false).await.unwrap();
+        assert_eq!(body["was_busy"], false);
+        assert!(state.agent_manager.read().await.get(agent_id).is_none());
+    }
+
+    #[test]
+    fn test_describe_workers_lists_capabilities() {
+        let capabilities = cca_acp::AgentCapabilities {
+            model: Some("claude-sonnet-4".to_string()),
+            max_context: Some(200_000),
+            supported_tools: vec!["Read".to_string(), "Grep".to_string()],
+        };
+        let agents = vec![
+            (AgentId::new(), Some("coordinator".to_string()), None),
+            (AgentId::new(), Some("backend".to_string()), Some(capabilities)),
+            (AgentId::new(), Some("qa".to_string()), None),
+            (AgentId::new(), None, None),
+        ];
+
+        assert_eq!(
+            describe_workers(&agents),
+            vec![
+                "backend (model: claude-sonnet-4, max context: 200000 tokens, tools: Read, Grep)".to_string(),
+                "qa".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_structured_delegations_match_results() {
+        let delegations = vec![
+            delegation("backend", "Add the endpoint"),
+            delegation("qa", "Test the endpoint"),
+        ];
+        let results = vec![
+            DelegateTaskResponse {
+                success: true,
+                agent_id: AgentId::new().to_string(),
+                role: "backend".to_string(),
+                output: Some("Endpoint added".to_string()),
+                error: None,
+                duration_ms: 1200,
+                tokens_used: 800,
+            },
+            DelegateTaskResponse {
+                success: false,
+                agent_id: AgentId::new().to_string(),
+                role: "qa".to_string(),
+                output: None,
+                error: Some("Agent timed out".to_string()),
+                duration_ms: 30_000,
+                tokens_used: 0,
+            },
+        ];
+
+        let (output, all_success, errors) = combine_delegation_results(None, &delegations, &results);
+        let response = TaskResponse {
+            task_id: "task-1".to_string(),
+            status: TaskRunStatus::Partial,
+            output: Some(output),
+            error: Some(errors.join("; ")),
+            assigned_agent: None,
+            delegations: results.iter().map(DelegationResult::from).collect(),
+            progress: None,
+        };
+        assert!(!all_success);
+
+        let json = serde_json::to_value(&response).unwrap();
+        let structured = json["delegations"].as_array().unwrap();
+        assert_eq!(structured.len(), results.len());
+        for (entry, result) in structured.iter().zip(&results) {
+            assert_eq!(entry["role"], result.role.as_str());
+            assert_eq!(entry["success"], result.success);
+            assert_eq!(entry["output"].as_str(), result.output.as_deref());
+            assert_eq!(entry["error"].as_str(), result.error.as_deref());
+            assert_eq!(entry["duration_ms"], result.duration_ms);
+            assert_eq!(entry["tokens_used"], result.tokens_used);
+        }
+
+        // The combined markdown is still there for existing clients
+        let output = json["output"].as_str().unwrap();
+        assert!(output.contains("## backend Agent\nEndpoint added"));
+        assert!(output.contains("Error: Agent timed out"));
+    }
+
+    #[test]
+    fn test_extract_json_ignores_braces_in_strings() {
+        let output = r#"Plan: {"action":"delegate","summary":"Handle } and { in \"quoted\" text"} done"#;
+        let json = extract_json_from_output(output).unwrap();
+        assert_eq!(
+            json,
+            r#"{"action":"delegate","summary":"Handle } and { in \"quoted\" text"}"#
+        );
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+
+        let fenced = "```json\n{\"action\":\"error\",\"error\":\"missing }\"}\n```";
+        assert_eq!(
+            extract_json_from_output(fenced).unwrap(),
+            r#"{"action":"error","error":"missing }"}"#
+        );
+    }
+
+    #[test]
+    fn test_extract_json_rejects_unbalanced_braces() {
+        assert_eq!(extract_json_from_output(r#"{"action":"delegate","delegations":[{"role":"qa""#), None);
+        assert_eq!(extract_json_from_output("```json\n{\"action\": {\n```"), None);
+        assert_eq!(extract_json_from_output("no json here"), None);
+    }
+
+    #[test]
+    fn test_extract_json_bails_on_huge_input() {
+        let huge = format!("{{\"output\": \"{}", "x".repeat(8 * 1024 * 1024));
+        let start = std::time::Instant::now();
+        assert_eq!(extract_json_from_output(&huge), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // Objects past the scan limit aren't found either
+        let late = format!("{{{}}}", " ".repeat(MAX_JSON_SCAN_LEN));
+        assert_eq!(extract_json_from_output(&late), None);
+    }
+
+    /// Coordinator stand-in that replies with `replies` in order, recording each task it gets
+    fn mock_coordinator(
+        replies: Vec<&'static str>,
+    ) -> (
+        Arc<std::sync::Mutex<Vec<String>>>,
+        impl FnMut(String) -> std::future::Ready<Result<cca_acp::TaskResponse>>,
+    ) {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = received.clone();
+        let mut replies = replies.into_iter();
+        let send = move |task: String| {
+            seen.lock().unwrap().push(task);
+            std::future::ready(Ok(cca_acp::TaskResponse {
+                output: replies.next().unwrap_or_default().to_string(),
+                tokens_used: 0,
+                success: true,
+            }))
+        };
+        (received, send)
+    }
+
+    const VALID_DECISION: &str =
+        r#"{"action":"delegate","delegations":[{"role":"backend","task":"Add it"}]}"#;
+
+    #[tokio::test]
+    async fn test_coordinator_retried_after_invalid_json() {
+        let (received, send) =
+            mock_coordinator(vec!["Sure! I'll hand this to the backend team.", VALID_DECISION]);
+
+        let (response, decision) = request_coordinator_decision("Add an endpoint", 1, send)
+            .await
+            .unwrap();
+
+        let decision = decision.expect("retry should produce a decision");
+        assert_eq!(decision.action, "delegate");
+        assert_eq!(decision.delegations[0].role, "backend");
+        assert_eq!(response.output, VALID_DECISION);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], "Add an endpoint");
+        assert!(received[1].starts_with("Add an endpoint"));
+        assert!(received[1].ends_with(COORDINATOR_JSON_REMINDER));
+    }
+
+    /// Specialist stand-in: every delegation succeeds with "<role> did: <task>"
+    fn mock_specialists(
+        rounds: &Arc<std::sync::Mutex<Vec<Vec<String>>>>,
+    ) -> impl FnMut(Vec<CoordinatorDelegation>) -> std::future::Ready<Vec<DelegateTaskResponse>> {
+        let rounds = rounds.clone();
+        move |delegations: Vec<CoordinatorDelegation>| {
+            rounds.lock().unwrap().push(delegations.iter().map(|d| d.task.clone()).collect());
+            std::future::ready(
+                delegations
+                    .iter()
+                    .map(|d| DelegateTaskResponse {
+                        success: true,
+                        agent_id: "agent-1".to_string(),
+                        role: d.role.clone(),
+                        output: Some(format!("{} did: {}", d.role, d.task)),
+                        error: None,
+                        duration_ms: 10,
+                        tokens_used: 0,
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_finalizes_after_seeing_results() {
+        let (received, ask) =
+            mock_coordinator(vec![r#"{"action":"final","response":"The endpoint is in place."}"#]);
+        let rounds = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let first = parse_coordinator_response(VALID_DECISION).unwrap();
+
+        let outcome =
+            run_coordination_rounds("Add an endpoint", first, 3, 0, ask, mock_specialists(&rounds)).await;
+
+        assert_eq!(outcome.rounds, 1);
+        assert_eq!(outcome.final_response.as_deref(), Some("The endpoint is in place."));
+        assert_eq!(outcome.delegations.len(), 1);
+        assert_eq!(outcome.results[0].output.as_deref(), Some("backend did: Add it"));
+        assert_eq!(*rounds.lock().unwrap(), vec![vec!["Add it".to_string()]]);
+
+        // The coordinator was shown the specialist's output before finalizing
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].starts_with("Add an endpoint"));
+        assert!(received[0].contains("backend did: Add it"));
+        assert!(received[0].ends_with(COORDINATOR_FOLLOW_UP));
+    }
+
+    #[tokio::test]
+    async fn test_coordination_rounds_are_bounded() {
+        let second = r#"{"action":"delegate","delegations":[{"role":"qa","task":"Test it"}]}"#;
+        let third = r#"{"action":"delegate","delegations":[{"role":"qa","task":"Test it again"}]}"#;
+
+        // A second round runs; the third would exceed max_rounds and is never asked for
+        let (received, ask) = mock_coordinator(vec![second, third]);
+        let rounds = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let first = parse_coordinator_response(VALID_DECISION).unwrap();
+        let outcome = run_coordination_rounds("task", first, 2, 0, ask, mock_specialists(&rounds)).await;
+        assert_eq!(outcome.rounds, 2);
+        assert_eq!(outcome.delegations.len(), 2);
+        assert!(outcome.final_response.is_none());
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        // Repeating the last round's delegations stops the loop early
+        let (received, ask) = mock_coordinator(vec![second, second, second]);
+        let rounds = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let first = parse_coordinator_response(VALID_DECISION).unwrap();
+        let outcome = run_coordination_rounds("task", first, 10, 0, ask, mock_specialists(&rounds)).await;
+        assert_eq!(outcome.rounds, 2);
+        assert_eq!(rounds.lock().unwrap().len(), 2);
+        assert_eq!(received.lock().unwrap().len(), 2);
+
+        // A single round never asks the coordinator again
+        let (received, ask) = mock_coordinator(vec![second]);
+        let first = parse_coordinator_response(VALID_DECISION).unwrap();
+        let outcome = run_coordination_rounds("task", first, 1, 0, ask, mock_specialists(&rounds)).await;
+        assert_eq!(outcome.rounds, 1);
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_probe_service_treats_errors_and_timeouts_as_dead() {
+        let timeout = std::time::Duration::from_secs(2);
+        assert!(probe_service("redis", async { Ok(()) }, timeout).await);
+        assert!(!probe_service("redis", async { Err(anyhow::anyhow!("refused")) }, timeout).await);
+        assert!(!probe_service("postgres", std::future::pending(), timeout).await);
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_check_bypasses_cache() {
+        let state = test_state();
+        // A shallow check cached while services looked fine
+        *state.health_cache.write().await = Some(CachedHealthCheck {
+            response: HealthResponse {
+                status: "healthy",
+                version: env!("CARGO_PKG_VERSION"),
+                services: ServiceHealth { redis: true, postgres: true, acp: true, embeddings: false },
+            },
+            cached_at: std::time::Instant::now(),
+        });
+
+        let Json(shallow) =
+            health_check(State(state.clone()), axum::extract::Query(HealthQuery::default())).await;
+        assert_eq!(shallow.status, "healthy");
+
+        let Json(deep) =
+            health_check(State(state.clone()), axum::extract::Query(HealthQuery { deep: true })).await;
+        assert_eq!(deep.status, "degraded");
+        assert!(!deep.services.redis);
+        assert!(!deep.services.postgres);
+
+        // The deep result isn't cached for load balancers
+        let cached = state.health_cache.read().await;
+        assert_eq!(cached.as_ref().unwrap().response.status, "healthy");
+    }
+
+    #[tokio::test]
+    async fn test_readiness_waits_for_startup_and_configured_services() {
+        let state = test_state();
+        let (status, Json(body)) = readiness_check(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.waiting_on, vec!["startup"]);
+
+        // No Redis or Postgres URL configured: neither is required
+        state.started.store(true, Ordering::Release);
+        let (status, Json(body)) = readiness_check(State(state.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.ready);
+
+        // A configured service that isn't connected keeps the daemon unready
+        let mut config = state.config.clone();
+        config.postgres.url = "postgres://cca@127.0.0.1:1/cca".to_string();
+        let state = DaemonState { config, ..state };
+        let (status, Json(body)) = readiness_check(State(state)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!body.ready);
+        assert_eq!(body.waiting_on, vec!["postgres"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_claude_binary_reports_actionable_error() {
+        let state = test_state();
+        let mut config = Config::default();
+        config.agents.claude_path = "/nonexistent/claude".to_string();
+        *state.agent_manager.write().await = AgentManager::new(&config);
+        let expected =
+            "Claude Code binary not found at /nonexistent/claude; set CCA__AGENTS__CLAUDE_PATH";
+
+        let Json(delegated) = run_delegate_task(
+            state.clone(),
+            DelegateTaskRequest {
+                role: "backend".to_string(),
+                task: "Add it".to_string(),
+                context: None,
+                timeout_seconds: 10,
+                idempotency_key: None,
+            },
+        )
+        .await;
+        assert!(!delegated.success);
+        assert!(delegated.error.unwrap().contains(expected));
+
+        let agent_id = delegated.agent_id;
+        let Json(sent) = send_to_agent(
+            State(state.clone()),
+            Path(agent_id.clone()),
+            Json(SendToAgentRequest { message: "Add it".to_string(), timeout_seconds: 10 }),
+        )
+        .await;
+        assert_eq!(sent.error.as_deref(), Some(expected));
+
+        // The failure is recorded in the agent's log too
+        let id = AgentId(Uuid::parse_str(&agent_id).unwrap());
+        let logs = state.agent_manager.read().await.get_logs(id, 10, None);
+        assert!(logs.iter().any(|entry| entry.message.contains(expected)));
+    }
+
+    #[tokio::test]
+    async fn test_claude_usage_feeds_token_metrics() {
+        let state = test_state();
+        let agent_id = AgentId::new();
+        let parsed = parse_claude_output(
+            r#"{"result":"ok","usage":{"input_tokens":1200,"output_tokens":345}}"#,
+        );
+
+        record_claude_usage(&state, agent_id, &parsed).await;
+        let metrics = state.token_service.metrics.get_agent_metrics(agent_id).await.unwrap();
+        assert_eq!(metrics.total_input, 1200);
+        assert_eq!(metrics.total_output, 345);
+
+        // Text output carries no usage and records nothing
+        let other = AgentId::new();
+        record_claude_usage(&state, other, &parse_claude_output("ok")).await;
+        assert!(state.token_service.metrics.get_agent_metrics(other).await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_later_delegations_get_reduced_timeouts() {
+        use std::time::Duration;
+        let default = Duration::from_secs(600);
+        let start = tokio::time::Instant::now();
+        let deadline = Some(start + Duration::from_secs(900));
+
+        // The first delegation fits inside the budget and gets the full default
+        assert_eq!(delegation_timeout(default, deadline, start), Ok(default));
+
+        // Later ones only get what's left of the task's deadline
+        tokio::time::advance(Duration::from_secs(500)).await;
+        let now = tokio::time::Instant::now();
+        assert_eq!(delegation_timeout(default, deadline, now), Ok(Duration::from_secs(400)));
+        tokio::time::advance(Duration::from_secs(350)).await;
+        let now = tokio::time::Instant::now();
+        assert_eq!(delegation_timeout(default, deadline, now), Ok(Duration::from_secs(50)));
+
+        tokio::time::advance(Duration::from_secs(50)).await;
+        let now = tokio::time::Instant::now();
+        assert_eq!(delegation_timeout(default, deadline, now), Err(TaskDeadlineExceeded));
+        assert_eq!(delegation_timeout(default, None, now), Ok(default));
+    }
+
+    #[tokio::test]
+    async fn test_delegations_fail_fast_past_task_deadline() {
+        let state = test_state();
+        let mut task = pending_task("t1", "Add an endpoint", Utc::now());
+        task.deadline = Some(tokio::time::Instant::now());
+        state.tasks.write().await.insert("t1".to_string(), task);
+
+        let delegations = vec![delegation("backend", "Add it"), delegation("qa", "Test it")];
+        let results = execute_delegations(&state, "t1", &delegations, &RequestId::new()).await;
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            assert!(!result.success);
+            assert_eq!(result.error.as_deref(), Some("Task deadline exceeded"));
+        }
+        assert_eq!(results[1].role, "qa");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_task_errors_carry_codes() {
+        use axum::response::IntoResponse;
+
+        let state = test_state();
+        let response = get_task(State(state.clone()), Path("missing".to_string()))
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "not_found");
+
+        let mut task = pending_task("t1", "Add an endpoint", Utc::now());
+        task.status = TaskRunStatus::Completed;
+        state.tasks.write().await.insert("t1".to_string(), task);
+        let response = cancel_task_endpoint(State(state), Path("t1".to_string()))
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_completed_tasks_are_searchable_through_pattern_store() {
+        let store = Arc::new(cca_core::memory::InMemoryPatternStore::new());
+        let state = DaemonState {
+            pattern_store: Some(store.clone()),
+            ..test_state()
+        };
+        let agent_id = AgentId::new();
+        store_task_as_pattern(&state, agent_id, "Backend", "Add retries", "Retry with backoff", 42).await;
+
+        let request = MemorySearchRequest {
+            query: "BACKOFF".to_string(),
+            limit: 10,
+            min_similarity: None,
+            pattern_type: Some("solution".to_string()),
+        };
+        let Json(body) = memory_search(State(state), Json(request)).await.unwrap();
+        assert_eq!(body["search_type"], "text");
+        assert_eq!(body["count"], 1);
+        let pattern = &body["patterns"][0];
+        assert_eq!(pattern["pattern_type"], "solution");
+        assert_eq!(pattern["content"], "Retry with backoff");
+
+        let id = PatternId(pattern["id"].as_str().unwrap().parse().unwrap());
+        let stored = store.get(id).await.unwrap().unwrap();
+        assert_eq!(stored.agent_id, Some(agent_id));
+        assert_eq!(stored.metadata["role"], "backend");
+    }
+
+    /// Pattern store with one embedded pattern that fails `failure_rate` of its operations
+    async fn flaky_pattern_store(failure_rate: f64) -> ChaosPatternStore {
+        use cca_chaos_tests::{ChaosTestable, FaultType};
+
+        let store = ChaosPatternStore::new(Arc::new(cca_core::memory::InMemoryPatternStore::new()), 7);
+        let mut pattern = Pattern::new(MemoryPatternType::Code, "Retry with backoff");
+        pattern.embedding = Some(vec![1.0, 0.0]);
+        store.create(pattern).await.unwrap();
+        store
+            .inject_fault(FaultType::PartialFailure { failure_rate })
+            .await
+            .unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_failing_semantic_search_falls_back_to_text() {
+        let store = flaky_pattern_store(1.0).await;
+        let query = PatternQuery::text("backoff", 5);
+
+        let err = search_patterns(&store, &query, Some(vec![1.0, 0.0]), 0.3, 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("injected failure"));
+
+        // The semantic search failed and the text fallback ran (and failed) after it
+        let metrics = store.get_metrics().await;
+        assert_eq!(metrics.requests_during_chaos, 2);
+        assert_eq!(metrics.successful_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_healthy_store_serves_semantic_search_without_fallback() {
+        let store = flaky_pattern_store(0.0).await;
+        let query = PatternQuery::text("backoff", 5);
+
+        let (matches, search_type) =
+            search_patterns(&store, &query, Some(vec![1.0, 0.0]), 0.3, 0.0).await.unwrap();
+        assert_eq!(search_type, "semantic");
+        assert_eq!(matches.len(), 1);
+
+        let metrics = store.get_metrics().await;
+        assert_eq!(metrics.requests_during_chaos, 1);
+        assert_eq!(metrics.successful_requests, 1);
+    }
+
+    #[test]
+    fn test_rerank_promotes_proven_pattern_over_marginally_closer_one() {
+        let scored = |content: &str, similarity: f64, successes: u32, failures: u32| {
+            let mut pattern = Pattern::new(MemoryPatternType::Code, content);
+            pattern.success_count = successes;
+            pattern.failure_count = failures;
+            SearchMatch { pattern, score: similarity }
+        };
+        let results = || vec![scored("flaky", 0.82, 1, 9), scored("proven", 0.80, 9, 1)];
+        let order = |matches: &[SearchMatch]| -> Vec<String> {
+            matches.iter().map(|m| m.pattern.content.clone()).collect()
+        };
+
+        let mut matches = results();
+        rerank_by_success_rate(&mut matches, 0.0);
+        assert_eq!(order(&matches), ["flaky", "proven"]);
+
+        let mut matches = results();
+        rerank_by_success_rate(&mut matches, 0.2);
+        assert_eq!(order(&matches), ["proven", "flaky"]);
+        assert!((matches[0].score - 0.80).abs() < f64::EPSILON);
+    }
+
+    fn solution_with_embedding(content: &str, embedding: Vec<f32>) -> Pattern {
+        let mut pattern = Pattern::new(MemoryPatternType::Custom("solution".to_string()), content);
+        pattern.embedding = Some(embedding);
+        pattern
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicate_pattern_merges_into_existing() {
+        let store = cca_core::memory::InMemoryPatternStore::new();
+        let first = solution_with_embedding("Retry with backoff", vec![1.0, 0.0]);
+        let outcome = store_or_merge_pattern(&store, first.clone(), 0.95).await.unwrap();
+        assert_eq!(outcome, StoredPattern::Created(first.id));
+
+        let again = solution_with_embedding("Retry with exponential backoff", vec![1.0, 0.05]);
+        let outcome = store_or_merge_pattern(&store, again.clone(), 0.95).await.unwrap();
+        assert_eq!(outcome, StoredPattern::Merged(first.id));
+
+        assert!(store.get(again.id).await.unwrap().is_none());
+        assert_eq!(store.get(first.id).await.unwrap().unwrap().success_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_distinct_pattern_inserts_alongside_existing() {
+        let store = cca_core::memory::InMemoryPatternStore::new();
+        let first = solution_with_embedding("Retry with backoff", vec![1.0, 0.0]);
+        store_or_merge_pattern(&store, first.clone(), 0.95).await.unwrap();
+
+        let other = solution_with_embedding("Add a database index", vec![0.0, 1.0]);
+        let outcome = store_or_merge_pattern(&store, other.clone(), 0.95).await.unwrap();
+        assert_eq!(outcome, StoredPattern::Created(other.id));
+
+        // A disabled threshold inserts even an identical pattern
+        let copy = solution_with_embedding("Retry with backoff", vec![1.0, 0.0]);
+        let outcome = store_or_merge_pattern(&store, copy.clone(), 0.0).await.unwrap();
+        assert_eq!(outcome, StoredPattern::Created(copy.id));
+        assert_eq!(store.get(first.id).await.unwrap().unwrap().success_count, 0);
+    }
+
+    /// State whose backend breaker is open, so dispatching to backend fails fast
+    async fn state_with_cached_task(use_delegation_cache: bool) -> DaemonState {
+        let state = test_state();
+        let orchestrator =
+            Orchestrator::new().with_circuit_breaker(1, std::time::Duration::from_secs(60));
+        orchestrator.record_role_outcome("backend", false).await;
+        *state.orchestrator.write().await = orchestrator;
+
+        let mut task = pending_task("t1", "Add an endpoint", Utc::now());
+        task.use_delegation_cache = use_delegation_cache;
+        state.tasks.write().await.insert("t1".to_string(), task);
+        state
+            .delegation_cache
+            .insert("backend", "Add it", None, "cached output".to_string());
+        state
+    }
+
+    #[tokio::test]
+    async fn test_delegation_cache_hit_returns_stored_output() {
+        let state = state_with_cached_task(true).await;
+        let delegations = vec![delegation("backend", "Add it")];
+
+        let results = execute_delegations(&state, "t1", &delegations, &RequestId::new()).await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].success);
+        assert_eq!(results[0].output.as_deref(), Some("cached output"));
+        assert!(results[0].agent_id.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delegation_cache_miss_dispatches_normally() {
+        // Different task text: not in the cache, so it goes to the (open) breaker
+        let state = state_with_cached_task(true).await;
+        let delegations = vec![delegation("backend", "Add something else")];
+        let results = execute_delegations(&state, "t1", &delegations, &RequestId::new()).await;
+        assert!(!results[0].success);
+        assert!(results[0].output.is_none());
+
+        // Tasks that didn't opt in never read the cache
+        let state = state_with_cached_task(false).await;
+        let delegations = vec![delegation("backend", "Add it")];
+        let results = execute_delegations(&state, "t1", &delegations, &RequestId::new()).await;
+        assert!(!results[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_retries_are_bounded() {
+        let (received, send) = mock_coordinator(vec!["not json", "still not json", "nope"]);
+        let (response, decision) = request_coordinator_decision("Add an endpoint", 1, send)
+            .await
+            .unwrap();
+        assert!(decision.is_none());
+        assert_eq!(response.output, "still not json");
+        assert_eq!(received.lock().unwrap().len(), 2);
+
+        // Valid JSON on the first try, or retries disabled: a single send
+        let (received, send) = mock_coordinator(vec![VALID_DECISION]);
+        let (_, decision) = request_coordinator_decision("task", 3, send).await.unwrap();
+        assert!(decision.is_some());
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let (received, send) = mock_coordinator(vec!["not json", VALID_DECISION]);
+        let (_, decision) = request_coordinator_decision("task", 0, send).await.unwrap();
+        assert!(decision.is_none());
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_join_bounded_caps_concurrency_and_keeps_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let futures = (0..12u64).map(|i| {
+            let (running, peak) = (running.clone(), peak.clone());
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // Later delegations finish first, so completion order differs from input order
+                tokio::time::sleep(std::time::Duration::from_millis(20 - i)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                i
+            }
+        });
+
+        let results = join_bounded(futures, 3).await;
+        assert_eq!(results, (0..12).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_join_bounded_zero_means_unbounded() {
+        let results = join_bounded((0..4).map(std::future::ready), 0).await;
+        assert_eq!(results, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_busy_agent_reused_when_freed_during_wait() {
+        let (busy, other) = (AgentId::new(), AgentId::new());
+        let busy_agents = Arc::new(RwLock::new(HashMap::from([
+            (busy, "Long-running task".to_string()),
+            (other, "Another task".to_string()),
+        ])));
+
+        let releaser = busy_agents.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            releaser.write().await.remove(&busy);
+        });
+
+        let queue = Arc::new(DispatchQueue::new(10, 0.0));
+        let ticket = queue.enqueue("backend", TaskPriority::Normal).unwrap();
+        let start = tokio::time::Instant::now();
+        let found = wait_for_idle_agent(
+            &busy_agents,
+            &[other, busy],
+            &ticket,
+            std::time::Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(found, Some(busy));
+        assert!(start.elapsed() < std::time::Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_freed_agent_goes_to_highest_priority_waiter() {
+        let agent = AgentId::new();
+        let busy_agents = Arc::new(RwLock::new(HashMap::from([(agent, "Task".to_string())])));
+        let queue = Arc::new(DispatchQueue::new(10, 0.0));
+        let low = queue.enqueue("backend", TaskPriority::Low).unwrap();
+        let high = queue.enqueue("backend", TaskPriority::High).unwrap();
+
+        let releaser = busy_agents.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            releaser.write().await.remove(&agent);
+        });
+
+        // The low-priority delegation queued first but must not take the agent
+        let (candidates, wait) = ([agent], std::time::Duration::from_secs(3));
+        let (low_found, high_found) = tokio::join!(
+            wait_for_idle_agent(&busy_agents, &candidates, &low, wait),
+            async {
+                let found = wait_for_idle_agent(&busy_agents, &candidates, &high, wait).await;
+                // Taking the agent marks it busy before the ticket is given up
+                busy_agents.write().await.insert(agent, "High-priority task".to_string());
+                drop(high);
+                found
+            },
+        );
+        assert_eq!(high_found, Some(agent));
+        assert_eq!(low_found, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_busy_agent_wait_times_out() {
+        let agent = AgentId::new();
+        let busy_agents = RwLock::new(HashMap::from([(agent, "Stuck".to_string())]));
+
+        let queue = Arc::new(DispatchQueue::new(10, 0.0));
+        let ticket = queue.enqueue("backend", TaskPriority::Normal).unwrap();
+        let start = tokio::time::Instant::now();
+        let found =
+            wait_for_idle_agent(&busy_agents, &[agent], &ticket, std::time::Duration::from_secs(5))
+                .await;
+        assert_eq!(found, None);
+        assert!(start.elapsed() >= std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_delegations_omitted_when_empty() {
+        let response = TaskResponse {
+            task_id: "task-1".to_string(),
+            status: TaskRunStatus::Failed,
+            output: None,
+            error: Some("No coordinator worker connected".to_string()),
+            assigned_agent: None,
+            delegations: Vec::new(),
+            progress: None,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("delegations").is_none());
+        assert_eq!(json["status"], "failed");
+    }
+
+    #[test]
+    fn test_acp_worker_json_includes_backpressure() {
+        let agent = AgentId::new();
+        let info = cca_acp::ConnectionBackpressureInfo {
+            agent_id: agent,
+            messages_sent: 40,
+            messages_dropped: 3,
+            consecutive_drops: 1,
+            channel_fullness: 0.9,
+            is_warning: true,
+        };
+
+        let json = acp_worker_json(agent, Some("backend"), Some("host-1:backend"), None, Some(&info));
+        assert_eq!(json["role"], "backend");
+        assert_eq!(json["worker_id"], "host-1:backend");
+        assert!((json["channel_fullness"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(json["messages_dropped"], 3);
+        assert_eq!(json["is_warning"], true);
+
+        let idle = acp_worker_json(agent, None, None, None, None);
+        assert_eq!(idle["role"], "unregistered");
+        assert!(idle["worker_id"].is_null());
+        assert_eq!(idle["channel_fullness"], 0.0);
+        assert_eq!(idle["messages_dropped"], 0);
+        assert_eq!(idle["is_warning"], false);
+    }
+
+    #[test]
+    fn test_slow_consumers_only_selects_over_threshold() {
+        let info = |drops: u32, fullness: f32| cca_acp::ConnectionBackpressureInfo {
+            agent_id: AgentId::new(),
+            messages_sent: 10,
+            messages_dropped: u64::from(drops),
+            consecutive_drops: drops,
+            channel_fullness: fullness,
+            is_warning: fullness >= 0.8,
+        };
+        let healthy = info(0, 0.1);
+        let dropping = info(5, 0.3);
+        let backed_up = info(0, 0.95);
+        let metrics = [healthy.clone(), dropping.clone(), backed_up.clone()];
+
+        assert_eq!(slow_consumers(&metrics, Some(5), None), vec![dropping.agent_id]);
+        assert_eq!(slow_consumers(&metrics, None, Some(0.9)), vec![backed_up.agent_id]);
+        assert_eq!(
+            slow_consumers(&metrics, Some(3), Some(0.9)),
+            vec![dropping.agent_id, backed_up.agent_id]
+        );
+        assert!(slow_consumers(&metrics, Some(10), Some(0.99)).is_empty());
+    }
+
+    #[test]
+    fn test_coordinator_context_uses_configured_prompt() {
+        let mut state = test_state();
+        assert!(coordinator_context(&state, &[]).starts_with(DEFAULT_COORDINATOR_SYSTEM_PROMPT));
+
+        state.config.agents.coordinator_system_prompt =
+            "Delegate everything. Output ONLY JSON with \"action\" and \"delegations\".".to_string();
+        let context = coordinator_context(&state, &["backend".to_string()]);
+        assert!(context.starts_with("Delegate everything."));
+        assert!(!context.contains("You are a COORDINATOR agent"));
+        assert!(context.contains("\n\nAvailable workers: backend."));
+    }
+}
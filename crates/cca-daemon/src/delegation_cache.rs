@@ -0,0 +1,93 @@
+//! Delegation result cache
+//!
+//! Tasks created with `use_delegation_cache` reuse the output of an identical
+//! delegation (same role, task text and context) that succeeded within
+//! `agents.delegation_cache_ttl_secs`, instead of sending it to an agent again.
+//! Only successful outputs are cached.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Role plus a hash of the delegated task and its context
+type CacheKey = (String, u64);
+
+struct Entry {
+    stored_at: Instant,
+    output: String,
+}
+
+/// Successful delegation outputs, keyed by role and task hash
+pub struct DelegationCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<CacheKey, Entry>>,
+}
+
+impl DelegationCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Output of an identical delegation that finished within the TTL
+    pub fn get(&self, role: &str, task: &str, context: Option<&str>) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&cache_key(role, task, context))
+            .filter(|entry| entry.stored_at.elapsed() < self.ttl)
+            .map(|entry| entry.output.clone())
+    }
+
+    pub fn insert(&self, role: &str, task: &str, context: Option<&str>, output: String) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.stored_at.elapsed() < self.ttl);
+        entries.insert(
+            cache_key(role, task, context),
+            Entry {
+                stored_at: Instant::now(),
+                output,
+            },
+        );
+    }
+}
+
+fn cache_key(role: &str, task: &str, context: Option<&str>) -> CacheKey {
+    let mut hasher = DefaultHasher::new();
+    task.hash(&mut hasher);
+    context.hash(&mut hasher);
+    (role.to_lowercase(), hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cached_output_expires_after_ttl() {
+        let cache = DelegationCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("backend", "Add it", None), None);
+
+        cache.insert("backend", "Add it", None, "done".to_string());
+        assert_eq!(cache.get("Backend", "Add it", None).as_deref(), Some("done"));
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(cache.get("backend", "Add it", None), None);
+    }
+
+    #[test]
+    fn test_key_covers_role_task_and_context() {
+        let cache = DelegationCache::new(Duration::from_secs(60));
+        cache.insert("backend", "Add it", Some("ctx"), "done".to_string());
+
+        assert!(cache.get("backend", "Add it", Some("ctx")).is_some());
+        assert!(cache.get("frontend", "Add it", Some("ctx")).is_none());
+        assert!(cache.get("backend", "Add it too", Some("ctx")).is_none());
+        assert!(cache.get("backend", "Add it", None).is_none());
+    }
+}
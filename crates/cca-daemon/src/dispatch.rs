@@ -0,0 +1,305 @@
+//! Dispatch queue - priority ordering for delegations waiting on busy agents
+//!
+//! When every agent of a role is busy, delegations for that role queue here
+//! while they wait for one to free up. A freed agent goes to the waiter with
+//! the highest effective priority: the task's priority level plus
+//! `agents.priority_aging_rate` points per second waited, oldest first on ties.
+//! Aging means a steady stream of high-priority work can delay low-priority
+//! tasks but never starve them.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Default priority aging rate (priority points gained per second of waiting)
+///
+/// At 0.05 a low-priority task overtakes fresh critical work after under 3 minutes.
+pub const DEFAULT_PRIORITY_AGING_RATE: f64 = 0.05;
+/// Scheduling priority of an API task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl TaskPriority {
+    /// Priority level on the `Task::priority` scale (normal is the task default of 5)
+    pub fn level(self) -> u8 {
+        match self {
+            Self::Low => 2,
+            Self::Normal => 5,
+            Self::High => 8,
+            Self::Critical => 10,
+        }
+    }
+}
+
+impl FromStr for TaskPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            other => Err(format!("Unknown priority: {other}")),
+        }
+    }
+}
+
+/// The dispatch queue already holds its maximum number of waiters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull {
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dispatch queue is full ({} delegations waiting for an agent)", self.capacity)
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+struct Waiter {
+    role: String,
+    priority: TaskPriority,
+    enqueued_at: Instant,
+    seq: u64,
+}
+
+impl Waiter {
+    /// Priority level plus `aging_rate` points per second waited
+    fn effective_priority(&self, aging_rate: f64, now: Instant) -> f64 {
+        let waited = now.saturating_duration_since(self.enqueued_at).as_secs_f64();
+        f64::from(self.priority.level()) + aging_rate * waited
+    }
+}
+
+#[derive(Default)]
+struct Waiters {
+    waiters: Vec<Waiter>,
+    next_seq: u64,
+}
+
+/// Bounded, priority-ordered queue of delegations waiting for a busy agent
+pub struct DispatchQueue {
+    /// Maximum delegations waiting across all roles
+    capacity: usize,
+    /// Priority points a waiter gains per second
+    aging_rate: f64,
+    state: Mutex<Waiters>,
+}
+
+impl DispatchQueue {
+    pub fn new(capacity: usize, aging_rate: f64) -> Self {
+        Self {
+            capacity,
+            aging_rate: aging_rate.max(0.0),
+            state: Mutex::new(Waiters::default()),
+        }
+    }
+
+    /// Join the queue for an agent of `role`
+    ///
+    /// Fails with [`QueueFull`] when the queue is at capacity. The caller
+    /// leaves the queue by dropping the ticket.
+    pub fn enqueue(self: &Arc<Self>, role: &str, priority: TaskPriority) -> Result<QueueTicket, QueueFull> {
+        let mut state = self.state.lock().unwrap();
+        if state.waiters.len() >= self.capacity {
+            return Err(QueueFull { capacity: self.capacity });
+        }
+
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.waiters.push(Waiter {
+            role: role.to_lowercase(),
+            priority,
+            enqueued_at: Instant::now(),
+            seq,
+        });
+        Ok(QueueTicket { queue: Arc::clone(self), seq })
+    }
+
+    /// Whether any delegation is waiting for an agent of `role`
+    ///
+    /// New delegations for the role must queue behind them rather than take
+    /// an agent that frees up.
+    pub fn has_waiters(&self, role: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .waiters
+            .iter()
+            .any(|w| w.role.eq_ignore_ascii_case(role))
+    }
+
+    /// Number of delegations waiting for an agent
+    pub fn waiting(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+
+    /// Number of delegations waiting, by role
+    pub fn waiting_by_role(&self) -> HashMap<String, usize> {
+        let mut roles = HashMap::new();
+        for waiter in &self.state.lock().unwrap().waiters {
+            *roles.entry(waiter.role.clone()).or_default() += 1;
+        }
+        roles
+    }
+
+    /// How long the longest-waiting delegation has been queued
+    pub fn oldest_wait(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.state
+            .lock()
+            .unwrap()
+            .waiters
+            .iter()
+            .map(|w| now.saturating_duration_since(w.enqueued_at))
+            .max()
+    }
+
+    /// Whether `seq` has the highest effective priority among waiters of its role
+    fn is_next(&self, seq: u64) -> bool {
+        let state = self.state.lock().unwrap();
+        let Some(role) = state.waiters.iter().find(|w| w.seq == seq).map(|w| &w.role) else {
+            return false;
+        };
+        let now = Instant::now();
+
+        state
+            .waiters
+            .iter()
+            .filter(|w| &w.role == role)
+            .max_by(|a, b| {
+                a.effective_priority(self.aging_rate, now)
+                    .total_cmp(&b.effective_priority(self.aging_rate, now))
+                    .then_with(|| b.seq.cmp(&a.seq))
+            })
+            .is_some_and(|w| w.seq == seq)
+    }
+
+    fn remove(&self, seq: u64) {
+        self.state.lock().unwrap().waiters.retain(|w| w.seq != seq);
+    }
+}
+
+/// A delegation's place in the dispatch queue, given up on drop
+pub struct QueueTicket {
+    queue: Arc<DispatchQueue>,
+    seq: u64,
+}
+
+impl QueueTicket {
+    /// Whether this delegation is first in line for the next free agent of its role
+    pub fn is_next(&self) -> bool {
+        self.queue.is_next(self.seq)
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        self.queue.remove(self.seq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The order in which `tickets` would be handed free agents
+    fn dispatch_order(mut tickets: Vec<(QueueTicket, &'static str)>) -> Vec<&'static str> {
+        let mut order = Vec::new();
+        while !tickets.is_empty() {
+            let next = tickets.iter().position(|(ticket, _)| ticket.is_next()).unwrap();
+            order.push(tickets.remove(next).1);
+        }
+        order
+    }
+
+    #[test]
+    fn test_priority_parses_case_insensitively() {
+        assert_eq!("HIGH".parse::<TaskPriority>(), Ok(TaskPriority::High));
+        assert_eq!("low".parse::<TaskPriority>(), Ok(TaskPriority::Low));
+        assert!("urgent".parse::<TaskPriority>().is_err());
+        assert!(TaskPriority::Critical > TaskPriority::Normal);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_higher_priority_gets_the_next_agent() {
+        let queue = Arc::new(DispatchQueue::new(10, 0.0));
+        let mut tickets = Vec::new();
+        for (priority, label) in [
+            (TaskPriority::Low, "low"),
+            (TaskPriority::Normal, "normal"),
+            (TaskPriority::Critical, "critical"),
+            (TaskPriority::High, "high"),
+            (TaskPriority::Normal, "normal-2"),
+        ] {
+            tickets.push((queue.enqueue("backend", priority).unwrap(), label));
+            tokio::time::advance(Duration::from_millis(10)).await;
+        }
+        assert_eq!(queue.waiting(), 5);
+
+        assert_eq!(
+            dispatch_order(tickets),
+            vec!["critical", "high", "normal", "normal-2", "low"]
+        );
+        assert_eq!(queue.waiting(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_aged_low_priority_task_eventually_dispatches() {
+        let queue = Arc::new(DispatchQueue::new(10, 0.05));
+        let low = queue.enqueue("backend", TaskPriority::Low).unwrap();
+
+        // 2 + 0.05 * 200 = 12 > 10: the low task has outwaited fresh critical work
+        tokio::time::advance(Duration::from_secs(200)).await;
+        let critical = queue.enqueue("backend", TaskPriority::Critical).unwrap();
+
+        assert_eq!(queue.oldest_wait(), Some(Duration::from_secs(200)));
+        assert_eq!(dispatch_order(vec![(low, "low"), (critical, "critical")]), vec!["low", "critical"]);
+        assert_eq!(queue.oldest_wait(), None);
+    }
+
+    #[test]
+    fn test_roles_queue_independently() {
+        let queue = Arc::new(DispatchQueue::new(10, 0.0));
+        let backend = queue.enqueue("backend", TaskPriority::Low).unwrap();
+        let frontend = queue.enqueue("frontend", TaskPriority::Critical).unwrap();
+
+        assert!(backend.is_next());
+        assert!(frontend.is_next());
+        assert!(queue.has_waiters("Backend"));
+        assert!(!queue.has_waiters("qa"));
+        assert_eq!(
+            queue.waiting_by_role(),
+            HashMap::from([("backend".to_string(), 1), ("frontend".to_string(), 1)])
+        );
+
+        drop(backend);
+        assert!(!queue.has_waiters("backend"));
+    }
+
+    #[test]
+    fn test_full_queue_rejects_and_dropped_tickets_free_their_place() {
+        let queue = Arc::new(DispatchQueue::new(1, 0.0));
+        let waiting = queue.enqueue("backend", TaskPriority::High).unwrap();
+        assert_eq!(
+            queue.enqueue("qa", TaskPriority::Critical).err(),
+            Some(QueueFull { capacity: 1 })
+        );
+
+        drop(waiting);
+        assert!(queue.enqueue("qa", TaskPriority::Low).is_ok());
+        assert_eq!(queue.waiting(), 0);
+    }
+}
@@ -0,0 +1,336 @@
+//! Idempotency keys for task-creating endpoints
+//!
+//! A client that retries `POST /api/v1/tasks` or `POST /api/v1/delegate` after a
+//! network error can send the same `Idempotency-Key` header (or `idempotency_key`
+//! body field) each time. The first request runs; replays within
+//! `daemon.idempotency_ttl_secs` get the original response instead of starting
+//! new work. Keys are scoped to the caller's API key and the endpoint, so
+//! different clients can't read each other's responses. Only responses the
+//! endpoint marks cacheable are kept, so a retry after a transient failure
+//! runs again, and reusing a key with a different body is rejected with a 422.
+//! At most `daemon.idempotency_max_entries` keys are kept; the oldest is evicted
+//! first, so a client sending unique keys can't grow the store without bound.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// Maximum accepted idempotency key length
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Where a request's idempotency key is scoped
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Scope {
+    /// API key (or bearer token) the request was made with; empty without auth
+    api_key: String,
+    endpoint: &'static str,
+    key: String,
+}
+
+struct Entry {
+    stored_at: Instant,
+    /// Fingerprint of the request body the key was first used with
+    body_hash: u64,
+    /// Set once a cacheable response has been stored
+    response: Arc<OnceCell<serde_json::Value>>,
+}
+
+/// Why a keyed request couldn't be run or replayed
+#[derive(Debug)]
+pub enum IdempotencyError {
+    /// The key was already used with a different request body
+    KeyReused,
+    /// The stored response couldn't be turned back into the endpoint's type
+    Unreadable(String),
+}
+
+impl fmt::Display for IdempotencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeyReused => write!(f, "Idempotency key was already used with a different request body"),
+            Self::Unreadable(e) => write!(f, "Failed to replay cached response: {e}"),
+        }
+    }
+}
+
+impl IntoResponse for IdempotencyError {
+    fn into_response(self) -> Response {
+        let (status, code) = match self {
+            Self::KeyReused => (StatusCode::UNPROCESSABLE_ENTITY, "idempotency_key_reused"),
+            Self::Unreadable(_) => {
+                warn!("{}", self);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
+            }
+        };
+        let body = serde_json::json!({
+            "success": false,
+            "error": self.to_string(),
+            "code": code
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Fingerprint of the request fields a replay must match
+pub fn body_hash(body: &impl Hash) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Cached responses keyed by (API key, endpoint, idempotency key)
+pub struct IdempotencyStore {
+    ttl: Duration,
+    /// Keys kept at once; the oldest is evicted to make room
+    max_entries: usize,
+    entries: Mutex<HashMap<Scope, Entry>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries: max_entries.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `work` once per key within the TTL, replaying its response afterwards
+    ///
+    /// A replay that arrives while the first request is still running waits
+    /// for it and gets the same response. Responses `cacheable` rejects aren't
+    /// stored, so the next request with the key runs `work` again. A key reused
+    /// with a different `body_hash` fails with [`IdempotencyError::KeyReused`].
+    /// Without a key, `work` always runs.
+    pub async fn run<T, F>(
+        &self,
+        api_key: &str,
+        endpoint: &'static str,
+        key: Option<&str>,
+        body_hash: u64,
+        cacheable: impl FnOnce(&T) -> bool,
+        work: F,
+    ) -> Result<T, IdempotencyError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = T>,
+    {
+        let Some(key) = key else {
+            return Ok(work.await);
+        };
+
+        let cell = {
+            let mut entries = self.entries.lock().unwrap();
+            let now = Instant::now();
+            entries.retain(|_, entry| now.saturating_duration_since(entry.stored_at) < self.ttl);
+
+            let scope = Scope {
+                api_key: api_key.to_string(),
+                endpoint,
+                key: key.to_string(),
+            };
+            if !entries.contains_key(&scope) && entries.len() >= self.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.stored_at)
+                    .map(|(scope, _)| scope.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+            let entry = entries.entry(scope).or_insert_with(|| Entry {
+                stored_at: now,
+                body_hash,
+                response: Arc::new(OnceCell::new()),
+            });
+            if entry.body_hash != body_hash {
+                return Err(IdempotencyError::KeyReused);
+            }
+            Arc::clone(&entry.response)
+        };
+
+        // An uncacheable response leaves the cell empty, so a waiting replay
+        // runs its own `work` instead of getting nothing
+        let mut fresh = None;
+        let cached = cell
+            .get_or_try_init(|| async {
+                let response = work.await;
+                let value = if cacheable(&response) {
+                    serde_json::to_value(&response).ok()
+                } else {
+                    None
+                };
+                fresh = Some(response);
+                value.ok_or(())
+            })
+            .await;
+
+        if let Some(response) = fresh {
+            return Ok(response);
+        }
+        match cached {
+            Ok(cached) => serde_json::from_value(cached.clone())
+                .map_err(|e| IdempotencyError::Unreadable(e.to_string())),
+            Err(()) => Err(IdempotencyError::Unreadable("no response was stored".to_string())),
+        }
+    }
+}
+
+/// Credential a request was authenticated with, used to scope its keys
+pub fn caller_api_key(headers: &HeaderMap) -> String {
+    headers
+        .get("X-API-Key")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get("Authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Idempotency key from the header, falling back to the body field
+pub fn request_key(headers: &HeaderMap, body_key: Option<&str>) -> Result<Option<String>, String> {
+    let key = match headers.get(IDEMPOTENCY_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .map_err(|_| format!("{IDEMPOTENCY_HEADER} must be visible ASCII"))?,
+        ),
+        None => body_key,
+    };
+
+    match key.map(str::trim) {
+        None => Ok(None),
+        Some("") => Err("Idempotency key must not be empty".to_string()),
+        Some(key) if key.len() > MAX_IDEMPOTENCY_KEY_LEN => Err(format!(
+            "Idempotency key too long: {} bytes (max: {MAX_IDEMPOTENCY_KEY_LEN} bytes)",
+            key.len()
+        )),
+        Some(key) => Ok(Some(key.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn create(store: &IdempotencyStore, api_key: &str, key: Option<&str>, runs: &AtomicU32) -> u32 {
+        store
+            .run(api_key, "tasks", key, body_hash(&"body"), |_| true, async {
+                runs.fetch_add(1, Ordering::SeqCst) + 1
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_returns_original_response_until_ttl() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 100);
+        let runs = AtomicU32::new(0);
+
+        assert_eq!(create(&store, "k1", Some("abc"), &runs).await, 1);
+        assert_eq!(create(&store, "k1", Some("abc"), &runs).await, 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(create(&store, "k1", Some("abc"), &runs).await, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_oldest_key_is_evicted_at_capacity() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 2);
+        let runs = AtomicU32::new(0);
+
+        assert_eq!(create(&store, "k1", Some("a"), &runs).await, 1);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(create(&store, "k1", Some("b"), &runs).await, 2);
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(create(&store, "k1", Some("c"), &runs).await, 3);
+        assert_eq!(store.entries.lock().unwrap().len(), 2);
+
+        // "a" was evicted and runs again; "c" is still replayed
+        assert_eq!(create(&store, "k1", Some("c"), &runs).await, 3);
+        assert_eq!(create(&store, "k1", Some("a"), &runs).await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_per_api_key_and_endpoint() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 100);
+        let runs = AtomicU32::new(0);
+
+        assert_eq!(create(&store, "k1", Some("abc"), &runs).await, 1);
+        assert_eq!(create(&store, "k2", Some("abc"), &runs).await, 2);
+        assert_eq!(create(&store, "k1", Some("other"), &runs).await, 3);
+        assert_eq!(create(&store, "k1", None, &runs).await, 4);
+
+        let delegated = store
+            .run("k1", "delegate", Some("abc"), body_hash(&"body"), |_| true, async { 99 })
+            .await
+            .unwrap();
+        assert_eq!(delegated, 99);
+    }
+
+    #[tokio::test]
+    async fn test_uncacheable_responses_run_again() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 100);
+        let runs = AtomicU32::new(0);
+        let attempt = || {
+            store.run("k1", "tasks", Some("abc"), body_hash(&"body"), |n: &u32| *n >= 2, async {
+                runs.fetch_add(1, Ordering::SeqCst) + 1
+            })
+        };
+
+        // The first response fails the check and isn't stored
+        assert_eq!(attempt().await.unwrap(), 1);
+        assert_eq!(attempt().await.unwrap(), 2);
+        assert_eq!(attempt().await.unwrap(), 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_different_body_is_rejected() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 100);
+        let runs = AtomicU32::new(0);
+        assert_eq!(create(&store, "k1", Some("abc"), &runs).await, 1);
+
+        let result = store
+            .run("k1", "tasks", Some("abc"), body_hash(&"other body"), |_| true, async { 7 })
+            .await;
+        assert!(matches!(result, Err(IdempotencyError::KeyReused)));
+        let response = result.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_request_key_prefers_header_and_rejects_bad_keys() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_key(&headers, Some("body")), Ok(Some("body".to_string())));
+        assert_eq!(request_key(&headers, None), Ok(None));
+
+        headers.insert(IDEMPOTENCY_HEADER, "header".parse().unwrap());
+        assert_eq!(request_key(&headers, Some("body")), Ok(Some("header".to_string())));
+
+        assert!(request_key(&HeaderMap::new(), Some("  ")).is_err());
+        let long = "x".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1);
+        assert!(request_key(&HeaderMap::new(), Some(&long)).is_err());
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -17,7 +18,7 @@ use walkdir::WalkDir;
 use crate::code_parser::{CodeChunk, CodeParser};
 use crate::config::IndexingConfig;
 use crate::embeddings::EmbeddingService;
-use crate::postgres::{IndexingJobRecord, PostgresServices};
+use crate::postgres::{CodeIndexStats, IndexingJobRecord, LanguageStats, PostgresServices};
 
 /// Status of an indexing job
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -79,9 +80,18 @@ pub struct StartIndexingRequest {
     #[serde(default)]
     #[validate(length(max = 100, message = "At most 100 extensions allowed"))]
     pub extensions: Option<Vec<String>>,
+    /// Replaces `indexing.default_excludes` when given
     #[serde(default)]
     #[validate(length(max = 100, message = "At most 100 exclude patterns allowed"))]
     pub exclude_patterns: Option<Vec<String>>,
+    /// Only index files matching at least one of these globs
+    #[serde(default)]
+    #[validate(length(max = 100, message = "At most 100 include globs allowed"))]
+    pub include_globs: Option<Vec<String>>,
+    /// Skipped in addition to the default (or `exclude_patterns`) excludes
+    #[serde(default)]
+    #[validate(length(max = 100, message = "At most 100 exclude globs allowed"))]
+    pub exclude_globs: Option<Vec<String>>,
     #[serde(default = "default_batch_size")]
     #[validate(range(min = 1, max = 100, message = "Batch size must be 1-100"))]
     pub batch_size: usize,
@@ -123,6 +133,65 @@ pub struct CodeSearchResult {
     pub similarity: f64,
 }
 
+/// Every indexing job slot is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyIndexingJobs {
+    pub limit: usize,
+}
+
+impl std::fmt::Display for TooManyIndexingJobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many indexing jobs running ({} of {}); retry when one finishes or is cancelled",
+            self.limit, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooManyIndexingJobs {}
+
+/// Caps how many indexing jobs run at once (0 = no limit)
+struct JobSlots {
+    limit: usize,
+    running: AtomicUsize,
+}
+
+/// A running job's slot, released when dropped
+struct JobSlot {
+    slots: Arc<JobSlots>,
+}
+
+impl JobSlots {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            running: AtomicUsize::new(0),
+        }
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Result<JobSlot, TooManyIndexingJobs> {
+        self.running
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                (self.limit == 0 || running < self.limit).then_some(running + 1)
+            })
+            .map_err(|_| TooManyIndexingJobs { limit: self.limit })?;
+        Ok(JobSlot {
+            slots: Arc::clone(self),
+        })
+    }
+
+    fn running(&self) -> usize {
+        self.running.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        self.slots.running.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
 /// Indexing service for managing codebase indexing
 pub struct IndexingService {
     config: IndexingConfig,
@@ -130,6 +199,8 @@ pub struct IndexingService {
     postgres: Arc<PostgresServices>,
     /// Active job cancellation tokens
     cancellation_tokens: Arc<RwLock<HashSet<Uuid>>>,
+    /// Slots for `indexing.max_concurrent_jobs`, held by each running job
+    job_slots: Arc<JobSlots>,
 }
 
 impl IndexingService {
@@ -139,21 +210,29 @@ impl IndexingService {
         embedding_service: Arc<EmbeddingService>,
         postgres: Arc<PostgresServices>,
     ) -> Self {
+        let job_slots = Arc::new(JobSlots::new(config.max_concurrent_jobs as usize));
         Self {
             config,
             embedding_service,
             postgres,
             cancellation_tokens: Arc::new(RwLock::new(HashSet::new())),
+            job_slots,
         }
     }
 
     /// Start a new indexing job (runs in background)
+    ///
+    /// Fails with [`TooManyIndexingJobs`] while `indexing.max_concurrent_jobs`
+    /// jobs are already running.
     pub async fn start_indexing(&self, request: StartIndexingRequest) -> Result<Uuid> {
         let path = PathBuf::from(&request.path);
         if !path.exists() {
             anyhow::bail!("Path does not exist: {}", request.path);
         }
 
+        // Held by the background task until the job ends
+        let slot = self.job_slots.try_acquire()?;
+
         // Create job record
         let job_id = self.postgres.indexing_jobs.create(&request.path).await?;
 
@@ -165,21 +244,26 @@ impl IndexingService {
         let cancellation_tokens = Arc::clone(&self.cancellation_tokens);
         let config = self.config.clone();
 
-        let extensions = request
-            .extensions
-            .unwrap_or_else(|| config.default_extensions.clone());
-        let exclude_patterns = request
+        let mut excludes = request
             .exclude_patterns
             .unwrap_or_else(|| config.default_excludes.clone());
+        excludes.extend(request.exclude_globs.unwrap_or_default());
+        let filter = FileFilter::new(
+            &request
+                .extensions
+                .unwrap_or_else(|| config.default_extensions.clone()),
+            &request.include_globs.unwrap_or_default(),
+            &excludes,
+        );
         let batch_size = request.batch_size;
 
         // Spawn background task
         tokio::spawn(async move {
+            let _slot = slot;
             if let Err(e) = run_indexing_job(
                 job_id,
                 path,
-                extensions,
-                exclude_patterns,
+                filter,
                 batch_size,
                 config.max_chunk_size,
                 postgres,
@@ -201,6 +285,16 @@ impl IndexingService {
         Ok(record.map(IndexingJobStatus::from))
     }
 
+    /// Number of indexing jobs currently running in this daemon
+    pub fn running_jobs(&self) -> usize {
+        self.job_slots.running()
+    }
+
+    /// Configured `indexing.max_concurrent_jobs` (0 = no limit)
+    pub fn max_concurrent_jobs(&self) -> usize {
+        self.job_slots.limit
+    }
+
     /// List recent indexing jobs
     pub async fn list_jobs(&self, limit: i32) -> Result<Vec<IndexingJobStatus>> {
         let records = self.postgres.indexing_jobs.list_recent(limit).await?;
@@ -231,6 +325,7 @@ impl IndexingService {
         query: &str,
         limit: i32,
         language: Option<&str>,
+        path_prefix: Option<&str>,
     ) -> Result<Vec<CodeSearchResult>> {
         // Generate embedding for query
         let embedding = self.embedding_service.embed(query).await?;
@@ -239,7 +334,7 @@ impl IndexingService {
         let chunks = self
             .postgres
             .code_chunks
-            .search_similar(&embedding, limit, 0.3, language)
+            .search_similar(&embedding, limit, 0.3, language, path_prefix)
             .await?;
 
         let results = chunks
@@ -264,23 +359,42 @@ impl IndexingService {
     /// Get indexing statistics
     pub async fn get_stats(&self) -> Result<serde_json::Value> {
         let stats = self.postgres.code_chunks.get_stats().await?;
-        let by_language = self.postgres.code_chunks.count_by_language().await?;
-
-        Ok(serde_json::json!({
-            "total_chunks": stats.total_chunks,
-            "total_files": stats.total_files,
-            "languages_count": stats.languages_count,
-            "by_language": by_language.into_iter().collect::<std::collections::HashMap<_, _>>(),
-        }))
+        let by_language = self.postgres.code_chunks.language_breakdown().await?;
+
+        Ok(stats_json(&stats, &by_language))
     }
 }
 
+/// Index totals plus chunk, file and byte counts for each language
+fn stats_json(stats: &CodeIndexStats, by_language: &[LanguageStats]) -> serde_json::Value {
+    let by_language: serde_json::Map<String, serde_json::Value> = by_language
+        .iter()
+        .map(|lang| {
+            (
+                lang.language.clone(),
+                serde_json::json!({
+                    "chunks": lang.chunks,
+                    "files": lang.files,
+                    "bytes": lang.bytes,
+                }),
+            )
+        })
+        .collect();
+
+    serde_json::json!({
+        "total_chunks": stats.total_chunks,
+        "total_files": stats.total_files,
+        "total_bytes": stats.total_bytes,
+        "languages_count": stats.languages_count,
+        "by_language": by_language,
+    })
+}
+
 /// Run the actual indexing job
 async fn run_indexing_job(
     job_id: Uuid,
     path: PathBuf,
-    extensions: Vec<String>,
-    exclude_patterns: Vec<String>,
+    filter: FileFilter,
     batch_size: usize,
     max_chunk_size: usize,
     postgres: Arc<PostgresServices>,
@@ -289,14 +403,8 @@ async fn run_indexing_job(
 ) -> Result<()> {
     let mut errors: Vec<String> = Vec::new();
 
-    // Compile exclude patterns
-    let exclude_globs: Vec<Pattern> = exclude_patterns
-        .iter()
-        .filter_map(|p| Pattern::new(p).ok())
-        .collect();
-
     // Collect files to process
-    let files: Vec<PathBuf> = collect_files(&path, &extensions, &exclude_globs);
+    let files: Vec<PathBuf> = collect_files(&path, &filter);
     let total_files = files.len() as i32;
 
     info!(
@@ -351,6 +459,7 @@ async fn run_indexing_job(
                     // Process batch if full
                     if pending_chunks.len() >= batch_size {
                         let batch_indexed = process_chunk_batch(
+                            job_id,
                             &mut pending_chunks,
                             &postgres,
                             &embedding_service,
@@ -382,6 +491,7 @@ async fn run_indexing_job(
     // Process remaining chunks
     if !pending_chunks.is_empty() {
         let batch_indexed = process_chunk_batch(
+            job_id,
             &mut pending_chunks,
             &postgres,
             &embedding_service,
@@ -412,32 +522,84 @@ async fn run_indexing_job(
     Ok(())
 }
 
-/// Collect files matching extensions and not excluded
-fn collect_files(path: &Path, extensions: &[String], exclude_globs: &[Pattern]) -> Vec<PathBuf> {
-    let ext_set: HashSet<String> = extensions.iter().map(|e| e.to_lowercase()).collect();
+/// Bytes read from the start of a file to decide whether it is binary
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Which files an indexing job walks into and indexes
+struct FileFilter {
+    extensions: HashSet<String>,
+    include_globs: Vec<Pattern>,
+    exclude_globs: Vec<Pattern>,
+}
+
+impl FileFilter {
+    /// Invalid globs are ignored
+    fn new(extensions: &[String], include_globs: &[String], exclude_globs: &[String]) -> Self {
+        let compile = |globs: &[String]| globs.iter().filter_map(|g| Pattern::new(g).ok()).collect();
+        Self {
+            extensions: extensions.iter().map(|e| e.to_lowercase()).collect(),
+            include_globs: compile(include_globs),
+            exclude_globs: compile(exclude_globs),
+        }
+    }
+
+    /// Excluded directories are matched with a trailing `/`, so `**/target/**`
+    /// prunes the whole `target` tree without visiting it
+    fn is_excluded(&self, entry: &walkdir::DirEntry) -> bool {
+        let mut path = entry.path().to_string_lossy().into_owned();
+        if entry.file_type().is_dir() {
+            path.push('/');
+        }
+        self.exclude_globs.iter().any(|g| g.matches(&path))
+    }
+
+    fn is_wanted_file(&self, path: &Path) -> bool {
+        let has_extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()));
+        let path_str = path.to_string_lossy();
+        has_extension
+            && (self.include_globs.is_empty() || self.include_globs.iter().any(|g| g.matches(&path_str)))
+    }
+}
 
+/// Collect files matching the filter, skipping excluded directories and binary files
+fn collect_files(path: &Path, filter: &FileFilter) -> Vec<PathBuf> {
     WalkDir::new(path)
         .follow_links(false)
         .into_iter()
+        // The root is always walked, even if it sits inside an excluded directory
+        .filter_entry(|e| e.depth() == 0 || !filter.is_excluded(e))
         .filter_map(std::result::Result::ok)
         .filter(|e| e.file_type().is_file())
+        .filter(|e| filter.is_wanted_file(e.path()))
         .filter(|e| {
-            // Check extension
-            e.path()
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .map(|ext| ext_set.contains(&ext.to_lowercase()))
-                .unwrap_or(false)
-        })
-        .filter(|e| {
-            // Check exclude patterns
-            let path_str = e.path().to_string_lossy();
-            !exclude_globs.iter().any(|g| g.matches(&path_str))
+            let binary = is_binary_file(e.path());
+            if binary {
+                debug!("Skipping binary file {}", e.path().display());
+            }
+            !binary
         })
         .map(|e| e.path().to_path_buf())
         .collect()
 }
 
+/// Whether the file looks binary: a NUL byte near its start, as git checks.
+/// Unreadable files count as text so parsing reports the error.
+fn is_binary_file(path: &Path) -> bool {
+    use std::io::Read;
+
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+    if file.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    head.contains(&0)
+}
+
 /// Create text for embedding from a code chunk
 fn create_embedding_text(chunk: &CodeChunk) -> String {
     let mut text = String::new();
@@ -468,6 +630,7 @@ fn create_embedding_text(chunk: &CodeChunk) -> String {
 
 /// Process a batch of chunks: generate embeddings and store
 async fn process_chunk_batch(
+    job_id: Uuid,
     pending: &mut Vec<(CodeChunk, String)>,
     postgres: &Arc<PostgresServices>,
     embedding_service: &Arc<EmbeddingService>,
@@ -484,8 +647,8 @@ async fn process_chunk_batch(
         Ok(embeddings) => {
             let mut indexed = 0;
             for ((chunk, _), embedding) in pending.drain(..).zip(embeddings) {
-                // Store chunk with embedding
-                if let Err(e) = postgres
+                // Store chunk with embedding (skipped once the job is cancelled)
+                match postgres
                     .code_chunks
                     .upsert(
                         &chunk.file_path,
@@ -498,12 +661,13 @@ async fn process_chunk_batch(
                         &chunk.language,
                         &embedding,
                         chunk.metadata.clone(),
+                        Some(job_id),
                     )
                     .await
                 {
-                    errors.push(format!("Failed to store chunk {}: {}", chunk.name, e));
-                } else {
-                    indexed += 1;
+                    Ok(Some(_)) => indexed += 1,
+                    Ok(None) => {}
+                    Err(e) => errors.push(format!("Failed to store chunk {}: {}", chunk.name, e)),
                 }
             }
             indexed
@@ -540,4 +704,82 @@ mod tests {
         assert!(text.contains("function hello"));
         assert!(text.contains("fn hello(name: &str)"));
     }
+
+    #[test]
+    fn test_walk_skips_excluded_dirs_and_binary_files() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |rel: &str, contents: &[u8]| {
+            let path = root.path().join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("src/lib.rs", b"fn lib() {}");
+        write("src/generated/blob.rs", b"\x7fELF\0\0\x01binary");
+        write("target/debug/build.rs", b"fn build() {}");
+        write("web/node_modules/pkg/index.js", b"module.exports = {}");
+        write("web/app.js", b"export default {}");
+        write("vendor/dep.rs", b"fn dep() {}");
+
+        let config = IndexingConfig::default();
+        let walk = |include: &[String], extra_exclude: &[String]| {
+            let mut excludes = config.default_excludes.clone();
+            excludes.extend_from_slice(extra_exclude);
+            let filter = FileFilter::new(&config.default_extensions, include, &excludes);
+            let mut files: Vec<String> = collect_files(root.path(), &filter)
+                .iter()
+                .map(|p| p.strip_prefix(root.path()).unwrap().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(walk(&[], &[]), ["src/lib.rs", "web/app.js"]);
+        assert_eq!(walk(&["**/*.js".to_string()], &[]), ["web/app.js"]);
+        assert_eq!(walk(&[], &["**/web/**".to_string()]), ["src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_jobs_past_the_concurrency_limit_are_rejected() {
+        let slots = Arc::new(JobSlots::new(2));
+        let first = slots.try_acquire().unwrap();
+        let _second = slots.try_acquire().unwrap();
+        assert_eq!(slots.running(), 2);
+
+        let err = slots.try_acquire().err().unwrap();
+        assert_eq!(err, TooManyIndexingJobs { limit: 2 });
+        assert!(err.to_string().contains("Too many indexing jobs running (2 of 2)"));
+
+        // A finished job frees its slot
+        drop(first);
+        assert_eq!(slots.running(), 1);
+        assert!(slots.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_zero_job_limit_is_unlimited() {
+        let slots = Arc::new(JobSlots::new(0));
+        let held: Vec<JobSlot> = (0..10).map(|_| slots.try_acquire().unwrap()).collect();
+        assert_eq!(slots.running(), 10);
+        drop(held);
+        assert_eq!(slots.running(), 0);
+    }
+
+    #[test]
+    fn test_stats_json_breaks_down_by_language() {
+        let stats = CodeIndexStats {
+            total_chunks: 15,
+            total_files: 4,
+            languages_count: 2,
+            total_bytes: 9000,
+        };
+        let by_language = [
+            LanguageStats { language: "rust".to_string(), chunks: 12, files: 3, bytes: 8000 },
+            LanguageStats { language: "python".to_string(), chunks: 3, files: 1, bytes: 1000 },
+        ];
+
+        let json = stats_json(&stats, &by_language);
+        assert_eq!(json["total_bytes"], 9000);
+        assert_eq!(json["by_language"]["rust"], serde_json::json!({"chunks": 12, "files": 3, "bytes": 8000}));
+        assert_eq!(json["by_language"]["python"]["files"], 1);
+    }
 }
@@ -56,22 +56,32 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod agent_manager;
 mod auth;
+mod cancellation;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod circuit_breaker;
 mod code_parser;
 mod config;
 mod daemon;
+mod delegation_cache;
+mod dispatch;
 mod embeddings;
+mod idempotency;
 mod indexing;
 mod metrics;
 mod orchestrator;
 mod postgres;
 mod redis;
+mod request_id;
 mod rl;
+mod service_error;
+mod templates;
 mod tmux;
 mod tokens;
 mod validation;
 
 use crate::config::Config;
-use crate::daemon::CCADaemon;
+use crate::daemon::{CCADaemon, LogLevelReloader};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -81,9 +91,19 @@ async fn main() -> Result<()> {
     // Load configuration to get log settings
     let config = Config::load()?;
 
-    // Initialize tracing with optional file logging
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| format!("ccad={},tower_http=debug", config.daemon.log_level).into());
+    // Initialize tracing with optional file logging. The filter sits behind a
+    // reload layer so a config reload can change `daemon.log_level`.
+    let rust_log = tracing_subscriber::EnvFilter::try_from_default_env().ok();
+    let from_config = rust_log.is_none();
+    let env_filter = rust_log.unwrap_or_else(|| log_filter(&config.daemon.log_level).into());
+    let (env_filter, filter_handle) = tracing_subscriber::reload::Layer::new(env_filter);
+    let log_reloader: Option<LogLevelReloader> = from_config.then(|| {
+        Arc::new(move |level: &str| {
+            let filter = tracing_subscriber::EnvFilter::try_new(log_filter(level))?;
+            filter_handle.reload(filter)?;
+            Ok(())
+        }) as LogLevelReloader
+    });
 
     let file_logging_enabled = if !config.daemon.log_file.is_empty() {
         // Try to set up file logging
@@ -156,6 +176,14 @@ async fn main() -> Result<()> {
         config.daemon.bind_address
     );
 
+    // Fail fast on invalid configuration, reporting every problem at once
+    if let Err(errors) = config.validate() {
+        for e in &errors {
+            error!("Invalid configuration: {}", e);
+        }
+        anyhow::bail!("Invalid configuration ({} problem(s) found)", errors.len());
+    }
+
     // Security warnings for authentication configuration
     // SECURITY: Use is_auth_required() which enforces auth in production builds
     if !config.daemon.is_auth_required() {
@@ -165,22 +193,15 @@ async fn main() -> Result<()> {
         warn!("This is only possible in dev builds (--features dev).");
         warn!("Production builds ALWAYS require authentication.");
         warn!("============================================================");
-    } else if config.daemon.api_keys.is_empty() {
-        error!("============================================================");
-        error!("ERROR: Authentication is enabled but no API keys configured!");
-        error!("All API requests will be rejected.");
-        error!("Set CCA__DAEMON__API_KEYS=your-secret-key");
-        error!("Or disable auth with CCA__DAEMON__REQUIRE_AUTH=false (dev only)");
-        error!("============================================================");
     } else {
         info!(
             "API authentication: enabled ({} API key(s) configured)",
-            config.daemon.api_keys.len()
+            config.daemon.api_keys.len() + config.daemon.api_key_configs.len()
         );
     }
 
     // Create and start daemon
-    let daemon = Arc::new(CCADaemon::new(config).await?);
+    let daemon = Arc::new(CCADaemon::new(config).await?.with_log_reloader(log_reloader));
 
     // Clone for signal handler
     let daemon_handle = daemon.clone();
@@ -206,6 +227,11 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Tracing filter for a `daemon.log_level` value
+fn log_filter(level: &str) -> String {
+    format!("ccad={level},tower_http=debug")
+}
+
 /// Wait for shutdown signal (SIGINT, SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -25,6 +25,9 @@ pub static REGISTRY: LazyLock<Registry> = LazyLock::new(|| {
     registry.register(Box::new(TASKS_TOTAL.clone())).unwrap();
     registry.register(Box::new(TASKS_IN_PROGRESS.clone())).unwrap();
     registry.register(Box::new(TASK_DURATION.clone())).unwrap();
+    registry.register(Box::new(TASKS_IN_FLIGHT.clone())).unwrap();
+    registry.register(Box::new(AGENTS_BUSY.clone())).unwrap();
+    registry.register(Box::new(ACP_CONNECTIONS.clone())).unwrap();
     registry.register(Box::new(WEBSOCKET_CONNECTIONS.clone())).unwrap();
     registry.register(Box::new(WEBSOCKET_MESSAGES_TOTAL.clone())).unwrap();
     registry.register(Box::new(REDIS_OPERATIONS_TOTAL.clone())).unwrap();
@@ -137,6 +140,28 @@ pub static TASK_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
     .unwrap()
 });
 
+// =============================================================================
+// Live State Metrics (set from daemon state on each scrape)
+// =============================================================================
+
+/// Tasks that are pending or running
+pub static TASKS_IN_FLIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    IntGauge::new("cca_tasks_in_flight", "Number of tasks pending or running")
+        .unwrap()
+});
+
+/// Agents currently working on a task
+pub static AGENTS_BUSY: LazyLock<IntGauge> = LazyLock::new(|| {
+    IntGauge::new("cca_agents_busy", "Number of agents currently working on a task")
+        .unwrap()
+});
+
+/// Workers connected over ACP
+pub static ACP_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    IntGauge::new("cca_acp_connections", "Number of connected ACP workers")
+        .unwrap()
+});
+
 // =============================================================================
 // WebSocket / ACP Metrics
 // =============================================================================
@@ -347,6 +372,13 @@ pub fn record_task_failed(priority: &str) {
     TASKS_IN_PROGRESS.dec();
 }
 
+/// Set the live state gauges, called at scrape time
+pub fn set_live_state(tasks_in_flight: usize, agents_busy: usize, acp_connections: usize) {
+    TASKS_IN_FLIGHT.set(i64::try_from(tasks_in_flight).unwrap_or(i64::MAX));
+    AGENTS_BUSY.set(i64::try_from(agents_busy).unwrap_or(i64::MAX));
+    ACP_CONNECTIONS.set(i64::try_from(acp_connections).unwrap_or(i64::MAX));
+}
+
 /// Update connection status for Redis
 pub fn set_redis_connected(connected: bool) {
     REDIS_CONNECTED.set(if connected { 1 } else { 0 });
@@ -8,11 +8,14 @@
 //! Note: Many methods are infrastructure for future features and not yet called.
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
@@ -24,9 +27,18 @@ use cca_core::communication::channels;
 use cca_core::{AgentId, AgentRole, Task, TaskId, TaskResult, TaskStatus};
 use cca_rl::{Action, Experience};
 
+use crate::circuit_breaker::{
+    BreakerSnapshot, CircuitBreaker, DEFAULT_BREAKER_COOLDOWN, DEFAULT_BREAKER_THRESHOLD,
+};
 use crate::redis::RedisServices;
 use crate::rl::{compute_reward, AgentInfo, RLService, StateBuilder};
 
+/// Default concurrent-task capacity for an agent that doesn't specify one
+pub const DEFAULT_AGENT_MAX_TASKS: u32 = 5;
+
+/// Workload samples kept for `/api/v1/workloads/history` (24 hours at one per minute)
+pub const WORKLOAD_HISTORY_CAPACITY: usize = 1440;
+
 /// Agent workload information
 #[derive(Debug, Clone)]
 pub struct AgentWorkload {
@@ -43,6 +55,93 @@ pub struct AgentWorkload {
     pub tasks_completed: u32,
     /// Total tasks failed
     pub tasks_failed: u32,
+    /// Stable worker ID of the connection, if the worker registered one
+    pub worker_id: Option<String>,
+}
+
+impl AgentWorkload {
+    /// Create a fresh workload entry with no task history
+    pub fn new(agent_id: AgentId, role: String, capabilities: Vec<String>, max_tasks: u32) -> Self {
+        Self {
+            agent_id,
+            role,
+            current_tasks: 0,
+            max_tasks,
+            capabilities,
+            success_rate: 1.0, // Start optimistic
+            avg_completion_time: 0.0,
+            tasks_completed: 0,
+            tasks_failed: 0,
+            worker_id: None,
+        }
+    }
+
+    /// Take over the task history of an earlier connection of the same worker
+    fn absorb_history(&mut self, earlier: &AgentWorkload) {
+        self.tasks_completed += earlier.tasks_completed;
+        self.tasks_failed += earlier.tasks_failed;
+        let total = self.tasks_completed + self.tasks_failed;
+        if total > 0 {
+            self.success_rate = f64::from(self.tasks_completed) / f64::from(total);
+        }
+        if self.avg_completion_time == 0.0 {
+            self.avg_completion_time = earlier.avg_completion_time;
+        }
+    }
+
+    /// Whether the agent can accept another task under its own `max_tasks`
+    pub fn has_capacity(&self) -> bool {
+        self.current_tasks < self.max_tasks
+    }
+
+    /// Fold a finished task into the success rate and average completion time
+    pub fn record_outcome(&mut self, success: bool, duration_ms: u64) {
+        if success {
+            self.tasks_completed += 1;
+        } else {
+            self.tasks_failed += 1;
+        }
+
+        let total = self.tasks_completed + self.tasks_failed;
+        if total > 0 {
+            self.success_rate = f64::from(self.tasks_completed) / f64::from(total);
+        }
+
+        // Exponential moving average
+        let alpha = 0.2; // Smoothing factor
+        if self.avg_completion_time == 0.0 {
+            self.avg_completion_time = duration_ms as f64;
+        } else {
+            self.avg_completion_time =
+                alpha * duration_ms as f64 + (1.0 - alpha) * self.avg_completion_time;
+        }
+    }
+
+    /// Fraction of the agent's capacity currently in use (0.0 - 1.0)
+    pub fn load_ratio(&self) -> f64 {
+        if self.max_tasks == 0 {
+            return 1.0;
+        }
+        f64::from(self.current_tasks) / f64::from(self.max_tasks)
+    }
+}
+
+/// Load on one role at a point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RoleLoad {
+    /// Registered agents of the role
+    pub agents: usize,
+    /// Agents working on at least one task
+    pub busy: usize,
+    /// Delegations waiting for a busy agent of the role
+    pub queued: usize,
+}
+
+/// Per-role workload at a point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkloadSample {
+    pub timestamp: DateTime<Utc>,
+    pub roles: BTreeMap<String, RoleLoad>,
 }
 
 /// Pending result aggregation
@@ -74,6 +173,16 @@ pub struct Orchestrator {
     use_rl_routing: bool,
     /// Task start times for duration tracking
     task_start_times: Arc<RwLock<HashMap<TaskId, std::time::Instant>>>,
+    /// Circuit breakers keyed by agent role
+    role_breakers: Arc<RwLock<HashMap<String, CircuitBreaker>>>,
+    /// Consecutive failures that open a role's breaker (0 disables breakers)
+    breaker_threshold: u32,
+    /// How long an open breaker refuses dispatch before testing recovery
+    breaker_cooldown: Duration,
+    /// Round-robin position for agents that tie in [`Self::pick_preferred_agent`]
+    route_cursor: AtomicUsize,
+    /// Periodic workload samples, oldest first (bounded by `WORKLOAD_HISTORY_CAPACITY`)
+    workload_history: Arc<RwLock<VecDeque<WorkloadSample>>>,
 }
 
 impl Orchestrator {
@@ -89,6 +198,11 @@ impl Orchestrator {
             request_timeout: Duration::from_secs(30),
             use_rl_routing: false,
             task_start_times: Arc::new(RwLock::new(HashMap::new())),
+            role_breakers: Arc::new(RwLock::new(HashMap::new())),
+            breaker_threshold: DEFAULT_BREAKER_THRESHOLD,
+            breaker_cooldown: DEFAULT_BREAKER_COOLDOWN,
+            route_cursor: AtomicUsize::new(0),
+            workload_history: Arc::new(RwLock::new(VecDeque::new())),
         }
     }
 
@@ -124,40 +238,210 @@ impl Orchestrator {
         self
     }
 
+    /// Configure per-role circuit breakers (threshold 0 disables them)
+    pub fn with_circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.breaker_threshold = threshold;
+        self.breaker_cooldown = cooldown;
+        self
+    }
+
+    /// Fail fast if the role's circuit breaker is open
+    ///
+    /// Once the cooldown has passed this lets one probe task through.
+    pub async fn check_role_breaker(&self, role: &str) -> Result<()> {
+        let mut breakers = self.role_breakers.write().await;
+        let Some(breaker) = breakers.get_mut(role) else {
+            return Ok(());
+        };
+
+        let now = tokio::time::Instant::now();
+        breaker.try_acquire(now).map_err(|retry_after| {
+            let failures = breaker.snapshot(now).consecutive_failures;
+            anyhow::anyhow!(
+                "Circuit breaker open for {role} agents after {failures} consecutive failures; \
+                 retry in {}s",
+                retry_after.as_secs().max(1)
+            )
+        })
+    }
+
+    /// Record a dispatch outcome for the role's circuit breaker
+    pub async fn record_role_outcome(&self, role: &str, success: bool) {
+        let mut breakers = self.role_breakers.write().await;
+        let breaker = breakers
+            .entry(role.to_string())
+            .or_insert_with(|| CircuitBreaker::new(self.breaker_threshold, self.breaker_cooldown));
+
+        if success {
+            breaker.record_success();
+        } else {
+            let now = tokio::time::Instant::now();
+            breaker.record_failure(now);
+            if breaker.snapshot(now).state == "open" {
+                warn!("Circuit breaker for {} agents is open", role);
+            }
+        }
+    }
+
+    /// Record a task an agent finished outside [`Self::route_task`] (e.g. a daemon delegation)
+    pub async fn record_agent_outcome(&self, agent_id: AgentId, success: bool, duration_ms: u64) {
+        let mut workloads = self.agent_workloads.write().await;
+        if let Some(workload) = workloads.get_mut(&agent_id) {
+            workload.record_outcome(success, duration_ms);
+        }
+    }
+
+    /// Pick the most reliable of several idle agents
+    ///
+    /// Agents are ranked by success rate (unregistered agents count as fresh,
+    /// i.e. 1.0), then by load ratio so work spreads across the pool. Remaining
+    /// ties rotate round-robin so equally good agents share the load.
+    pub async fn pick_preferred_agent(&self, candidates: &[AgentId]) -> Option<AgentId> {
+        let ranked: Vec<(AgentId, f64, f64)> = {
+            let workloads = self.agent_workloads.read().await;
+            candidates
+                .iter()
+                .map(|id| {
+                    workloads
+                        .get(id)
+                        .map_or((*id, 1.0, 0.0), |w| (*id, w.success_rate, w.load_ratio()))
+                })
+                .collect()
+        };
+
+        let best_rate = ranked.iter().map(|(_, rate, _)| *rate).max_by(f64::total_cmp)?;
+        let lowest_load = ranked
+            .iter()
+            .filter(|(_, rate, _)| *rate == best_rate)
+            .map(|(_, _, load)| *load)
+            .min_by(f64::total_cmp)?;
+        let mut tied: Vec<AgentId> = ranked
+            .into_iter()
+            .filter(|(_, rate, load)| *rate == best_rate && *load == lowest_load)
+            .map(|(id, _, _)| id)
+            .collect();
+        tied.sort_by_key(|id| id.0);
+
+        let turn = self.route_cursor.fetch_add(1, Ordering::Relaxed);
+        Some(tied[turn % tied.len()])
+    }
+
+    /// Circuit breaker state of every role that has reported an outcome
+    pub async fn role_breakers(&self) -> HashMap<String, BreakerSnapshot> {
+        let now = tokio::time::Instant::now();
+        let breakers = self.role_breakers.read().await;
+        breakers
+            .iter()
+            .map(|(role, breaker)| (role.clone(), breaker.snapshot(now)))
+            .collect()
+    }
+
     /// Register an agent with the orchestrator
+    ///
+    /// Replaces any existing registration for the same agent. Returns true if
+    /// one was replaced.
     pub async fn register_agent(
         &self,
         agent_id: AgentId,
         role: String,
         capabilities: Vec<String>,
         max_tasks: u32,
-    ) {
-        let workload = AgentWorkload {
-            agent_id,
-            role: role.clone(),
-            current_tasks: 0,
-            max_tasks,
-            capabilities,
-            success_rate: 1.0, // Start optimistic
-            avg_completion_time: 0.0,
-            tasks_completed: 0,
-            tasks_failed: 0,
-        };
+    ) -> bool {
+        let workload = AgentWorkload::new(agent_id, role.clone(), capabilities, max_tasks);
 
         let mut workloads = self.agent_workloads.write().await;
-        workloads.insert(agent_id, workload);
+        let replaced = workloads.insert(agent_id, workload).is_some();
 
         info!(
             "Registered agent {} with role {} (max {} tasks)",
             agent_id, role, max_tasks
         );
+        replaced
+    }
+
+    /// Register an agent only if it is not already known to the orchestrator
+    ///
+    /// This is the implicit fallback used when routing discovers a connected
+    /// worker that was never registered explicitly. An existing registration
+    /// (and its capabilities, capacity and stats) is left untouched.
+    ///
+    /// Returns true if the agent was newly registered.
+    pub async fn register_agent_if_absent(
+        &self,
+        agent_id: AgentId,
+        role: String,
+        capabilities: Vec<String>,
+        max_tasks: u32,
+    ) -> bool {
+        let mut workloads = self.agent_workloads.write().await;
+        if workloads.contains_key(&agent_id) {
+            return false;
+        }
+
+        workloads.insert(
+            agent_id,
+            AgentWorkload::new(agent_id, role.clone(), capabilities, max_tasks),
+        );
+
+        debug!(
+            "Implicitly registered agent {} with role {} (max {} tasks)",
+            agent_id, role, max_tasks
+        );
+        true
+    }
+
+    /// Record an agent's stable worker ID and carry over the stats of the
+    /// worker's earlier connections
+    ///
+    /// Agent IDs change on every reconnect, so without this a reconnected
+    /// worker starts with a blank history. Earlier entries are removed.
+    /// Returns the number of earlier entries merged.
+    pub async fn link_worker(&self, agent_id: AgentId, worker_id: &str) -> usize {
+        let mut workloads = self.agent_workloads.write().await;
+        if !workloads.contains_key(&agent_id) {
+            return 0;
+        }
+
+        let earlier: Vec<AgentId> = workloads
+            .values()
+            .filter(|w| w.agent_id != agent_id && w.worker_id.as_deref() == Some(worker_id))
+            .map(|w| w.agent_id)
+            .collect();
+        let earlier: Vec<AgentWorkload> = earlier.iter().filter_map(|id| workloads.remove(id)).collect();
+
+        if let Some(workload) = workloads.get_mut(&agent_id) {
+            workload.worker_id = Some(worker_id.to_string());
+            for previous in &earlier {
+                workload.absorb_history(previous);
+            }
+        }
+        if !earlier.is_empty() {
+            info!(
+                "Agent {} is a reconnect of worker {}; carried over stats from {} earlier connection(s)",
+                agent_id,
+                worker_id,
+                earlier.len()
+            );
+        }
+        earlier.len()
     }
 
     /// Unregister an agent
-    pub async fn unregister_agent(&self, agent_id: AgentId) {
+    ///
+    /// Returns true if the agent was registered.
+    pub async fn unregister_agent(&self, agent_id: AgentId) -> bool {
         let mut workloads = self.agent_workloads.write().await;
-        workloads.remove(&agent_id);
-        info!("Unregistered agent {}", agent_id);
+        let removed = workloads.remove(&agent_id).is_some();
+        if removed {
+            info!("Unregistered agent {}", agent_id);
+        }
+        removed
+    }
+
+    /// Check whether an agent is registered with the orchestrator
+    pub async fn is_agent_registered(&self, agent_id: AgentId) -> bool {
+        let workloads = self.agent_workloads.read().await;
+        workloads.contains_key(&agent_id)
     }
 
     /// Route a task to a specific agent
@@ -233,6 +517,8 @@ impl Orchestrator {
     /// Route a task to the best available agent based on role/capabilities
     /// Uses RL predictions when enabled
     pub async fn route_task_auto(&self, task: Task, required_role: &str) -> Result<TaskId> {
+        self.check_role_breaker(required_role).await?;
+
         let agent_id = if self.use_rl_routing && self.rl_service.is_some() {
             self.find_best_agent_rl(required_role, &task).await?
         } else {
@@ -248,7 +534,7 @@ impl Orchestrator {
         // Find agents with matching role and available capacity
         let candidates: Vec<_> = workloads
             .values()
-            .filter(|w| w.role == required_role && w.current_tasks < w.max_tasks)
+            .filter(|w| w.role == required_role && w.has_capacity())
             .collect();
 
         if candidates.is_empty() {
@@ -283,7 +569,7 @@ impl Orchestrator {
                 candidates
                     .iter()
                     .filter(|a| AgentRole::from(&a.role as &str) == *role)
-                    .min_by_key(|a| a.current_tasks)
+                    .min_by(|a, b| a.load_ratio().total_cmp(&b.load_ratio()))
                     .map(|a| a.agent_id)
             }
             Action::Composite(actions) => {
@@ -299,7 +585,7 @@ impl Orchestrator {
                     candidates
                         .iter()
                         .filter(|a| AgentRole::from(&a.role as &str) == role)
-                        .min_by_key(|a| a.current_tasks)
+                        .min_by(|a, b| a.load_ratio().total_cmp(&b.load_ratio()))
                         .map(|a| a.agent_id)
                 } else {
                     None
@@ -325,21 +611,27 @@ impl Orchestrator {
 
     /// Find the best available agent using simple heuristic (least busy)
     async fn find_best_agent_heuristic(&self, required_role: &str) -> Result<AgentId> {
+        self.least_loaded_agent(required_role)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No available agent for role: {required_role}"))
+    }
+
+    /// The agent of `role` with the lowest `current_tasks`/`max_tasks` ratio
+    ///
+    /// Agents at capacity are skipped. Ties go to the agent with more free
+    /// slots, so larger agents absorb more work.
+    pub async fn least_loaded_agent(&self, role: &str) -> Option<AgentId> {
         let workloads = self.agent_workloads.read().await;
 
-        // Find agents with matching role and available capacity
-        let mut candidates: Vec<_> = workloads
+        workloads
             .values()
-            .filter(|w| w.role == required_role && w.current_tasks < w.max_tasks)
-            .collect();
-
-        // Sort by current workload (prefer least busy)
-        candidates.sort_by_key(|w| w.current_tasks);
-
-        candidates
-            .first()
+            .filter(|w| w.role == role && w.has_capacity())
+            .min_by(|a, b| {
+                a.load_ratio().total_cmp(&b.load_ratio()).then_with(|| {
+                    (b.max_tasks - b.current_tasks).cmp(&(a.max_tasks - a.current_tasks))
+                })
+            })
             .map(|w| w.agent_id)
-            .ok_or_else(|| anyhow::anyhow!("No available agent for role: {required_role}"))
     }
 
     /// Delegate task to multiple specialists and aggregate results
@@ -416,35 +708,18 @@ impl Orchestrator {
         };
 
         // Update agent workload and stats
+        let mut agent_role = None;
         if let Some(agent_id) = assigned_agent {
             let mut workloads = self.agent_workloads.write().await;
             if let Some(workload) = workloads.get_mut(&agent_id) {
+                agent_role = Some(workload.role.clone());
+
                 // Decrement current tasks
                 if workload.current_tasks > 0 {
                     workload.current_tasks -= 1;
                 }
 
-                // Update success/failure counts
-                if result.success {
-                    workload.tasks_completed += 1;
-                } else {
-                    workload.tasks_failed += 1;
-                }
-
-                // Update success rate
-                let total = workload.tasks_completed + workload.tasks_failed;
-                if total > 0 {
-                    workload.success_rate = workload.tasks_completed as f64 / total as f64;
-                }
-
-                // Update average completion time (exponential moving average)
-                let alpha = 0.2; // Smoothing factor
-                if workload.avg_completion_time == 0.0 {
-                    workload.avg_completion_time = duration_ms as f64;
-                } else {
-                    workload.avg_completion_time =
-                        alpha * duration_ms as f64 + (1.0 - alpha) * workload.avg_completion_time;
-                }
+                workload.record_outcome(result.success, duration_ms);
 
                 debug!(
                     "Agent {} stats: success_rate={:.2}, avg_time={:.0}ms, completed={}, failed={}",
@@ -454,6 +729,10 @@ impl Orchestrator {
             }
         }
 
+        if let Some(role) = agent_role {
+            self.record_role_outcome(&role, result.success).await;
+        }
+
         // Record RL experience for learning
         if let Some(ref rl_service) = self.rl_service {
             if let Some(agent_id) = assigned_agent {
@@ -733,7 +1012,7 @@ impl Orchestrator {
     pub async fn list_tasks(&self, limit: usize) -> Vec<Task> {
         let tasks = self.tasks.read().await;
         let mut task_list: Vec<_> = tasks.values().cloned().collect();
-        task_list.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        task_list.sort_by_key(|t| std::cmp::Reverse(t.created_at));
         task_list.into_iter().take(limit).collect()
     }
 
@@ -743,6 +1022,47 @@ impl Orchestrator {
         workloads.values().cloned().collect()
     }
 
+    /// Record the current per-role workload in the history ring buffer
+    ///
+    /// `busy_agents` are counted as busy even if the orchestrator didn't route
+    /// their current task (e.g. daemon delegations). `queued` is the number of
+    /// delegations waiting for an agent, by role.
+    pub async fn record_workload_sample(&self, busy_agents: &[AgentId], queued: &HashMap<String, usize>) {
+        let mut roles: BTreeMap<String, RoleLoad> = BTreeMap::new();
+        {
+            let workloads = self.agent_workloads.read().await;
+            for w in workloads.values() {
+                let load = roles.entry(w.role.clone()).or_default();
+                load.agents += 1;
+                if w.current_tasks > 0 || busy_agents.contains(&w.agent_id) {
+                    load.busy += 1;
+                }
+            }
+        }
+        for (role, waiting) in queued {
+            roles.entry(role.clone()).or_default().queued += waiting;
+        }
+
+        let mut history = self.workload_history.write().await;
+        if history.len() == WORKLOAD_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(WorkloadSample {
+            timestamp: Utc::now(),
+            roles,
+        });
+    }
+
+    /// Workload samples taken at or after `since`, oldest first
+    pub async fn workload_history(&self, since: DateTime<Utc>) -> Vec<WorkloadSample> {
+        let history = self.workload_history.read().await;
+        history
+            .iter()
+            .filter(|sample| sample.timestamp >= since)
+            .cloned()
+            .collect()
+    }
+
     /// Get pending aggregation count
     pub async fn pending_aggregation_count(&self) -> usize {
         let pending = self.pending_aggregations.read().await;
@@ -765,7 +1085,7 @@ mod tests {
         let orchestrator = Orchestrator::new();
 
         let agent_id = AgentId::new();
-        orchestrator
+        let replaced = orchestrator
             .register_agent(
                 agent_id,
                 "specialist".to_string(),
@@ -773,11 +1093,96 @@ mod tests {
                 5,
             )
             .await;
+        assert!(!replaced);
 
         let workloads = orchestrator.get_agent_workloads().await;
         assert_eq!(workloads.len(), 1);
         assert_eq!(workloads[0].role, "specialist");
         assert_eq!(workloads[0].max_tasks, 5);
+
+        // Registering again replaces the entry
+        assert!(
+            orchestrator
+                .register_agent(agent_id, "specialist".to_string(), vec![], 2)
+                .await
+        );
+        let workloads = orchestrator.get_agent_workloads().await;
+        assert_eq!(workloads.len(), 1);
+        assert_eq!(workloads[0].max_tasks, 2);
+    }
+
+    #[tokio::test]
+    async fn test_register_if_absent_keeps_explicit_registration() {
+        let orchestrator = Orchestrator::new();
+        let agent_id = AgentId::new();
+
+        orchestrator
+            .register_agent(agent_id, "security".to_string(), vec!["audit".to_string()], 2)
+            .await;
+
+        // Implicit fallback must not overwrite the explicit registration
+        let registered = orchestrator
+            .register_agent_if_absent(agent_id, "security".to_string(), vec![], 5)
+            .await;
+        assert!(!registered);
+
+        let workloads = orchestrator.get_agent_workloads().await;
+        assert_eq!(workloads.len(), 1);
+        assert_eq!(workloads[0].max_tasks, 2);
+        assert_eq!(workloads[0].capabilities, vec!["audit".to_string()]);
+
+        // Unknown agents are registered by the fallback
+        let other = AgentId::new();
+        assert!(
+            orchestrator
+                .register_agent_if_absent(other, "qa".to_string(), vec![], 5)
+                .await
+        );
+        assert!(orchestrator.is_agent_registered(other).await);
+    }
+
+    #[tokio::test]
+    async fn test_link_worker_carries_stats_across_reconnects() {
+        let orchestrator = Orchestrator::new();
+        let (old, new) = (AgentId::new(), AgentId::new());
+        orchestrator.register_agent(old, "backend".to_string(), vec![], 5).await;
+        assert_eq!(orchestrator.link_worker(old, "worker-7").await, 0);
+        {
+            let mut workloads = orchestrator.agent_workloads.write().await;
+            let workload = workloads.get_mut(&old).unwrap();
+            workload.record_outcome(true, 100);
+            workload.record_outcome(false, 300);
+        }
+
+        // The reconnect gets a new agent ID but the same worker ID
+        orchestrator.register_agent(new, "backend".to_string(), vec![], 5).await;
+        assert_eq!(orchestrator.link_worker(new, "worker-7").await, 1);
+
+        let workloads = orchestrator.get_agent_workloads().await;
+        assert_eq!(workloads.len(), 1);
+        let workload = &workloads[0];
+        assert_eq!(workload.agent_id, new);
+        assert_eq!((workload.tasks_completed, workload.tasks_failed), (1, 1));
+        assert!((workload.success_rate - 0.5).abs() < f64::EPSILON);
+        assert!(workload.avg_completion_time > 0.0);
+
+        // Unknown agents and other workers are left alone
+        assert_eq!(orchestrator.link_worker(AgentId::new(), "worker-7").await, 0);
+        assert_eq!(orchestrator.get_agent_workloads().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unregister_agent() {
+        let orchestrator = Orchestrator::new();
+        let agent_id = AgentId::new();
+
+        orchestrator
+            .register_agent(agent_id, "backend".to_string(), vec![], 5)
+            .await;
+
+        assert!(orchestrator.unregister_agent(agent_id).await);
+        assert!(!orchestrator.is_agent_registered(agent_id).await);
+        assert!(!orchestrator.unregister_agent(agent_id).await);
     }
 
     #[tokio::test]
@@ -810,6 +1215,174 @@ mod tests {
         assert_eq!(best, agent2);
     }
 
+    #[tokio::test]
+    async fn test_find_best_agent_respects_max_tasks() {
+        let orchestrator = Orchestrator::new();
+
+        let small = AgentId::new();
+        let large = AgentId::new();
+
+        orchestrator
+            .register_agent(small, "backend".to_string(), vec![], 2)
+            .await;
+        orchestrator
+            .register_agent(large, "backend".to_string(), vec![], 10)
+            .await;
+
+        {
+            let mut workloads = orchestrator.agent_workloads.write().await;
+            workloads.get_mut(&small).unwrap().current_tasks = 1;
+            workloads.get_mut(&large).unwrap().current_tasks = 3;
+        }
+
+        // 3/10 is less loaded than 1/2 even though it has more tasks
+        let best = orchestrator.find_best_agent_heuristic("backend").await.unwrap();
+        assert_eq!(best, large);
+
+        // Agents at their own capacity are never selected
+        orchestrator.agent_workloads.write().await.get_mut(&large).unwrap().current_tasks = 10;
+        let best = orchestrator.find_best_agent_heuristic("backend").await.unwrap();
+        assert_eq!(best, small);
+
+        orchestrator.agent_workloads.write().await.get_mut(&small).unwrap().current_tasks = 2;
+        assert!(orchestrator.find_best_agent_heuristic("backend").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_workload_history_samples_and_window() {
+        let orchestrator = Orchestrator::new();
+        let (busy, idle) = (AgentId::new(), AgentId::new());
+        for agent_id in [busy, idle] {
+            orchestrator
+                .register_agent(agent_id, "qa".to_string(), vec![], 1)
+                .await;
+        }
+        let queued = HashMap::from([("qa".to_string(), 1)]);
+
+        for _ in 0..3 {
+            orchestrator.record_workload_sample(&[busy], &queued).await;
+        }
+
+        let all = orchestrator.workload_history(Utc::now() - chrono::Duration::minutes(5)).await;
+        assert_eq!(all.len(), 3);
+        assert_eq!(
+            all[0].roles["qa"],
+            RoleLoad { agents: 2, busy: 1, queued: 1 }
+        );
+
+        // Backdate the first sample out of the window
+        orchestrator.workload_history.write().await[0].timestamp =
+            Utc::now() - chrono::Duration::minutes(10);
+        let recent = orchestrator.workload_history(Utc::now() - chrono::Duration::minutes(5)).await;
+        assert_eq!(recent.len(), 2);
+
+        for _ in 0..WORKLOAD_HISTORY_CAPACITY {
+            orchestrator.record_workload_sample(&[], &HashMap::new()).await;
+        }
+        assert_eq!(
+            orchestrator.workload_history.read().await.len(),
+            WORKLOAD_HISTORY_CAPACITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_least_loaded_agent_spreads_work() {
+        let orchestrator = Orchestrator::new();
+        let (busy, half, light) = (AgentId::new(), AgentId::new(), AgentId::new());
+        for (agent_id, current) in [(busy, 4), (half, 2), (light, 1)] {
+            orchestrator
+                .register_agent(agent_id, "frontend".to_string(), vec![], 4)
+                .await;
+            orchestrator.agent_workloads.write().await.get_mut(&agent_id).unwrap().current_tasks =
+                current;
+        }
+
+        assert_eq!(orchestrator.least_loaded_agent("frontend").await, Some(light));
+        assert_eq!(
+            orchestrator.pick_preferred_agent(&[busy, half, light]).await,
+            Some(light)
+        );
+
+        // Once the light agent catches up, the half-loaded one is next
+        orchestrator.agent_workloads.write().await.get_mut(&light).unwrap().current_tasks = 3;
+        assert_eq!(orchestrator.least_loaded_agent("frontend").await, Some(half));
+        assert_eq!(orchestrator.least_loaded_agent("dba").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_pick_preferred_agent_favors_success_rate() {
+        let orchestrator = Orchestrator::new();
+        let (flaky, reliable) = (AgentId::new(), AgentId::new());
+        for agent_id in [flaky, reliable] {
+            orchestrator
+                .register_agent(agent_id, "backend".to_string(), vec![], 5)
+                .await;
+        }
+
+        orchestrator.record_agent_outcome(flaky, true, 100).await;
+        orchestrator.record_agent_outcome(flaky, false, 100).await;
+        orchestrator.record_agent_outcome(reliable, true, 100).await;
+
+        for _ in 0..3 {
+            assert_eq!(
+                orchestrator.pick_preferred_agent(&[flaky, reliable]).await,
+                Some(reliable)
+            );
+        }
+
+        // Equally reliable agents (3 of 4 each) take turns
+        orchestrator.record_agent_outcome(flaky, true, 100).await;
+        orchestrator.record_agent_outcome(flaky, true, 100).await;
+        orchestrator.record_agent_outcome(reliable, false, 100).await;
+        orchestrator.record_agent_outcome(reliable, true, 100).await;
+        orchestrator.record_agent_outcome(reliable, true, 100).await;
+        let first = orchestrator.pick_preferred_agent(&[flaky, reliable]).await.unwrap();
+        let second = orchestrator.pick_preferred_agent(&[flaky, reliable]).await.unwrap();
+        assert_ne!(first, second);
+
+        assert_eq!(orchestrator.pick_preferred_agent(&[]).await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_role_breaker_opens_and_recovers_after_cooldown() {
+        let orchestrator =
+            Orchestrator::new().with_circuit_breaker(2, Duration::from_secs(30));
+        let agent_id = AgentId::new();
+        orchestrator
+            .register_agent(agent_id, "devops".to_string(), vec![], 5)
+            .await;
+
+        for _ in 0..2 {
+            let task_id = orchestrator.route_task_auto(Task::new("deploy"), "devops").await.unwrap();
+            orchestrator
+                .process_result(TaskResult::failure(task_id, "worker misconfigured"))
+                .await
+                .unwrap();
+        }
+
+        let err = orchestrator
+            .route_task_auto(Task::new("deploy"), "devops")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Circuit breaker open for devops agents"), "{err}");
+        assert_eq!(orchestrator.role_breakers().await["devops"].state, "open");
+
+        // After the cooldown a probe is let through, and its success closes the breaker
+        tokio::time::advance(Duration::from_secs(30)).await;
+        let probe = orchestrator.route_task_auto(Task::new("deploy"), "devops").await.unwrap();
+        assert!(orchestrator.route_task_auto(Task::new("deploy"), "devops").await.is_err());
+        orchestrator
+            .process_result(TaskResult::success(probe, "deployed"))
+            .await
+            .unwrap();
+
+        let breaker = &orchestrator.role_breakers().await["devops"];
+        assert_eq!(breaker.state, "closed");
+        assert_eq!(breaker.consecutive_failures, 0);
+        assert!(orchestrator.route_task_auto(Task::new("deploy"), "devops").await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_task_routing() {
         let orchestrator = Orchestrator::new();
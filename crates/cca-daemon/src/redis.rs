@@ -3,14 +3,21 @@
 //! Provides connection pooling, session state storage, context caching,
 //! and Pub/Sub for inter-agent communication.
 //!
+//! Connects to a single node, to the master behind a set of Sentinels, or to a
+//! Redis Cluster, depending on `redis.mode`. With Sentinel, connections that
+//! fail are replaced by new ones to whichever node the Sentinels report as
+//! master, so a failover costs the requests in flight but needs no restart.
+//!
 //! Note: Many methods are infrastructure for future features and not yet called.
 #![allow(dead_code)]
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use deadpool_redis::{Config as PoolConfig, Connection, Pool, Runtime};
+use deadpool_redis::{cluster, sentinel, Config as PoolConfig, Connection, Pool, Runtime};
+use redis::aio::ConnectionLike;
 use redis::AsyncCommands;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::broadcast;
@@ -18,7 +25,7 @@ use tracing::{debug, error, info, warn};
 
 use cca_core::{AgentId, TaskId};
 
-use crate::config::RedisConfig;
+use crate::config::{RedisConfig, RedisMode};
 
 /// Redis key prefixes
 mod keys {
@@ -29,51 +36,213 @@ mod keys {
     pub const PUBSUB_AGENTS: &str = "cca:pubsub:agents";
     pub const PUBSUB_TASKS: &str = "cca:pubsub:tasks";
     pub const PUBSUB_BROADCAST: &str = "cca:pubsub:broadcast";
+    pub const PUBSUB_BACKLOG: &str = "cca:pubsub:backlog";
+    pub const LOCK: &str = "cca:lock:";
+}
+
+/// Deletes the lock only while it still holds the caller's token, so a daemon
+/// whose lock expired can't release the lock another daemon took over
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Extends the lock's expiry only while it still holds the caller's token
+const EXTEND_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Connection pool for the configured Redis topology
+enum RedisPool {
+    Single(Pool),
+    Sentinel(sentinel::Pool),
+    Cluster(cluster::Pool),
+}
+
+impl RedisPool {
+    fn new(config: &RedisConfig) -> Result<Self> {
+        let pool = match config.mode {
+            RedisMode::Single => Self::Single(
+                PoolConfig::from_url(&config.url)
+                    .builder()
+                    .map_err(|e| anyhow::anyhow!("Failed to create pool builder: {e}"))?
+                    .max_size(config.pool_size)
+                    .runtime(Runtime::Tokio1)
+                    .build()
+                    .context("Failed to create Redis connection pool")?,
+            ),
+            RedisMode::Sentinel => Self::Sentinel(
+                sentinel::Config::from_urls(
+                    config.node_urls.clone(),
+                    config.sentinel_master.clone(),
+                    sentinel::SentinelServerType::Master,
+                )
+                .builder()
+                .map_err(|e| anyhow::anyhow!("Failed to create Sentinel pool builder: {e}"))?
+                .max_size(config.pool_size)
+                .runtime(Runtime::Tokio1)
+                .build()
+                .context("Failed to create Redis Sentinel connection pool")?,
+            ),
+            RedisMode::Cluster => Self::Cluster(
+                cluster::Config::from_urls(config.node_urls.clone())
+                    .builder()
+                    .map_err(|e| anyhow::anyhow!("Failed to create cluster pool builder: {e}"))?
+                    .max_size(config.pool_size)
+                    .runtime(Runtime::Tokio1)
+                    .build()
+                    .context("Failed to create Redis Cluster connection pool")?,
+            ),
+        };
+        Ok(pool)
+    }
+
+    async fn get(&self) -> Result<RedisConnection> {
+        let conn = match self {
+            Self::Single(pool) => pool.get().await.map(RedisConnection::Single)?,
+            Self::Sentinel(pool) => pool.get().await.map(RedisConnection::Sentinel)?,
+            Self::Cluster(pool) => pool.get().await.map(RedisConnection::Cluster)?,
+        };
+        Ok(conn)
+    }
+}
+
+/// Pooled connection to a single node, a Sentinel-managed master or a cluster
+pub enum RedisConnection {
+    Single(Connection),
+    Sentinel(sentinel::Connection),
+    Cluster(cluster::Connection),
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(
+        &'a mut self,
+        cmd: &'a redis::Cmd,
+    ) -> redis::RedisFuture<'a, redis::Value> {
+        match self {
+            Self::Single(conn) => conn.req_packed_command(cmd),
+            Self::Sentinel(conn) => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'a, Vec<redis::Value>> {
+        match self {
+            Self::Single(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Sentinel(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single(conn) => conn.get_db(),
+            Self::Sentinel(conn) => conn.get_db(),
+            Self::Cluster(conn) => conn.get_db(),
+        }
+    }
 }
 
 /// Redis client with connection pool
 pub struct RedisClient {
-    pool: Pool,
+    pool: RedisPool,
     config: RedisConfig,
 }
 
 impl RedisClient {
     /// Create a new Redis client with connection pool
     pub async fn new(config: &RedisConfig) -> Result<Self> {
-        info!("Connecting to Redis at {}", config.url);
+        match config.mode {
+            RedisMode::Single => info!("Connecting to Redis at {}", config.url),
+            RedisMode::Sentinel => info!(
+                "Connecting to Redis master '{}' through {} Sentinel(s)",
+                config.sentinel_master,
+                config.node_urls.len()
+            ),
+            RedisMode::Cluster => info!(
+                "Connecting to Redis Cluster through {} seed node(s)",
+                config.node_urls.len()
+            ),
+        }
 
-        let pool_config = PoolConfig::from_url(&config.url);
-        let pool = pool_config
-            .builder()
-            .map_err(|e| anyhow::anyhow!("Failed to create pool builder: {e}"))?
-            .max_size(config.pool_size)
-            .runtime(Runtime::Tokio1)
-            .build()
-            .context("Failed to create Redis connection pool")?;
+        let client = Self {
+            pool: RedisPool::new(config)?,
+            config: config.clone(),
+        };
 
         // Test connection
-        let mut conn = pool.get().await.context("Failed to get Redis connection")?;
+        client.ping().await?;
+
+        info!("Redis connection established (pool size: {})", config.pool_size);
+
+        Ok(client)
+    }
+
+    /// Send `PING` over a pooled connection
+    pub async fn ping(&self) -> Result<()> {
+        let mut conn = self.get_conn().await?;
         let _: String = redis::cmd("PING")
             .query_async(&mut conn)
             .await
             .context("Redis PING failed")?;
-
-        info!("Redis connection established (pool size: {})", config.pool_size);
-
-        Ok(Self {
-            pool,
-            config: config.clone(),
-        })
+        Ok(())
     }
 
     /// Get a connection from the pool
-    pub async fn get_conn(&self) -> Result<Connection> {
+    pub async fn get_conn(&self) -> Result<RedisConnection> {
         self.pool
             .get()
             .await
             .context("Failed to get Redis connection from pool")
     }
 
+    /// Open a Pub/Sub connection
+    ///
+    /// With Sentinel this asks for the current master each time, so a listener
+    /// that reconnects after a failover follows the new master. Cluster
+    /// `PUBLISH` reaches every node, so the first reachable seed node will do.
+    async fn pubsub(&self) -> Result<redis::aio::PubSub> {
+        match self.config.mode {
+            RedisMode::Single => {
+                let client = redis::Client::open(self.config.url.as_str())?;
+                Ok(client.get_async_pubsub().await?)
+            }
+            RedisMode::Sentinel => {
+                let mut sentinel = redis::sentinel::Sentinel::build(self.config.node_urls.clone())?;
+                let client = sentinel
+                    .async_master_for(&self.config.sentinel_master, None)
+                    .await?;
+                Ok(client.get_async_pubsub().await?)
+            }
+            RedisMode::Cluster => {
+                let mut last_error = None;
+                for url in &self.config.node_urls {
+                    match redis::Client::open(url.as_str()) {
+                        Ok(client) => match client.get_async_pubsub().await {
+                            Ok(pubsub) => return Ok(pubsub),
+                            Err(e) => last_error = Some(e),
+                        },
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                Err(last_error.map_or_else(
+                    || anyhow::anyhow!("No Redis Cluster nodes configured"),
+                    anyhow::Error::from,
+                ))
+            }
+        }
+    }
+
     /// Get the default TTL for context entries
     pub fn context_ttl(&self) -> Duration {
         Duration::from_secs(self.config.context_ttl_seconds)
@@ -385,13 +554,35 @@ impl PubSubHandler {
     }
 
     /// Internal publish method for typed messages
+    ///
+    /// The message is first appended to the backlog stream, and its stream ID
+    /// is sent along so listeners can tell replayed messages from live ones.
     async fn publish_to(&self, channel: &str, message: &PubSubMessage) -> Result<()> {
         let json = serde_json::to_string(message)?;
         let mut conn = self.client.get_conn().await?;
 
+        let backlog_size = self.client.config.pubsub_backlog_size;
+        let stream_id = if backlog_size > 0 {
+            let id: String = redis::cmd("XADD")
+                .arg(keys::PUBSUB_BACKLOG)
+                .arg("MAXLEN")
+                .arg(backlog_size)
+                .arg("*")
+                .arg("channel")
+                .arg(channel)
+                .arg("message")
+                .arg(&json)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to append to Pub/Sub backlog")?;
+            Some(id)
+        } else {
+            None
+        };
+
         redis::cmd("PUBLISH")
             .arg(channel)
-            .arg(&json)
+            .arg(encode_payload(message, stream_id.as_deref())?)
             .query_async::<()>(&mut conn)
             .await?;
 
@@ -404,10 +595,14 @@ impl PubSubHandler {
     }
 
     /// Start listening for messages (runs in background)
+    ///
+    /// After a dropped connection the listener reconnects and replays what was
+    /// published in the meantime from the backlog.
     pub async fn start_listener(client: Arc<RedisClient>, tx: broadcast::Sender<PubSubMessage>) {
         tokio::spawn(async move {
+            let mut cursor = BacklogCursor::default();
             loop {
-                if let Err(e) = Self::listen_loop(&client, &tx).await {
+                if let Err(e) = Self::listen_loop(&client, &tx, &mut cursor).await {
                     error!("Pub/Sub listener error: {}", e);
                     tokio::time::sleep(Duration::from_secs(1)).await;
                 }
@@ -418,30 +613,181 @@ impl PubSubHandler {
     async fn listen_loop(
         client: &RedisClient,
         tx: &broadcast::Sender<PubSubMessage>,
+        cursor: &mut BacklogCursor,
     ) -> Result<()> {
-        let redis_client = redis::Client::open(client.config.url.as_str())?;
-        let mut pubsub = redis_client.get_async_pubsub().await?;
+        let mut pubsub = client.pubsub().await?;
 
         pubsub.subscribe(keys::PUBSUB_AGENTS).await?;
         pubsub.subscribe(keys::PUBSUB_TASKS).await?;
         pubsub.subscribe(keys::PUBSUB_BROADCAST).await?;
 
+        // Subscribed first, so nothing published while the backlog is read is
+        // lost; the cursor drops live copies of what was just replayed
+        if client.config.pubsub_backlog_size > 0 {
+            Self::replay_backlog(client, tx, cursor).await?;
+        }
+
         info!("Pub/Sub listener started");
 
+        let mut messages = pubsub.on_message();
         loop {
-            let msg = pubsub.on_message().next().await;
-            if let Some(msg) = msg {
-                let payload: String = msg.get_payload()?;
-                match serde_json::from_str::<PubSubMessage>(&payload) {
-                    Ok(message) => {
+            let Some(msg) = messages.next().await else {
+                anyhow::bail!("Pub/Sub connection closed");
+            };
+            let payload: String = msg.get_payload()?;
+            match decode_payload(&payload) {
+                Ok((stream_id, message)) => {
+                    if stream_id.map_or(true, |id| cursor.live(&id)) {
                         let _ = tx.send(message);
                     }
-                    Err(e) => {
-                        warn!("Failed to parse Pub/Sub message: {}", e);
-                    }
                 }
+                Err(e) => {
+                    warn!("Failed to parse Pub/Sub message: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Deliver backlog entries newer than `cursor`
+    ///
+    /// On first connect there's nothing to catch up on, so this only moves the
+    /// cursor to the newest entry.
+    async fn replay_backlog(
+        client: &RedisClient,
+        tx: &broadcast::Sender<PubSubMessage>,
+        cursor: &mut BacklogCursor,
+    ) -> Result<()> {
+        let mut conn = client.get_conn().await?;
+
+        let Some(start) = cursor.next_id() else {
+            let newest: redis::streams::StreamRangeReply = redis::cmd("XREVRANGE")
+                .arg(keys::PUBSUB_BACKLOG)
+                .arg("+")
+                .arg("-")
+                .arg("COUNT")
+                .arg(1)
+                .query_async(&mut conn)
+                .await?;
+            cursor.seen(newest.ids.first().map_or("0-0", |entry| entry.id.as_str()));
+            return Ok(());
+        };
+        cursor.begin_replay();
+
+        let missed: redis::streams::StreamRangeReply = redis::cmd("XRANGE")
+            .arg(keys::PUBSUB_BACKLOG)
+            .arg(start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(client.config.pubsub_backlog_size)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read Pub/Sub backlog")?;
+
+        if !missed.ids.is_empty() {
+            info!("Replaying {} Pub/Sub message(s) from the backlog", missed.ids.len());
+        }
+        for entry in missed.ids {
+            let Some(json) = entry.get::<String>("message") else {
+                continue;
+            };
+            if !cursor.replay(&entry.id) {
+                continue;
+            }
+            match serde_json::from_str::<PubSubMessage>(&json) {
+                Ok(message) => {
+                    let _ = tx.send(message);
+                }
+                Err(e) => warn!("Failed to parse backlog message {}: {}", entry.id, e),
             }
         }
+        Ok(())
+    }
+}
+
+/// Serialize a message for `PUBLISH`, tagged with its backlog stream ID
+fn encode_payload(message: &PubSubMessage, stream_id: Option<&str>) -> serde_json::Result<String> {
+    let mut value = serde_json::to_value(message)?;
+    if let (Some(id), Some(object)) = (stream_id, value.as_object_mut()) {
+        object.insert("stream_id".to_string(), id.into());
+    }
+    serde_json::to_string(&value)
+}
+
+/// Parse a `PUBLISH` payload into its backlog stream ID, if any, and message
+fn decode_payload(payload: &str) -> serde_json::Result<(Option<String>, PubSubMessage)> {
+    let mut value: serde_json::Value = serde_json::from_str(payload)?;
+    let stream_id = value
+        .as_object_mut()
+        .and_then(|object| object.remove("stream_id"))
+        .and_then(|id| id.as_str().map(str::to_string));
+    Ok((stream_id, serde_json::from_value(value)?))
+}
+
+/// Where a listener is in the backlog
+///
+/// `last` is the newest stream ID seen, where the next replay starts. It only
+/// filters the replayed batch: `XADD` and `PUBLISH` are separate round trips,
+/// so with several publishers live messages can arrive out of ID order, and a
+/// live message below `last` is still new. Live messages are instead checked
+/// against the IDs delivered by the latest replay.
+#[derive(Debug, Default)]
+struct BacklogCursor {
+    last: Option<(u64, u64)>,
+    /// IDs delivered by the latest replay whose live copy hasn't arrived yet,
+    /// at most one `XRANGE` page
+    replayed: HashSet<(u64, u64)>,
+}
+
+impl BacklogCursor {
+    fn parse(id: &str) -> Option<(u64, u64)> {
+        let (ms, seq) = id.split_once('-')?;
+        Some((ms.parse().ok()?, seq.parse().ok()?))
+    }
+
+    /// Move `last` up to `id` without delivering anything
+    fn seen(&mut self, id: &str) {
+        if let Some(id) = Self::parse(id) {
+            self.last = self.last.max(Some(id));
+        }
+    }
+
+    /// Start a new replay, forgetting the previous batch
+    fn begin_replay(&mut self) {
+        self.replayed.clear();
+    }
+
+    /// Whether a backlog entry should be replayed; `false` if it's at or below
+    /// `last`, i.e. it was read before the connection dropped
+    fn replay(&mut self, id: &str) -> bool {
+        let Some(parsed) = Self::parse(id) else {
+            return true;
+        };
+        if self.last.is_some_and(|last| parsed <= last) {
+            return false;
+        }
+        self.replayed.insert(parsed);
+        self.last = Some(parsed);
+        true
+    }
+
+    /// Whether a live message should be delivered; `false` only if the latest
+    /// replay already delivered it
+    ///
+    /// An unparseable ID is always delivered.
+    fn live(&mut self, id: &str) -> bool {
+        let Some(parsed) = Self::parse(id) else {
+            return true;
+        };
+        if self.replayed.remove(&parsed) {
+            return false;
+        }
+        self.last = self.last.max(Some(parsed));
+        true
+    }
+
+    /// First stream ID after `last`, for `XRANGE`
+    fn next_id(&self) -> Option<String> {
+        self.last.map(|(ms, seq)| format!("{}-{}", ms, seq + 1))
     }
 }
 
@@ -474,6 +820,61 @@ impl RedisServices {
             pubsub,
         })
     }
+
+    /// Take the lock `key` for `ttl` if nobody holds it
+    ///
+    /// Returns the ownership token to pass to [`Self::release_lock`], or `None`
+    /// when another holder has it. The lock expires on its own after `ttl`, so
+    /// a daemon that dies while holding it only blocks others until then.
+    pub async fn try_acquire_lock(&self, key: &str, ttl: Duration) -> Result<Option<String>> {
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut conn = self.client.get_conn().await?;
+
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", keys::LOCK, key))
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis().max(1) as u64)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to acquire lock")?;
+
+        Ok(acquired.map(|_| token))
+    }
+
+    /// Release the lock `key` if it's still held with `token`
+    ///
+    /// Returns `false` when the lock had already expired or been taken over.
+    pub async fn release_lock(&self, key: &str, token: &str) -> Result<bool> {
+        let mut conn = self.client.get_conn().await?;
+
+        let deleted: i64 = redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(format!("{}{}", keys::LOCK, key))
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to release lock")?;
+
+        Ok(deleted == 1)
+    }
+
+    /// Reset the expiry of the lock `key` to `ttl` if it's still held with `token`
+    ///
+    /// Returns `false` when the lock had already expired or been taken over.
+    pub async fn extend_lock(&self, key: &str, token: &str, ttl: Duration) -> Result<bool> {
+        let mut conn = self.client.get_conn().await?;
+
+        let extended: i64 = redis::Script::new(EXTEND_LOCK_SCRIPT)
+            .key(format!("{}{}", keys::LOCK, key))
+            .arg(token)
+            .arg(ttl.as_millis().max(1) as u64)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to extend lock")?;
+
+        Ok(extended == 1)
+    }
 }
 
 // Needed for the listen_loop
@@ -488,6 +889,7 @@ mod tests {
         assert!(keys::SESSION.starts_with("cca:"));
         assert!(keys::CONTEXT.starts_with("cca:"));
         assert!(keys::AGENT_STATE.starts_with("cca:"));
+        assert!(keys::LOCK.starts_with("cca:"));
     }
 
     #[test]
@@ -520,4 +922,285 @@ mod tests {
         assert_eq!(parsed.context_hash, "abc123");
         assert_eq!(parsed.token_count, 1000);
     }
+
+    #[tokio::test]
+    async fn test_ping_fails_without_a_server() {
+        // Building the pool doesn't connect; nothing listens on port 1
+        let config = RedisConfig {
+            url: "redis://127.0.0.1:1".to_string(),
+            ..RedisConfig::default()
+        };
+        let client = RedisClient {
+            pool: RedisPool::new(&config).unwrap(),
+            config,
+        };
+        assert!(client.ping().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sentinel_pool_builds_without_connecting() {
+        // Sentinels are only contacted when a connection is first requested
+        let config = RedisConfig {
+            mode: RedisMode::Sentinel,
+            node_urls: vec!["redis://127.0.0.1:1".to_string()],
+            ..RedisConfig::default()
+        };
+        let client = RedisClient {
+            pool: RedisPool::new(&config).unwrap(),
+            config,
+        };
+        assert!(client.ping().await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis Sentinel (set CCA_TEST_SENTINEL_URLS and CCA_TEST_SENTINEL_MASTER)"]
+    async fn test_sentinel_failover_reconnects_to_new_master() {
+        let urls = std::env::var("CCA_TEST_SENTINEL_URLS").expect("CCA_TEST_SENTINEL_URLS not set");
+        let config = RedisConfig {
+            mode: RedisMode::Sentinel,
+            node_urls: urls.split(',').map(|u| u.trim().to_string()).collect(),
+            sentinel_master: std::env::var("CCA_TEST_SENTINEL_MASTER")
+                .unwrap_or_else(|_| "mymaster".to_string()),
+            ..RedisConfig::default()
+        };
+        let client = RedisClient::new(&config).await.unwrap();
+        let key = format!("cca:test:failover:{}", uuid::Uuid::new_v4());
+        let mut conn = client.get_conn().await.unwrap();
+        conn.set::<_, _, ()>(&key, "before").await.unwrap();
+        drop(conn);
+
+        let master_addr = |sentinel: redis::Client| {
+            let master = config.sentinel_master.clone();
+            async move {
+                let mut conn = sentinel.get_multiplexed_async_connection().await.unwrap();
+                redis::cmd("SENTINEL")
+                    .arg("get-master-addr-by-name")
+                    .arg(master)
+                    .query_async::<Vec<String>>(&mut conn)
+                    .await
+                    .unwrap()
+            }
+        };
+        let sentinel = redis::Client::open(config.node_urls[0].as_str()).unwrap();
+        let old_master = master_addr(sentinel.clone()).await;
+
+        let mut sentinel_conn = sentinel.get_multiplexed_async_connection().await.unwrap();
+        redis::cmd("SENTINEL")
+            .arg("FAILOVER")
+            .arg(&config.sentinel_master)
+            .query_async::<()>(&mut sentinel_conn)
+            .await
+            .unwrap();
+
+        // Writes fail while the master changes, then succeed against the new one
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        loop {
+            assert!(tokio::time::Instant::now() < deadline, "no write succeeded after failover");
+            if master_addr(sentinel.clone()).await != old_master {
+                if let Ok(mut conn) = client.get_conn().await {
+                    if conn.set::<_, _, ()>(&key, "after").await.is_ok() {
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        let mut conn = client.get_conn().await.unwrap();
+        let value: String = conn.get(&key).await.unwrap();
+        assert_eq!(value, "after");
+        conn.del::<_, ()>(&key).await.unwrap();
+    }
+
+    #[test]
+    fn test_payload_carries_stream_id() {
+        let msg = PubSubMessage::Broadcast {
+            from: AgentId::new(),
+            message: "hello".to_string(),
+        };
+
+        let (id, parsed) = decode_payload(&encode_payload(&msg, Some("5-1")).unwrap()).unwrap();
+        assert_eq!(id.as_deref(), Some("5-1"));
+        assert!(matches!(parsed, PubSubMessage::Broadcast { message, .. } if message == "hello"));
+
+        // Payloads from publishers without a backlog have no ID
+        let (id, _) = decode_payload(&serde_json::to_string(&msg).unwrap()).unwrap();
+        assert_eq!(id, None);
+    }
+
+    #[test]
+    fn test_backlog_cursor_skips_delivered_ids() {
+        let mut cursor = BacklogCursor::default();
+        assert_eq!(cursor.next_id(), None);
+
+        assert!(cursor.live("10-0"));
+        assert!(cursor.live("10-1"));
+        assert_eq!(cursor.next_id().as_deref(), Some("10-2"));
+
+        // Replay after a reconnect skips what was read before the drop
+        cursor.begin_replay();
+        assert!(!cursor.replay("10-1"));
+        assert!(!cursor.replay("9-5"));
+        assert!(cursor.replay("11-0"));
+        // ...and the live copy of a replayed message is sent once
+        assert!(!cursor.live("11-0"));
+        assert!(cursor.live("12-0"));
+
+        assert!(cursor.live("not-an-id"));
+        assert_eq!(cursor.next_id().as_deref(), Some("12-1"));
+    }
+
+    #[test]
+    fn test_backlog_cursor_delivers_live_ids_out_of_order() {
+        let mut cursor = BacklogCursor::default();
+        cursor.seen("5-0");
+
+        // Two publishers: the later XADD's PUBLISH overtakes the earlier one
+        assert!(cursor.live("20-0"));
+        assert!(cursor.live("19-0"));
+        assert!(cursor.live("19-1"));
+        assert_eq!(cursor.next_id().as_deref(), Some("20-1"));
+
+        // Same after a replay: only replayed IDs are dropped
+        cursor.begin_replay();
+        assert!(cursor.replay("21-0"));
+        assert!(cursor.replay("23-0"));
+        assert!(cursor.live("22-0"));
+        assert!(!cursor.live("23-0"));
+        assert!(!cursor.live("21-0"));
+        assert!(cursor.replayed.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_listener_replays_messages_published_while_disconnected() {
+        let config = RedisConfig {
+            url: std::env::var("CCA_TEST_REDIS_URL").expect("CCA_TEST_REDIS_URL not set"),
+            ..RedisConfig::default()
+        };
+        let client = Arc::new(RedisClient::new(&config).await.unwrap());
+        let publisher = PubSubHandler::new(client.clone()).await.unwrap();
+        let (tx, mut rx) = broadcast::channel(100);
+        PubSubHandler::start_listener(client.clone(), tx).await;
+
+        let broadcast = |message: &str| PubSubMessage::Broadcast {
+            from: AgentId::new(),
+            message: message.to_string(),
+        };
+
+        // Wait until the listener is subscribed
+        let received = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                publisher.broadcast(&broadcast("ready")).await.unwrap();
+                if let Ok(Ok(_)) = tokio::time::timeout(Duration::from_millis(200), rx.recv()).await {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(received.is_ok(), "listener never subscribed");
+        while rx.try_recv().is_ok() {}
+
+        // Drop the listener's connection, then publish before it reconnects
+        let mut conn = client.get_conn().await.unwrap();
+        redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("TYPE")
+            .arg("pubsub")
+            .query_async::<i64>(&mut conn)
+            .await
+            .unwrap();
+        for i in 0..3 {
+            publisher.broadcast(&broadcast(&format!("gap-{i}"))).await.unwrap();
+        }
+
+        let mut delivered = Vec::new();
+        while delivered.len() < 3 {
+            let message = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+                .await
+                .expect("messages published during the disconnect were not replayed")
+                .unwrap();
+            if let PubSubMessage::Broadcast { message, .. } = message {
+                if message.starts_with("gap-") {
+                    delivered.push(message);
+                }
+            }
+        }
+        assert_eq!(delivered, vec!["gap-0", "gap-1", "gap-2"]);
+    }
+
+    /// Redis services for lock tests, when `CCA_TEST_REDIS_URL` is set
+    async fn lock_services() -> RedisServices {
+        let url = std::env::var("CCA_TEST_REDIS_URL").expect("CCA_TEST_REDIS_URL not set");
+        let config = RedisConfig {
+            url,
+            ..RedisConfig::default()
+        };
+        RedisServices::new(&config).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_lock_acquire_contend_release() {
+        let redis = lock_services().await;
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+        let ttl = Duration::from_secs(30);
+
+        let token = redis.try_acquire_lock(&key, ttl).await.unwrap().unwrap();
+        assert!(redis.try_acquire_lock(&key, ttl).await.unwrap().is_none());
+
+        // Only the holder's token releases the lock
+        assert!(!redis.release_lock(&key, "someone-else").await.unwrap());
+        assert!(redis.try_acquire_lock(&key, ttl).await.unwrap().is_none());
+        assert!(redis.release_lock(&key, &token).await.unwrap());
+        assert!(!redis.release_lock(&key, &token).await.unwrap());
+
+        let token = redis.try_acquire_lock(&key, ttl).await.unwrap().unwrap();
+        assert!(redis.release_lock(&key, &token).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_lock_expiry_allows_takeover() {
+        let redis = lock_services().await;
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+
+        let stale = redis
+            .try_acquire_lock(&key, Duration::from_millis(200))
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+
+        let token = redis
+            .try_acquire_lock(&key, Duration::from_secs(30))
+            .await
+            .unwrap()
+            .expect("expired lock should be free");
+
+        // The first holder's late release must not free the new holder's lock
+        assert!(!redis.release_lock(&key, &stale).await.unwrap());
+        assert!(redis.try_acquire_lock(&key, Duration::from_secs(30)).await.unwrap().is_none());
+        assert!(redis.release_lock(&key, &token).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires Redis (set CCA_TEST_REDIS_URL)"]
+    async fn test_lock_extend_keeps_holder() {
+        let redis = lock_services().await;
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+        let ttl = Duration::from_millis(300);
+
+        let token = redis.try_acquire_lock(&key, ttl).await.unwrap().unwrap();
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            assert!(redis.extend_lock(&key, &token, ttl).await.unwrap());
+        }
+        // Held well past the original TTL; other tokens can't extend it
+        assert!(redis.try_acquire_lock(&key, ttl).await.unwrap().is_none());
+        assert!(!redis.extend_lock(&key, "someone-else", ttl).await.unwrap());
+
+        assert!(redis.release_lock(&key, &token).await.unwrap());
+        assert!(!redis.extend_lock(&key, &token, ttl).await.unwrap());
+    }
 }
@@ -0,0 +1,141 @@
+//! Request ID propagation
+//!
+//! Every HTTP request gets an id, either taken from a well-formed incoming
+//! `X-Request-Id` header or freshly generated. The id is stored as a request
+//! extension, attached to a `tracing` span wrapping the handler (so every log
+//! line emitted while serving the request carries it), echoed back in the
+//! response header, and forwarded to agents with delegated tasks.
+
+use std::fmt;
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Header carrying the request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied id we accept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation id for a single API request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Accept a client-supplied id if it is short, non-empty visible ASCII
+    ///
+    /// Anything else could break log parsing or header echoing.
+    pub fn parse(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Assign a request id, run the rest of the stack inside its span and echo it back
+pub async fn request_id_middleware(mut request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(RequestId::parse)
+        .unwrap_or_default();
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    request.extensions_mut().insert(request_id.clone());
+
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/echo",
+                get(|Extension(id): Extension<RequestId>| async move { id.to_string() }),
+            )
+            .layer(axum::middleware::from_fn(request_id_middleware))
+    }
+
+    async fn send(header: Option<&str>) -> (String, String) {
+        let mut request = Request::builder().uri("/echo");
+        if let Some(value) = header {
+            request = request.header(REQUEST_ID_HEADER, value);
+        }
+        let response = router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let echoed = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        (echoed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_response_echoes_request_id() {
+        let (echoed, seen_by_handler) = send(Some("trace-abc-123")).await;
+        assert_eq!(echoed, "trace-abc-123");
+        assert_eq!(seen_by_handler, "trace-abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_missing_or_invalid() {
+        let (echoed, seen_by_handler) = send(None).await;
+        assert!(Uuid::parse_str(&echoed).is_ok());
+        assert_eq!(echoed, seen_by_handler);
+
+        let too_long = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+        let (echoed, _) = send(Some(&too_long)).await;
+        assert!(Uuid::parse_str(&echoed).is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_unsafe_values() {
+        assert!(RequestId::parse("abc-123_DEF.4").is_some());
+        assert!(RequestId::parse("").is_none());
+        assert!(RequestId::parse("has space").is_none());
+        assert!(RequestId::parse("line\nbreak").is_none());
+    }
+}
@@ -0,0 +1,261 @@
+//! Service-unavailable responses for API handlers
+//!
+//! Handlers that depend on an optional backing service (PostgreSQL, Redis,
+//! embeddings, indexing) return `ServiceResult` so a missing service surfaces
+//! as `503 Service Unavailable` instead of a 200 with `"success": false`.
+//! The JSON body keeps the shape each endpoint already used. Invalid input
+//! short-circuits the same way, as a `400` from [`ValidationError`], and a
+//! [`CCAError`] becomes a response carrying its stable `code`. Handlers can
+//! also `?` an `anyhow::Error`: [`classify_error`] picks the variant (and so
+//! the status) from the errors in its chain.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use cca_core::CCAError;
+use serde::de::Error as _;
+use tracing::warn;
+
+use crate::idempotency::IdempotencyError;
+use crate::postgres::QueryTimedOut;
+use crate::validation::ValidationError;
+
+/// A backing service required by the endpoint isn't available
+#[derive(Debug)]
+pub struct ServiceUnavailable(pub serde_json::Value);
+
+impl IntoResponse for ServiceUnavailable {
+    fn into_response(self) -> Response {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(self.0)).into_response()
+    }
+}
+
+/// HTTP status for a core error
+fn core_error_status(err: &CCAError) -> StatusCode {
+    match err {
+        CCAError::NotFound(_) => StatusCode::NOT_FOUND,
+        CCAError::Task(_) => StatusCode::CONFLICT,
+        CCAError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        CCAError::Agent(_) | CCAError::Communication(_) => StatusCode::BAD_GATEWAY,
+        CCAError::Redis(_) | CCAError::Database(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Why a service-backed handler stopped early
+#[derive(Debug)]
+pub enum ServiceError {
+    Unavailable(ServiceUnavailable),
+    Invalid(ValidationError),
+    Core(CCAError),
+    Idempotency(IdempotencyError),
+}
+
+impl From<CCAError> for ServiceError {
+    fn from(err: CCAError) -> Self {
+        Self::Core(err)
+    }
+}
+
+impl From<anyhow::Error> for ServiceError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::Core(classify_error(err))
+    }
+}
+
+/// Map an error chain onto the closest [`CCAError`] variant
+///
+/// The first recognized error in the chain decides the variant; the message
+/// keeps the whole chain, contexts included. Anything unrecognized is
+/// `Internal`.
+pub fn classify_error(err: anyhow::Error) -> CCAError {
+    let err = match err.downcast::<CCAError>() {
+        Ok(core) => return core,
+        Err(err) => err,
+    };
+    let message = format!("{err:#}");
+
+    for cause in err.chain() {
+        if cause.is::<QueryTimedOut>() || cause.is::<tokio::time::error::Elapsed>() {
+            return CCAError::Timeout(message);
+        }
+        if let Some(e) = cause.downcast_ref::<sqlx::Error>() {
+            return match e {
+                sqlx::Error::PoolTimedOut => CCAError::Timeout(message),
+                _ => CCAError::Database(message),
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<redis::RedisError>() {
+            return if e.is_timeout() { CCAError::Timeout(message) } else { CCAError::Redis(message) };
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_timeout() {
+                CCAError::Timeout(message)
+            } else {
+                CCAError::Communication(message)
+            };
+        }
+        if cause.is::<serde_json::Error>() {
+            return CCAError::Serialization(serde_json::Error::custom(message));
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return match e.kind() {
+                std::io::ErrorKind::TimedOut => CCAError::Timeout(message),
+                kind => CCAError::Io(std::io::Error::new(kind, message)),
+            };
+        }
+    }
+    CCAError::Internal(message)
+}
+
+impl From<ServiceUnavailable> for ServiceError {
+    fn from(err: ServiceUnavailable) -> Self {
+        Self::Unavailable(err)
+    }
+}
+
+impl From<IdempotencyError> for ServiceError {
+    fn from(err: IdempotencyError) -> Self {
+        Self::Idempotency(err)
+    }
+}
+
+impl From<ValidationError> for ServiceError {
+    fn from(err: ValidationError) -> Self {
+        Self::Invalid(err)
+    }
+}
+
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Unavailable(err) => err.into_response(),
+            Self::Invalid(err) => err.into_response(),
+            Self::Idempotency(err) => err.into_response(),
+            Self::Core(err) => {
+                let status = core_error_status(&err);
+                if status.is_server_error() {
+                    warn!("Request failed: {}", err);
+                }
+                let body = serde_json::json!({
+                    "success": false,
+                    "error": err.to_string(),
+                    "code": err.code()
+                });
+                (status, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+/// Handler result that short-circuits with a 503 when a service is missing
+/// and a 400 when the request is invalid
+pub type ServiceResult = Result<Json<serde_json::Value>, ServiceError>;
+
+/// Get an optional service or fail with a 503 carrying `body`
+pub fn require_service<T>(
+    service: Option<&T>,
+    body: impl FnOnce() -> serde_json::Value,
+) -> Result<&T, ServiceUnavailable> {
+    service.ok_or_else(|| ServiceUnavailable(body()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn status_of(result: ServiceResult) -> (StatusCode, serde_json::Value) {
+        let response = result.into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// Mirrors the handlers: look up a service, fail with 503 if it's missing
+    fn lookup(service: Option<&&str>) -> ServiceResult {
+        let name = require_service(service, || {
+            serde_json::json!({
+                "success": false,
+                "error": "PostgreSQL not available"
+            })
+        })?;
+        Ok(Json(serde_json::json!({ "success": true, "service": name })))
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input_returns_400() {
+        let result: ServiceResult =
+            Err(ValidationError::new("query", "too_long", "Must be at most 1000 bytes").into());
+        let (status, body) = status_of(result).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "too_long");
+    }
+
+    #[tokio::test]
+    async fn test_core_error_carries_its_code() {
+        let result: ServiceResult = Err(CCAError::NotFound("Task abc".to_string()).into());
+        let (status, body) = status_of(result).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["error"], "Not found: Task abc");
+
+        let result: ServiceResult = Err(CCAError::Task("already completed".to_string()).into());
+        let (status, body) = status_of(result).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "task_error");
+    }
+
+    #[tokio::test]
+    async fn test_anyhow_timeout_maps_to_504() {
+        let err = anyhow::Error::from(sqlx::Error::PoolTimedOut).context("Failed to get stats");
+        let core = classify_error(err);
+        assert!(matches!(core, CCAError::Timeout(_)));
+        assert_eq!(core.to_string(), "Timeout: Failed to get stats: pool timed out while waiting for an open connection");
+
+        let timed_out = QueryTimedOut { timeout: std::time::Duration::from_millis(250) };
+        let result: ServiceResult = Err(anyhow::Error::from(timed_out).context("Search failed").into());
+        let (status, body) = status_of(result).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["code"], "timeout");
+    }
+
+    #[tokio::test]
+    async fn test_anyhow_parse_error_maps_to_serialization() {
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let err = anyhow::Error::from(parse).context("Failed to read stored pattern");
+        assert!(matches!(classify_error(err), CCAError::Serialization(_)));
+
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let result: ServiceResult = Err(anyhow::Error::from(parse).into());
+        let (status, body) = status_of(result).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "serialization_error");
+    }
+
+    #[test]
+    fn test_classify_keeps_core_errors_and_defaults_to_internal() {
+        let err = anyhow::Error::from(CCAError::NotFound("Job j1".to_string()));
+        assert!(matches!(classify_error(err), CCAError::NotFound(_)));
+
+        let err = anyhow::anyhow!("something odd");
+        assert!(matches!(classify_error(err), CCAError::Internal(_)));
+    }
+
+    #[tokio::test]
+    async fn test_missing_service_returns_503_with_json_body() {
+        let (status, body) = status_of(lookup(None)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "PostgreSQL not available");
+    }
+
+    #[tokio::test]
+    async fn test_available_service_returns_200() {
+        let (status, body) = status_of(lookup(Some(&"postgres"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["service"], "postgres");
+    }
+}
@@ -0,0 +1,191 @@
+//! Task templates - reusable multi-step delegation workflows
+//!
+//! Templates are defined in config under `[templates.<name>]` and expand a
+//! single request into a predefined list of delegations, so common workflows
+//! don't need the coordinator to re-derive them each time. Step text may
+//! contain `{{param}}` placeholders that are filled from the request.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// A named workflow of delegations
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskTemplate {
+    /// Human-readable summary of what the template does
+    #[serde(default)]
+    pub description: String,
+    /// Delegations to run, in order
+    pub steps: Vec<TemplateStep>,
+    /// Run all steps concurrently instead of one after another
+    #[serde(default)]
+    pub parallel: bool,
+}
+
+/// A single delegation within a template
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TemplateStep {
+    /// Specialist role to delegate to
+    pub role: String,
+    /// Task text (may contain `{{param}}` placeholders)
+    pub task: String,
+    /// Optional extra context (may contain `{{param}}` placeholders)
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+impl TaskTemplate {
+    /// Substitute parameters into every step
+    ///
+    /// Fails if the template has no steps or references a parameter that
+    /// wasn't supplied.
+    pub fn expand(&self, params: &HashMap<String, String>) -> Result<Vec<TemplateStep>> {
+        if self.steps.is_empty() {
+            anyhow::bail!("Template has no steps");
+        }
+
+        let mut missing = Vec::new();
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| TemplateStep {
+                role: step.role.to_lowercase(),
+                task: substitute(&step.task, params, &mut missing),
+                context: step
+                    .context
+                    .as_deref()
+                    .map(|c| substitute(c, params, &mut missing)),
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            missing.sort();
+            missing.dedup();
+            anyhow::bail!("Missing template parameters: {}", missing.join(", "));
+        }
+
+        Ok(steps)
+    }
+}
+
+/// Replace `{{name}}` placeholders, recording names that have no value
+pub(crate) fn substitute(text: &str, params: &HashMap<String, String>, missing: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+
+        out.push_str(&rest[..start]);
+        match params.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                missing.push(name.to_string());
+                out.push_str(&rest[start..start + 4 + len]);
+            }
+        }
+        rest = &rest[start + 4 + len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature_template() -> TaskTemplate {
+        TaskTemplate {
+            description: "Build a feature end to end".to_string(),
+            steps: vec![
+                TemplateStep {
+                    role: "backend".to_string(),
+                    task: "Implement the API for {{feature}}".to_string(),
+                    context: Some("Service: {{ service }}".to_string()),
+                },
+                TemplateStep {
+                    role: "QA".to_string(),
+                    task: "Write tests for {{feature}}".to_string(),
+                    context: None,
+                },
+            ],
+            parallel: false,
+        }
+    }
+
+    #[test]
+    fn test_expand_substitutes_params() {
+        let params = HashMap::from([
+            ("feature".to_string(), "password reset".to_string()),
+            ("service".to_string(), "auth".to_string()),
+        ]);
+
+        let steps = feature_template().expand(&params).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].task, "Implement the API for password reset");
+        assert_eq!(steps[0].context.as_deref(), Some("Service: auth"));
+        assert_eq!(steps[1].role, "qa");
+        assert_eq!(steps[1].task, "Write tests for password reset");
+    }
+
+    #[test]
+    fn test_expand_reports_missing_params() {
+        let params = HashMap::from([("feature".to_string(), "search".to_string())]);
+
+        let err = feature_template().expand(&params).unwrap_err().to_string();
+        assert_eq!(err, "Missing template parameters: service");
+    }
+
+    #[test]
+    fn test_substitute_leaves_unmatched_braces() {
+        let mut missing = Vec::new();
+        let params = HashMap::from([("x".to_string(), "1".to_string())]);
+
+        assert_eq!(substitute("{{x}} and {{x", &params, &mut missing), "1 and {{x");
+        assert_eq!(substitute("json: {\"a\": 1}", &params, &mut missing), "json: {\"a\": 1}");
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_templates_load_from_config() {
+        let toml = r#"
+            [templates.Feature]
+            description = "End to end"
+
+            [[templates.Feature.steps]]
+            role = "backend"
+            task = "Implement {{feature}}"
+
+            [[templates.Feature.steps]]
+            role = "qa"
+            task = "Test {{feature}}"
+        "#;
+        let config: crate::config::Config = config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        // Config keys are lowercased, so lookups use lowercase names
+        let template = &config.templates["feature"];
+        assert!(!template.parallel);
+        assert_eq!(template.steps.len(), 2);
+        assert_eq!(template.steps[1].task, "Test {{feature}}");
+    }
+
+    #[test]
+    fn test_expand_rejects_empty_template() {
+        let template = TaskTemplate {
+            description: String::new(),
+            steps: vec![],
+            parallel: false,
+        };
+        assert!(template.expand(&HashMap::new()).is_err());
+    }
+}
@@ -3,8 +3,10 @@
 //! Manages agent workers in tmux windows with 2x2 pane layouts.
 //! Maximum 2 windows ("CCA-Workers", "CCA-Workers-2") = 8 agent slots.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -15,12 +17,132 @@ const PANES_PER_WINDOW: usize = 4;
 /// Maximum total auto-spawned agents
 pub const MAX_AUTO_AGENTS: usize = MAX_WINDOWS * PANES_PER_WINDOW;
 
+/// Result of a tmux invocation
+#[derive(Debug, Clone, Default)]
+pub struct TmuxOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs tmux commands; swapped out in tests
+pub trait TmuxRunner: Send + Sync + std::fmt::Debug {
+    fn run(&self, args: &[&str]) -> std::io::Result<TmuxOutput>;
+}
+
+/// Runs the real `tmux` binary
+#[derive(Debug, Default)]
+pub struct SystemTmux;
+
+impl TmuxRunner for SystemTmux {
+    fn run(&self, args: &[&str]) -> std::io::Result<TmuxOutput> {
+        let output = Command::new("tmux").args(args).output()?;
+        Ok(TmuxOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Default command used to launch a worker in a pane
+pub const DEFAULT_SPAWN_COMMAND: &str = "cca agent worker {{role}} --worker-id {{worker_id}}";
+
+/// Placeholders a spawn command may use
+const SPAWN_PLACEHOLDERS: &[&str] = &["role", "worker_id", "daemon_url", "acp_url", "api_key"];
+
+/// Values substituted into the spawn command (besides the role)
+#[derive(Debug, Clone, Default)]
+pub struct SpawnVars {
+    pub daemon_url: String,
+    pub acp_url: String,
+    pub api_key: String,
+}
+
+/// Validated command template for launching workers
+///
+/// Supports `{{role}}`, `{{worker_id}}`, `{{daemon_url}}`, `{{acp_url}}` and
+/// `{{api_key}}`; `{{role}}` is required. Workers started without
+/// `{{worker_id}}` can't be matched to their pane, so stopping them leaves
+/// the pane alone. Substituted values are shell-quoted when needed
+/// since the command is typed into a shell.
+#[derive(Debug, Clone)]
+pub struct SpawnCommand {
+    template: String,
+    vars: SpawnVars,
+}
+
+impl SpawnCommand {
+    pub fn new(template: &str, vars: SpawnVars) -> Result<Self, String> {
+        let mut placeholders = Vec::new();
+        crate::templates::substitute(template, &HashMap::new(), &mut placeholders);
+
+        if let Some(unknown) = placeholders.iter().find(|p| !SPAWN_PLACEHOLDERS.contains(&p.as_str())) {
+            return Err(format!(
+                "Unknown placeholder '{{{{{unknown}}}}}' in spawn command (available: {})",
+                SPAWN_PLACEHOLDERS.join(", ")
+            ));
+        }
+        if !placeholders.iter().any(|p| p == "role") {
+            return Err("Spawn command must contain the {{role}} placeholder".to_string());
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            vars,
+        })
+    }
+
+    /// Render the command for a worker of `role` with `worker_id`
+    pub fn render(&self, role: &str, worker_id: &str) -> String {
+        let params = HashMap::from([
+            ("role".to_string(), shell_quote(role)),
+            ("worker_id".to_string(), shell_quote(worker_id)),
+            ("daemon_url".to_string(), shell_quote(&self.vars.daemon_url)),
+            ("acp_url".to_string(), shell_quote(&self.vars.acp_url)),
+            ("api_key".to_string(), shell_quote(&self.vars.api_key)),
+        ]);
+        crate::templates::substitute(&self.template, &params, &mut Vec::new())
+    }
+}
+
+impl Default for SpawnCommand {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_SPAWN_COMMAND.to_string(),
+            vars: SpawnVars::default(),
+        }
+    }
+}
+
+/// Worker ID given to the worker spawned in `pane_id`
+///
+/// Tmux pane ids look like `%3`; `%` isn't allowed in worker IDs.
+pub fn pane_worker_id(pane_id: &str) -> String {
+    format!("tmux:{}", pane_id.trim_start_matches('%'))
+}
+
+/// Quote a value for a POSIX shell unless it only has safe characters
+fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@=+,%".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
 /// Tracks a spawned agent in tmux
 #[derive(Debug, Clone)]
 pub struct TmuxAgent {
     pub role: String,
     pub window_name: String,
     pub pane_id: String,
+    /// Worker ID the worker registers with over ACP
+    pub worker_id: String,
     pub spawned_at: std::time::Instant,
 }
 
@@ -33,11 +155,22 @@ pub struct TmuxManager {
     windows: RwLock<Vec<String>>,
     /// Whether tmux is available
     tmux_available: bool,
+    /// Executes tmux commands
+    runner: Arc<dyn TmuxRunner>,
+    /// Total agents removed because their pane died
+    pruned_total: AtomicU64,
+    /// Command typed into a new pane to start the worker
+    spawn_command: SpawnCommand,
 }
 
 impl TmuxManager {
     pub fn new() -> Self {
-        let tmux_available = Self::check_tmux_available();
+        Self::with_runner(Arc::new(SystemTmux))
+    }
+
+    /// Create a manager that runs tmux through `runner`
+    pub fn with_runner(runner: Arc<dyn TmuxRunner>) -> Self {
+        let tmux_available = Self::check_tmux_available(runner.as_ref());
         if tmux_available {
             info!("Tmux detected - auto-spawn feature enabled");
         } else {
@@ -48,27 +181,29 @@ impl TmuxManager {
             agents: RwLock::new(HashMap::new()),
             windows: RwLock::new(Vec::new()),
             tmux_available,
+            runner,
+            pruned_total: AtomicU64::new(0),
+            spawn_command: SpawnCommand::default(),
         }
     }
 
+    /// Launch workers with `command` instead of the default
+    pub fn with_spawn_command(mut self, command: SpawnCommand) -> Self {
+        self.spawn_command = command;
+        self
+    }
+
     /// Check if tmux is available
-    fn check_tmux_available() -> bool {
+    fn check_tmux_available(runner: &dyn TmuxRunner) -> bool {
         // First check if we're inside a tmux session
         if std::env::var("TMUX").is_ok() {
             return true;
         }
 
         // Not inside tmux, but check if tmux server is running and has sessions
-        let output = Command::new("tmux")
-            .args(["list-sessions", "-F", "#{session_name}"])
-            .output();
-
-        match output {
-            Ok(out) if out.status.success() => {
-                let stdout = String::from_utf8_lossy(&out.stdout);
-                // Check if there's at least one session
-                stdout.lines().any(|s| !s.trim().is_empty())
-            }
+        match runner.run(&["list-sessions", "-F", "#{session_name}"]) {
+            // Check if there's at least one session
+            Ok(out) if out.success => out.stdout.lines().any(|s| !s.trim().is_empty()),
             _ => false,
         }
     }
@@ -94,6 +229,55 @@ impl TmuxManager {
             .collect()
     }
 
+    /// Stop tracking agents whose tmux pane no longer exists (or has exited)
+    ///
+    /// Returns the number of agents removed. If tmux can't be queried nothing
+    /// is removed, so a transient failure doesn't drop live agents.
+    pub async fn prune_dead_agents(&self) -> usize {
+        if !self.tmux_available || self.agents.read().await.is_empty() {
+            return 0;
+        }
+
+        let live_panes: HashSet<String> =
+            match self.runner.run(&["list-panes", "-a", "-F", "#{pane_id} #{pane_dead}"]) {
+                Ok(out) if out.success => out
+                    .stdout
+                    .lines()
+                    .filter_map(|line| {
+                        let (pane_id, dead) = line.trim().split_once(' ')?;
+                        (dead != "1").then(|| pane_id.to_string())
+                    })
+                    .collect(),
+                Ok(out) => {
+                    warn!("Tmux list-panes failed, skipping dead agent check: {}", out.stderr.trim());
+                    return 0;
+                }
+                Err(e) => {
+                    warn!("Failed to list tmux panes, skipping dead agent check: {}", e);
+                    return 0;
+                }
+            };
+
+        let mut agents = self.agents.write().await;
+        let before = agents.len();
+        agents.retain(|pane_id, agent| {
+            let alive = live_panes.contains(pane_id);
+            if !alive {
+                warn!("Tmux pane {} for {} agent is gone, no longer tracking it", pane_id, agent.role);
+            }
+            alive
+        });
+
+        let pruned = before - agents.len();
+        self.pruned_total.fetch_add(pruned as u64, Ordering::Relaxed);
+        pruned
+    }
+
+    /// Total agents pruned because their pane died, since startup
+    pub fn pruned_total(&self) -> u64 {
+        self.pruned_total.load(Ordering::Relaxed)
+    }
+
     /// Spawn a new agent worker in tmux
     /// Returns the pane_id if successful
     pub async fn spawn_agent(&self, role: &str) -> Result<String, String> {
@@ -101,10 +285,13 @@ impl TmuxManager {
             return Err("Tmux not available".to_string());
         }
 
-        let spawned = self.spawned_count().await;
+        // Hold the agent map for the whole spawn so concurrent spawns for
+        // different roles can't both pass the cap check
+        let mut agents = self.agents.write().await;
+        let spawned = agents.len();
         if spawned >= MAX_AUTO_AGENTS {
             return Err(format!(
-                "Maximum auto-spawned agents reached ({MAX_AUTO_AGENTS})"
+                "Maximum auto-spawned agents reached ({spawned}/{MAX_AUTO_AGENTS} across all roles)"
             ));
         }
 
@@ -135,7 +322,8 @@ impl TmuxManager {
         };
 
         // Run the agent command in the pane
-        let cmd = format!("cca agent worker {role}");
+        let worker_id = pane_worker_id(&pane_id);
+        let cmd = self.spawn_command.render(role, &worker_id);
         self.run_in_pane(&pane_id, &cmd)?;
 
         // Track the agent
@@ -143,10 +331,11 @@ impl TmuxManager {
             role: role.to_string(),
             window_name: window_name.clone(),
             pane_id: pane_id.clone(),
+            worker_id,
             spawned_at: std::time::Instant::now(),
         };
 
-        self.agents.write().await.insert(pane_id.clone(), agent);
+        agents.insert(pane_id.clone(), agent);
 
         info!(
             "Spawned {} agent in tmux window '{}' pane {}",
@@ -165,14 +354,10 @@ impl TmuxManager {
         }
 
         // Otherwise get the first available session
-        let output = Command::new("tmux")
-            .args(["list-sessions", "-F", "#{session_name}"])
-            .output()
-            .ok()?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().next().map(|s| s.trim().to_string())
+        let output = self.runner.run(&["list-sessions", "-F", "#{session_name}"]).ok()?;
+
+        if output.success {
+            output.stdout.lines().next().map(|s| s.trim().to_string())
         } else {
             None
         }
@@ -199,14 +384,13 @@ impl TmuxManager {
             args.push(&target);
         }
 
-        let output = Command::new("tmux")
-            .args(&args)
-            .output()
+        let output = self
+            .runner
+            .run(&args)
             .map_err(|e| format!("Failed to create tmux window: {e}"))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Tmux new-window failed: {stderr}"));
+        if !output.success {
+            return Err(format!("Tmux new-window failed: {}", output.stderr));
         }
 
         debug!("Created tmux window: {}", name);
@@ -222,43 +406,29 @@ impl TmuxManager {
         let target = self.window_target(window_name);
 
         // Split horizontally first (creates 2 panes side by side)
-        let output = Command::new("tmux")
-            .args(["split-window", "-h", "-t", &target])
-            .output();
-        if let Ok(out) = &output {
-            if !out.status.success() {
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                warn!("split-window -h failed: {}", stderr);
+        if let Ok(out) = self.runner.run(&["split-window", "-h", "-t", &target]) {
+            if !out.success {
+                warn!("split-window -h failed: {}", out.stderr);
             }
         }
 
         // Split each pane vertically (creates 4 panes in 2x2)
         let target_pane0 = format!("{}.0", target);
-        let output = Command::new("tmux")
-            .args(["split-window", "-v", "-t", &target_pane0])
-            .output();
-        if let Ok(out) = &output {
-            if !out.status.success() {
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                warn!("split-window -v pane 0 failed: {}", stderr);
+        if let Ok(out) = self.runner.run(&["split-window", "-v", "-t", &target_pane0]) {
+            if !out.success {
+                warn!("split-window -v pane 0 failed: {}", out.stderr);
             }
         }
 
         let target_pane2 = format!("{}.2", target);
-        let output = Command::new("tmux")
-            .args(["split-window", "-v", "-t", &target_pane2])
-            .output();
-        if let Ok(out) = &output {
-            if !out.status.success() {
-                let stderr = String::from_utf8_lossy(&out.stderr);
-                warn!("split-window -v pane 2 failed: {}", stderr);
+        if let Ok(out) = self.runner.run(&["split-window", "-v", "-t", &target_pane2]) {
+            if !out.success {
+                warn!("split-window -v pane 2 failed: {}", out.stderr);
             }
         }
 
         // Select tiled layout for even distribution
-        let _ = Command::new("tmux")
-            .args(["select-layout", "-t", &target, "tiled"])
-            .output();
+        let _ = self.runner.run(&["select-layout", "-t", &target, "tiled"]);
 
         debug!("Set up 2x2 layout for window: {}", window_name);
         Ok(())
@@ -267,18 +437,17 @@ impl TmuxManager {
     /// Get the first pane ID of a window
     fn get_window_pane(&self, window_name: &str) -> Result<String, String> {
         let target = self.window_target(window_name);
-        let output = Command::new("tmux")
-            .args(["list-panes", "-t", &target, "-F", "#{pane_id}"])
-            .output()
+        let output = self
+            .runner
+            .run(&["list-panes", "-t", &target, "-F", "#{pane_id}"])
             .map_err(|e| format!("Failed to list panes: {e}"))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Tmux list-panes failed: {stderr}"));
+        if !output.success {
+            return Err(format!("Tmux list-panes failed: {}", output.stderr));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let pane_id = stdout
+        let pane_id = output
+            .stdout
             .lines()
             .next()
             .ok_or("No panes found")?
@@ -292,18 +461,16 @@ impl TmuxManager {
     fn create_pane(&self, window_name: &str, pane_index: usize) -> Result<String, String> {
         let target = self.window_target(window_name);
         // The layout is already set up, just get the pane at the index
-        let output = Command::new("tmux")
-            .args(["list-panes", "-t", &target, "-F", "#{pane_id}"])
-            .output()
+        let output = self
+            .runner
+            .run(&["list-panes", "-t", &target, "-F", "#{pane_id}"])
             .map_err(|e| format!("Failed to list panes: {e}"))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Tmux list-panes failed: {stderr}"));
+        if !output.success {
+            return Err(format!("Tmux list-panes failed: {}", output.stderr));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let panes: Vec<&str> = stdout.lines().collect();
+        let panes: Vec<&str> = output.stdout.lines().collect();
 
         if pane_index >= panes.len() {
             return Err(format!(
@@ -318,38 +485,41 @@ impl TmuxManager {
 
     /// Run a command in a specific pane
     fn run_in_pane(&self, pane_id: &str, command: &str) -> Result<(), String> {
-        let output = Command::new("tmux")
-            .args(["send-keys", "-t", pane_id, command, "Enter"])
-            .output()
+        let output = self
+            .runner
+            .run(&["send-keys", "-t", pane_id, command, "Enter"])
             .map_err(|e| format!("Failed to send keys to pane: {e}"))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("Tmux send-keys failed: {stderr}"));
+        if !output.success {
+            return Err(format!("Tmux send-keys failed: {}", output.stderr));
         }
 
         debug!("Ran command in pane {}: {}", pane_id, command);
         Ok(())
     }
 
-    /// Remove the oldest tracked agent for a given role
+    /// Stop tracking the agent that registered with `worker_id`
     /// Returns the removed agent's pane_id if found
-    pub async fn remove_agent_by_role(&self, role: &str) -> Option<String> {
+    pub async fn remove_agent_by_worker_id(&self, worker_id: &str) -> Option<String> {
         let mut agents = self.agents.write().await;
-        // Find the oldest agent with this role
-        let oldest_pane_id = agents
-            .iter()
-            .filter(|(_, a)| a.role.to_lowercase() == role.to_lowercase())
-            .min_by_key(|(_, a)| a.spawned_at)
-            .map(|(pane_id, _)| pane_id.clone());
-
-        if let Some(pane_id) = oldest_pane_id {
-            agents.remove(&pane_id);
-            info!("Removed tracked {} agent from pane {}", role, pane_id);
-            Some(pane_id)
-        } else {
-            None
-        }
+        let pane_id = agents
+            .values()
+            .find(|a| a.worker_id == worker_id)
+            .map(|a| a.pane_id.clone())?;
+
+        let agent = agents.remove(&pane_id)?;
+        info!("Removed tracked {} agent from pane {}", agent.role, pane_id);
+        Some(pane_id)
+    }
+
+    /// Stop the agent that registered with `worker_id` and stop tracking it
+    ///
+    /// Sends Ctrl+C to that agent's pane only; returns the pane id if one was
+    /// found.
+    pub async fn stop_agent_by_worker_id(&self, worker_id: &str) -> Option<String> {
+        let pane_id = self.remove_agent_by_worker_id(worker_id).await?;
+        let _ = self.runner.run(&["send-keys", "-t", &pane_id, "C-c"]);
+        Some(pane_id)
     }
 
     /// Get all spawned agents info
@@ -363,9 +533,7 @@ impl TmuxManager {
         for (pane_id, agent) in agents.iter() {
             info!("Cleaning up agent {} in pane {}", agent.role, pane_id);
             // Send Ctrl+C to stop the agent
-            let _ = Command::new("tmux")
-                .args(["send-keys", "-t", pane_id, "C-c"])
-                .output();
+            let _ = self.runner.run(&["send-keys", "-t", pane_id, "C-c"]);
         }
         drop(agents);
 
@@ -376,9 +544,7 @@ impl TmuxManager {
         let windows = self.windows.read().await;
         for window in windows.iter() {
             let target = self.window_target(window);
-            let _ = Command::new("tmux")
-                .args(["kill-window", "-t", &target])
-                .output();
+            let _ = self.runner.run(&["kill-window", "-t", &target]);
         }
     }
 
@@ -393,3 +559,193 @@ impl Default for TmuxManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers `list-sessions` and `list-panes` with canned output and
+    /// accepts window creation and `send-keys`
+    #[derive(Debug, Default)]
+    struct MockTmux {
+        panes: Option<&'static str>,
+        /// Panes that were sent Ctrl+C
+        interrupted: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl TmuxRunner for MockTmux {
+        fn run(&self, args: &[&str]) -> std::io::Result<TmuxOutput> {
+            let output = match args.first().copied() {
+                Some("list-sessions") => TmuxOutput {
+                    success: true,
+                    stdout: "main\n".to_string(),
+                    ..TmuxOutput::default()
+                },
+                // Per-window listing: four panes numbered by window
+                Some("list-panes") if args.get(1) == Some(&"-t") => {
+                    let window = args[2].rsplit("CCA-Workers").next().unwrap_or("");
+                    let base = window
+                        .trim_start_matches('-')
+                        .parse::<usize>()
+                        .map_or(0, |n| (n - 1) * PANES_PER_WINDOW);
+                    TmuxOutput {
+                        success: true,
+                        stdout: (base..base + PANES_PER_WINDOW)
+                            .map(|i| format!("%{i}\n"))
+                            .collect(),
+                        ..TmuxOutput::default()
+                    }
+                }
+                Some("list-panes") => match self.panes {
+                    Some(panes) => TmuxOutput {
+                        success: true,
+                        stdout: panes.to_string(),
+                        ..TmuxOutput::default()
+                    },
+                    None => TmuxOutput {
+                        success: false,
+                        stderr: "no server running".to_string(),
+                        ..TmuxOutput::default()
+                    },
+                },
+                Some("send-keys") => {
+                    if args.last() == Some(&"C-c") {
+                        self.interrupted.lock().unwrap().push(args[2].to_string());
+                    }
+                    TmuxOutput {
+                        success: true,
+                        ..TmuxOutput::default()
+                    }
+                }
+                Some("new-window") => TmuxOutput {
+                    success: true,
+                    ..TmuxOutput::default()
+                },
+                _ => TmuxOutput::default(),
+            };
+            Ok(output)
+        }
+    }
+
+    async fn manager_with_agents(panes: Option<&'static str>, pane_ids: &[&str]) -> TmuxManager {
+        let manager = TmuxManager::with_runner(Arc::new(MockTmux { panes, ..MockTmux::default() }));
+        let mut agents = manager.agents.write().await;
+        for pane_id in pane_ids {
+            agents.insert(
+                (*pane_id).to_string(),
+                TmuxAgent {
+                    role: "backend".to_string(),
+                    window_name: "CCA-Workers".to_string(),
+                    pane_id: (*pane_id).to_string(),
+                    worker_id: pane_worker_id(pane_id),
+                    spawned_at: std::time::Instant::now(),
+                },
+            );
+        }
+        drop(agents);
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_prune_removes_missing_and_dead_panes() {
+        // %2 no longer exists, %3 exited but is kept open by remain-on-exit
+        let manager = manager_with_agents(Some("%0 0\n%1 0\n%3 1\n"), &["%1", "%2", "%3"]).await;
+        assert!(manager.is_available());
+
+        assert_eq!(manager.prune_dead_agents().await, 2);
+        let remaining: Vec<String> =
+            manager.list_agents().await.into_iter().map(|a| a.pane_id).collect();
+        assert_eq!(remaining, vec!["%1".to_string()]);
+        assert_eq!(manager.pruned_total(), 2);
+
+        // Nothing left to prune
+        assert_eq!(manager.prune_dead_agents().await, 0);
+        assert_eq!(manager.pruned_total(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_enforces_global_cap_across_roles() {
+        let manager = TmuxManager::with_runner(Arc::new(MockTmux::default()));
+        let roles = ["backend", "frontend", "qa", "security"];
+
+        for i in 0..MAX_AUTO_AGENTS {
+            let pane_id = manager.spawn_agent(roles[i % roles.len()]).await.unwrap();
+            assert_eq!(pane_id, format!("%{i}"));
+        }
+        assert_eq!(manager.spawned_count().await, MAX_AUTO_AGENTS);
+
+        // No role gets past the cap, including one not spawned yet
+        for role in ["backend", "devops"] {
+            let err = manager.spawn_agent(role).await.unwrap_err();
+            assert!(err.contains("across all roles"), "{err}");
+        }
+        assert_eq!(manager.spawned_count().await, MAX_AUTO_AGENTS);
+    }
+
+    #[tokio::test]
+    async fn test_stop_by_worker_id_leaves_same_role_agents_alone() {
+        let runner = Arc::new(MockTmux::default());
+        let manager = TmuxManager::with_runner(runner.clone());
+
+        let first = manager.spawn_agent("backend").await.unwrap();
+        let second = manager.spawn_agent("backend").await.unwrap();
+        assert_eq!(pane_worker_id(&second), "tmux:1");
+
+        assert_eq!(manager.stop_agent_by_worker_id("tmux:1").await, Some(second.clone()));
+        assert_eq!(*runner.interrupted.lock().unwrap(), vec![second]);
+        let remaining: Vec<String> =
+            manager.list_agents().await.into_iter().map(|a| a.pane_id).collect();
+        assert_eq!(remaining, vec![first]);
+
+        // Workers the manager didn't spawn have no pane to stop
+        assert_eq!(manager.stop_agent_by_worker_id("host-1:backend").await, None);
+        assert_eq!(runner.interrupted.lock().unwrap().len(), 1);
+    }
+
+    fn vars() -> SpawnVars {
+        SpawnVars {
+            daemon_url: "http://127.0.0.1:8580".to_string(),
+            acp_url: "ws://127.0.0.1:8581".to_string(),
+            api_key: "secret key".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_spawn_command_substitutes_role_and_urls() {
+        let command = SpawnCommand::new(
+            "CCA_DAEMON_URL={{daemon_url}} CCA_ACP_URL={{ acp_url }} cca agent worker {{role}}",
+            vars(),
+        )
+        .unwrap();
+        assert_eq!(
+            command.render("backend", "tmux:0"),
+            "CCA_DAEMON_URL=http://127.0.0.1:8580 CCA_ACP_URL=ws://127.0.0.1:8581 cca agent worker backend"
+        );
+
+        // Values with shell metacharacters are quoted
+        let command = SpawnCommand::new("CCA_KEY={{api_key}} worker {{role}}", vars()).unwrap();
+        assert_eq!(command.render("qa", "tmux:1"), "CCA_KEY='secret key' worker qa");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+
+        assert_eq!(
+            SpawnCommand::default().render("dba", "tmux:2"),
+            "cca agent worker dba --worker-id tmux:2"
+        );
+    }
+
+    #[test]
+    fn test_spawn_command_validates_placeholders() {
+        let err = SpawnCommand::new("cca agent worker", vars()).unwrap_err();
+        assert!(err.contains("{{role}}"));
+
+        let err = SpawnCommand::new("cca agent worker {{role}} --url {{url}}", vars()).unwrap_err();
+        assert!(err.contains("'{{url}}'"));
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_agents_when_tmux_query_fails() {
+        let manager = manager_with_agents(None, &["%1", "%2"]).await;
+        assert_eq!(manager.prune_dead_agents().await, 0);
+        assert_eq!(manager.spawned_count().await, 2);
+    }
+}
@@ -7,30 +7,68 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::io::{BufRead, Write};
 use std::sync::Arc;
+use std::time::Duration;
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use cca_core::AgentId;
 
+/// Tokenizer backend used for counting
+pub trait TokenCounter: Send + Sync {
+    /// Count tokens in a string
+    fn count(&self, text: &str) -> u32;
+
+    /// Backend name as used in `[token_efficiency] tokenizer`
+    fn name(&self) -> &'static str;
+
+    /// Count tokens in a structured message (JSON-like)
+    fn count_message(&self, content: &str, role: &str) -> u32 {
+        // Message overhead: role markers, formatting
+        let overhead = 4; // Approximate overhead for message structure
+        self.count(content) + self.count(role) + overhead
+    }
+
+    /// Count tokens in a conversation history
+    fn count_conversation(&self, messages: &[ConversationMessage]) -> u32 {
+        messages.iter().map(|m| self.count_message(&m.content, &m.role)).sum()
+    }
+}
+
+/// Build the counter for a configured tokenizer name
+pub fn counter_for(tokenizer: &str) -> anyhow::Result<Arc<dyn TokenCounter>> {
+    match tokenizer {
+        "heuristic" => Ok(Arc::new(HeuristicCounter::new())),
+        #[cfg(feature = "tiktoken")]
+        "tiktoken" => Ok(Arc::new(TiktokenCounter::new()?)),
+        #[cfg(not(feature = "tiktoken"))]
+        "tiktoken" => anyhow::bail!("Tokenizer 'tiktoken' requires building with --features tiktoken"),
+        other => anyhow::bail!("Unknown tokenizer '{other}' (expected 'heuristic' or 'tiktoken')"),
+    }
+}
+
 /// Token counter using a simple BPE-like estimation
 /// Based on GPT-4/Claude tokenization patterns (~4 chars per token average)
-pub struct TokenCounter {
+pub struct HeuristicCounter {
     /// Average characters per token (adjustable)
     chars_per_token: f64,
 }
 
-impl TokenCounter {
+impl HeuristicCounter {
     pub fn new() -> Self {
         Self {
             chars_per_token: 4.0, // Conservative estimate for English text
         }
     }
+}
 
+impl TokenCounter for HeuristicCounter {
     /// Estimate token count for a string
-    pub fn count(&self, text: &str) -> u32 {
+    fn count(&self, text: &str) -> u32 {
         if text.is_empty() {
             return 0;
         }
@@ -49,25 +87,46 @@ impl TokenCounter {
         word_estimate.max(char_estimate).max(1)
     }
 
-    /// Count tokens in a structured message (JSON-like)
-    pub fn count_message(&self, content: &str, role: &str) -> u32 {
-        // Message overhead: role markers, formatting
-        let overhead = 4; // Approximate overhead for message structure
-        self.count(content) + self.count(role) + overhead
-    }
-
-    /// Count tokens in a conversation history
-    pub fn count_conversation(&self, messages: &[ConversationMessage]) -> u32 {
-        messages.iter().map(|m| self.count_message(&m.content, &m.role)).sum()
+    fn name(&self) -> &'static str {
+        "heuristic"
     }
 }
 
-impl Default for TokenCounter {
+impl Default for HeuristicCounter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Exact BPE counts using the `cl100k_base` encoding
+///
+/// Claude's tokenizer isn't public; `cl100k_base` tracks it far more closely
+/// than the heuristic, which matters when enforcing budgets.
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenCounter {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            bpe: tiktoken_rs::cl100k_base()?,
+        })
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn count(&self, text: &str) -> u32 {
+        self.bpe.encode_ordinary(text).len() as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "tiktoken"
+    }
+}
+
 /// A message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationMessage {
@@ -90,7 +149,7 @@ pub struct RedundancyResult {
 
 /// Context analyzer for detecting redundancy across agents
 pub struct ContextAnalyzer {
-    counter: TokenCounter,
+    counter: Arc<dyn TokenCounter>,
     /// N-gram size for similarity detection
     ngram_size: usize,
 }
@@ -98,11 +157,17 @@ pub struct ContextAnalyzer {
 impl ContextAnalyzer {
     pub fn new() -> Self {
         Self {
-            counter: TokenCounter::new(),
+            counter: Arc::new(HeuristicCounter::new()),
             ngram_size: 3,
         }
     }
 
+    /// Count with a different tokenizer backend
+    pub fn with_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = counter;
+        self
+    }
+
     /// Analyze a single context for token usage
     pub fn analyze(&self, content: &str) -> ContextAnalysis {
         let total_tokens = self.counter.count(content);
@@ -134,6 +199,8 @@ impl ContextAnalyzer {
         // Detect long lines (potential for summarization)
         let long_lines = lines.iter().filter(|l| l.len() > 200).count();
 
+        let (sections, top_repeated_blocks) = self.section_breakdown(content, total_tokens);
+
         ContextAnalysis {
             total_tokens,
             repeated_tokens,
@@ -141,9 +208,62 @@ impl ContextAnalyzer {
             code_block_count,
             long_line_count: long_lines,
             compression_potential: self.estimate_compression_potential(content),
+            sections,
+            top_repeated_blocks,
         }
     }
 
+    /// Attribute `total_tokens` to code, prose and repeated blocks
+    ///
+    /// Token estimates aren't additive, so per-block counts are used as weights
+    /// and the total is apportioned by largest remainder to sum exactly.
+    fn section_breakdown(
+        &self,
+        content: &str,
+        total_tokens: u32,
+    ) -> (SectionBreakdown, Vec<RepeatedBlock>) {
+        let mut weights = [0u64; 3]; // code, prose, repeated
+        let mut seen: HashMap<String, (SectionKind, u32, usize)> = HashMap::new();
+        let mut order = Vec::new();
+
+        for (kind, block) in split_blocks(content) {
+            let tokens = self.counter.count(&block);
+            match seen.get_mut(&block) {
+                Some(entry) => {
+                    entry.2 += 1;
+                    weights[2] += u64::from(tokens);
+                }
+                None => {
+                    weights[if kind == SectionKind::Code { 0 } else { 1 }] += u64::from(tokens);
+                    order.push(block.clone());
+                    seen.insert(block, (kind, tokens, 1));
+                }
+            }
+        }
+
+        let [code, prose, repeated] = apportion(total_tokens, weights);
+
+        let mut top_repeated_blocks: Vec<RepeatedBlock> = order
+            .into_iter()
+            .filter_map(|block| {
+                let (kind, tokens, count) = seen[&block];
+                (count > 1).then(|| RepeatedBlock {
+                    preview: preview(&block),
+                    kind,
+                    tokens,
+                    count,
+                })
+            })
+            .collect();
+        top_repeated_blocks.sort_by(|a, b| {
+            let wasted = |r: &RepeatedBlock| u64::from(r.tokens) * (r.count as u64 - 1);
+            wasted(b).cmp(&wasted(a))
+        });
+        top_repeated_blocks.truncate(TOP_REPEATED_BLOCKS);
+
+        (SectionBreakdown { code, prose, repeated }, top_repeated_blocks)
+    }
+
     /// Compare two contexts for redundancy
     pub fn compare(&self, context_a: &str, context_b: &str) -> RedundancyResult {
         let ngrams_a = self.extract_ngrams(context_a);
@@ -247,6 +367,84 @@ impl Default for ContextAnalyzer {
     }
 }
 
+/// Number of repeated blocks reported by `ContextAnalyzer::analyze`
+pub const TOP_REPEATED_BLOCKS: usize = 5;
+
+/// Characters of a repeated block included in its preview
+const BLOCK_PREVIEW_CHARS: usize = 120;
+
+/// Split content into fenced code blocks and blank-line separated paragraphs
+fn split_blocks(content: &str) -> Vec<(SectionKind, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let is_fence = line.trim().starts_with("```");
+
+        if in_code_block {
+            current.push(line);
+            if is_fence {
+                blocks.push((SectionKind::Code, current.join("\n")));
+                current.clear();
+                in_code_block = false;
+            }
+        } else if is_fence {
+            if !current.is_empty() {
+                blocks.push((SectionKind::Prose, current.join("\n")));
+                current.clear();
+            }
+            current.push(line);
+            in_code_block = true;
+        } else if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push((SectionKind::Prose, current.join("\n")));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+
+    if !current.is_empty() {
+        let kind = if in_code_block { SectionKind::Code } else { SectionKind::Prose };
+        blocks.push((kind, current.join("\n")));
+    }
+    blocks
+}
+
+/// Split `total` proportionally to `weights` using the largest remainder method
+fn apportion(total: u32, weights: [u64; 3]) -> [u32; 3] {
+    let sum: u64 = weights.iter().sum();
+    if sum == 0 {
+        // Whitespace-only content: nothing to attribute it to but prose
+        return [0, total, 0];
+    }
+
+    let total = u64::from(total);
+    let mut shares = [0u32; 3];
+    let mut remainders = [(0u64, 0usize); 3];
+    for (i, weight) in weights.iter().enumerate() {
+        let exact = total * weight;
+        shares[i] = (exact / sum) as u32;
+        remainders[i] = (exact % sum, i);
+    }
+
+    let assigned: u64 = shares.iter().map(|s| u64::from(*s)).sum();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, i) in remainders.iter().take((total - assigned) as usize) {
+        shares[*i] += 1;
+    }
+    shares
+}
+
+fn preview(block: &str) -> String {
+    match block.char_indices().nth(BLOCK_PREVIEW_CHARS) {
+        Some((idx, _)) => format!("{}...", &block[..idx]),
+        None => block.to_string(),
+    }
+}
+
 #[derive(Debug)]
 struct CompressibilityFactors {
     repetition_ratio: f64,
@@ -263,20 +461,96 @@ pub struct ContextAnalysis {
     pub code_block_count: usize,
     pub long_line_count: usize,
     pub compression_potential: f64,
+    /// Where `total_tokens` goes; sums to `total_tokens`
+    pub sections: SectionBreakdown,
+    /// Largest repeated blocks by tokens wasted on repeats
+    pub top_repeated_blocks: Vec<RepeatedBlock>,
 }
 
+/// Kind of block found in a context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    Code,
+    Prose,
+}
+
+/// Token counts by section type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionBreakdown {
+    /// First occurrences of fenced code blocks
+    pub code: u32,
+    /// First occurrences of prose paragraphs
+    pub prose: u32,
+    /// Second and later occurrences of any block
+    pub repeated: u32,
+}
+
+impl SectionBreakdown {
+    pub fn total(&self) -> u32 {
+        self.code + self.prose + self.repeated
+    }
+}
+
+/// A block that appears more than once in a context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatedBlock {
+    /// Start of the block (truncated)
+    pub preview: String,
+    pub kind: SectionKind,
+    /// Tokens in a single occurrence
+    pub tokens: u32,
+    /// Number of occurrences
+    pub count: usize,
+}
+
+/// Patterns for lines worth keeping when debugging (errors, panics, stack frames)
+pub const DEFAULT_PRESERVE_PATTERNS: &[&str] = &[
+    r"(?i)\berror\b",
+    r"(?i)\bpanic",
+    r"(?i)\bexception\b",
+    r"\bat .*:\d+",
+];
+
 /// Context compression strategies
 pub struct ContextCompressor {
-    counter: TokenCounter,
+    counter: Arc<dyn TokenCounter>,
+    /// Lines matching any of these are never dropped by `summarize`
+    preserve_patterns: Vec<Regex>,
 }
 
 impl ContextCompressor {
     pub fn new() -> Self {
         Self {
-            counter: TokenCounter::new(),
+            counter: Arc::new(HeuristicCounter::new()),
+            preserve_patterns: Vec::new(),
         }
     }
 
+    /// Count with a different tokenizer backend
+    pub fn with_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Never summarize away lines matching these patterns
+    pub fn with_preserve_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.preserve_patterns = patterns;
+        self
+    }
+
+    /// Compile `DEFAULT_PRESERVE_PATTERNS`
+    pub fn default_preserve_patterns() -> Vec<Regex> {
+        DEFAULT_PRESERVE_PATTERNS
+            .iter()
+            .map(|p| Regex::new(p).expect("default preserve pattern is valid"))
+            .collect()
+    }
+
+    fn is_preserved(&self, line: &str) -> bool {
+        self.preserve_patterns.iter().any(|re| re.is_match(line))
+    }
+
     /// Prune old messages from conversation history
     /// Keeps system message, recent messages, and important context
     pub fn prune_history(
@@ -331,15 +605,19 @@ impl ContextCompressor {
         }
 
         let keep_lines = (lines.len() as f64 * (1.0 - target_reduction)) as usize;
-        let keep_start = keep_lines / 2;
-        let keep_end = keep_lines - keep_start;
 
-        let mut result = Vec::new();
-        result.extend(lines.iter().take(keep_start));
-        result.push("... [content summarized] ...");
-        result.extend(lines.iter().rev().take(keep_end).rev());
+        let summarized = if self.preserve_patterns.is_empty() {
+            let keep_start = keep_lines / 2;
+            let keep_end = keep_lines - keep_start;
 
-        let summarized = result.join("\n");
+            let mut result = Vec::new();
+            result.extend(lines.iter().take(keep_start));
+            result.push("... [content summarized] ...");
+            result.extend(lines.iter().rev().take(keep_end).rev());
+            result.join("\n")
+        } else {
+            self.summarize_preserving(&lines, keep_lines)
+        };
         let new_tokens = self.counter.count(&summarized);
 
         debug!(
@@ -352,6 +630,28 @@ impl ContextCompressor {
         summarized
     }
 
+    /// Keep protected lines first, then spend what's left of the budget on
+    /// the head and tail, marking each removed run
+    fn summarize_preserving(&self, lines: &[&str], keep_lines: usize) -> String {
+        let protected: Vec<bool> = lines.iter().map(|l| self.is_preserved(l)).collect();
+        let remaining = keep_lines.saturating_sub(protected.iter().filter(|p| **p).count());
+        let keep_start = remaining / 2;
+        let tail_from = lines.len() - (remaining - keep_start);
+
+        let mut result = Vec::new();
+        let mut in_gap = false;
+        for (i, line) in lines.iter().enumerate() {
+            if i < keep_start || i >= tail_from || protected[i] {
+                result.push(*line);
+                in_gap = false;
+            } else if !in_gap {
+                result.push("... [content summarized] ...");
+                in_gap = true;
+            }
+        }
+        result.join("\n")
+    }
+
     /// Remove redundant content between contexts
     pub fn deduplicate(&self, contexts: &[String]) -> Vec<String> {
         if contexts.len() <= 1 {
@@ -435,6 +735,164 @@ impl ContextCompressor {
 
         result.trim_end().to_string()
     }
+
+    /// Compress a large context in bounded chunks
+    ///
+    /// Reads `reader` line by line and cuts chunks only outside fenced code
+    /// blocks, so `compress_code` and `summarize` never see half a block. A
+    /// block larger than `max_block_size` is passed through unmodified to keep
+    /// memory bounded.
+    pub fn compress_streaming<R: BufRead, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        options: &StreamingOptions,
+    ) -> std::io::Result<StreamingStats> {
+        let mut stats = StreamingStats::default();
+        let mut chunk = String::new();
+        let mut line = String::new();
+        let mut in_code_block = false;
+        // Inside an oversized block: lines go straight to the writer
+        let mut passthrough = false;
+        let mut needs_separator = false;
+
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            stats.bytes_in += read;
+
+            let is_fence = line.trim().starts_with("```");
+            if is_fence {
+                in_code_block = !in_code_block;
+            }
+
+            if passthrough {
+                self.write_raw(&line, &mut writer, &mut stats, &mut needs_separator)?;
+                passthrough = in_code_block;
+                continue;
+            }
+
+            chunk.push_str(&line);
+
+            if in_code_block && chunk.len() >= options.max_block_size {
+                // Block too large to buffer: emit what we have verbatim
+                self.write_raw(&chunk, &mut writer, &mut stats, &mut needs_separator)?;
+                chunk.clear();
+                passthrough = true;
+            } else if !in_code_block && chunk.len() >= options.chunk_size {
+                self.write_chunk(&chunk, &mut writer, options, &mut stats, &mut needs_separator)?;
+                chunk.clear();
+            }
+        }
+
+        if !chunk.is_empty() {
+            self.write_chunk(&chunk, &mut writer, options, &mut stats, &mut needs_separator)?;
+        }
+
+        writer.flush()?;
+        debug!(
+            "Streamed {} bytes in {} chunks: {} -> {} tokens",
+            stats.bytes_in, stats.chunks, stats.original_tokens, stats.final_tokens
+        );
+        Ok(stats)
+    }
+
+    /// Compress one chunk and write it
+    fn write_chunk<W: Write>(
+        &self,
+        chunk: &str,
+        writer: &mut W,
+        options: &StreamingOptions,
+        stats: &mut StreamingStats,
+        needs_separator: &mut bool,
+    ) -> std::io::Result<()> {
+        let mut out = if options.compress_code {
+            self.compress_code(chunk)
+        } else {
+            chunk.trim_end().to_string()
+        };
+        if let Some(target) = options.target_reduction {
+            if target > 0.0 && target < 1.0 {
+                out = self.summarize(&out, target);
+            }
+        }
+
+        if out.is_empty() {
+            return Ok(());
+        }
+        if *needs_separator {
+            writer.write_all(b"\n")?;
+            stats.bytes_out += 1;
+        }
+        writer.write_all(out.as_bytes())?;
+
+        stats.chunks += 1;
+        stats.bytes_out += out.len();
+        stats.original_tokens += u64::from(self.counter.count(chunk));
+        stats.final_tokens += u64::from(self.counter.count(&out));
+        *needs_separator = true;
+        Ok(())
+    }
+
+    /// Write text unmodified (used for oversized code blocks)
+    fn write_raw<W: Write>(
+        &self,
+        text: &str,
+        writer: &mut W,
+        stats: &mut StreamingStats,
+        needs_separator: &mut bool,
+    ) -> std::io::Result<()> {
+        if *needs_separator {
+            writer.write_all(b"\n")?;
+            stats.bytes_out += 1;
+        }
+        writer.write_all(text.as_bytes())?;
+
+        let tokens = u64::from(self.counter.count(text));
+        stats.bytes_out += text.len();
+        stats.original_tokens += tokens;
+        stats.final_tokens += tokens;
+        *needs_separator = !text.ends_with('\n');
+        Ok(())
+    }
+}
+
+/// Options for `ContextCompressor::compress_streaming`
+#[derive(Debug, Clone)]
+pub struct StreamingOptions {
+    /// Target chunk size in bytes (chunks end at the first line boundary past this
+    /// that is outside a code block)
+    pub chunk_size: usize,
+    /// Largest code block buffered for compression; bigger blocks pass through as-is
+    pub max_block_size: usize,
+    /// Strip comments and blank lines inside code blocks
+    pub compress_code: bool,
+    /// Summarize each chunk to this reduction (0.0 - 1.0)
+    pub target_reduction: Option<f64>,
+}
+
+impl Default for StreamingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 64 * 1024,
+            max_block_size: 1024 * 1024,
+            compress_code: true,
+            target_reduction: None,
+        }
+    }
+}
+
+/// Statistics from a streaming compression run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamingStats {
+    pub bytes_in: usize,
+    pub bytes_out: usize,
+    pub chunks: usize,
+    pub original_tokens: u64,
+    pub final_tokens: u64,
 }
 
 impl Default for ContextCompressor {
@@ -473,7 +931,7 @@ pub struct TokenMetrics {
     agent_metrics: Arc<RwLock<HashMap<AgentId, AgentTokenMetrics>>>,
     /// Global metrics
     global_metrics: Arc<RwLock<GlobalTokenMetrics>>,
-    counter: TokenCounter,
+    counter: Arc<dyn TokenCounter>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -489,10 +947,16 @@ impl TokenMetrics {
         Self {
             agent_metrics: Arc::new(RwLock::new(HashMap::new())),
             global_metrics: Arc::new(RwLock::new(GlobalTokenMetrics::default())),
-            counter: TokenCounter::new(),
+            counter: Arc::new(HeuristicCounter::new()),
         }
     }
 
+    /// Count with a different tokenizer backend
+    pub fn with_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = counter;
+        self
+    }
+
     /// Record token usage for an agent
     pub async fn record(&self, usage: TokenUsage) {
         let mut metrics = self.agent_metrics.write().await;
@@ -613,25 +1077,215 @@ pub struct TokenRecommendation {
     pub potential_savings: u32,
 }
 
+/// Fraction of the budget above which requests are throttled rather than allowed
+pub const BUDGET_THROTTLE_RATIO: f64 = 0.8;
+
+/// Default budget window
+pub const DEFAULT_BUDGET_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Outcome of a token budget check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum BudgetDecision {
+    /// Well within budget
+    Allow,
+    /// Within budget but close to the limit; callers should slow down
+    Throttle { remaining: u64 },
+    /// Request would exceed the budget for the current window
+    Deny { used: u64, limit: u64 },
+}
+
+/// Usage within the current budget window
+#[derive(Debug, Clone, Copy)]
+struct BudgetWindow {
+    started: tokio::time::Instant,
+    used: u64,
+}
+
+/// Per-agent token budgets over a rolling reset window
+pub struct TokenBudgets {
+    /// Default tokens per agent per window (0 = unlimited)
+    default_limit: u64,
+    /// How often usage resets
+    window: Duration,
+    /// Per-agent limit overrides
+    limits: RwLock<HashMap<AgentId, u64>>,
+    /// Usage in the current window per agent
+    usage: RwLock<HashMap<AgentId, BudgetWindow>>,
+}
+
+impl TokenBudgets {
+    pub fn new(default_limit: u64, window: Duration) -> Self {
+        Self {
+            default_limit,
+            window,
+            limits: RwLock::new(HashMap::new()),
+            usage: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Override the budget for a single agent (0 = unlimited)
+    pub async fn set_limit(&self, agent_id: AgentId, limit: u64) {
+        self.limits.write().await.insert(agent_id, limit);
+    }
+
+    /// Effective budget for an agent
+    pub async fn limit_for(&self, agent_id: AgentId) -> u64 {
+        self.limits
+            .read()
+            .await
+            .get(&agent_id)
+            .copied()
+            .unwrap_or(self.default_limit)
+    }
+
+    /// Tokens used by an agent in the current window
+    pub async fn used(&self, agent_id: AgentId) -> u64 {
+        let usage = self.usage.read().await;
+        usage
+            .get(&agent_id)
+            .filter(|w| w.started.elapsed() < self.window)
+            .map_or(0, |w| w.used)
+    }
+
+    /// Current window for an agent, starting a new one if it has expired
+    fn current_window(&self, usage: &mut HashMap<AgentId, BudgetWindow>, agent_id: AgentId) -> BudgetWindow {
+        let now = tokio::time::Instant::now();
+        let window = usage.entry(agent_id).or_insert(BudgetWindow { started: now, used: 0 });
+        if now.duration_since(window.started) >= self.window {
+            *window = BudgetWindow { started: now, used: 0 };
+        }
+        *window
+    }
+
+    /// Record tokens consumed by an agent
+    pub async fn record(&self, agent_id: AgentId, tokens: u64) {
+        let mut usage = self.usage.write().await;
+        let window = self.current_window(&mut usage, agent_id);
+        usage.insert(agent_id, BudgetWindow { used: window.used.saturating_add(tokens), ..window });
+    }
+
+    /// Decide whether an agent may spend `requested` more tokens, reserving them if so
+    ///
+    /// Allowed and throttled requests count against the budget right away, so
+    /// concurrent delegations can't all pass against the same usage. Settle the
+    /// reservation with [`TokenBudgets::reconcile`] once the real usage is known.
+    pub async fn check(&self, agent_id: AgentId, requested: u64) -> BudgetDecision {
+        let limit = self.limit_for(agent_id).await;
+        let mut usage = self.usage.write().await;
+        let window = self.current_window(&mut usage, agent_id);
+        let projected = window.used.saturating_add(requested);
+        let decision = if limit == 0 {
+            BudgetDecision::Allow
+        } else if projected > limit {
+            return BudgetDecision::Deny { used: window.used, limit };
+        } else if projected as f64 >= limit as f64 * BUDGET_THROTTLE_RATIO {
+            BudgetDecision::Throttle {
+                remaining: limit - projected,
+            }
+        } else {
+            BudgetDecision::Allow
+        };
+        usage.insert(agent_id, BudgetWindow { used: projected, ..window });
+        decision
+    }
+
+    /// Replace a reservation made by [`TokenBudgets::check`] at `reserved_at`
+    /// with the tokens actually used
+    ///
+    /// If the window has reset since the reservation, only `actual` is recorded.
+    pub async fn reconcile(
+        &self,
+        agent_id: AgentId,
+        reserved: u64,
+        actual: u64,
+        reserved_at: tokio::time::Instant,
+    ) {
+        let mut usage = self.usage.write().await;
+        let window = self.current_window(&mut usage, agent_id);
+        let used = if reserved_at >= window.started {
+            window.used.saturating_sub(reserved)
+        } else {
+            window.used
+        };
+        usage.insert(agent_id, BudgetWindow { used: used.saturating_add(actual), ..window });
+    }
+}
+
+impl Default for TokenBudgets {
+    fn default() -> Self {
+        Self::new(0, DEFAULT_BUDGET_WINDOW)
+    }
+}
+
 /// Token efficiency service combining all components
 pub struct TokenService {
-    pub counter: TokenCounter,
+    pub counter: Arc<dyn TokenCounter>,
     pub analyzer: ContextAnalyzer,
     pub compressor: ContextCompressor,
     pub metrics: TokenMetrics,
+    pub budgets: TokenBudgets,
 }
 
 impl TokenService {
     pub fn new() -> Self {
         info!("Token efficiency service initialized");
         Self {
-            counter: TokenCounter::new(),
+            counter: Arc::new(HeuristicCounter::new()),
             analyzer: ContextAnalyzer::new(),
             compressor: ContextCompressor::new(),
             metrics: TokenMetrics::new(),
+            budgets: TokenBudgets::default(),
+        }
+    }
+
+    /// Use a different tokenizer backend for every component
+    pub fn with_counter(self, counter: Arc<dyn TokenCounter>) -> Self {
+        info!("Token counting with '{}' tokenizer", counter.name());
+        Self {
+            analyzer: self.analyzer.with_counter(counter.clone()),
+            compressor: self.compressor.with_counter(counter.clone()),
+            metrics: self.metrics.with_counter(counter.clone()),
+            counter,
+            budgets: self.budgets,
         }
     }
 
+    /// Keep lines matching `patterns` when summarizing (none by default)
+    pub fn with_preserve_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.compressor = self.compressor.with_preserve_patterns(patterns);
+        self
+    }
+
+    /// Count tokens with the configured tokenizer
+    pub fn count(&self, text: &str) -> u32 {
+        self.counter.count(text)
+    }
+
+    /// Configure per-agent token budgets (0 = unlimited)
+    pub fn with_budget(mut self, limit_per_agent: u64, window: Duration) -> Self {
+        self.budgets = TokenBudgets::new(limit_per_agent, window);
+        self
+    }
+
+    /// Check whether an agent may spend `requested_tokens` in the current budget window
+    pub async fn check_budget(&self, agent_id: AgentId, requested_tokens: u64) -> BudgetDecision {
+        let decision = self.budgets.check(agent_id, requested_tokens).await;
+        match decision {
+            BudgetDecision::Allow => {}
+            BudgetDecision::Throttle { remaining } => {
+                debug!("Agent {} near token budget ({} remaining)", agent_id, remaining);
+            }
+            BudgetDecision::Deny { used, limit } => {
+                warn!(
+                    "Agent {} over token budget: {} used + {} requested > {}",
+                    agent_id, used, requested_tokens, limit
+                );
+            }
+        }
+        decision
+    }
+
     /// Analyze and optionally compress a context
     pub async fn process_context(
         &self,
@@ -724,9 +1378,241 @@ pub struct EfficiencySummary {
 mod tests {
     use super::*;
 
+    /// A service log with a panic and stack trace buried in the middle
+    fn log_with_stack_trace() -> String {
+        let mut log = String::new();
+        for i in 0..20 {
+            log.push_str(&format!("INFO request {i} handled in 12ms\n"));
+        }
+        log.push_str("ERROR failed to load config: missing field `port`\n");
+        log.push_str("thread 'main' panicked at src/config.rs:42:9\n");
+        log.push_str("    at cca_daemon::config::load (src/config.rs:42)\n");
+        log.push_str("    at cca_daemon::main (src/main.rs:17)\n");
+        for i in 20..40 {
+            log.push_str(&format!("INFO request {i} handled in 9ms\n"));
+        }
+        log
+    }
+
+    #[test]
+    fn test_summarize_preserves_stack_trace() {
+        let compressor = ContextCompressor::new()
+            .with_preserve_patterns(ContextCompressor::default_preserve_patterns());
+        let log = log_with_stack_trace();
+
+        let summarized = compressor.summarize(&log, 0.5);
+        assert!(summarized.contains("ERROR failed to load config: missing field `port`"));
+        assert!(summarized.contains("thread 'main' panicked at src/config.rs:42:9"));
+        assert!(summarized.contains("    at cca_daemon::config::load (src/config.rs:42)"));
+        assert!(summarized.contains("    at cca_daemon::main (src/main.rs:17)"));
+
+        // Protected lines count against the 50% budget, not on top of it
+        let kept = summarized
+            .lines()
+            .filter(|l| *l != "... [content summarized] ...")
+            .count();
+        assert_eq!(kept, log.lines().count() / 2);
+        assert!(summarized.starts_with("INFO request 0 "));
+        assert!(summarized.ends_with("INFO request 39 handled in 9ms"));
+    }
+
+    #[test]
+    fn test_summarize_without_patterns_drops_stack_trace() {
+        let summarized = ContextCompressor::new().summarize(&log_with_stack_trace(), 0.5);
+        assert!(!summarized.contains("panicked"));
+        assert_eq!(summarized.matches("[content summarized]").count(), 1);
+    }
+
+    #[test]
+    fn test_service_preserves_lines_only_when_enabled() {
+        let log = log_with_stack_trace();
+        let summarized = TokenService::new().compressor.summarize(&log, 0.5);
+        assert!(!summarized.contains("panicked"));
+
+        let service = TokenService::new().with_preserve_patterns(ContextCompressor::default_preserve_patterns());
+        assert!(service.compressor.summarize(&log, 0.5).contains("panicked"));
+    }
+
+    #[test]
+    fn test_summarize_keeps_all_protected_lines_over_budget() {
+        let compressor = ContextCompressor::new()
+            .with_preserve_patterns(vec![Regex::new("^ERROR").unwrap()]);
+        let log: String = (0..10).map(|i| format!("ERROR {i}\ninfo {i}\n")).collect();
+
+        let summarized = compressor.summarize(&log, 0.9);
+        assert_eq!(summarized.matches("ERROR").count(), 10);
+        assert!(!summarized.contains("info"));
+    }
+
+    /// Prose interleaved with rust blocks whose comments compress away
+    fn synthetic_context(sections: usize) -> String {
+        let mut content = String::new();
+        for i in 0..sections {
+            content.push_str(&format!("Section {i} explains the change.\n"));
+            content.push_str("```rust\n");
+            content.push_str(&format!("// comment {i} that should be stripped\n"));
+            content.push_str(&format!("fn section_{i}() -> u32 {{\n"));
+            content.push_str("    // inner comment\n");
+            content.push_str(&format!("    {i}\n"));
+            content.push_str("}\n");
+            content.push_str("```\n");
+        }
+        content
+    }
+
+    #[test]
+    fn test_compress_streaming_matches_whole_input() {
+        let compressor = ContextCompressor::new();
+        let content = synthetic_context(200);
+
+        // Tiny chunks force fences to straddle the chunk size repeatedly
+        let options = StreamingOptions {
+            chunk_size: 100,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let stats = compressor
+            .compress_streaming(content.as_bytes(), &mut output, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(stats.chunks > 10);
+        assert_eq!(stats.bytes_in, content.len());
+        assert_eq!(stats.bytes_out, output.len());
+        assert!(stats.final_tokens < stats.original_tokens);
+        assert_eq!(output, compressor.compress_code(&content));
+        assert!(!output.contains("// comment"));
+        assert_eq!(output.matches("```").count() % 2, 0);
+    }
+
+    #[test]
+    fn test_compress_streaming_passes_oversized_block_through() {
+        let compressor = ContextCompressor::new();
+        let mut content = String::from("Intro\n```rust\n");
+        for i in 0..50 {
+            content.push_str(&format!("// keep {i}\nlet x{i} = {i};\n"));
+        }
+        content.push_str("```\n");
+        content.push_str(&synthetic_context(2));
+
+        let options = StreamingOptions {
+            chunk_size: 64,
+            max_block_size: 200,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        compressor
+            .compress_streaming(content.as_bytes(), &mut output, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The oversized block is intact, later blocks are still compressed
+        assert!(output.contains("// keep 0\n"));
+        assert!(output.contains("// keep 49\nlet x49 = 49;\n```\n"));
+        assert!(!output.contains("// comment"));
+        assert_eq!(output.matches("```").count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_budget_under_at_and_over() {
+        let service = TokenService::new().with_budget(1000, DEFAULT_BUDGET_WINDOW);
+        let agent = AgentId::new();
+
+        assert_eq!(service.check_budget(agent, 100).await, BudgetDecision::Allow);
+        assert_eq!(service.budgets.used(agent).await, 100);
+
+        service.budgets.record(agent, 400).await;
+        assert_eq!(
+            service.check_budget(agent, 501).await,
+            BudgetDecision::Deny { used: 500, limit: 1000 }
+        );
+        // A denied request reserves nothing
+        assert_eq!(service.budgets.used(agent).await, 500);
+
+        // Exactly at the limit is still allowed, but throttled
+        assert_eq!(
+            service.check_budget(agent, 500).await,
+            BudgetDecision::Throttle { remaining: 0 }
+        );
+        assert!(matches!(service.check_budget(agent, 1).await, BudgetDecision::Deny { .. }));
+
+        // Other agents have their own budget
+        assert_eq!(
+            service.check_budget(AgentId::new(), 900).await,
+            BudgetDecision::Throttle { remaining: 100 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_reservations_stop_concurrent_overspend() {
+        let service = TokenService::new().with_budget(1000, DEFAULT_BUDGET_WINDOW);
+        let agent = AgentId::new();
+
+        // Parallel delegations checked before any of them reports usage
+        let decisions = futures_util::future::join_all((0..4).map(|_| service.check_budget(agent, 300))).await;
+        let allowed = decisions.iter().filter(|d| !matches!(d, BudgetDecision::Deny { .. })).count();
+        assert_eq!(allowed, 3);
+        assert_eq!(service.budgets.used(agent).await, 900);
+
+        // Reservations are replaced by what the delegations actually used
+        let reserved_at = tokio::time::Instant::now();
+        service.budgets.reconcile(agent, 300, 50, reserved_at).await;
+        service.budgets.reconcile(agent, 300, 0, reserved_at).await;
+        assert_eq!(service.budgets.used(agent).await, 350);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_budget_reconcile_after_window_reset() {
+        let service = TokenService::new().with_budget(1000, Duration::from_secs(60));
+        let agent = AgentId::new();
+
+        let reserved_at = tokio::time::Instant::now();
+        service.check_budget(agent, 300).await;
+        tokio::time::advance(Duration::from_secs(61)).await;
+        service.check_budget(agent, 100).await;
+
+        // The old reservation isn't in the new window, so only usage is added
+        service.budgets.reconcile(agent, 300, 40, reserved_at).await;
+        assert_eq!(service.budgets.used(agent).await, 140);
+    }
+
+    #[tokio::test]
+    async fn test_budget_unlimited_and_override() {
+        let service = TokenService::new();
+        let agent = AgentId::new();
+
+        service.budgets.record(agent, 1_000_000).await;
+        assert_eq!(service.check_budget(agent, 1_000_000).await, BudgetDecision::Allow);
+
+        service.budgets.set_limit(agent, 10).await;
+        assert!(matches!(
+            service.check_budget(agent, 1).await,
+            BudgetDecision::Deny { .. }
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_budget_resets_after_window() {
+        let service = TokenService::new().with_budget(100, Duration::from_secs(60));
+        let agent = AgentId::new();
+
+        service.budgets.record(agent, 100).await;
+        assert!(matches!(
+            service.check_budget(agent, 1).await,
+            BudgetDecision::Deny { .. }
+        ));
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(service.budgets.used(agent).await, 0);
+        assert_eq!(service.check_budget(agent, 1).await, BudgetDecision::Allow);
+
+        service.budgets.record(agent, 30).await;
+        assert_eq!(service.budgets.used(agent).await, 31);
+    }
+
     #[test]
     fn test_token_counter() {
-        let counter = TokenCounter::new();
+        let counter = HeuristicCounter::new();
 
         // Empty string
         assert_eq!(counter.count(""), 0);
@@ -742,6 +1628,57 @@ mod tests {
         assert!(long_tokens > 50);
     }
 
+    /// Exact-style counter: one token per word and per punctuation mark
+    struct StubExactCounter;
+
+    impl TokenCounter for StubExactCounter {
+        fn count(&self, text: &str) -> u32 {
+            text.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+                .filter(|w| !w.is_empty())
+                .count() as u32
+                + text.chars().filter(char::is_ascii_punctuation).count() as u32
+        }
+
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+    }
+
+    #[test]
+    fn test_heuristic_and_exact_counters_differ_plausibly() {
+        let text = "fn main() { let total: u32 = items.iter().map(|i| i.len()).sum(); }";
+        let heuristic = HeuristicCounter::new().count(text);
+        let exact = StubExactCounter.count(text);
+
+        assert_ne!(heuristic, exact);
+        // Both land within a few tokens per word of the input
+        let words = text.split_whitespace().count() as u32;
+        for count in [heuristic, exact] {
+            assert!(count >= words && count <= text.len() as u32, "{count}");
+        }
+    }
+
+    #[test]
+    fn test_token_service_delegates_to_counter() {
+        let content = "Hello, world! How are you?";
+        let service = TokenService::new().with_counter(Arc::new(StubExactCounter));
+
+        assert_eq!(service.count(content), StubExactCounter.count(content));
+        assert_eq!(service.analyzer.analyze(content).total_tokens, service.count(content));
+        assert_ne!(TokenService::new().count(content), service.count(content));
+    }
+
+    #[test]
+    fn test_counter_for_config_names() {
+        assert_eq!(counter_for("heuristic").unwrap().name(), "heuristic");
+        assert!(counter_for("sentencepiece").is_err());
+
+        #[cfg(feature = "tiktoken")]
+        assert_eq!(counter_for("tiktoken").unwrap().count("Hello world"), 2);
+        #[cfg(not(feature = "tiktoken"))]
+        assert!(counter_for("tiktoken").is_err());
+    }
+
     #[test]
     fn test_context_analyzer() {
         let analyzer = ContextAnalyzer::new();
@@ -754,6 +1691,54 @@ mod tests {
         assert!(!analysis.repeated_lines.is_empty());
     }
 
+    #[test]
+    fn test_section_breakdown_sums_to_total() {
+        let analyzer = ContextAnalyzer::new();
+        let code = "```rust\nfn main() {\n    println!(\"hello\");\n}\n```";
+        let note = "Remember to run the tests before pushing.";
+        let content = format!(
+            "Intro paragraph describing the change in detail.\n\n{code}\n\n{note}\n\n\
+             Some more prose here.\n{code}\n{note}\n\n{code}\n"
+        );
+
+        let analysis = analyzer.analyze(&content);
+        let sections = analysis.sections;
+        assert_eq!(sections.total(), analysis.total_tokens);
+        assert!(sections.code > 0);
+        assert!(sections.prose > 0);
+        assert!(sections.repeated > sections.code);
+
+        let top = &analysis.top_repeated_blocks;
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].kind, SectionKind::Code);
+        assert_eq!(top[0].count, 3);
+        assert!(top[0].preview.starts_with("```rust"));
+        assert_eq!(top[1].kind, SectionKind::Prose);
+        assert_eq!(top[1].count, 2);
+    }
+
+    #[test]
+    fn test_section_breakdown_edge_cases() {
+        let analyzer = ContextAnalyzer::new();
+
+        for content in ["   \n  ", "one line", "```\nunterminated\ncode", "a\n\nb\n\na\n\nb"] {
+            let analysis = analyzer.analyze(content);
+            assert_eq!(analysis.sections.total(), analysis.total_tokens, "{content:?}");
+        }
+
+        let analysis = analyzer.analyze("```\nunterminated\ncode");
+        assert_eq!(analysis.sections.prose, 0);
+        assert!(analysis.top_repeated_blocks.is_empty());
+    }
+
+    #[test]
+    fn test_apportion_is_exact() {
+        assert_eq!(apportion(10, [1, 1, 1]), [4, 3, 3]);
+        assert_eq!(apportion(7, [0, 5, 0]), [0, 7, 0]);
+        assert_eq!(apportion(3, [0, 0, 0]), [0, 3, 0]);
+        assert_eq!(apportion(0, [4, 2, 1]), [0, 0, 0]);
+    }
+
     #[test]
     fn test_redundancy_detection() {
         let analyzer = ContextAnalyzer::new();
@@ -6,6 +6,8 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::path::PathBuf;
 use tracing::{debug, error};
 
+use crate::types::DelegationResult;
+
 /// Minimal config structure to extract API key from cca.toml
 #[derive(Debug, Deserialize, Default)]
 struct MinimalConfig {
@@ -383,6 +385,8 @@ pub struct TaskResponse {
     pub error: Option<String>,
     #[serde(default)]
     pub assigned_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<DelegationResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
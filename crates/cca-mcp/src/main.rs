@@ -10,58 +10,14 @@
 #![allow(clippy::must_use_candidate)]
 #![allow(clippy::return_self_not_must_use)]
 
-use std::path::Path;
-
 use anyhow::Result;
 use clap::Parser;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
+use cca_core::util::load_env_file;
 use cca_mcp::McpServer;
 
-/// Load environment variables from CCA env file if not already set
-fn load_env_file() {
-    let env_paths = [
-        "/usr/local/etc/cca/cca.env".to_string(),
-        dirs::config_dir()
-            .map(|p| p.join("cca/cca.env").to_string_lossy().to_string())
-            .unwrap_or_default(),
-        dirs::home_dir()
-            .map(|p| p.join(".config/cca/cca.env").to_string_lossy().to_string())
-            .unwrap_or_default(),
-    ];
-
-    for path in &env_paths {
-        if path.is_empty() {
-            continue;
-        }
-        if Path::new(path).exists() {
-            if let Ok(contents) = std::fs::read_to_string(path) {
-                parse_env_file(&contents);
-            }
-            break;
-        }
-    }
-}
-
-/// Parse env file contents and set environment variables (only if not already set)
-fn parse_env_file(contents: &str) {
-    for line in contents.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-        let line = line.strip_prefix("export ").unwrap_or(line);
-        if let Some((key, value)) = line.split_once('=') {
-            let key = key.trim();
-            let value = value.trim().trim_matches('"').trim_matches('\'');
-            if std::env::var(key).is_err() {
-                std::env::set_var(key, value);
-            }
-        }
-    }
-}
-
 /// CCA MCP Server - Model Context Protocol integration for Claude Code
 #[derive(Parser, Debug)]
 #[command(name = "cca-mcp")]
@@ -1,9 +1,9 @@
 //! MCP Tool implementations
 
 use anyhow::{anyhow, Result};
-use tracing::info;
+use tracing::{debug, info};
 
-use crate::client::{CreateTaskRequest, DaemonClient};
+use crate::client::{CreateTaskRequest, DaemonClient, RLStatsResponse};
 use crate::types::{McpTool, PatternMatch, MemoryResult};
 
 /// Registry of available MCP tools
@@ -129,7 +129,7 @@ impl ToolRegistry {
             },
             McpTool {
                 name: "cca_rl_algorithm".to_string(),
-                description: "Set the RL algorithm to use. Available: q_learning, dqn, ppo.".to_string(),
+                description: "Set the RL algorithm to use. The name is checked against the algorithms the daemon reports in cca_rl_status (q_learning, dqn, ppo by default).".to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -557,6 +557,17 @@ impl ToolRegistry {
             }))?);
         }
 
+        // Reject unknown names before asking the daemon to switch. If stats are
+        // unavailable the daemon's own validation still applies.
+        match client.get_rl_stats().await {
+            Ok(stats) => {
+                if let Err(error) = check_rl_algorithm(algorithm, &stats) {
+                    return Ok(serde_json::to_string_pretty(&serde_json::json!({ "error": error }))?);
+                }
+            }
+            Err(e) => debug!("Could not fetch RL stats to validate algorithm: {}", e),
+        }
+
         info!("Setting RL algorithm to: {}", algorithm);
 
         match client.set_rl_algorithm(algorithm).await {
@@ -730,6 +741,20 @@ impl ToolRegistry {
     }
 }
 
+/// Check that `algorithm` is one the daemon reports as available
+///
+/// An empty list (older daemons) accepts any name.
+pub fn check_rl_algorithm(algorithm: &str, stats: &RLStatsResponse) -> Result<(), String> {
+    if stats.algorithms_available.is_empty() || stats.algorithms_available.iter().any(|a| a == algorithm) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unknown RL algorithm '{algorithm}'. Available: {}",
+            stats.algorithms_available.join(", ")
+        ))
+    }
+}
+
 impl Default for ToolRegistry {
     fn default() -> Self {
         Self::new()
@@ -89,6 +89,23 @@ pub struct TaskResponse {
     pub status: String,
     pub output: Option<String>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<DelegationResult>,
+}
+
+/// Per-agent result of a delegated task step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegationResult {
+    pub role: String,
+    pub success: bool,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub tokens_used: u64,
 }
 
 /// Status request for cca_status tool
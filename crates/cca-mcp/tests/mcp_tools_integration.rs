@@ -55,6 +55,60 @@ async fn test_cca_task_tool() {
     assert_eq!(json["status"], "pending");
 }
 
+/// Task responses expose structured per-delegation results
+#[tokio::test]
+async fn test_task_response_delegations() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/tasks/task-12345"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "task_id": "task-12345",
+            "status": "partial",
+            "output": "## backend Agent\nDone\n\n## qa Agent\nError: timed out\n\n",
+            "error": "qa: timed out",
+            "assigned_agent": "coordinator-1",
+            "delegations": [
+                {
+                    "role": "backend",
+                    "success": true,
+                    "output": "Done",
+                    "error": null,
+                    "duration_ms": 1200,
+                    "tokens_used": 800
+                },
+                {
+                    "role": "qa",
+                    "success": false,
+                    "output": null,
+                    "error": "timed out",
+                    "duration_ms": 30000,
+                    "tokens_used": 0
+                }
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = cca_mcp::DaemonClient::new(mock_server.uri());
+    let task = client.get_task("task-12345").await.unwrap();
+
+    assert_eq!(task.delegations.len(), 2);
+    assert_eq!(
+        task.delegations[0],
+        cca_mcp::DelegationResult {
+            role: "backend".to_string(),
+            success: true,
+            output: Some("Done".to_string()),
+            error: None,
+            duration_ms: 1200,
+            tokens_used: 800,
+        }
+    );
+    assert!(!task.delegations[1].success);
+    assert_eq!(task.delegations[1].error.as_deref(), Some("timed out"));
+}
+
 /// Test cca_status tool
 #[tokio::test]
 async fn test_cca_status_tool() {
@@ -333,6 +387,70 @@ async fn test_cca_rl_algorithm_tool() {
     assert_eq!(json["algorithm"], "dqn");
 }
 
+/// RL stats from the daemon parse into the typed response
+#[tokio::test]
+async fn test_rl_status_tool_parses_stats() {
+    let mock_server = MockServer::start().await;
+    mount_rl_daemon(&mock_server).await;
+
+    let output = cca_mcp::tools::ToolRegistry::new()
+        .call("cca_rl_status", &json!({}), &mock_server.uri())
+        .await
+        .unwrap();
+
+    let stats: cca_mcp::client::RLStatsResponse = serde_json::from_str(&output).unwrap();
+    assert_eq!(stats.algorithm, "q_learning");
+    assert_eq!(stats.total_steps, 5000);
+    assert_eq!(stats.experience_count, 420);
+    assert_eq!(stats.algorithms_available, vec!["q_learning", "dqn", "ppo"]);
+}
+
+/// Unknown algorithms are rejected without calling the set-algorithm endpoint
+#[tokio::test]
+async fn test_rl_algorithm_tool_rejects_unknown_algorithm() {
+    let mock_server = MockServer::start().await;
+    mount_rl_daemon(&mock_server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/rl/algorithm"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let output = cca_mcp::tools::ToolRegistry::new()
+        .call("cca_rl_algorithm", &json!({ "algorithm": "sarsa" }), &mock_server.uri())
+        .await
+        .unwrap();
+
+    let json: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["error"], "Unknown RL algorithm 'sarsa'. Available: q_learning, dqn, ppo");
+}
+
+/// Mount health and RL stats endpoints for the RL tool tests
+async fn mount_rl_daemon(mock_server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+        .mount(mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/rl/stats"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "algorithm": "q_learning",
+            "total_steps": 5000,
+            "total_rewards": 212.5,
+            "average_reward": 0.85,
+            "buffer_size": 1000,
+            "last_training_loss": 0.02,
+            "experience_count": 420,
+            "algorithms_available": ["q_learning", "dqn", "ppo"]
+        })))
+        .mount(mock_server)
+        .await;
+}
+
 /// Test cca_acp_status tool
 #[tokio::test]
 async fn test_cca_acp_status_tool() {
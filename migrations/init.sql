@@ -9,6 +9,27 @@
 CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
 CREATE EXTENSION IF NOT EXISTS "vector";
 
+-- ============================================================================
+-- Schema Version Table
+-- ============================================================================
+
+-- The daemon refuses to start if the highest version here is older than the
+-- version it was built against (see EXPECTED_SCHEMA_VERSION in postgres.rs).
+-- Bump both together whenever the schema changes.
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    description TEXT,
+    applied_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+INSERT INTO schema_version (version, description)
+VALUES (1, 'Consolidated initial schema'),
+       (2, 'Agent log archive'),
+       (3, 'Code chunk indexing job ownership')
+ON CONFLICT (version) DO NOTHING;
+
+COMMENT ON TABLE schema_version IS 'Applied schema versions, checked by the daemon at startup';
+
 -- ============================================================================
 -- Agents Table
 -- ============================================================================
@@ -129,17 +150,23 @@ CREATE TABLE IF NOT EXISTS code_chunks (
     embedding vector(768),
     metadata JSONB DEFAULT '{}',
     indexed_at TIMESTAMPTZ DEFAULT NOW(),
+    job_id UUID,  -- indexing job that first inserted the chunk
     UNIQUE(file_path, chunk_type, name, start_line)
 );
 
+-- Added in schema version 3; brings existing installations up to date
+ALTER TABLE code_chunks ADD COLUMN IF NOT EXISTS job_id UUID;
+
 CREATE INDEX IF NOT EXISTS idx_code_chunks_embedding ON code_chunks
     USING ivfflat (embedding vector_cosine_ops) WITH (lists = 100);
 CREATE INDEX IF NOT EXISTS idx_code_chunks_file_path ON code_chunks(file_path);
 CREATE INDEX IF NOT EXISTS idx_code_chunks_language ON code_chunks(language);
 CREATE INDEX IF NOT EXISTS idx_code_chunks_type ON code_chunks(chunk_type);
+CREATE INDEX IF NOT EXISTS idx_code_chunks_job_id ON code_chunks(job_id);
 
 COMMENT ON TABLE code_chunks IS 'Indexed code chunks with embeddings for semantic search';
 COMMENT ON COLUMN code_chunks.embedding IS 'nomic-embed-text embedding (768 dimensions via Ollama)';
+COMMENT ON COLUMN code_chunks.job_id IS 'Indexing job that inserted the chunk; its chunks are deleted if it is cancelled';
 
 -- ============================================================================
 -- Indexing Jobs Table (for background indexing)
@@ -164,6 +191,23 @@ CREATE INDEX IF NOT EXISTS idx_indexing_jobs_created_at ON indexing_jobs(created
 
 COMMENT ON TABLE indexing_jobs IS 'Background codebase indexing job tracking';
 
+-- ============================================================================
+-- Agent Logs Table (durable archive of per-agent log entries)
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS agent_logs (
+    id BIGSERIAL PRIMARY KEY,
+    agent_id UUID NOT NULL,
+    level VARCHAR(10) NOT NULL,
+    message TEXT NOT NULL,
+    logged_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_agent_logs_agent_id ON agent_logs(agent_id, logged_at DESC);
+CREATE INDEX IF NOT EXISTS idx_agent_logs_logged_at ON agent_logs(logged_at);
+
+COMMENT ON TABLE agent_logs IS 'Agent log entries, pruned after postgres.agent_log_retention_days';
+
 -- ============================================================================
 -- Triggers for updated_at
 -- ============================================================================
@@ -11,10 +11,8 @@ use std::collections::HashMap;
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
-#[cfg(test)]
-use std::time::Duration;
-use std::time::Instant;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use tokio::time::sleep;
 
 use async_trait::async_trait;
@@ -239,10 +237,14 @@ pub struct ProcessAgent {
 impl ProcessAgent {
     /// Spawn a new process-based agent
     pub fn spawn(id: impl Into<String>, command: &str, args: &[&str]) -> ChaosResult<Self> {
-        let child = Command::new(command)
-            .args(args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+        let mut command = Command::new(command);
+        command.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+        Self::from_command(id, &mut command)
+    }
+
+    /// Spawn a process-based agent from a fully configured command
+    pub fn from_command(id: impl Into<String>, command: &mut Command) -> ChaosResult<Self> {
+        let child = command
             .spawn()
             .map_err(|e| ChaosError::ProcessError(e.to_string()))?;
 
@@ -304,6 +306,264 @@ impl Drop for ProcessAgent {
     }
 }
 
+/// Daemon the chaos workers connect to, whose view of them decides recovery
+#[derive(Debug, Clone)]
+pub struct DaemonTarget {
+    /// HTTP API base URL
+    pub url: String,
+    /// ACP WebSocket URL the workers connect to
+    pub acp_url: String,
+    /// Sent as `X-API-Key` when polling the daemon. Workers read their own key
+    /// from the config file, like any `cca agent worker`.
+    pub api_key: Option<String>,
+}
+
+impl DaemonTarget {
+    /// Endpoints from `CCA_DAEMON_URL`, `CCA_ACP_URL` and `CHAOS_API_KEY`, with the CLI's defaults
+    pub fn from_env() -> Self {
+        Self {
+            url: std::env::var("CCA_DAEMON_URL").unwrap_or_else(|_| "http://127.0.0.1:8580".into()),
+            acp_url: std::env::var("CCA_ACP_URL").unwrap_or_else(|_| "ws://127.0.0.1:8581".into()),
+            api_key: std::env::var("CHAOS_API_KEY").ok(),
+        }
+    }
+
+    /// Workers of `role` whose `worker_id` starts with `prefix`, per `/api/v1/acp/status`
+    pub async fn connected_workers(
+        &self,
+        client: &reqwest::Client,
+        role: &str,
+        prefix: &str,
+    ) -> ChaosResult<usize> {
+        let mut request = client.get(format!("{}/api/v1/acp/status", self.url));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+        let status: serde_json::Value = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| ChaosError::ConnectionError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| ChaosError::ConnectionError(e.to_string()))?;
+
+        let workers = status["workers"].as_array().map_or(&[][..], Vec::as_slice);
+        Ok(workers
+            .iter()
+            .filter(|w| w["role"].as_str().is_some_and(|r| r.eq_ignore_ascii_case(role)))
+            .filter(|w| w["worker_id"].as_str().is_some_and(|id| id.starts_with(prefix)))
+            .count())
+    }
+}
+
+/// Chaos harness over a pool of real `cca agent worker` processes
+///
+/// Workers get stable worker IDs under a per-harness prefix, so only this
+/// pool is counted. `inject_fault(ProcessKill { signal })` sends the signal to
+/// a running worker and waits until the daemon has dropped its connection.
+/// `verify_recovery` then polls the daemon's `/api/v1/acp/status` up to
+/// `reconnect_attempts` times, `injection_delay` apart, until every worker of
+/// the role is connected again, recording the time from the fault into
+/// `ChaosMetrics`. `restore` respawns exited workers the way the daemon
+/// respawns agents; call `verify_recovery` alone when something else does the
+/// respawn.
+pub struct AgentCrashChaos {
+    cca_bin: String,
+    role: String,
+    worker_prefix: String,
+    daemon: DaemonTarget,
+    http: reqwest::Client,
+    workers: Mutex<Vec<ProcessAgent>>,
+    config: ChaosConfig,
+    metrics: Arc<RwLock<ChaosMetrics>>,
+    last_fault: Mutex<Option<Instant>>,
+}
+
+impl AgentCrashChaos {
+    /// Start `count` `<cca_bin> agent worker <role>` processes connected to `daemon`
+    pub fn spawn(
+        cca_bin: &str,
+        role: &str,
+        count: usize,
+        daemon: DaemonTarget,
+        config: ChaosConfig,
+    ) -> ChaosResult<Self> {
+        let mut chaos = Self {
+            cca_bin: cca_bin.to_string(),
+            role: role.to_string(),
+            worker_prefix: format!("chaos-{role}-{}-", &uuid::Uuid::new_v4().to_string()[..8]),
+            daemon,
+            http: reqwest::Client::builder()
+                .timeout(config.test_timeout)
+                .build()
+                .map_err(|e| ChaosError::ConnectionError(e.to_string()))?,
+            workers: Mutex::new(Vec::new()),
+            config,
+            metrics: Arc::new(RwLock::new(ChaosMetrics::default())),
+            last_fault: Mutex::new(None),
+        };
+        let workers = (0..count)
+            .map(|i| chaos.spawn_worker(&format!("{}{i}", chaos.worker_prefix)))
+            .collect::<ChaosResult<Vec<_>>>()?;
+        *chaos.workers.get_mut() = workers;
+        Ok(chaos)
+    }
+
+    fn spawn_worker(&self, worker_id: &str) -> ChaosResult<ProcessAgent> {
+        let mut command = Command::new(&self.cca_bin);
+        command
+            .args(["agent", "worker", &self.role, "--worker-id", worker_id])
+            .env("CCA_DAEMON_URL", &self.daemon.url)
+            .env("CCA_ACP_URL", &self.daemon.acp_url)
+            // Nobody reads a long-running worker's output; a full pipe would stall it
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        ProcessAgent::from_command(worker_id, &mut command)
+    }
+
+    /// Replace every exited worker with a fresh process under the same worker ID;
+    /// returns how many were respawned
+    pub async fn respawn_exited(&self) -> ChaosResult<usize> {
+        let mut workers = self.workers.lock().await;
+        let mut respawned = 0;
+        for worker in workers.iter_mut() {
+            if !worker.is_running() {
+                *worker = self.spawn_worker(&worker.id)?;
+                respawned += 1;
+            }
+        }
+        Ok(respawned)
+    }
+
+    /// Workers of this pool the daemon currently has connected
+    pub async fn connected_workers(&self) -> ChaosResult<usize> {
+        self.daemon
+            .connected_workers(&self.http, &self.role, &self.worker_prefix)
+            .await
+    }
+
+    /// Wait for the daemon to see the whole pool connected again
+    ///
+    /// Returns the time since the last injected fault (zero if none was injected).
+    pub async fn verify_recovery(&self) -> ChaosResult<Duration> {
+        let attempts = self.config.reconnect_attempts;
+        let mut reason = String::from("daemon never saw the pool reconnect");
+        for attempt in 1..=attempts {
+            match self.health_check().await {
+                Ok(true) => {
+                    let recovery_time = self
+                        .last_fault
+                        .lock()
+                        .await
+                        .take()
+                        .map_or(Duration::ZERO, |at| at.elapsed());
+                    let mut metrics = self.metrics.write().await;
+                    metrics.recoveries_successful += 1;
+                    metrics.recovery_times_ms.push(recovery_time.as_millis() as u64);
+                    return Ok(recovery_time);
+                }
+                Ok(false) => {
+                    let connected = self.connected_workers().await.unwrap_or_default();
+                    reason = format!(
+                        "daemon has {connected} of {} {} workers connected",
+                        self.workers.lock().await.len(),
+                        self.role
+                    );
+                }
+                Err(e) => reason = e.to_string(),
+            }
+            if attempt < attempts {
+                sleep(self.config.injection_delay).await;
+            }
+        }
+
+        self.metrics.write().await.recoveries_failed += 1;
+        Err(ChaosError::RecoveryFailed { attempts, reason })
+    }
+
+    /// Get metrics
+    pub async fn get_metrics(&self) -> ChaosMetrics {
+        let metrics = self.metrics.read().await;
+        ChaosMetrics {
+            faults_injected: metrics.faults_injected,
+            recoveries_successful: metrics.recoveries_successful,
+            recoveries_failed: metrics.recoveries_failed,
+            recovery_times_ms: metrics.recovery_times_ms.clone(),
+            requests_during_chaos: metrics.requests_during_chaos,
+            successful_requests: metrics.successful_requests,
+        }
+    }
+
+    /// Signal one running worker, then wait (up to `test_timeout`) for it to exit
+    /// and for the daemon to drop its connection
+    async fn kill_worker(&self, signal: i32) -> ChaosResult<()> {
+        let expected = {
+            let mut workers = self.workers.lock().await;
+            let worker = workers
+                .iter_mut()
+                .find_map(|w| w.is_running().then_some(w))
+                .ok_or_else(|| ChaosError::PreconditionFailed("No running worker to kill".into()))?;
+            worker.signal(signal)?;
+            *self.last_fault.lock().await = Some(Instant::now());
+            self.metrics.write().await.faults_injected += 1;
+
+            let deadline = Instant::now() + self.config.test_timeout;
+            while worker.is_running() {
+                if Instant::now() >= deadline {
+                    return Err(ChaosError::Timeout {
+                        operation: format!("{} to exit after signal {signal}", worker.id),
+                    });
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+            workers.len()
+        };
+
+        let deadline = Instant::now() + self.config.test_timeout;
+        while self.connected_workers().await? >= expected {
+            if Instant::now() >= deadline {
+                return Err(ChaosError::Timeout {
+                    operation: format!("daemon to notice the killed {} worker", self.role),
+                });
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChaosTestable for AgentCrashChaos {
+    /// Whether the daemon has every worker of the pool connected
+    async fn health_check(&self) -> ChaosResult<bool> {
+        let expected = self.workers.lock().await.len();
+        Ok(expected > 0 && self.connected_workers().await? >= expected)
+    }
+
+    async fn inject_fault(&self, fault: FaultType) -> ChaosResult<()> {
+        match fault {
+            FaultType::ProcessKill { signal } => {
+                if !self.config.enable_destructive {
+                    return Err(ChaosError::PreconditionFailed(
+                        "Killing worker processes requires CHAOS_ENABLE_DESTRUCTIVE=1".into(),
+                    ));
+                }
+                self.kill_worker(signal).await
+            }
+            _ => Err(ChaosError::PreconditionFailed(
+                "Unsupported fault type for agent processes".into(),
+            )),
+        }
+    }
+
+    async fn restore(&self) -> ChaosResult<()> {
+        self.respawn_exited().await?;
+        self.verify_recovery().await?;
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Test Cases
 // ============================================================================
@@ -501,6 +761,60 @@ mod tests {
         assert_eq!(metrics.faults_injected, 10);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_kill_needs_destructive_mode() {
+        let config = ChaosConfig {
+            enable_destructive: false,
+            ..Default::default()
+        };
+        // No workers and no daemon needed: the fault is refused before either is touched
+        let chaos = AgentCrashChaos::spawn("cca", "backend", 0, DaemonTarget::from_env(), config).unwrap();
+
+        let result = chaos.inject_fault(FaultType::ProcessKill { signal: 9 }).await;
+        assert!(matches!(result, Err(ChaosError::PreconditionFailed(_))));
+        assert_eq!(chaos.get_metrics().await.faults_injected, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[ignore = "needs a running daemon and the cca CLI (see README; run with --ignored)"]
+    async fn test_killed_worker_reconnects_to_daemon() {
+        use nix::sys::signal::Signal;
+
+        let config = ChaosConfig {
+            enable_destructive: true,
+            reconnect_attempts: 10,
+            injection_delay: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let cca_bin = std::env::var("CHAOS_CCA_BIN").unwrap_or_else(|_| "cca".into());
+        let chaos = AgentCrashChaos::spawn(&cca_bin, "qa", 2, DaemonTarget::from_env(), config).unwrap();
+        chaos.verify_recovery().await.expect("workers never connected to the daemon");
+
+        chaos
+            .inject_fault(FaultType::ProcessKill { signal: Signal::SIGKILL as i32 })
+            .await
+            .unwrap();
+        assert_eq!(chaos.connected_workers().await.unwrap(), 1);
+        assert!(!chaos.health_check().await.unwrap());
+
+        // Nothing respawns the worker, so the daemon never sees the role come back
+        let err = chaos.verify_recovery().await.unwrap_err();
+        assert!(matches!(err, ChaosError::RecoveryFailed { attempts: 10, .. }));
+
+        chaos.restore().await.unwrap();
+        assert_eq!(chaos.connected_workers().await.unwrap(), 2);
+
+        let metrics = chaos.get_metrics().await;
+        assert_eq!(metrics.faults_injected, 1);
+        assert_eq!(metrics.recoveries_failed, 1);
+        // The initial connect and the restore
+        assert_eq!(metrics.recoveries_successful, 2);
+        // Includes the failed verification's waits
+        assert!(metrics.recovery_times_ms[1] >= 1800);
+    }
+
     #[tokio::test]
     async fn test_concurrent_crash_recovery() {
         let config = ChaosConfig {
@@ -7,6 +7,7 @@
 //! - User-facing errors are graceful and informative
 //! - Recovery to full functionality when services return
 
+use async_trait::async_trait;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -15,7 +16,9 @@ use tokio::sync::RwLock;
 use crate::agent_crash_tests::ChaosAgentManager;
 use crate::postgres_chaos_tests::MockPgPool;
 use crate::redis_chaos_tests::{MockRedisCache, MockRedisPool};
-use crate::{ChaosConfig, ChaosError, ChaosMetrics, ChaosResult, ChaosTestable, FaultType};
+use crate::{
+    ChaosConfig, ChaosError, ChaosMetrics, ChaosResult, ChaosScenario, ChaosTestable, FaultType,
+};
 
 /// Service availability status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -538,6 +541,152 @@ impl SystemStats {
     }
 }
 
+/// Scenario that disconnects both Redis and PostgreSQL and asserts the system
+/// degrades instead of failing: health reports `Degraded`, task creation is
+/// refused with a clear message, and requests keep being answered.
+pub struct DegradationChaos {
+    system: Arc<MockCCASystem>,
+    batch_size: u32,
+    metrics: Arc<RwLock<ChaosMetrics>>,
+    injected_at: RwLock<Option<Instant>>,
+}
+
+impl DegradationChaos {
+    /// Number of requests sent during the outage unless overridden
+    pub const DEFAULT_BATCH_SIZE: u32 = 20;
+
+    pub fn new(system: Arc<MockCCASystem>) -> Self {
+        Self {
+            system,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            metrics: Arc::new(RwLock::new(ChaosMetrics::default())),
+            injected_at: RwLock::new(None),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Get metrics
+    pub async fn get_metrics(&self) -> ChaosMetrics {
+        let metrics = self.metrics.read().await;
+        ChaosMetrics {
+            faults_injected: metrics.faults_injected,
+            recoveries_successful: metrics.recoveries_successful,
+            recoveries_failed: metrics.recoveries_failed,
+            recovery_times_ms: metrics.recovery_times_ms.clone(),
+            requests_during_chaos: metrics.requests_during_chaos,
+            successful_requests: metrics.successful_requests,
+        }
+    }
+
+    /// Health must report degraded, never unavailable, while the stores are down
+    async fn expect_degraded_health(&self) -> ChaosResult<()> {
+        let health = self.system.check_health().await;
+        if health.overall != ServiceStatus::Degraded {
+            return Err(ChaosError::UnexpectedState {
+                expected: "overall health Degraded".to_string(),
+                actual: format!("{:?}", health.overall),
+            });
+        }
+        Ok(())
+    }
+
+    /// Task creation needs PostgreSQL, so it must fail with a clear reason
+    async fn create_task(&self, task_id: &str) -> ChaosResult<bool> {
+        match self.system.execute_task(task_id, TaskType::DatabaseWrite).await {
+            Err(ChaosError::ServiceUnavailable(message)) if message.contains("Database") => {
+                Ok(false)
+            }
+            Err(other) => Err(ChaosError::UnexpectedState {
+                expected: "ServiceUnavailable naming the database".to_string(),
+                actual: other.to_string(),
+            }),
+            Ok(_) => Err(ChaosError::UnexpectedState {
+                expected: "task creation refused".to_string(),
+                actual: "task created".to_string(),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl ChaosScenario for DegradationChaos {
+    fn name(&self) -> &str {
+        "degradation"
+    }
+
+    async fn inject(&self) -> ChaosResult<()> {
+        self.system
+            .redis_pool()
+            .inject_fault(FaultType::NetworkDisconnect)
+            .await?;
+        self.system
+            .pg_pool()
+            .inject_fault(FaultType::NetworkDisconnect)
+            .await?;
+
+        self.metrics.write().await.faults_injected += 2;
+        *self.injected_at.write().await = Some(Instant::now());
+        Ok(())
+    }
+
+    async fn verify(&self) -> ChaosResult<()> {
+        self.expect_degraded_health().await?;
+
+        // Alternate health checks and task creations so the batch covers both
+        // the always-available and the store-backed paths
+        let mut successful = 0;
+        for i in 0..self.batch_size {
+            let ok = if i % 2 == 0 {
+                self.system
+                    .execute_task(&format!("health-{i}"), TaskType::HealthCheck)
+                    .await
+                    .is_ok()
+            } else {
+                self.create_task(&format!("task-{i}")).await?
+            };
+            if ok {
+                successful += 1;
+            }
+        }
+
+        {
+            let mut metrics = self.metrics.write().await;
+            metrics.requests_during_chaos += self.batch_size;
+            metrics.successful_requests += successful;
+        }
+
+        // Still serving after the batch
+        self.expect_degraded_health().await
+    }
+
+    async fn restore(&self) -> ChaosResult<()> {
+        self.system.redis_pool().restore().await?;
+        self.system.pg_pool().restore().await?;
+
+        let health = self.system.check_health().await;
+        let mut metrics = self.metrics.write().await;
+        if health.overall != ServiceStatus::Healthy {
+            metrics.recoveries_failed += 1;
+            return Err(ChaosError::UnexpectedState {
+                expected: "overall health Healthy".to_string(),
+                actual: format!("{:?}", health.overall),
+            });
+        }
+
+        metrics.recoveries_successful += 1;
+        if let Some(injected_at) = self.injected_at.write().await.take() {
+            metrics
+                .recovery_times_ms
+                .push(injected_at.elapsed().as_millis() as u64);
+        }
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Test Cases
 // ============================================================================
@@ -933,4 +1082,54 @@ mod tests {
             assert_eq!(health.overall, ServiceStatus::Healthy);
         }
     }
+
+    #[tokio::test]
+    async fn test_degradation_scenario_with_both_stores_disconnected() {
+        let system = Arc::new(MockCCASystem::new());
+        system.initialize(5).await.unwrap();
+        let scenario = DegradationChaos::new(Arc::clone(&system)).with_batch_size(10);
+        assert_eq!(scenario.name(), "degradation");
+
+        scenario.inject().await.unwrap();
+        let health = system.check_health().await;
+        assert_eq!(health.redis, ServiceStatus::Unavailable);
+        assert_eq!(health.postgres, ServiceStatus::Unavailable);
+        assert_eq!(health.overall, ServiceStatus::Degraded);
+
+        let err = system
+            .execute_task("task-x", TaskType::DatabaseWrite)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Database unavailable"));
+
+        scenario.verify().await.unwrap();
+        scenario.restore().await.unwrap();
+
+        let metrics = scenario.get_metrics().await;
+        assert_eq!(metrics.faults_injected, 2);
+        assert_eq!(metrics.requests_during_chaos, 10);
+        assert_eq!(metrics.successful_requests, 5);
+        assert!((metrics.success_rate() - 0.5).abs() < 0.001);
+        assert_eq!(metrics.recoveries_successful, 1);
+        assert_eq!(metrics.recovery_times_ms.len(), 1);
+        assert_eq!(system.check_health().await.overall, ServiceStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_degradation_scenario_run_restores_on_failure() {
+        // With every agent down the outage is total, so verification fails,
+        // but run must still bring the stores back
+        let system = Arc::new(MockCCASystem::new());
+        system.initialize(3).await.unwrap();
+        system
+            .inject_system_fault(SystemFault::AgentsCrash { percentage: 1.0 })
+            .await
+            .unwrap();
+        let scenario = DegradationChaos::new(Arc::clone(&system));
+
+        let err = scenario.run().await.unwrap_err();
+        assert!(matches!(err, ChaosError::UnexpectedState { .. }));
+        assert!(system.redis_pool().health_check().await.unwrap());
+        assert!(system.pg_pool().health_check().await.unwrap());
+    }
 }
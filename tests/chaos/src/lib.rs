@@ -5,6 +5,7 @@
 //! - Redis disconnection handling
 //! - `PostgreSQL` failover testing
 //! - Graceful degradation scenarios
+//! - Intermittent pattern store failures
 
 // Clippy pedantic allows - these are intentional design choices
 #![allow(clippy::doc_markdown)]
@@ -32,6 +33,7 @@
 
 pub mod agent_crash_tests;
 pub mod degradation_tests;
+pub mod pattern_store_chaos_tests;
 pub mod postgres_chaos_tests;
 pub mod redis_chaos_tests;
 
@@ -118,6 +120,103 @@ pub trait ChaosTestable: Send + Sync {
     async fn restore(&self) -> ChaosResult<()>;
 }
 
+/// An end-to-end chaos scenario: inject faults, check behavior under them, then restore
+#[async_trait]
+pub trait ChaosScenario: Send + Sync {
+    /// Scenario name for reporting
+    fn name(&self) -> &str;
+
+    /// Inject the scenario's faults
+    async fn inject(&self) -> ChaosResult<()>;
+
+    /// Assert the expected behavior while the faults are active
+    async fn verify(&self) -> ChaosResult<()>;
+
+    /// Remove the faults and confirm the system recovered
+    async fn restore(&self) -> ChaosResult<()>;
+
+    /// Inject the faults at a given intensity (0.0 to 1.0), for ramping schedules
+    ///
+    /// Scenarios whose faults have no intensity can keep the default, which
+    /// ignores it.
+    async fn inject_with_intensity(&self, _intensity: f64) -> ChaosResult<()> {
+        self.inject().await
+    }
+
+    /// Run inject, verify and restore; restore runs even if verification fails
+    async fn run(&self) -> ChaosResult<()> {
+        self.inject().await?;
+        let verified = self.verify().await;
+        self.restore().await?;
+        verified
+    }
+
+    /// Inject following `schedule`, then verify and restore
+    ///
+    /// Each injection is counted in `metrics.faults_injected`. Restore runs
+    /// even if an injection or verification fails.
+    async fn run_with_schedule(
+        &self,
+        schedule: &FaultSchedule,
+        metrics: &mut ChaosMetrics,
+    ) -> ChaosResult<()> {
+        let mut outcome = Ok(());
+        for (i, intensity) in schedule.intensities().into_iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(schedule.interval()).await;
+            }
+            outcome = self.inject_with_intensity(intensity).await;
+            if outcome.is_err() {
+                break;
+            }
+            metrics.faults_injected += 1;
+        }
+        if outcome.is_ok() {
+            outcome = self.verify().await;
+        }
+        self.restore().await?;
+        outcome
+    }
+}
+
+/// How often, and how hard, a scenario's faults are injected
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaultSchedule {
+    /// A single injection at full intensity
+    Once,
+    /// `count` full-intensity injections, `interval` apart
+    Burst { count: u32, interval: Duration },
+    /// `steps` injections `interval` apart, intensity moving linearly from `from` to `to`
+    Ramp {
+        from: f64,
+        to: f64,
+        steps: u32,
+        interval: Duration,
+    },
+}
+
+impl FaultSchedule {
+    /// Intensity of each injection, in order
+    pub fn intensities(&self) -> Vec<f64> {
+        match *self {
+            FaultSchedule::Once => vec![1.0],
+            FaultSchedule::Burst { count, .. } => vec![1.0; count as usize],
+            FaultSchedule::Ramp { to, steps: 1, .. } => vec![to],
+            FaultSchedule::Ramp { from, to, steps, .. } => (0..steps)
+                .map(|i| from + (to - from) * f64::from(i) / f64::from(steps - 1))
+                .collect(),
+        }
+    }
+
+    /// Pause between consecutive injections
+    pub fn interval(&self) -> Duration {
+        match *self {
+            FaultSchedule::Once => Duration::ZERO,
+            FaultSchedule::Burst { interval, .. } | FaultSchedule::Ramp { interval, .. } => interval,
+        }
+    }
+}
+
 /// Types of faults that can be injected
 #[derive(Debug, Clone)]
 pub enum FaultType {
@@ -171,6 +270,71 @@ impl ChaosMetrics {
             f64::from(self.successful_requests) / f64::from(self.requests_during_chaos)
         }
     }
+
+    /// Metrics plus derived rates, for uploading as a CI artifact
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "faults_injected": self.faults_injected,
+            "recoveries_successful": self.recoveries_successful,
+            "recoveries_failed": self.recoveries_failed,
+            "recovery_times_ms": self.recovery_times_ms,
+            "avg_recovery_time_ms": self.avg_recovery_time_ms(),
+            "requests_during_chaos": self.requests_during_chaos,
+            "successful_requests": self.successful_requests,
+            "success_rate": self.success_rate(),
+        })
+    }
+
+    /// JUnit XML report with one testcase for the scenario
+    ///
+    /// The testcase fails if the success rate is below `min_success_rate` or
+    /// any recovery failed. Its time is the total recovery time.
+    pub fn to_junit_xml(&self, scenario_name: &str, min_success_rate: f64) -> String {
+        let mut failures = Vec::new();
+        if self.success_rate() < min_success_rate {
+            failures.push(format!(
+                "success rate {:.3} is below {:.3} ({} of {} requests succeeded)",
+                self.success_rate(),
+                min_success_rate,
+                self.successful_requests,
+                self.requests_during_chaos
+            ));
+        }
+        if self.recoveries_failed > 0 {
+            failures.push(format!("{} recoveries failed", self.recoveries_failed));
+        }
+
+        let name = xml_escape(scenario_name);
+        let time = self.recovery_times_ms.iter().sum::<u64>() as f64 / 1000.0;
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuite name=\"chaos\" tests=\"1\" failures=\"{}\" time=\"{time:.3}\">\n",
+            usize::from(!failures.is_empty())
+        ));
+        xml.push_str(&format!(
+            "  <testcase classname=\"chaos\" name=\"{name}\" time=\"{time:.3}\">\n"
+        ));
+        if !failures.is_empty() {
+            let message = xml_escape(&failures.join("; "));
+            xml.push_str(&format!(
+                "    <failure message=\"{message}\" type=\"ChaosFailure\">{message}</failure>\n"
+            ));
+        }
+        xml.push_str(&format!(
+            "    <system-out>{}</system-out>\n",
+            xml_escape(&self.to_json().to_string())
+        ));
+        xml.push_str("  </testcase>\n</testsuite>\n");
+        xml
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
 
 #[cfg(test)]
@@ -203,4 +367,161 @@ mod tests {
         metrics.successful_requests = 8;
         assert!((metrics.success_rate() - 0.8).abs() < 0.001);
     }
+
+    /// Scenario that records when, and at what intensity, it was injected
+    #[derive(Default)]
+    struct RecordingScenario {
+        injections: std::sync::Mutex<Vec<(tokio::time::Instant, f64)>>,
+        restored: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl ChaosScenario for RecordingScenario {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn inject(&self) -> ChaosResult<()> {
+            self.inject_with_intensity(1.0).await
+        }
+
+        async fn inject_with_intensity(&self, intensity: f64) -> ChaosResult<()> {
+            let mut injections = self.injections.lock().unwrap();
+            injections.push((tokio::time::Instant::now(), intensity));
+            Ok(())
+        }
+
+        async fn verify(&self) -> ChaosResult<()> {
+            Ok(())
+        }
+
+        async fn restore(&self) -> ChaosResult<()> {
+            self.restored.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    impl RecordingScenario {
+        /// Offsets of each injection from the first, and their intensities
+        fn timeline(&self) -> Vec<(Duration, f64)> {
+            let injections = self.injections.lock().unwrap();
+            let start = injections[0].0;
+            injections
+                .iter()
+                .map(|&(at, intensity)| (at - start, intensity))
+                .collect()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_once_schedule_injects_once() {
+        let scenario = RecordingScenario::default();
+        let mut metrics = ChaosMetrics::default();
+        scenario
+            .run_with_schedule(&FaultSchedule::Once, &mut metrics)
+            .await
+            .unwrap();
+
+        assert_eq!(metrics.faults_injected, 1);
+        assert_eq!(scenario.timeline(), vec![(Duration::ZERO, 1.0)]);
+        assert!(scenario.restored.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_schedule_spaces_injections_by_interval() {
+        let scenario = RecordingScenario::default();
+        let mut metrics = ChaosMetrics::default();
+        let schedule = FaultSchedule::Burst {
+            count: 3,
+            interval: Duration::from_millis(500),
+        };
+        scenario.run_with_schedule(&schedule, &mut metrics).await.unwrap();
+
+        assert_eq!(metrics.faults_injected, 3);
+        assert_eq!(
+            scenario.timeline(),
+            vec![
+                (Duration::ZERO, 1.0),
+                (Duration::from_millis(500), 1.0),
+                (Duration::from_millis(1000), 1.0),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ramp_schedule_raises_intensity_linearly() {
+        let scenario = RecordingScenario::default();
+        let mut metrics = ChaosMetrics::default();
+        let schedule = FaultSchedule::Ramp {
+            from: 0.25,
+            to: 1.0,
+            steps: 4,
+            interval: Duration::from_secs(1),
+        };
+        scenario.run_with_schedule(&schedule, &mut metrics).await.unwrap();
+
+        assert_eq!(metrics.faults_injected, 4);
+        assert_eq!(
+            scenario.timeline(),
+            vec![
+                (Duration::ZERO, 0.25),
+                (Duration::from_secs(1), 0.5),
+                (Duration::from_secs(2), 0.75),
+                (Duration::from_secs(3), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chaos_metrics_to_json() {
+        let metrics = ChaosMetrics {
+            faults_injected: 2,
+            recoveries_successful: 1,
+            recovery_times_ms: vec![150],
+            requests_during_chaos: 4,
+            successful_requests: 3,
+            ..Default::default()
+        };
+        let json = metrics.to_json();
+        assert_eq!(json["faults_injected"], 2);
+        assert_eq!(json["recovery_times_ms"], serde_json::json!([150]));
+        assert_eq!(json["avg_recovery_time_ms"], 150.0);
+        assert_eq!(json["success_rate"], 0.75);
+    }
+
+    #[test]
+    fn test_junit_report_passes_healthy_run() {
+        let metrics = ChaosMetrics {
+            recovery_times_ms: vec![1500],
+            requests_during_chaos: 10,
+            successful_requests: 10,
+            ..Default::default()
+        };
+        let xml = metrics.to_junit_xml("redis <disconnect>", 0.9);
+        assert!(xml.contains(
+            "<testcase classname=\"chaos\" name=\"redis &lt;disconnect&gt;\" time=\"1.500\">"
+        ));
+        assert!(xml.contains("failures=\"0\""));
+        assert!(!xml.contains("<failure"));
+    }
+
+    #[test]
+    fn test_junit_report_fails_low_success_rate_or_failed_recovery() {
+        let low_success = ChaosMetrics {
+            requests_during_chaos: 10,
+            successful_requests: 5,
+            ..Default::default()
+        };
+        let xml = low_success.to_junit_xml("degradation", 0.9);
+        assert!(xml.contains("<testcase classname=\"chaos\" name=\"degradation\""));
+        assert!(xml.contains("failures=\"1\""));
+        assert!(xml.contains("<failure message=\"success rate 0.500 is below 0.900"));
+
+        let failed_recovery = ChaosMetrics {
+            recoveries_failed: 1,
+            ..Default::default()
+        };
+        let xml = failed_recovery.to_junit_xml("agent_crash", 0.9);
+        assert!(xml.contains("<failure message=\"1 recoveries failed\""));
+    }
 }
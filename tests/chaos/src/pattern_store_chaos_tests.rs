@@ -0,0 +1,229 @@
+//! Pattern Store Chaos Tests
+//!
+//! Wraps any `PatternStore` so a fraction of its operations fail, simulating
+//! intermittent database errors. Failures are drawn from a seeded RNG, so a
+//! given seed and failure rate always fail the same operations.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use cca_core::memory::{Pattern, PatternQuery, PatternStore, SearchMatch};
+use cca_core::{CCAError, PatternId, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::RwLock;
+
+use crate::{ChaosError, ChaosMetrics, ChaosResult, ChaosTestable, FaultType};
+
+struct FaultState {
+    /// Set while a `PartialFailure` fault is injected
+    failure_rate: Option<f64>,
+    rng: StdRng,
+}
+
+/// `PatternStore` wrapper that fails a configured fraction of operations
+pub struct ChaosPatternStore {
+    inner: Arc<dyn PatternStore>,
+    fault: Mutex<FaultState>,
+    metrics: Arc<RwLock<ChaosMetrics>>,
+}
+
+impl ChaosPatternStore {
+    pub fn new(inner: Arc<dyn PatternStore>, seed: u64) -> Self {
+        Self {
+            inner,
+            fault: Mutex::new(FaultState {
+                failure_rate: None,
+                rng: StdRng::seed_from_u64(seed),
+            }),
+            metrics: Arc::new(RwLock::new(ChaosMetrics::default())),
+        }
+    }
+
+    /// Get metrics
+    pub async fn get_metrics(&self) -> ChaosMetrics {
+        let metrics = self.metrics.read().await;
+        ChaosMetrics {
+            faults_injected: metrics.faults_injected,
+            recoveries_successful: metrics.recoveries_successful,
+            recoveries_failed: metrics.recoveries_failed,
+            recovery_times_ms: metrics.recovery_times_ms.clone(),
+            requests_during_chaos: metrics.requests_during_chaos,
+            successful_requests: metrics.successful_requests,
+        }
+    }
+
+    /// Whether the next operation should fail, or `None` when no fault is injected
+    fn roll(&self) -> Option<bool> {
+        let mut fault = self.fault.lock().unwrap();
+        let rate = fault.failure_rate?;
+        Some(fault.rng.gen_bool(rate))
+    }
+
+    /// Run one store operation, failing it instead when the fault picks it
+    async fn call<T>(&self, operation: &str, run: impl Future<Output = Result<T>>) -> Result<T> {
+        let injected = self.roll();
+        let result = if injected == Some(true) {
+            Err(CCAError::Database(format!(
+                "chaos: injected failure in pattern {operation}"
+            )))
+        } else {
+            run.await
+        };
+
+        if injected.is_some() {
+            let mut metrics = self.metrics.write().await;
+            metrics.requests_during_chaos += 1;
+            if result.is_ok() {
+                metrics.successful_requests += 1;
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl PatternStore for ChaosPatternStore {
+    async fn create(&self, pattern: Pattern) -> Result<PatternId> {
+        self.call("create", self.inner.create(pattern)).await
+    }
+
+    async fn search(&self, query: &PatternQuery) -> Result<Vec<SearchMatch>> {
+        self.call("search", self.inner.search(query)).await
+    }
+
+    async fn get(&self, id: PatternId) -> Result<Option<Pattern>> {
+        self.call("get", self.inner.get(id)).await
+    }
+
+    async fn find_duplicate(&self, embedding: &[f32], threshold: f64) -> Result<Option<PatternId>> {
+        self.call("find_duplicate", self.inner.find_duplicate(embedding, threshold))
+            .await
+    }
+
+    async fn record_success(&self, id: PatternId) -> Result<()> {
+        self.call("record_success", self.inner.record_success(id))
+            .await
+    }
+}
+
+#[async_trait]
+impl ChaosTestable for ChaosPatternStore {
+    async fn health_check(&self) -> ChaosResult<bool> {
+        let fault = self.fault.lock().unwrap();
+        Ok(!matches!(fault.failure_rate, Some(rate) if rate > 0.0))
+    }
+
+    async fn inject_fault(&self, fault: FaultType) -> ChaosResult<()> {
+        match fault {
+            FaultType::PartialFailure { failure_rate } if (0.0..=1.0).contains(&failure_rate) => {
+                self.fault.lock().unwrap().failure_rate = Some(failure_rate);
+            }
+            FaultType::PartialFailure { failure_rate } => {
+                return Err(ChaosError::PreconditionFailed(format!(
+                    "failure_rate must be between 0.0 and 1.0, got {failure_rate}"
+                )))
+            }
+            _ => {
+                return Err(ChaosError::PreconditionFailed(
+                    "Unsupported fault type for pattern store".into(),
+                ))
+            }
+        }
+        self.metrics.write().await.faults_injected += 1;
+        Ok(())
+    }
+
+    async fn restore(&self) -> ChaosResult<()> {
+        self.fault.lock().unwrap().failure_rate = None;
+        self.metrics.write().await.recoveries_successful += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cca_core::memory::{InMemoryPatternStore, PatternType};
+
+    async fn store_with_pattern(seed: u64) -> (ChaosPatternStore, PatternId) {
+        let store = ChaosPatternStore::new(Arc::new(InMemoryPatternStore::new()), seed);
+        let id = store
+            .create(Pattern::new(PatternType::Code, "Retry with backoff"))
+            .await
+            .unwrap();
+        (store, id)
+    }
+
+    #[tokio::test]
+    async fn test_full_failure_rate_fails_every_operation() {
+        let (store, id) = store_with_pattern(7).await;
+        store
+            .inject_fault(FaultType::PartialFailure { failure_rate: 1.0 })
+            .await
+            .unwrap();
+        assert!(!store.health_check().await.unwrap());
+
+        for _ in 0..10 {
+            let err = store.get(id).await.unwrap_err();
+            assert!(matches!(err, CCAError::Database(_)));
+        }
+        assert!(store.search(&PatternQuery::text("backoff", 5)).await.is_err());
+
+        let metrics = store.get_metrics().await;
+        assert_eq!(metrics.requests_during_chaos, 11);
+        assert_eq!(metrics.successful_requests, 0);
+
+        store.restore().await.unwrap();
+        assert!(store.health_check().await.unwrap());
+        assert!(store.get(id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_zero_failure_rate_fails_nothing() {
+        let (store, id) = store_with_pattern(7).await;
+        store
+            .inject_fault(FaultType::PartialFailure { failure_rate: 0.0 })
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            assert!(store.get(id).await.unwrap().is_some());
+        }
+        let metrics = store.get_metrics().await;
+        assert_eq!(metrics.requests_during_chaos, 50);
+        assert_eq!(metrics.success_rate(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_fails_same_operations() {
+        let outcomes = |store: ChaosPatternStore, id: PatternId| async move {
+            store
+                .inject_fault(FaultType::PartialFailure { failure_rate: 0.5 })
+                .await
+                .unwrap();
+            let mut failed = Vec::new();
+            for _ in 0..32 {
+                failed.push(store.get(id).await.is_err());
+            }
+            failed
+        };
+
+        let (first, id) = store_with_pattern(42).await;
+        let (second, second_id) = store_with_pattern(42).await;
+        let first = outcomes(first, id).await;
+        assert_eq!(first, outcomes(second, second_id).await);
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unsupported_faults() {
+        let (store, _) = store_with_pattern(1).await;
+        assert!(store.inject_fault(FaultType::NetworkDisconnect).await.is_err());
+        assert!(store
+            .inject_fault(FaultType::PartialFailure { failure_rate: 1.5 })
+            .await
+            .is_err());
+    }
+}